@@ -0,0 +1,60 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package test;
+import java.util.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.lattices.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.env.*;
+import bonsai.runtime.synchronous.statements.*;
+import bonsai.runtime.synchronous.expressions.*;
+import bonsai.runtime.synchronous.interfaces.*;
+public class CodegenDelay implements BModule
+{
+  private static int __num_instances = -1;
+  private int __object_instance;
+  public String __uid(String var)
+  {
+    return "test." + "CodegenDelay." + __object_instance + "." + var;
+  }
+  public void __init()
+  {
+    this.__object_instance = ++this.__num_instances;
+  }
+  public Statement __wrap_process(boolean __root, Statement __process)
+  {
+    Statement __fields = __process;
+    if (__root)
+    {
+      __fields = __fields;
+    }
+    return __fields;
+  }
+  public CodegenDelay() {}
+  public Object __construct() { return this; }
+  static int __proc_delays_instance = -1;
+  public Statement delays()  {
+    __proc_delays_instance++;
+    int __proc_instance = __proc_delays_instance;
+    java.util.function.Function<String, String> __proc_uid = 
+      (var) -> __uid("delays." + __proc_instance + "." + var);
+    return
+      new Sequence(Arrays.asList(
+        new Delay(CompletionCode.PAUSE),
+        new Delay(CompletionCode.PAUSE_UP),
+        new Delay(CompletionCode.STOP)));
+  }
+
+}
@@ -0,0 +1,91 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+package test;
+import test.Module;
+import test.Module2;
+import java.util.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.lattices.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.env.*;
+import bonsai.runtime.synchronous.statements.*;
+import bonsai.runtime.synchronous.expressions.*;
+import bonsai.runtime.synchronous.interfaces.*;
+public class CodegenModule implements BModule
+{
+  public T a;
+  public String __uid_a;
+  public T b = new T();
+  public String __uid_b;
+  public Module m1 = (Module) new Module().__construct();
+  public Module2 m2 = new Module2();
+  public Module2 m3 = (Module2) new Module2().__construct(b);
+  private static int __num_instances = -1;
+  private int __object_instance;
+  public String __uid(String var)
+  {
+    return "test." + "CodegenModule." + __object_instance + "." + var;
+  }
+  public void __init(String __uid_a)
+  {
+    this.__object_instance = ++this.__num_instances;
+    if(__uid_a == null) {this.__uid_a = __uid("a"); }
+    else {this.__uid_a = __uid_a; }
+    this.__uid_b = __uid("b");
+    this.m1.__init();
+    this.m2.__init();
+    this.m3.__init(this.__uid_b);
+  }
+  public void __init()  {
+    this.__init(null);
+  }
+  public Statement __wrap_process(boolean __root, Statement __process)
+  {
+    Statement __fields = new SingleSpaceVarDecl(this.__uid_b,
+      new FunctionCall(Arrays.asList(), (__args) -> { return b; }),
+      this.m1.__wrap_process(false, 
+        this.m2.__wrap_process(true, 
+          this.m3.__wrap_process(false, 
+            __process))));
+    if (__root)
+    {
+      __fields = new SingleSpaceVarDecl(this.__uid_a,
+        new FunctionCall(Arrays.asList(), (__args) -> { return a; }),
+        __fields);
+    }
+    return __fields;
+  }
+  public T __get_a()
+  {
+    return this.a;
+  }
+  public CodegenModule() {}
+  public Object __construct(T a){
+    {
+    this.a = a;
+  }
+
+    return this;
+  }
+  static int __proc_test_instance = -1;
+  public Statement test()  {
+    __proc_test_instance++;
+    int __proc_instance = __proc_test_instance;
+    java.util.function.Function<String, String> __proc_uid = 
+      (var) -> __uid("test." + __proc_instance + "." + var);
+    return
+      new Nothing();
+  }
+
+}
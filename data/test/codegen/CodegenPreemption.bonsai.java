@@ -0,0 +1,27 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package test;
+
+import bonsai.runtime.lattices.LMax;
+
+public class CodegenPreemption
+{
+  public proc preemption() =
+    single_space LMax x = new LMax(0);
+    suspend when x |= 1 in pause end;
+    abort when x |= 1 in pause end;
+    weak abort when x |= 1 in stop end
+  end
+}
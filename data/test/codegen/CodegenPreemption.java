@@ -0,0 +1,74 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+package test;
+import bonsai.runtime.lattices.LMax;
+import java.util.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.lattices.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.env.*;
+import bonsai.runtime.synchronous.statements.*;
+import bonsai.runtime.synchronous.expressions.*;
+import bonsai.runtime.synchronous.interfaces.*;
+public class CodegenPreemption implements BModule
+{
+  private static int __num_instances = -1;
+  private int __object_instance;
+  public String __uid(String var)
+  {
+    return "test." + "CodegenPreemption." + __object_instance + "." + var;
+  }
+  public void __init()
+  {
+    this.__object_instance = ++this.__num_instances;
+  }
+  public Statement __wrap_process(boolean __root, Statement __process)
+  {
+    Statement __fields = __process;
+    if (__root)
+    {
+      __fields = __fields;
+    }
+    return __fields;
+  }
+  public CodegenPreemption() {}
+  public Object __construct() { return this; }
+  static int __proc_preemption_instance = -1;
+  public Statement preemption()  {
+    __proc_preemption_instance++;
+    int __proc_instance = __proc_preemption_instance;
+    java.util.function.Function<String, String> __proc_uid = 
+      (var) -> __uid("preemption." + __proc_instance + "." + var);
+    return
+      new SingleSpaceVarDecl(__proc_uid.apply("x"),
+        new FunctionCall(Arrays.asList(
+          ), (__args) -> {
+          return new LMax(0);}
+        ),
+        new Sequence(Arrays.asList(
+          new SuspendWhen(new Entailment(Arrays.asList(
+              new FreeAccess(__proc_uid.apply("x"))), Arrays.asList(
+              ), (__args) -> Cast.toLattice("<expr in entailment relation>",((LMax) (__args.get(0)))).entails(1), false),
+            new Delay(CompletionCode.PAUSE)),
+          new AbortWhen(new Entailment(Arrays.asList(
+              new FreeAccess(__proc_uid.apply("x"))), Arrays.asList(
+              ), (__args) -> Cast.toLattice("<expr in entailment relation>",((LMax) (__args.get(0)))).entails(1), false),
+            new Delay(CompletionCode.PAUSE), AbortWhen.STRONG),
+          new AbortWhen(new Entailment(Arrays.asList(
+              new FreeAccess(__proc_uid.apply("x"))), Arrays.asList(
+              ), (__args) -> Cast.toLattice("<expr in entailment relation>",((LMax) (__args.get(0)))).entails(1), false),
+            new Delay(CompletionCode.STOP), AbortWhen.WEAK))));
+  }
+
+}
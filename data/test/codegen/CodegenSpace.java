@@ -0,0 +1,78 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+package test;
+import bonsai.runtime.lattices.LMax;
+import bonsai.runtime.queueing.StackLR;
+import java.util.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.lattices.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.env.*;
+import bonsai.runtime.synchronous.statements.*;
+import bonsai.runtime.synchronous.expressions.*;
+import bonsai.runtime.synchronous.interfaces.*;
+public class CodegenSpace implements BModule
+{
+  private static int __num_instances = -1;
+  private int __object_instance;
+  public String __uid(String var)
+  {
+    return "test." + "CodegenSpace." + __object_instance + "." + var;
+  }
+  public void __init()
+  {
+    this.__object_instance = ++this.__num_instances;
+  }
+  public Statement __wrap_process(boolean __root, Statement __process)
+  {
+    Statement __fields = __process;
+    if (__root)
+    {
+      __fields = __fields;
+    }
+    return __fields;
+  }
+  public CodegenSpace() {}
+  public Object __construct() { return this; }
+  static int __proc_spacePrune_instance = -1;
+  public Statement spacePrune()  {
+    __proc_spacePrune_instance++;
+    int __proc_instance = __proc_spacePrune_instance;
+    java.util.function.Function<String, String> __proc_uid = 
+      (var) -> __uid("spacePrune." + __proc_instance + "." + var);
+    return
+      new SingleSpaceVarDecl(__proc_uid.apply("stack"),
+        new FunctionCall(Arrays.asList(
+          ), (__args) -> {
+          return new StackLR();}
+        ),
+        new Universe(
+          __proc_uid.apply("stack"), 
+          new SingleSpaceVarDecl(__proc_uid.apply("count"),
+            new FunctionCall(Arrays.asList(
+              ), (__args) -> {
+              return new LMax(0);}
+            ),
+            new Sequence(Arrays.asList(
+              new SpaceStmt(
+                new ArrayList<>(Arrays.asList(
+                  __proc_uid.apply("count"))),
+                new ProcedureCall(Arrays.asList(
+                  new ReadWriteAccess(__proc_uid.apply("count"))), (__args) -> {
+                  ((LMax) (__args.get(0))).inc();}
+                )),
+              new Prune())))));
+  }
+
+}
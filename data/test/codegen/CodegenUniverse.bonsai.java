@@ -0,0 +1,30 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package test;
+
+import bonsai.runtime.queueing.StackLR;
+
+public class CodegenUniverse
+{
+  public proc universes() =
+    single_space StackLR stack = new StackLR();
+    universe with stack in
+      pause
+    end;
+    universe
+      pause up
+    end
+  end
+}
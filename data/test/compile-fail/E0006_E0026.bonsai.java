@@ -0,0 +1,31 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[error(E0006, 25, 4)]
+#[error(E0026, 29, 4)]
+
+package test;
+
+public class E0006_E0026
+{
+  public single_space LMax a;
+
+  public proc undeclared() =
+    b <- 1;
+  end
+
+  public proc permission() =
+    read a <- 1;
+  end
+}
@@ -0,0 +1,25 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[error(E0020, 22, 17)]
+#[error(E0020, 23, 37)]
+
+package test;
+
+public class IllegalParamKind
+{
+  proc hostParam(T x) = nothing
+  proc moduleParam(single_space T a, module Module m) = nothing
+  proc ok(single_space T a, world_line T b, single_time T c) = nothing
+}
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0021, 29, 9)]
 #[error(E0021, 30, 9)]
+#[error(E0021, 31, 42)]
 
 package test;
 
@@ -26,8 +26,9 @@ public class ModuleRefFieldInitializer
   public module Module ok1;
   public module Module ok2 = new Module();
   public module Module2 ok3;
-  public module Module2 ko1 = new Module2(a);
-  public module Module2 ko2 = Module2.create(a);
+  public module Module2 ok4 = new Module2(a);
+  public module Module2 ko1 = Module2.create(a);
+  public module Module2 ko2 = new Module2(ok2.a);
 
   public proc test() = nothing
 }
@@ -0,0 +1,37 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[error(E0023, 33, 10)]
+#[error(E0023, 34, 10)]
+#[error(E0023, 35, 13)]
+
+package test;
+
+public class ProcessArgumentMismatch
+{
+  public single_space T a;
+  public single_space T2 b;
+  public single_time T c;
+
+  proc p(single_space T x) = nothing
+  proc q(single_space T x, single_time T y) = nothing
+
+  public proc test() =
+    run p(a);
+    run q(a, c);
+    run p(b);
+    run p(c);
+    run q(a, a);
+  end
+}
@@ -0,0 +1,45 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[error(E0026, 41, 14)]
+#[error(E0026, 42, 15)]
+#[error(E0026, 43, 19)]
+
+package test;
+
+public class IllegalArgumentPermission
+{
+  public single_space LMax a;
+
+  proc readX(single_space LMax x) =
+    when x |= 1 then nothing end
+  end
+
+  proc writeX(single_space LMax x) = x <- 1
+
+  proc readWriteX(single_space LMax x) =
+    run readX(x);
+    run writeX(x);
+  end
+
+  public proc test() =
+    run readX(read a); // OK
+    run writeX(write a); // OK
+    run readWriteX(readwrite a); // OK
+    run readX(readwrite a); // OK
+    run readX(write a); // KO
+    run writeX(read a); // KO
+    run readWriteX(write a); // KO
+  end
+}
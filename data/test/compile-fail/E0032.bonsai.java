@@ -12,10 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0032, 28, 4)]
-#[error(E0032, 32, 19)]
-#[error(E0032, 37, 6)]
-#[error(E0032, 41, 21)]
+#[error(E0032, 32, 4, "second readwrite access")]
+#[label(31, 4, "previous readwrite access")]
+#[error(E0032, 36, 19)]
+#[label(36, 6)]
+#[error(E0032, 41, 6)]
+#[label(40, 6, "previous")]
+#[error(E0032, 45, 21, "second readwrite")]
+#[label(45, 6)]
 
 package test;
 
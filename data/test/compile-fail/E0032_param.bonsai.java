@@ -0,0 +1,35 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[error(E0032, 23, 44, "second readwrite access")]
+
+package test;
+
+import bonsai.runtime.lattices.LMax;
+
+public class E0032_param
+{
+  proc inc(single_space LMax x) = readwrite x.inc()
+
+  // `x` is accessed twice with `readwrite` in the same instant through `inc`.
+  proc incTwice(single_space LMax y) =
+    run inc(y);
+    run inc(y);
+  end
+
+  public proc test() =
+    single_space LMax a = new LMax(0);
+    run incTwice(a);
+  end
+}
@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
+#[error(E0033, 27, 6)]
+#[error(E0033, 31, 11)]
+#[error(E0033, 37, 4)]
 
 package test;
 
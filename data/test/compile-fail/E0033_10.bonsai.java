@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0033, 1, 0)]
+#[error(E0033, 26, 26)]
 
 package test;
 
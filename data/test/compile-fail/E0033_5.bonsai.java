@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
+#[error(E0033, 25, 15)]
+#[error(E0033, 29, 15)]
 
 package test;
 
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
+#[error(E0033, 28, 4)]
+#[error(E0033, 28, 4)]
 
 package test;
 
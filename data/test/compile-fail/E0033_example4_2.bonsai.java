@@ -14,7 +14,7 @@
 
 // Example 4.2, Section 4.5.2 in the dissertation (Talbot, 2018).
 
-#[error(E0033, 1, 0)]
+#[error(E0033, 26, 36)]
 
 package test;
 
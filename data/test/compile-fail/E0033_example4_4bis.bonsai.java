@@ -14,7 +14,7 @@
 
 // Variation of example 4.4 Section 4.5.4 in the dissertation (Talbot, 2018).
 
-#[error(E0033, 1, 0)]
+#[error(E0033, 31, 4)]
 
 package test;
 
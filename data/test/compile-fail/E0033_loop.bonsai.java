@@ -12,12 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
-// To fix when we have proper error message for causality errors.
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
+#[error(E0033, 30, 23)]
+#[error(E0033, 30, 23)]
+#[error(E0033, 41, 23)]
 
 package test;
 
@@ -14,8 +14,8 @@
 
 // Corrected version of the nondeterministic example Section 4.5.1 in the dissertation (Talbot, 2018).
 
-#[error(E0033, 1, 0)]
-#[error(E0033, 1, 0)]
+#[error(E0033, 29, 24)]
+#[error(E0033, 38, 24)]
 
 package test;
 
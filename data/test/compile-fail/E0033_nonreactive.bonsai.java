@@ -14,7 +14,7 @@
 
 // Example Section 4.5.1 in the dissertation (Talbot, 2018).
 
-#[error(E0033, 1, 0)]
+#[error(E0033, 25, 34)]
 
 package test;
 
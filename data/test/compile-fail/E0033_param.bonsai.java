@@ -0,0 +1,33 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[error(E0033, 26, 4)]
+
+package test;
+
+import bonsai.runtime.lattices.LMax;
+
+public class E0033_param
+{
+  // The parameter is written after being read in the same instant.
+  proc readThenWrite(single_time LMax x) =
+    when x |= 1 then nothing end;
+    x <- 1;
+  end
+
+  public proc test() =
+    single_time LMax a = new LMax(0);
+    run readThenWrite(a);
+  end
+}
@@ -0,0 +1,38 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#[error(E0035, 27, 9)]
+#[error(E0035, 29, 11)]
+#[error(E0035, 33, 22)]
+#[error(E0035, 36, 19)]
+
+package test;
+
+import java.lang.System;
+
+public class E0035_2
+{
+  public proc first() =
+    single_time LMax x = new LMax(1);
+    x <- ;
+    when x |= 1 then
+      x <- ;
+    end
+  end
+
+  public proc second( = nothing end
+
+  public proc third() =
+    par nothing || ) end
+  end
+}
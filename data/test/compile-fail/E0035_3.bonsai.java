@@ -0,0 +1,26 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#[label(24, 4)]
+#[error(E0035, 14, 2, "does not follow an `#[error(...)]`")]
+
+package test;
+
+import java.lang.System;
+
+public class E0035_3
+{
+  public proc first() =
+    nothing
+  end
+}
@@ -0,0 +1,32 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package test;
+
+import test.Module;
+import test.Module2;
+
+public class ModuleField
+{
+  public single_space T a;
+  public module Module m1;
+  public module Module m2 = new Module();
+  public module Module2 m3;
+
+  public proc test() =
+    run m1.test();
+    run m3.test();
+    when m2.a |= m3.b then nothing end;
+  end
+}
@@ -0,0 +1,29 @@
+// Copyright 2019 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package test;
+
+import bonsai.runtime.lattices.LMax;
+
+public class WeakIdentifier
+{
+  // `weak` is only a keyword before `abort`.
+  public proc weakAsVariable() =
+    single_time LMax weak = new LMax(0);
+    weak <- 1;
+    weak abort when weak |= 1 in
+      pause;
+    end
+  end
+}
@@ -0,0 +1,35 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package test;
+
+import java.lang.System;
+import bonsai.runtime.lattices.LMax;
+
+public class Counter
+{
+  public single_space LMax value = new LMax(0);
+  public ref single_space LMax total;
+
+  public Counter(LMax total) {
+    this.total = total;
+  }
+
+  public proc inc() =
+    readwrite value.inc();
+    readwrite total.inc();
+  end
+
+  public proc print() = System.out.print(read value)
+}
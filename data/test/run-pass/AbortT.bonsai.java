@@ -0,0 +1,129 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[run(AbortT.abortFalse, "123")]
+#[run(AbortT.abortTrue, "e")]
+#[run(AbortT.abortSecondInstant, "ae")]
+#[run(AbortT.abortLoop, "aae")]
+#[run(AbortT.weakAbortFalse, "123")]
+#[run(AbortT.weakAbortTrue, "ae")]
+#[run(AbortT.weakAbortSecondInstant, "abe")]
+#[run(AbortT.weakAbortLoop, "aaae")]
+
+package test;
+
+import java.lang.System;
+import java.util.*;
+import bonsai.runtime.lattices.LMax;
+
+public class AbortT
+{
+  public proc abortFalse() =
+    abort when false in
+      System.out.print(1);
+      pause;
+      System.out.print(2);
+    end;
+    System.out.print(3);
+  end
+
+  public proc abortTrue() =
+    abort when true in
+      System.out.print("unreachable");
+    end;
+    System.out.print("e");
+  end
+
+  public proc abortSecondInstant() =
+    single_time LMax x = new LMax(0);
+    par
+    || abort when x |= 1 in
+         System.out.print("a");
+         pause;
+         System.out.print("b");
+         pause;
+         System.out.print("c");
+       end;
+       System.out.print("e");
+    || pause;
+       x <- 1;
+    end
+  end
+
+  public proc abortLoop() =
+    single_time LMax x = new LMax(0);
+    par
+    || abort when x |= 1 in
+         loop
+           System.out.print("a");
+           pause;
+         end
+       end;
+       System.out.print("e");
+    || pause;
+       pause;
+       x <- 1;
+    end
+  end
+
+  public proc weakAbortFalse() =
+    weak abort when false in
+      System.out.print(1);
+      pause;
+      System.out.print(2);
+    end;
+    System.out.print(3);
+  end
+
+  public proc weakAbortTrue() =
+    weak abort when true in
+      System.out.print("a");
+      pause;
+      System.out.print("unreachable");
+    end;
+    System.out.print("e");
+  end
+
+  public proc weakAbortSecondInstant() =
+    single_time LMax x = new LMax(0);
+    par
+    || weak abort when x |= 1 in
+         System.out.print("a");
+         pause;
+         System.out.print("b");
+         pause;
+         System.out.print("c");
+       end;
+       System.out.print("e");
+    || pause;
+       x <- 1;
+    end
+  end
+
+  public proc weakAbortLoop() =
+    single_time LMax x = new LMax(0);
+    par
+    || weak abort when x |= 1 in
+         loop
+           System.out.print("a");
+           pause;
+         end
+       end;
+       System.out.print("e");
+    || pause;
+       pause;
+       x <- 1;
+    end
+  end
+}
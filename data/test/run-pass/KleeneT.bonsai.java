@@ -0,0 +1,118 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[run(KleeneT.andTrue, "1")]
+#[run(KleeneT.andFalse, "2")]
+#[run(KleeneT.orTrue, "1")]
+#[run(KleeneT.orFalse, "2")]
+#[run(KleeneT.notEntailment, "1")]
+#[run(KleeneT.notTrue, "2")]
+#[run(KleeneT.combined, "12")]
+#[run(KleeneT.monotoneAnd, "4")]
+#[run(KleeneT.antiMonotoneNot, "5")]
+
+package test;
+
+import java.lang.System;
+import java.util.*;
+
+public class KleeneT
+{
+  public proc andTrue() =
+    single_time LMax x = new LMax(3);
+    when x |= 2 and x |= 3 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc andFalse() =
+    single_time LMax x = new LMax(3);
+    when x |= 2 and x |= 4 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc orTrue() =
+    single_time LMax x = new LMax(3);
+    when x |= 4 or x |= 3 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc orFalse() =
+    single_time LMax x = new LMax(3);
+    when x |= 4 or x |= 5 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc notEntailment() =
+    single_time LMax x = new LMax(3);
+    when not (x |= 4) then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc notTrue() =
+    when not true then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+
+  public proc combined() =
+    single_time LMax x = new LMax(3);
+    single_time LMax y = new LMax(2);
+    when x |= y and not (y |= x) then
+      System.out.print(1);
+    end;
+    when not (x |= y or y |= x) then nothing else
+      System.out.print(2);
+    end
+  end
+
+  public proc monotoneAnd() =
+    single_time LMax x = new LMax(3);
+    single_time LMax y = new LMax(2);
+    when x |= y and x |= 3 then
+      readwrite x.inc();
+      System.out.print(read x);
+    else
+      readwrite y.inc();
+      System.out.print(read y);
+    end
+  end
+
+  public proc antiMonotoneNot() =
+    single_time LMax x = new LMax(3);
+    single_time LMax y = new LMax(4);
+    when not (x |= y) then
+      readwrite y.inc();
+      System.out.print(read y);
+    else
+      readwrite x.inc();
+      System.out.print(read x);
+    end
+  end
+}
@@ -0,0 +1,86 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[run(ProcParamT.incLocal, "12")]
+#[run(ProcParamT.incField, "2")]
+#[run(ProcParamT.tellTwoVars, "12")]
+#[run(ProcParamT.cascade, "2")]
+#[run(ProcParamT.paramAcrossInstants, "12")]
+
+package test;
+
+import java.lang.System;
+import java.util.*;
+import bonsai.runtime.lattices.LMax;
+
+public class ProcParamT
+{
+  single_space LMax f = new LMax(1);
+
+  proc inc(single_space LMax x) = readwrite x.inc()
+
+  public proc incLocal() =
+    single_space LMax a = new LMax(0);
+    run inc(a);
+    System.out.print(read a);
+    pause;
+    run inc(a);
+    System.out.print(read a);
+  end
+
+  public proc incField() =
+    run inc(f);
+    System.out.print(read f);
+  end
+
+  proc tell(single_time LMax x, single_time LMax y) =
+    x <- 1;
+    y <- 2;
+  end
+
+  public proc tellTwoVars() =
+    single_time LMax a = new LMax(0);
+    single_time LMax b = new LMax(0);
+    run tell(a, b);
+    System.out.print(read a);
+    System.out.print(read b);
+  end
+
+  // Two `readwrite` accesses on `x` must happen in distinct instants.
+  proc incTwice(single_space LMax x) =
+    run inc(x);
+    pause;
+    run inc(x);
+  end
+
+  public proc cascade() =
+    single_space LMax a = new LMax(0);
+    run incTwice(a);
+    System.out.print(read a);
+  end
+
+  proc printEachInstant(single_space LMax x) =
+    System.out.print(read x);
+    pause;
+    System.out.print(read x);
+  end
+
+  public proc paramAcrossInstants() =
+    single_space LMax a = new LMax(1);
+    par
+    || run printEachInstant(a)
+    || pause; readwrite a.inc()
+    end
+  end
+}
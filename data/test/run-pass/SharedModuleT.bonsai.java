@@ -0,0 +1,49 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[run(SharedModuleT.shared, "122")]
+
+package test;
+
+import java.lang.System;
+import java.util.*;
+import bonsai.runtime.lattices.LMax;
+import test.Counter;
+
+public class SharedModuleT
+{
+  single_space LMax total = new LMax(0);
+  module Counter c = new Counter(total);
+
+  proc incEachInstant() =
+    run c.inc();
+    pause;
+    run c.inc();
+  end
+
+  proc printEachInstant() =
+    run c.print();
+    pause;
+    run c.print();
+  end
+
+  // `c` is shared by both processes, and its `ref` field `total` is bound to a field of this module.
+  public proc shared() =
+    par
+    || run incEachInstant()
+    || run printEachInstant()
+    end;
+    System.out.print(read total);
+  end
+}
@@ -0,0 +1,94 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[run(StrictEqualityT.strictTrue, "1")]
+#[run(StrictEqualityT.strictOnEqual, "2")]
+#[run(StrictEqualityT.strictLMin, "1")]
+#[run(StrictEqualityT.equalTrue, "1")]
+#[run(StrictEqualityT.equalFalse, "2")]
+#[run(StrictEqualityT.equalVars, "12")]
+#[run(StrictEqualityT.betterBound, "54")]
+
+package test;
+
+import java.lang.System;
+import java.util.*;
+
+public class StrictEqualityT
+{
+  public proc strictTrue() =
+    single_time LMax x = new LMax(3);
+    when x |< 2 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc strictOnEqual() =
+    single_time LMax x = new LMax(3);
+    when x |< 3 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc strictLMin() =
+    single_time LMin x = new LMin(2);
+    when x |< 3 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc equalTrue() =
+    single_time LMax x = new LMax(3);
+    when x == 3 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc equalFalse() =
+    single_time LMax x = new LMax(3);
+    when x == 2 then
+      System.out.print(1);
+    else
+      System.out.print(2);
+    end
+  end
+
+  public proc equalVars() =
+    single_time LMax x = new LMax(3);
+    single_time LMax y = new LMax(3);
+    when x == y then
+      System.out.print(1);
+    end;
+    when y |< x then nothing else
+      System.out.print(2);
+    end
+  end
+
+  public proc betterBound() =
+    single_time LMin bound = new LMin(5);
+    single_time LMin obj = new LMin(4);
+    when obj |< bound then
+      System.out.print(read bound);
+      System.out.print(read obj);
+    end
+  end
+}
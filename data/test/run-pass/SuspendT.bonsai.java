@@ -0,0 +1,80 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[run(SuspendT.suspendFalse, "12")]
+#[run(SuspendT.suspendTrue, "a")]
+#[run(SuspendT.suspendSecondInstant, "asbc")]
+#[run(SuspendT.suspendFirstInstant, "sab")]
+#[run(SuspendT.suspendThenTerminate, "12")]
+
+package test;
+
+import java.lang.System;
+import java.util.*;
+import bonsai.runtime.lattices.LMax;
+
+public class SuspendT
+{
+  public proc suspendFalse() =
+    suspend when false in
+      System.out.print(1);
+      pause;
+      System.out.print(2);
+    end
+  end
+
+  public proc suspendTrue() =
+    System.out.print("a");
+    par
+    || suspend when true in System.out.print(1) end
+    || stop
+    end
+  end
+
+  public proc suspendSecondInstant() =
+    single_time LMax x = new LMax(0);
+    par
+    || suspend when x |= 1 in
+         System.out.print("a");
+         pause;
+         System.out.print("b");
+         pause;
+         System.out.print("c");
+       end
+    || pause;
+       x <- 1;
+       System.out.print("s");
+    end
+  end
+
+  public proc suspendFirstInstant() =
+    single_time LMax x = new LMax(0);
+    par
+    || suspend when x |= 1 in
+         System.out.print("a");
+         pause;
+         System.out.print("b");
+       end
+    || x <- 1;
+       System.out.print("s");
+    end
+  end
+
+  public proc suspendThenTerminate() =
+    suspend when false in
+      System.out.print(1);
+    end;
+    System.out.print(2);
+  end
+}
@@ -27,6 +27,7 @@
 #[run(WhenT.whenAndTell, "94")]
 #[run(WhenT.nonMonotonicTell, "4")]
 #[run(WhenT.nonMonotonicTell2, "344")]
+#[run(WhenT.whenBareThenWrite, "1")]
 // #[debug(WhenT.example4_4_thesis, "33")]
 
 package test;
@@ -175,6 +176,15 @@ public class WhenT
     System.out.print(read x);
   end
 
+  public proc whenBareThenWrite() =
+    single_time ES b = unknown;
+    b <- true;
+    when b then
+      b <- true;
+      System.out.print(1);
+    end
+  end
+
   public proc example4_4_thesis() =
     single_space LMax x = bot;
     single_space LMax y = new LMax(1);
@@ -0,0 +1,27 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#[run(Passing.printTwice, "12")]
+
+package project;
+
+import java.lang.System;
+
+public class Passing
+{
+  public proc printTwice() =
+    System.out.print(1);
+    pause;
+    System.out.print(2)
+  end
+}
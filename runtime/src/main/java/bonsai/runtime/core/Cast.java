@@ -14,6 +14,8 @@
 
 package bonsai.runtime.core;
 
+import bonsai.runtime.lattices.ES;
+
 public class Cast
 {
   static public void checkNull(String what, String where, Object value) {
@@ -71,4 +73,20 @@ public class Cast
     checkQueueingInterface(var, o);
     return (Queueing) o;
   }
+
+  static public Kleene toKleene(String var, Object o) {
+    if (o instanceof Kleene) {
+      return (Kleene) o;
+    }
+    else if (o instanceof ES) {
+      return ((ES) o).unwrap();
+    }
+    else if (o instanceof Boolean) {
+      return Kleene.fromBool((Boolean) o);
+    }
+    else {
+      throw new RuntimeException(
+        "The expression `" + var + "` is not a three-valued condition. Object: " + o);
+    }
+  }
 }
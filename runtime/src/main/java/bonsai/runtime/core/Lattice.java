@@ -73,4 +73,19 @@ public interface Lattice
         Kleene.and(this.entails(o), Kleene.fromBool(!equals_default(this,o)));
     }
   }
+
+  // Written `a == b` in spacetime.
+  // a.equals_lattice(b) == TRUE <=> a.entails(b) == TRUE /\ b.entails(a) == TRUE
+  default Kleene equals_lattice(Object other) {
+    if (other == null) {
+      return Kleene.FALSE;
+    }
+    else if (!(other instanceof Lattice)) {
+      return Kleene.FALSE;
+    }
+    else {
+      Lattice o = (Lattice) other;
+      return Kleene.and(this.entails(o), o.entails(this));
+    }
+  }
 }
@@ -71,6 +71,17 @@ public abstract class TotalOrder<T>
     }
   }
 
+  // In a total order, the result is never unknown.
+  public Kleene strict_entail(Object o) {
+    TotalOrder<T> v = castTotalOrder("strict_entail", wrapInteger(o));
+    return Kleene.fromBool(entails_inner(v) && !value.equals(v.value));
+  }
+
+  public Kleene equals_lattice(Object o) {
+    TotalOrder<T> v = castTotalOrder("equals_lattice", wrapInteger(o));
+    return Kleene.fromBool(value.equals(v.value));
+  }
+
   public boolean equals(Object o) {
     TotalOrder<T> v = castTotalOrder("equals", o);
     return value.equals(v.value);
@@ -20,7 +20,7 @@ import bonsai.runtime.core.*;
 import bonsai.runtime.synchronous.variables.*;
 import bonsai.runtime.synchronous.interfaces.*;
 import bonsai.runtime.synchronous.statements.SpaceStmt;
-import bonsai.runtime.synchronous.expressions.Entailment;
+import bonsai.runtime.synchronous.expressions.Condition;
 
 public class Layer
 {
@@ -70,7 +70,7 @@ public class Layer
     return space.unblock(body, layersRemaining, this);
   }
 
-  public void subscribeUnblocked(String uid, Entailment cond, Kleene result) {
+  public void subscribeUnblocked(String uid, Condition cond, Kleene result) {
     scheduler.subscribeUnblocked(uid, cond, result);
   }
 
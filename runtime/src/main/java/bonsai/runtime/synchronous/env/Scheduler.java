@@ -19,7 +19,7 @@ import java.util.function.*;
 import bonsai.runtime.core.*;
 import bonsai.runtime.synchronous.variables.*;
 import bonsai.runtime.synchronous.interfaces.*;
-import bonsai.runtime.synchronous.expressions.Entailment;
+import bonsai.runtime.synchronous.expressions.Condition;
 
 public class Scheduler
 {
@@ -28,9 +28,9 @@ public class Scheduler
   private HashMap<String, PartialCond> unblocked;
 
   class PartialCond {
-    public Entailment cond;
+    public Condition cond;
     public Kleene result;
-    public PartialCond(Entailment cond, Kleene result) {
+    public PartialCond(Condition cond, Kleene result) {
       this.cond = cond;
       this.result = result;
     }
@@ -72,7 +72,7 @@ public class Scheduler
     }
   }
 
-  public void subscribeUnblocked(String uid, Entailment entailment, Kleene result) {
+  public void subscribeUnblocked(String uid, Condition entailment, Kleene result) {
     unblocked.put(uid, new PartialCond(entailment, result));
   }
 
@@ -0,0 +1,70 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+package bonsai.runtime.synchronous.expressions;
+
+import java.util.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.lattices.*;
+import bonsai.runtime.synchronous.interfaces.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.env.*;
+
+/// A condition is either an entailment `e |= e'`, or a combination of conditions with the Kleene operators `and`, `or` and `not`.
+/// Its result is committed once it cannot change anymore in the current instant.
+public abstract class Condition extends ASTNode implements Expression
+{
+  private ExprResult result;
+
+  public Condition() {
+    super();
+    this.result = new ExprResult();
+  }
+
+  public abstract Condition copy();
+
+  public void canInstant(Layer layer) {
+    this.result = new ExprResult();
+  }
+
+  public ExprResult execute(Layer layer) {
+    if (result.isSuspended()) {
+      Kleene promoted = execute(layer, "");
+      if (promoted != null) {
+        commit(promoted);
+      }
+    }
+    return result;
+  }
+
+  /// Returns the result of the condition if it cannot change anymore in the current instant, `null` otherwise.
+  /// The variable `readOnlyHypothesis` is considered read-only, even if it can still be written.
+  public abstract Kleene execute(Layer layer, String readOnlyHypothesis);
+
+  public void commit(Kleene partialRes) {
+    result = new ExprResult(new ES(partialRes));
+  }
+
+  public boolean canWriteOn(String uid) {
+    return false;
+  }
+
+  /// `null` represents a result not yet known, which is `unknown` in Kleene logic.
+  protected static Kleene fromPromoted(Kleene k) {
+    return k == null ? Kleene.UNKNOWN : k;
+  }
+
+  protected static Kleene toPromoted(Kleene k) {
+    return k == Kleene.UNKNOWN ? null : k;
+  }
+}
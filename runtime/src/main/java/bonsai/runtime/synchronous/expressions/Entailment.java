@@ -23,29 +23,37 @@ import bonsai.runtime.synchronous.*;
 import bonsai.runtime.synchronous.env.*;
 import bonsai.runtime.synchronous.variables.*;
 
-// e |= e'
-public class Entailment extends ASTNode implements Expression
+// e |= e', e |< e' and e == e'
+public class Entailment extends Condition
 {
   // We have the variables appearing in the left and right side of the entailment.
   // Note that constants are not represented in this class and directly compiled into the closure.
   private final List<FreeAccess> leftVars;
   private final List<FreeAccess> rightVars;
   private final Function<ArrayList<Object>, Kleene> eval;
-  private ExprResult result;
+  // `e |= e'` and `e |< e'` are monotonic in `e` and anti-monotonic in `e'`, but `e == e'` is neither.
+  private final boolean isEquality;
 
   public Entailment(List<FreeAccess> leftVars, List<FreeAccess> rightVars,
     Function<ArrayList<Object>, Kleene> eval) {
+    this(leftVars, rightVars, eval, false);
+  }
+
+  public Entailment(List<FreeAccess> leftVars, List<FreeAccess> rightVars,
+    Function<ArrayList<Object>, Kleene> eval, boolean isEquality) {
+    super();
     this.leftVars = leftVars;
     this.rightVars = rightVars;
     this.eval = eval;
-    this.result = new ExprResult();
+    this.isEquality = isEquality;
   }
 
   public Entailment copy() {
     return new Entailment(
       ASTNode.copyList(leftVars),
       ASTNode.copyList(rightVars),
-      eval);
+      eval,
+      isEquality);
   }
 
   private boolean evalArgs(Layer layer, String readOnlyHypothesis, List<FreeAccess> accesses,
@@ -62,9 +70,15 @@ public class Entailment extends ASTNode implements Expression
 
   // We check if the result of the entailment cannot change anymore in the current instant.
   // In this case, we promote unknown to false.
+  // An equality can only be decided when both sides cannot change anymore.
   private Kleene promoteResult(Kleene r, boolean leftReadOnly, boolean rightReadOnly) {
     Kleene promoted = null;
-    if (r == Kleene.UNKNOWN && leftReadOnly && rightReadOnly) {
+    if (isEquality) {
+      if (leftReadOnly && rightReadOnly) {
+        promoted = r == Kleene.UNKNOWN ? Kleene.FALSE : r;
+      }
+    }
+    else if (r == Kleene.UNKNOWN && leftReadOnly && rightReadOnly) {
       promoted = Kleene.FALSE;
     }
     else if (r == Kleene.TRUE && rightReadOnly) {
@@ -83,7 +97,7 @@ public class Entailment extends ASTNode implements Expression
   }
 
   public void canInstant(Layer layer) {
-    this.result = new ExprResult();
+    super.canInstant(layer);
     canInstantArgs(layer, leftVars);
     canInstantArgs(layer, rightVars);
   }
@@ -99,16 +113,6 @@ public class Entailment extends ASTNode implements Expression
     terminateArgs(layer, rightVars);
   }
 
-  public ExprResult execute(Layer layer) {
-    if (result.isSuspended()) {
-      Kleene promoted = execute(layer, "");
-      if (promoted != null) {
-        commit(promoted);
-      }
-    }
-    return result;
-  }
-
   public Kleene execute(Layer layer, String readOnlyHypothesis) {
     ArrayList<Object> args = new ArrayList();
     boolean leftReadOnly = evalArgs(layer, readOnlyHypothesis, leftVars, args);
@@ -117,12 +121,4 @@ public class Entailment extends ASTNode implements Expression
     Kleene promoted = promoteResult(r, leftReadOnly, rightReadOnly);
     return promoted;
   }
-
-  public void commit(Kleene partialRes) {
-    result = new ExprResult(new ES(partialRes));
-  }
-
-  public boolean canWriteOn(String uid) {
-    return false;
-  }
 }
@@ -0,0 +1,48 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+package bonsai.runtime.synchronous.expressions;
+
+import java.util.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.synchronous.interfaces.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.env.*;
+
+// not c
+public class KleeneNot extends Condition
+{
+  private final Condition cond;
+
+  public KleeneNot(Condition cond) {
+    super();
+    this.cond = cond;
+  }
+
+  public KleeneNot copy() {
+    return new KleeneNot(cond.copy());
+  }
+
+  public void canInstant(Layer layer) {
+    super.canInstant(layer);
+    cond.canInstant(layer);
+  }
+
+  public void terminate(Layer layer) {
+    cond.terminate(layer);
+  }
+
+  public Kleene execute(Layer layer, String readOnlyHypothesis) {
+    return toPromoted(Kleene.not(fromPromoted(cond.execute(layer, readOnlyHypothesis))));
+  }
+}
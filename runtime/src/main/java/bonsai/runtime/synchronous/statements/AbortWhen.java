@@ -0,0 +1,261 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package bonsai.runtime.synchronous.statements;
+
+import java.util.*;
+import java.util.function.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.interfaces.*;
+import bonsai.runtime.synchronous.env.*;
+import bonsai.runtime.synchronous.expressions.*;
+import bonsai.runtime.lattices.*;
+
+/// `abort when cond in body end`: the condition is evaluated at the beginning of every instant.
+/// If it is `true`:
+///   - STRONG: the body is aborted and the statement terminates in the current instant.
+///   - WEAK: the body is executed in the current instant, and aborted at the beginning of the next instant.
+
+public class AbortWhen extends ASTNode implements Statement
+{
+  public static int STRONG = 0;
+  public static int WEAK = 1;
+
+  private final Condition cond;
+  private final Statement body;
+  private final int kind;
+
+  private StmtResult res;
+  private ExprResult condResult;
+  // `true` if the condition of a weak abortion was entailed in the previous instant.
+  private boolean preempted;
+
+  public AbortWhen(Condition cond, Statement body, int kind) {
+    super();
+    this.cond = cond;
+    this.body = body;
+    this.kind = kind;
+    if (kind != STRONG && kind != WEAK) {
+      throw new RuntimeException("`kind` must be either `STRONG` or `WEAK`.");
+    }
+    init();
+  }
+
+  public AbortWhen copy() {
+    return new AbortWhen(cond.copy(), body.copy(), kind);
+  }
+
+  private void init() {
+    res = new StmtResult(CompletionCode.WAIT);
+    condResult = new ExprResult();
+    preempted = false;
+  }
+
+  public void prepare() {
+    body.prepare();
+    body.setParent(this);
+    init();
+  }
+
+  private boolean isWeak() {
+    return kind == WEAK;
+  }
+
+  private Kleene condition() {
+    if (condResult.isSuspended()) {
+      return Kleene.UNKNOWN;
+    }
+    else {
+      Object r = condResult.unwrap();
+      if (r instanceof ES) {
+        return ((ES) r).unwrap();
+      }
+      else if (r instanceof Kleene) {
+        return (Kleene) r;
+      }
+      else {
+        throw new RuntimeException("A condition in an `abort` statement has type `" + r.getClass().getName() + "`\n"
+          + "Object value: " + r);
+      }
+    }
+  }
+
+  // in the condition of the current instant.
+  private boolean state1() {
+    return res.k != CompletionCode.TERMINATE && !preempted && condition() == Kleene.UNKNOWN;
+  }
+
+  // the body must be aborted in the current instant.
+  private boolean state2a() {
+    return res.k != CompletionCode.TERMINATE &&
+      (preempted || (!isWeak() && condition() == Kleene.TRUE));
+  }
+
+  // the body is executed in the current instant.
+  private boolean state2b() {
+    return res.k != CompletionCode.TERMINATE && !preempted &&
+      (condition() == Kleene.FALSE || (isWeak() && condition() == Kleene.TRUE));
+  }
+
+  // terminated.
+  private boolean state3() {
+    return res.k == CompletionCode.TERMINATE;
+  }
+
+  public void canInstant(int layersRemaining, Layer layer) {
+    if (layersRemaining == 0) {
+      if (!state3()) {
+        res = new StmtResult(CompletionCode.WAIT);
+        if (!preempted) {
+          condResult = new ExprResult();
+          cond.canInstant(layer);
+        }
+        body.canInstant(layersRemaining, layer);
+      }
+    }
+    else if (state2b()) {
+      body.canInstant(layersRemaining, layer);
+    }
+  }
+
+  public HashSet<String> activeQueues(int layersRemaining) {
+    if (layersRemaining == 0 || state2b()) {
+      return body.activeQueues(layersRemaining);
+    }
+    else {
+      return new HashSet();
+    }
+  }
+
+  public CompletionCode endOfInstant(int layersRemaining, Layer layer) {
+    if (layersRemaining == 0) {
+      checkNonTerminatedEOI("abort", res.k);
+      checkExpressionStateEOI("abort", state1());
+      res.k = body.endOfInstant(layersRemaining, layer);
+      if (isWeak() && condition() == Kleene.TRUE && !state3()) {
+        preempted = true;
+      }
+      return res.k;
+    }
+    else if (state2b()) {
+      return body.endOfInstant(layersRemaining, layer);
+    }
+    else {
+      return res.k;
+    }
+  }
+
+  public boolean canTerminate() {
+    if (state1()) {
+      return !isWeak() || body.canTerminate();
+    }
+    else if (state2b()) {
+      return body.canTerminate();
+    }
+    else {
+      return true;
+    }
+  }
+
+  public void abort(Layer layer) {
+    if (state1()) {
+      cond.terminate(layer);
+    }
+    if (!state3()) {
+      body.abort(layer);
+    }
+  }
+
+  public void suspend(Layer layer) {
+    if (state1()) {
+      cond.terminate(layer);
+    }
+    if (!state3()) {
+      body.suspend(layer);
+    }
+  }
+
+  public StmtResult execute(int layersRemaining, Layer layer) {
+    if (layersRemaining == 0) {
+      executeState1(layer);
+      if (state2a()) {
+        body.abort(layer);
+        res = new StmtResult(CompletionCode.TERMINATE);
+      }
+      else if (state2b()) {
+        res = body.execute(layersRemaining, layer);
+      }
+      return res;
+    }
+    else {
+      checkExpressionStateEOI("abort", state1());
+      if (state2b()) {
+        return body.execute(layersRemaining, layer);
+      }
+      else {
+        return new StmtResult(CompletionCode.TERMINATE);
+      }
+    }
+  }
+
+  private void executeState1(Layer layer) {
+    if (state1()) {
+      condResult = cond.execute(layer);
+      if (!condResult.isSuspended()) {
+        cond.terminate(layer);
+      }
+    }
+  }
+
+  public CanWriteOnResult canWriteOn(int layersRemaining, Layer layer, String uid, boolean inSurface) {
+    if (layersRemaining == 0 && state1()) {
+      if(inSurface) {
+        Kleene k = cond.execute(layer, uid);
+        if (k != null) {
+          switch (k) {
+            case TRUE:
+              layer.subscribeUnblocked(uid, cond, k);
+              if (isWeak()) {
+                return body.canWriteOn(layersRemaining, layer, uid, false);
+              }
+              else {
+                return new CanWriteOnResult(true, false);
+              }
+            case FALSE:
+              layer.subscribeUnblocked(uid, cond, k);
+              return body.canWriteOn(layersRemaining, layer, uid, false);
+            case UNKNOWN: throw new RuntimeException(
+              "[BUG] Condition.execute(layer,uid) returned an unknown result.");
+          }
+        }
+      }
+      CanWriteOnResult bodyRes = body.canWriteOn(layersRemaining, layer, uid, inSurface);
+      if (!isWeak()) {
+        bodyRes.canTerminate = true;
+      }
+      return bodyRes;
+    }
+    else if (state2b()) {
+      return body.canWriteOn(layersRemaining, layer, uid, inSurface);
+    }
+    else {
+      return new CanWriteOnResult(true, false);
+    }
+  }
+
+  public int countLayers() {
+    return body.countLayers();
+  }
+}
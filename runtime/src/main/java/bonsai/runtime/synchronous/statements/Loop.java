@@ -15,6 +15,7 @@
 package bonsai.runtime.synchronous.statements;
 
 import java.util.*;
+import java.util.function.*;
 import bonsai.runtime.core.*;
 import bonsai.runtime.synchronous.*;
 import bonsai.runtime.synchronous.interfaces.*;
@@ -25,15 +26,20 @@ public class Loop extends ASTNode implements Statement
 {
   // We use two bodies: this is only useful when we must "link" the end of the loop with its beginning (when the pause is not at the end, e.g. loop S; pause; S' end).
   // When we reach the end of the loop, we switch between `body` and `surfaceBody`.
+  // The two bodies are two incarnations of the loop, they must not share the variables declared inside the loop.
+  // `incarnation` builds a body where the UIDs of these variables are suffixed by its parameter.
+  // Only two incarnations are alive in an instant (the one terminating and the one starting), so two suffixes are enough.
+  private Function<String, Statement> incarnation;
   private Statement body;
   private Statement surfaceBody;
   private StmtResult res;
   private boolean canSurface;
 
-  public Loop(Statement body) {
+  public Loop(Function<String, Statement> incarnation) {
     super();
-    this.body = body;
-    this.surfaceBody = body.copy();
+    this.incarnation = incarnation;
+    this.body = incarnation.apply("#0");
+    this.surfaceBody = incarnation.apply("#1");
     init();
   }
 
@@ -43,7 +49,7 @@ public class Loop extends ASTNode implements Statement
   }
 
   public Loop copy() {
-    return new Loop(body.copy());
+    return new Loop(incarnation);
   }
 
   public void prepare() {
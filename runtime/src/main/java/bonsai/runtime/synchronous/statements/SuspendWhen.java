@@ -0,0 +1,231 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+package bonsai.runtime.synchronous.statements;
+
+import java.util.*;
+import java.util.function.*;
+import bonsai.runtime.core.*;
+import bonsai.runtime.synchronous.*;
+import bonsai.runtime.synchronous.interfaces.*;
+import bonsai.runtime.synchronous.env.*;
+import bonsai.runtime.synchronous.expressions.*;
+import bonsai.runtime.lattices.*;
+
+/// `suspend when cond in body end`: the condition is evaluated at the beginning of every instant.
+/// If it is `true`, the body is suspended and the statement pauses, otherwise the body is executed.
+
+public class SuspendWhen extends ASTNode implements Statement
+{
+  private final Condition cond;
+  private final Statement body;
+
+  private StmtResult res;
+  private ExprResult condResult;
+
+  public SuspendWhen(Condition cond, Statement body) {
+    super();
+    this.cond = cond;
+    this.body = body;
+    init();
+  }
+
+  public SuspendWhen copy() {
+    return new SuspendWhen(cond.copy(), body.copy());
+  }
+
+  private void init() {
+    res = new StmtResult(CompletionCode.WAIT);
+    condResult = new ExprResult();
+  }
+
+  public void prepare() {
+    body.prepare();
+    body.setParent(this);
+    init();
+  }
+
+  private Kleene condition() {
+    if (condResult.isSuspended()) {
+      return Kleene.UNKNOWN;
+    }
+    else {
+      Object r = condResult.unwrap();
+      if (r instanceof ES) {
+        return ((ES) r).unwrap();
+      }
+      else if (r instanceof Kleene) {
+        return (Kleene) r;
+      }
+      else {
+        throw new RuntimeException("A condition in a `suspend` statement has type `" + r.getClass().getName() + "`\n"
+          + "Object value: " + r);
+      }
+    }
+  }
+
+  // in the condition of the current instant.
+  private boolean state1() {
+    return res.k != CompletionCode.TERMINATE && condition() == Kleene.UNKNOWN;
+  }
+
+  // the body is suspended in the current instant.
+  private boolean state2a() {
+    return res.k != CompletionCode.TERMINATE && condition() == Kleene.TRUE;
+  }
+
+  // the body is executed in the current instant.
+  private boolean state2b() {
+    return res.k != CompletionCode.TERMINATE && condition() == Kleene.FALSE;
+  }
+
+  // terminated.
+  private boolean state3() {
+    return res.k == CompletionCode.TERMINATE;
+  }
+
+  public void canInstant(int layersRemaining, Layer layer) {
+    if (layersRemaining == 0) {
+      if (!state3()) {
+        init();
+        cond.canInstant(layer);
+        body.canInstant(layersRemaining, layer);
+      }
+    }
+    else if (state2b()) {
+      body.canInstant(layersRemaining, layer);
+    }
+  }
+
+  public HashSet<String> activeQueues(int layersRemaining) {
+    if (layersRemaining == 0 || state2b()) {
+      return body.activeQueues(layersRemaining);
+    }
+    else {
+      return new HashSet();
+    }
+  }
+
+  public CompletionCode endOfInstant(int layersRemaining, Layer layer) {
+    if (layersRemaining == 0) {
+      checkNonTerminatedEOI("suspend", res.k);
+      checkExpressionStateEOI("suspend", state1());
+      if (state2b()) {
+        res.k = body.endOfInstant(layersRemaining, layer);
+      }
+      return res.k;
+    }
+    else if (state2b()) {
+      return body.endOfInstant(layersRemaining, layer);
+    }
+    else {
+      return suspendedInSubLayer();
+    }
+  }
+
+  // A suspended body gives the control back to the upper layer.
+  private CompletionCode suspendedInSubLayer() {
+    return CompletionCode.PAUSE_UP;
+  }
+
+  public boolean canTerminate() {
+    if (state1() || state2b()) {
+      return body.canTerminate();
+    }
+    else {
+      return state3();
+    }
+  }
+
+  public void abort(Layer layer) {
+    if (state1()) {
+      cond.terminate(layer);
+    }
+    if (state1() || state2b()) {
+      body.abort(layer);
+    }
+  }
+
+  public void suspend(Layer layer) {
+    if (state1()) {
+      cond.terminate(layer);
+    }
+    if (state1() || state2b()) {
+      body.suspend(layer);
+    }
+  }
+
+  public StmtResult execute(int layersRemaining, Layer layer) {
+    if (layersRemaining == 0) {
+      executeState1(layer);
+      if (state2b()) {
+        res = body.execute(layersRemaining, layer);
+      }
+      return res;
+    }
+    else {
+      checkExpressionStateEOI("suspend", state1());
+      if (state2b()) {
+        return body.execute(layersRemaining, layer);
+      }
+      else {
+        return new StmtResult(suspendedInSubLayer());
+      }
+    }
+  }
+
+  private void executeState1(Layer layer) {
+    if (state1()) {
+      condResult = cond.execute(layer);
+      if (!condResult.isSuspended()) {
+        cond.terminate(layer);
+        if (state2a()) {
+          body.suspend(layer);
+          res = new StmtResult(CompletionCode.PAUSE);
+        }
+      }
+    }
+  }
+
+  public CanWriteOnResult canWriteOn(int layersRemaining, Layer layer, String uid, boolean inSurface) {
+    if (layersRemaining == 0 && state1()) {
+      if(inSurface) {
+        Kleene k = cond.execute(layer, uid);
+        if (k != null) {
+          switch (k) {
+            case TRUE:
+              layer.subscribeUnblocked(uid, cond, k);
+              return new CanWriteOnResult(false, false);
+            case FALSE:
+              layer.subscribeUnblocked(uid, cond, k);
+              return body.canWriteOn(layersRemaining, layer, uid, false);
+            case UNKNOWN: throw new RuntimeException(
+              "[BUG] Condition.execute(layer,uid) returned an unknown result.");
+          }
+        }
+      }
+      return body.canWriteOn(layersRemaining, layer, uid, inSurface);
+    }
+    else if (state2b()) {
+      return body.canWriteOn(layersRemaining, layer, uid, inSurface);
+    }
+    else {
+      return new CanWriteOnResult(state3(), false);
+    }
+  }
+
+  public int countLayers() {
+    return body.countLayers();
+  }
+}
@@ -25,14 +25,14 @@ import bonsai.runtime.lattices.*;
 
 public class WhenElse extends ASTNode implements Statement
 {
-  private final Entailment cond;
+  private final Condition cond;
   private final Statement then;
   private final Statement els;
 
   private StmtResult res;
   private ExprResult condResult;
 
-  public WhenElse(Entailment cond, Statement then, Statement els) {
+  public WhenElse(Condition cond, Statement then, Statement els) {
     super();
     this.cond = cond;
     this.then = then;
@@ -96,7 +96,7 @@ public class WhenElse extends ASTNode implements Statement
     return res.k == CompletionCode.TERMINATE;
   }
 
-  public void canBranchOrDefault(boolean extraCond, Consumer<Entailment> expr, Consumer<Statement> branch) {
+  public void canBranchOrDefault(boolean extraCond, Consumer<Condition> expr, Consumer<Statement> branch) {
     boolean canCond = (extraCond && state1());
     if(canCond) {
       expr.accept(cond);
@@ -208,7 +208,7 @@ public class WhenElse extends ASTNode implements Statement
               layer.subscribeUnblocked(uid, cond, k);
               return els.canWriteOn(layersRemaining, layer, uid, false);
             case UNKNOWN: throw new RuntimeException(
-              "[BUG] Entailment.execute(layer,uid) returned an unknown result.");
+              "[BUG] Condition.execute(layer,uid) returned an unknown result.");
           }
         }
       }
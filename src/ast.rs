@@ -27,15 +27,61 @@ pub use trilean::SKleene;
 #[derive(Clone, Debug)]
 pub enum TestAnnotation {
   Compiler(CompilerTest),
+  /// A `#[label(line, column, "message")]` is a secondary span of the previous compiler test.
+  /// The span is the one of the annotation, to report a label without a previous compiler test.
+  Label(Span, LabelTest),
   Execution(ExecutionTest)
 }
 
+/// Given a test specification `#[error(code, line, column, "message")]`, the compiler must report this diagnostic.
+/// The message is optional, if present it must be a substring of the message reported.
 #[derive(Clone, Debug, PartialEq)]
 pub struct CompilerTest {
   pub level: Level,
   pub code: String,
   pub line: usize,
-  pub column: usize
+  pub column: usize,
+  pub message: Option<String>,
+  pub labels: Vec<LabelTest>
+}
+
+/// A secondary span of a diagnostic, its message is optional and matched as a substring of the label.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelTest {
+  pub line: usize,
+  pub column: usize,
+  pub message: Option<String>
+}
+
+impl LabelTest {
+  pub fn new(line: usize, column: usize, message: Option<String>) -> Self {
+    LabelTest { line, column, message }
+  }
+
+  pub fn matches(&self, obtained: &LabelTest) -> bool {
+    self.line == obtained.line
+    && self.column == obtained.column
+    && message_matches(&self.message, &obtained.message)
+  }
+}
+
+impl Display for LabelTest
+{
+  fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+    fmt.write_fmt(format_args!("label:{}:{}", self.line, self.column))?;
+    if let Some(ref message) = self.message {
+      fmt.write_fmt(format_args!(":\"{}\"", message))?;
+    }
+    Ok(())
+  }
+}
+
+fn message_matches(expected: &Option<String>, obtained: &Option<String>) -> bool {
+  match (expected, obtained) {
+    (&None, _) => true,
+    (&Some(ref expected), &Some(ref obtained)) => obtained.contains(expected.as_str()),
+    (&Some(_), &None) => false
+  }
 }
 
 impl Eq for CompilerTest { }
@@ -61,8 +107,63 @@ impl CompilerTest {
       level: level,
       code: code,
       line: line,
-      column: column
+      column: column,
+      message: None,
+      labels: vec![]
+    }
+  }
+
+  pub fn with_message(mut self, message: Option<String>) -> Self {
+    self.message = message;
+    self
+  }
+
+  /// `self` is the expected diagnostic: the message and labels are only checked if they are specified in the test.
+  pub fn matches(&self, obtained: &CompilerTest) -> bool {
+    self.level == obtained.level
+    && self.code == obtained.code
+    && self.line == obtained.line
+    && self.column == obtained.column
+    && message_matches(&self.message, &obtained.message)
+    && self.labels.iter().all(|label| obtained.labels.iter().any(|o| label.matches(o)))
+  }
+
+  /// Each expected diagnostic must match a distinct obtained one, and the obtained diagnostics must all be expected.
+  /// An obtained diagnostic can match several expectations, so we search a perfect matching between both lists with augmenting paths.
+  pub fn match_all(expected: &[CompilerTest], obtained: &[CompilerTest]) -> bool {
+    if expected.len() != obtained.len() {
+      return false;
+    }
+    // `matched_by[o]` is the expectation currently matched with the obtained diagnostic `o`.
+    let mut matched_by = vec![None; obtained.len()];
+    for e in 0..expected.len() {
+      let mut visited = vec![false; obtained.len()];
+      if !CompilerTest::augment(e, expected, obtained, &mut matched_by, &mut visited) {
+        return false;
+      }
+    }
+    true
+  }
+
+  /// Match the expectation `e` with an obtained diagnostic, possibly by moving a previously matched expectation to another diagnostic.
+  fn augment(e: usize, expected: &[CompilerTest], obtained: &[CompilerTest],
+    matched_by: &mut Vec<Option<usize>>, visited: &mut Vec<bool>) -> bool
+  {
+    for o in 0..obtained.len() {
+      if !visited[o] && expected[e].matches(&obtained[o]) {
+        visited[o] = true;
+        let previous = matched_by[o];
+        let free = match previous {
+          None => true,
+          Some(previous) => CompilerTest::augment(previous, expected, obtained, matched_by, visited)
+        };
+        if free {
+          matched_by[o] = Some(e);
+          return true;
+        }
+      }
     }
+    false
   }
 
   fn from_string_level(level: String) -> Level {
@@ -81,7 +182,14 @@ impl Display for CompilerTest
 {
   fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
     fmt.write_fmt(format_args!("{}:{}:{}:{}", self.level,
-      self.code, self.line, self.column))
+      self.code, self.line, self.column))?;
+    if let Some(ref message) = self.message {
+      fmt.write_fmt(format_args!(":\"{}\"", message))?;
+    }
+    for label in &self.labels {
+      fmt.write_fmt(format_args!(" ({})", label))?;
+    }
+    Ok(())
   }
 }
 
@@ -161,7 +269,8 @@ impl Module<JClass> {
         Item::Field(field) => module.fields.push(field),
         Item::Proc(process) => module.processes.push(process),
         Item::JavaMethod(decl) => module.host.java_methods.push(decl),
-        Item::JavaConstructor(decl) => module.host.java_constructors.push(decl)
+        Item::JavaConstructor(decl) => module.host.java_constructors.push(decl),
+        Item::SyntaxError(_) => unreachable!("[BUG] Syntax errors are removed in the front-end (see `front::parse_program`).")
       }
     }
     module
@@ -228,20 +337,23 @@ pub enum Item {
   Proc(Process),
   JavaMethod(JMethod),
   JavaConstructor(JConstructor),
+  /// An item that could not be parsed, it is reported by the front-end.
+  SyntaxError(Span),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Process {
   pub visibility: JVisibility,
   pub name: Ident,
-  pub params: JParameters,
+  /// Parameters are passed by reference, their uids are resolved at the call site.
+  pub params: Vec<Binding>,
   pub body: Stmt,
   pub span: Span
 }
 
 impl Process {
   pub fn new(span: Span, visibility: Option<JVisibility>, name: Ident,
-   params: JParameters, body: Stmt) -> Self
+   params: Vec<Binding>, body: Stmt) -> Self
   {
     Process {
       visibility: visibility.unwrap_or(JVisibility::Private),
@@ -297,7 +409,7 @@ pub enum StmtKind {
   Let(LetStmt),
   When(Expr, Box<Stmt>, Box<Stmt>),
   Suspend(SuspendStmt),
-  Abort(Expr, Box<Stmt>),
+  Abort(AbortStmt),
   Tell(Variable, Expr),
   DelayStmt(Delay),
   Loop(Box<Stmt>),
@@ -306,6 +418,8 @@ pub enum StmtKind {
   QFUniverse(Box<Stmt>), // Queue-free universe
   Universe(Variable, Box<Stmt>), // Queue-free universe
   Nothing,
+  /// A statement that could not be parsed, it is reported by the front-end.
+  SyntaxError,
 }
 
 impl StmtKind {
@@ -347,6 +461,32 @@ impl SuspendStmt {
   }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AbortStmt {
+  pub condition: Expr,
+  pub body: Box<Stmt>,
+  pub kind: AbortKind,
+  pub state_num: usize,
+}
+
+impl AbortStmt {
+  pub fn new(condition: Expr, body: Box<Stmt>, kind: AbortKind) -> Self {
+    AbortStmt { condition, body, kind, state_num: 0 }
+  }
+
+  pub fn is_weak(&self) -> bool {
+    self.kind == AbortKind::Weak
+  }
+}
+
+/// A strong abortion (`abort when e in p end`) terminates the statement at the beginning of the instant in which `e` holds, without executing `p`.
+/// A weak abortion (`weak abort when e in p end`) executes `p` in this instant and terminates the statement at the beginning of the next instant.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AbortKind {
+  Strong,
+  Weak
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct LetStmt {
   pub binding: Binding,
@@ -1042,3 +1182,38 @@ impl Display for JVisibility {
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn diagnostic(message: &str, labels: Vec<LabelTest>) -> CompilerTest {
+    let mut test = CompilerTest::new(format!("error"), format!("E0001"), 10, 2)
+      .with_message(Some(format!("{}", message)));
+    test.labels = labels;
+    test
+  }
+
+  fn label() -> LabelTest {
+    LabelTest::new(12, 4, None)
+  }
+
+  #[test]
+  fn match_all_moves_previous_matches() {
+    // The first expectation matches both diagnostics, but only the first diagnostic has the label of the second expectation.
+    let expected = vec![diagnostic("undeclared", vec![]), diagnostic("undeclared", vec![label()])];
+    let obtained = vec![diagnostic("undeclared variable", vec![label()]), diagnostic("undeclared", vec![])];
+    assert!(CompilerTest::match_all(&expected, &obtained));
+    let reversed: Vec<CompilerTest> = obtained.into_iter().rev().collect();
+    assert!(CompilerTest::match_all(&expected, &reversed));
+  }
+
+  #[test]
+  fn match_all_requires_distinct_diagnostics() {
+    let expected = vec![diagnostic("undeclared", vec![label()]), diagnostic("undeclared", vec![label()])];
+    let obtained = vec![diagnostic("undeclared", vec![label()]), diagnostic("undeclared", vec![])];
+    assert!(!CompilerTest::match_all(&expected, &obtained));
+    assert!(!CompilerTest::match_all(&expected[..1], &obtained));
+    assert!(!CompilerTest::match_all(&expected, &obtained[..1]));
+  }
+}
@@ -37,6 +37,14 @@ impl CodeFormatter {
     self.indent -= 2;
   }
 
+  pub fn indent_by(&mut self, n: usize) {
+    self.indent += n;
+  }
+
+  pub fn unindent_by(&mut self, n: usize) {
+    self.indent -= n;
+  }
+
   pub fn open_block(&mut self) {
     self.push_line("{");
     self.indent();
@@ -79,10 +87,22 @@ impl CodeFormatter {
     }
   }
 
+  /// Appends `code` to the current line without indenting it, for code printed verbatim.
+  pub fn push_raw(&mut self, code: &str) {
+    self.code += code;
+  }
+
   pub fn newline(&mut self) {
     self.code += "\n";
   }
 
+  /// Adds an empty line after the current line, unless there is already one.
+  pub fn blank_line(&mut self) {
+    if self.code.ends_with("\n") && !self.code.ends_with("\n\n") {
+      self.newline();
+    }
+  }
+
   fn push_indent(&mut self) {
     for _ in 0..self.indent {
       self.code.push(' ');
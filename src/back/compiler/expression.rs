@@ -32,6 +32,12 @@ pub fn compile_closure(session: &Session, context: &Context, fmt: &mut CodeForma
   ExpressionCompiler::new(session, context, fmt).closure(expr, return_expr, None)
 }
 
+/// Compile a condition of a statement (e.g. `when`) into a tree of `Condition` objects.
+/// The Kleene operators `and`, `or` and `not` are evaluated at runtime on the results of the entailments.
+pub fn compile_condition(session: &Session, context: &Context, fmt: &mut CodeFormatter, cond: Expr) {
+  ExpressionCompiler::new(session, context, fmt).condition(cond)
+}
+
 pub fn compile_var_uid(session: &Session, context: &Context, fmt: &mut CodeFormatter, var: Variable) {
   ExpressionCompiler::new(session, context, fmt).var_uid(var)
 }
@@ -39,6 +45,8 @@ pub fn compile_var_uid(session: &Session, context: &Context, fmt: &mut CodeForma
 static CLOSURE_ARGS: &str = "__args";
 static LOCAL_UID_FN: &str = "__proc_uid.apply";
 pub static FIELD_UID_PREFIX: &str = "__uid_";
+pub static PARAM_UID_PREFIX: &str = "__param_uid_";
+pub static INCARNATION_PREFIX: &str = "__incarnation";
 
 struct ExpressionCompiler<'a> {
   _session: &'a Session,
@@ -75,12 +83,64 @@ impl<'a> ExpressionCompiler<'a>
       Bottom => self.bottom(ty),
       Top => self.top(ty),
       Entailment(rel) => self.entailment(*rel, vars),
-      Or(_, _) =>  unimplemented!("trilean or is unimplemented"),
-      And(_, _) => unimplemented!("trilean and is unimplemented"),
-      Not(_) => unimplemented!("trilean not is unimplemented"),
+      Or(e1, e2) => self.kleene_bin_op("or", *e1, *e2, vars),
+      And(e1, e2) => self.kleene_bin_op("and", *e1, *e2, vars),
+      Not(e) => self.kleene_not(*e, vars),
     }
   }
 
+  fn condition(&mut self, cond: Expr) {
+    use ast::ExprKind::*;
+    match cond.node.clone() {
+      Or(e1, e2) => self.condition_bin_op("KleeneOr", *e1, *e2),
+      And(e1, e2) => self.condition_bin_op("KleeneAnd", *e1, *e2),
+      Not(e) => {
+        self.fmt.push("new KleeneNot(");
+        self.condition(*e);
+        self.fmt.push(")");
+      }
+      Entailment(rel) => self.entailment_expr(*rel),
+      _ => {
+        // transform x to x |= true, which is monotonic in `x` as expected by the causality analysis.
+        let rel = EntailmentRel {
+          left: cond.clone(),
+          right: Expr::new(DUMMY_SP, Trilean(SKleene::True)),
+          op: EntailmentKind::Entailment
+        };
+        self.entailment_expr(rel)
+      }
+    }
+  }
+
+  fn condition_bin_op(&mut self, class: &str, left: Expr, right: Expr) {
+    self.fmt.push(&format!("new {}(", class));
+    self.condition(left);
+    self.fmt.push(", ");
+    self.condition(right);
+    self.fmt.push(")");
+  }
+
+  /// Inside a closure, Kleene operators are directly evaluated on the values of their operands.
+  fn kleene_bin_op(&mut self, op: &str, left: Expr, right: Expr, vars: &Vec<Variable>) {
+    self.fmt.push(&format!("new ES(Kleene.{}(", op));
+    self.kleene_operand(op, left, vars);
+    self.fmt.push(", ");
+    self.kleene_operand(op, right, vars);
+    self.fmt.push("))");
+  }
+
+  fn kleene_not(&mut self, expr: Expr, vars: &Vec<Variable>) {
+    self.fmt.push("new ES(Kleene.not(");
+    self.kleene_operand("not", expr, vars);
+    self.fmt.push("))");
+  }
+
+  fn kleene_operand(&mut self, op: &str, expr: Expr, vars: &Vec<Variable>) {
+    self.fmt.push(&format!("Cast.toKleene(\"<operand of {}>\", ", op));
+    self.compile(expr, vars, None);
+    self.fmt.push(")");
+  }
+
   fn variable(&mut self, var: Variable, vars: &Vec<Variable>) {
     let v = vars.iter().enumerate().find(|&(_,v)| v.last_uid() == var.last_uid());
     match v {
@@ -148,9 +208,13 @@ impl<'a> ExpressionCompiler<'a>
 
   fn var_uid(&mut self, var: Variable) {
     let var_info = self.context.var_by_uid(var.first_uid());
+    // Parameter of a process, its UID is given by the caller.
+    if var_info.is_param && var.len() == 1 {
+      self.fmt.push(&format!("{}{}", PARAM_UID_PREFIX, var.first()));
+    }
     // Variable local to a process.
-    if !var_info.is_field() && var.len() == 1 {
-      self.local_var(var.first());
+    else if !var_info.is_field() && var.len() == 1 {
+      self.local_var(var.first(), var_info.loop_depth);
     }
     // Variable local to a module.
     else {
@@ -168,8 +232,13 @@ impl<'a> ExpressionCompiler<'a>
     }
   }
 
-  fn local_var(&mut self, var_name: Ident) {
-    self.fmt.push(&format!("{}(\"{}\")", LOCAL_UID_FN, var_name));
+  /// The UID of a variable declared inside loops is suffixed by the incarnation of each enclosing loop (see `loop_stmt` in `statement.rs`).
+  fn local_var(&mut self, var_name: Ident, loop_depth: usize) {
+    self.fmt.push(&format!("{}(\"{}\"", LOCAL_UID_FN, var_name));
+    for depth in 1..(loop_depth+1) {
+      self.fmt.push(&format!(" + {}{}", INCARNATION_PREFIX, depth));
+    }
+    self.fmt.push(")");
   }
 
   /// Collect all the variables appearing in `expr` and insert them in `variables`.
@@ -261,14 +330,14 @@ impl<'a> ExpressionCompiler<'a>
   }
 
   fn entailment(&mut self, rel: EntailmentRel, vars: &Vec<Variable>) {
-    match rel.op {
-      EntailmentKind::StrictEntailment => { panic!("|< is not yet implemented."); }
-      // EntailmentKind::Equality => { panic!("== is not yet implemented."); }
-      _ => ()
+    let method = match rel.op {
+      EntailmentKind::Entailment => "entails",
+      EntailmentKind::StrictEntailment => "strict_entail",
+      EntailmentKind::Equality => "equals_lattice"
     };
     self.fmt.push("Cast.toLattice(\"<expr in entailment relation>\",");
     self.compile(rel.left, vars, None);
-    self.fmt.push(").entails(");
+    self.fmt.push(&format!(").{}(", method));
     self.compile(rel.right, vars, None);
     self.fmt.push(")");
   }
@@ -285,8 +354,10 @@ impl<'a> ExpressionCompiler<'a>
     let mut vars = vars_left;
     vars.extend(vars_right.into_iter());
     self.fmt.push(&format!(", ({}) -> ", CLOSURE_ARGS));
+    // The equality is not monotonic in any of its sides, the runtime must know it to decide when its result is stable.
+    let is_equality = rel.op == EntailmentKind::Equality;
     self.entailment(rel, &vars);
-    self.fmt.push(")");
+    self.fmt.push(&format!(", {})", is_equality));
   }
 
   fn bottom(&mut self, ty: Option<JType>) {
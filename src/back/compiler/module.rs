@@ -196,7 +196,10 @@ impl<'a> ModuleCompiler<'a>
       field.binding.name.unwrap()
     ].iter().flat_map(|x| x.chars()).collect();
     self.fmt.push(&code);
-    if let Some(expr) = field.binding.expr {
+    if field.binding.is_module() {
+      self.module_field_init(field.binding);
+    }
+    else if let Some(expr) = field.binding.expr {
       self.fmt.push(" = ");
       if expr.node == ExprKind::Bottom {
         self.fmt.push(&format!("new {}()", field.binding.ty.name));
@@ -208,6 +211,31 @@ impl<'a> ModuleCompiler<'a>
     self.fmt.terminate_line(";");
   }
 
+  // A module field is always created with the empty Java constructor, the constructors of the module being compiled to `__construct` (see `java_constructor`).
+  // For example: `module T m = new T(a, b)` is compiled to `T m = (T) new T().__construct(a, b)`.
+  // The UIDs of its `ref` fields are bound to the ones of `a` and `b` in `__init`.
+  fn module_field_init(&mut self, binding: Binding) {
+    let ty = binding.ty;
+    match binding.expr {
+      Some(Expr { node: ExprKind::NewInstance(new_instance), .. }) => {
+        self.fmt.push(&format!(" = ({}) new {}().__construct(", ty, ty));
+        let n = new_instance.args.len();
+        for (i, arg) in new_instance.args.into_iter().enumerate() {
+          compile_expression(self.session, self.context, &mut self.fmt, arg);
+          if i != n - 1 {
+            self.fmt.push(", ");
+          }
+        }
+        self.fmt.push(")");
+      }
+      Some(expr) => {
+        self.fmt.push(" = ");
+        compile_expression(self.session, self.context, &mut self.fmt, expr);
+      }
+      None => self.fmt.push(&format!(" = new {}()", ty))
+    }
+  }
+
   // We generate a field `String __uid_<name_field>` to store the `uid` of the fields.
   fn field_uid(&mut self, field: ModuleField) {
     if !field.binding.is_module() {
@@ -257,10 +285,7 @@ impl<'a> ModuleCompiler<'a>
     self.fmt.open_block();
     self.fmt.push_line("this.__object_instance = ++this.__num_instances;");
     for field in module.fields.clone() {
-      if field.binding.is_module() {
-        unimplemented!("fields of type `module` are not supported yet.");
-      }
-      else {
+      if !field.binding.is_module() {
         let field_name = field.binding.name.clone();
         let uid = self.fuid(&field);
         let uid_assignment = format!("this.{} = {}(\"{}\");", uid, MODULE_UID_FN, field_name);
@@ -277,9 +302,39 @@ impl<'a> ModuleCompiler<'a>
         }
       }
     }
+    // The UIDs of the fields of a module field are registered by its own `__init` method.
+    // It is called last because its `ref` fields are bound to the UIDs of the fields of this module.
+    for field in module.fields.clone() {
+      if field.binding.is_module() {
+        self.module_field_uid_init(field.binding);
+      }
+    }
     self.fmt.close_block();
   }
 
+  // Without initializer, the `ref` fields of the module field are treated as normal fields (see `__init()`).
+  fn module_field_uid_init(&mut self, binding: Binding) {
+    self.fmt.push(&format!("this.{}.__init(", binding.name));
+    let mod_info = self.context.module_by_name(self.mod_name.clone());
+    if let Some(refs) = mod_info.module_field_refs(binding.uid) {
+      let target = self.context.ast
+        .find_mod_by_name(&binding.ty.name)
+        .expect(&format!("module {} undeclared", binding.ty.name));
+      let ref_fields = target.ref_fields();
+      let n = ref_fields.len();
+      for (i, field) in ref_fields.into_iter().enumerate() {
+        match refs.instantiated_refs.get(&field.binding.uid) {
+          Some(var) => self.fmt.push(&format!("this.{}{}", FIELD_UID_PREFIX, var.last())),
+          None => self.fmt.push("null")
+        }
+        if i != n - 1 {
+          self.fmt.push(", ");
+        }
+      }
+    }
+    self.fmt.terminate_line(");");
+  }
+
   fn runtime_object_uid(&mut self, module: &JModule) {
     self.fmt.push_line("private static int __num_instances = -1;");
     self.fmt.push_line("private int __object_instance;");
@@ -309,7 +364,18 @@ impl<'a> ModuleCompiler<'a>
   fn compile_wrapped_fields(&mut self, module: &JModule, is_ref: bool, body: &str) {
     let mut num_fields = 0;
     for field in module.fields.clone() {
-      if !field.binding.is_module() && field.is_ref.is_some() == is_ref {
+      // The fields of a module field are declared by its own `__wrap_process` method.
+      // Its `ref` fields are bound to fields of this module, or treated as normal fields if it is not initialized (as in the root module).
+      if field.binding.is_module() {
+        if !is_ref {
+          let has_refs = self.context.module_by_name(field.binding.ty.name.clone()).has_refs();
+          let declare_refs = has_refs && field.binding.expr.is_none();
+          self.fmt.push_line(&format!("this.{}.__wrap_process({}, ", field.binding.name, declare_refs));
+          self.fmt.indent();
+          num_fields += 1;
+        }
+      }
+      else if field.is_ref.is_some() == is_ref {
         let mut field_binding = field.binding.clone();
         // If it is a `ref` field, we do not want to initialize it with `new T()` (`T` being the type of the field), because it might be an abstract class.
         // Moreover, it is already initialized by the constructor of the current object.
@@ -370,9 +436,7 @@ impl<'a> ModuleCompiler<'a>
     self.fmt.push_line(&format!("static int {} = -1;", proc_instance));
     self.fmt.push(&format!(
       "{} Statement {}(", process.visibility, process.name));
-    if process.params.len() > 0 {
-      unimplemented!("process arguments are not supported yet.");
-    }
+    self.proc_params(&process);
     self.fmt.push(")");
     self.fmt.open_block();
     self.proc_uid(&process, proc_instance);
@@ -387,6 +451,17 @@ impl<'a> ModuleCompiler<'a>
     self.fmt.newline();
   }
 
+  // Parameters are passed by reference: we receive the UIDs of the arguments.
+  fn proc_params(&mut self, process: &Process) {
+    let len = process.params.len();
+    for (i, param) in process.params.iter().enumerate() {
+      self.fmt.push(&format!("String {}{}", PARAM_UID_PREFIX, param.name));
+      if i != len - 1 {
+        self.fmt.push(", ");
+      }
+    }
+  }
+
   fn proc_uid(&mut self, process: &Process, proc_instance: String) {
     self.fmt.push_line(&format!("{}++;", proc_instance));
     // Avoid the capture of the static variable `__proc_{}_instance` in the closure `__proc_uid`: we need its current value.
@@ -397,30 +472,14 @@ impl<'a> ModuleCompiler<'a>
       process.name));
   }
 
+  /// The modules declared inside a loop are created by the loop itself (see `loop_stmt` in `statement.rs`).
   fn proc_local_modules(&mut self, process: &Process) {
     let proc_uid = ProcessUID::new(self.mod_name.clone(), process.name.clone());
     let process_info = self.context.process_by_uid(proc_uid);
     for module_decl in process_info.local_module_vars {
-      let var_info = self.context.var_by_uid(module_decl.target);
-      let ty = var_info.ty.clone();
-      let name = var_info.name.clone();
-      self.fmt.push_line(&format!("{} {} = new {}();",
-        ty.clone(), name.clone(), ty.clone()));
-      self.fmt.push(&format!("{}.__init(", name));
-      let target_module = self.context.ast
-        .find_mod_by_name(&ty.name)
-        .expect(&format!("module {} undeclared", ty.name));
-      let mut i = 0;
-      let n = target_module.ref_fields().len();
-      for field in target_module.ref_fields() {
-        let var = module_decl.find_var_by_field_uid(field.binding.uid);
-        compile_var_uid(self.session, self.context, &mut self.fmt, var);
-        if i < (n-1) {
-          self.fmt.push(",");
-        }
-        i += 1;
+      if self.context.var_by_uid(module_decl.target).loop_depth == 0 {
+        compile_local_module(self.session, self.context, &mut self.fmt, module_decl);
       }
-      self.fmt.terminate_line(");");
     }
   }
 }
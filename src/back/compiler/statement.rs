@@ -17,7 +17,6 @@ use session::*;
 use back::code_formatter::*;
 use back::free_variables::*;
 use back::compiler::expression::*;
-use trilean::SKleene;
 
 pub fn compile_statement(session: &Session, context: &Context, fmt: &mut CodeFormatter, proc_uid: ProcessUID, stmt: Stmt) {
   StatementCompiler::new(session, context, proc_uid, fmt).compile(stmt)
@@ -29,18 +28,45 @@ pub fn compile_field(session: &Session, context: &Context, fmt: &mut CodeFormatt
   StatementCompiler::new(session, context, proc_uid, fmt).open_decl(binding, true)
 }
 
+// We create the Java object of a module declared in a process, and initialize its `ref` fields.
+pub fn compile_local_module(session: &Session, context: &Context, fmt: &mut CodeFormatter, module_decl: LocalModuleVarInfo) {
+  let var_info = context.var_by_uid(module_decl.target);
+  let ty = var_info.ty.clone();
+  let name = var_info.name.clone();
+  fmt.push_line(&format!("{} {} = new {}();",
+    ty.clone(), name.clone(), ty.clone()));
+  fmt.push(&format!("{}.__init(", name));
+  let target_module = context.ast
+    .find_mod_by_name(&ty.name)
+    .expect(&format!("module {} undeclared", ty.name));
+  let mut i = 0;
+  let n = target_module.ref_fields().len();
+  for field in target_module.ref_fields() {
+    let var = module_decl.find_var_by_field_uid(field.binding.uid);
+    compile_var_uid(session, context, fmt, var);
+    if i < (n-1) {
+      fmt.push(",");
+    }
+    i += 1;
+  }
+  fmt.terminate_line(");");
+}
+
 struct StatementCompiler<'a> {
   session: &'a Session,
   context: &'a Context,
   proc_uid: ProcessUID,
-  fmt: &'a mut CodeFormatter
+  fmt: &'a mut CodeFormatter,
+  /// Number of loops enclosing the statement being compiled.
+  loops: usize
 }
 
 impl<'a> StatementCompiler<'a>
 {
   pub fn new(session: &'a Session, context: &'a Context, proc_uid: ProcessUID, fmt: &'a mut CodeFormatter) -> Self {
     StatementCompiler {
-      session, context, proc_uid, fmt
+      session, context, proc_uid, fmt,
+      loops: 0
     }
   }
 
@@ -62,7 +88,8 @@ impl<'a> StatementCompiler<'a>
       AndPar(branches) => self.and_parallel(branches),
       Loop(body) => self.loop_stmt(body),
       ProcCall(target, process, args) => self.process_call(target, process, args),
-      // Suspend(entailment, body) => self.suspend(entailment, body),
+      Suspend(suspend) => self.suspend(suspend),
+      Abort(abort) => self.abort(abort),
       // ModuleCall(run_expr) => self.module_call(run_expr),
       stmt => unimplemented!("statement unimplemented: {:?}.", stmt)
     }
@@ -257,6 +284,7 @@ impl<'a> StatementCompiler<'a>
     self.fmt.unindent();
     self.compile(*branch);
     self.fmt.push(")");
+    self.fmt.unindent();
   }
 
   fn prune(&mut self) {
@@ -281,19 +309,8 @@ impl<'a> StatementCompiler<'a>
     self.fmt.push(")");
   }
 
-  fn condition(&mut self, mut cond: Expr) {
-    let rel = match cond.node.clone() {
-      ExprKind::Entailment(rel) => rel,
-      _ =>
-        // transform x to x == true
-        Box::new(EntailmentRel {
-          left: cond.clone(),
-          right: Expr::new(DUMMY_SP, ExprKind::Trilean(SKleene::True)),
-          op: EntailmentKind::Equality
-        })
-    };
-    cond.node = ExprKind::Entailment(rel);
-    compile_functional_expr(self.session, self.context, self.fmt, cond, None);
+  fn condition(&mut self, cond: Expr) {
+    compile_condition(self.session, self.context, self.fmt, cond);
   }
 
   fn when(&mut self, cond: Expr, then: Box<Stmt>, els: Box<Stmt>) {
@@ -326,12 +343,32 @@ impl<'a> StatementCompiler<'a>
       Some("LayeredParallel.DISJUNCTIVE_PAR"));
   }
 
+  /// The body of the loop is a function building an incarnation of the loop.
+  /// Its parameter is appended to the UIDs of the variables declared in the body, and the modules declared in the body are created for each incarnation.
   fn loop_stmt(&mut self, body: Box<Stmt>) {
-    self.fmt.push_line("new Loop(");
+    self.loops += 1;
+    self.fmt.push_line(&format!("new Loop((String {}{}) -> {{", INCARNATION_PREFIX, self.loops));
+    self.fmt.indent();
+    self.loop_local_modules(&body);
+    self.fmt.push_line("return");
     self.fmt.indent();
     self.compile(*body);
-    self.fmt.push(")");
+    self.fmt.terminate_line(";");
     self.fmt.unindent();
+    self.fmt.unindent();
+    self.fmt.push("})");
+    self.loops -= 1;
+  }
+
+  fn loop_local_modules(&mut self, body: &Stmt) {
+    let mut modules = LoopLocalModules::new();
+    modules.visit_stmt(body.clone());
+    let process_info = self.context.process_by_uid(self.proc_uid.clone());
+    for module_decl in process_info.local_module_vars {
+      if modules.uids.contains(&module_decl.target) {
+        compile_local_module(self.session, self.context, self.fmt, module_decl);
+      }
+    }
   }
 
   fn process_call(&mut self, target: Option<Variable>, name: Ident, args: Vec<Variable>) {
@@ -339,19 +376,60 @@ impl<'a> StatementCompiler<'a>
       self.fmt.push(&format!("{}.", target.path));
     }
     self.fmt.push(&format!("{}(", name));
-    if args.len() > 0 {
-      unimplemented!("process call with arguments is not yet supported.");
+    // Arguments are passed by reference, hence we only give their UIDs.
+    let len = args.len();
+    for (i, arg) in args.into_iter().enumerate() {
+      compile_var_uid(self.session, self.context, self.fmt, arg);
+      if i != len - 1 {
+        self.fmt.push(", ");
+      }
     }
     self.fmt.push(")");
   }
 
-  // fn suspend(&mut self, condition: Condition, body: Box<Stmt>) {
-  //   self.fmt.push("new SuspendWhen(");
-  //   self.condition(condition);
-  //   self.fmt.terminate_line(",");
-  //   self.fmt.indent();
-  //   self.compile(*body);
-  //   self.fmt.push(")");
-  //   self.fmt.unindent();
-  // }
+  fn suspend(&mut self, suspend: SuspendStmt) {
+    self.fmt.push("new SuspendWhen(");
+    self.fmt.indent();
+    self.condition(suspend.condition);
+    self.fmt.terminate_line(",");
+    self.compile(*suspend.body);
+    self.fmt.push(")");
+    self.fmt.unindent();
+  }
+
+  fn abort(&mut self, abort: AbortStmt) {
+    let kind = match abort.kind {
+      AbortKind::Strong => "AbortWhen.STRONG",
+      AbortKind::Weak => "AbortWhen.WEAK"
+    };
+    self.fmt.push("new AbortWhen(");
+    self.fmt.indent();
+    self.condition(abort.condition);
+    self.fmt.terminate_line(",");
+    self.compile(*abort.body);
+    self.fmt.push(&format!(", {})", kind));
+    self.fmt.unindent();
+  }
+}
+
+/// Collect the modules declared in the body of a loop, but not in its nested loops.
+struct LoopLocalModules {
+  uids: Vec<usize>
+}
+
+impl LoopLocalModules {
+  pub fn new() -> Self {
+    LoopLocalModules { uids: vec![] }
+  }
+}
+
+impl Visitor<JClass> for LoopLocalModules
+{
+  fn visit_loop(&mut self, _child: Stmt) {}
+
+  fn visit_binding(&mut self, binding: Binding) {
+    if binding.is_module() {
+      self.uids.push(binding.uid);
+    }
+  }
 }
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-mod code_formatter;
+pub mod code_formatter;
 mod compiler;
-mod free_variables;
+pub mod free_variables;
 
 pub use back::compiler::module::compile_module;
@@ -28,6 +28,15 @@ pub struct VarInfo {
   /// This is useful to compute the size of the stream.
   /// For example: `pre pre x` gives `[x: 2]`.
   pub stream_bound: usize,
+  /// `true` if the variable is a parameter of a process.
+  /// Its UID is not allocated by the process but received from the caller.
+  pub is_param: bool,
+  /// Number of `loop` statements enclosing the declaration of a local variable.
+  /// Every incarnation of a loop body has its own copy of the variables declared inside (see `Loop.java` in the runtime).
+  pub loop_depth: usize,
+  /// The UID of the variable declared in the source code if this variable is another incarnation of it.
+  /// These incarnations are only created during the causality analysis (see `reincarnate` in `symbolic_execution.rs`).
+  pub incarnation_of: Option<usize>,
 }
 
 impl VarInfo {
@@ -37,7 +46,10 @@ impl VarInfo {
       kind: kind,
       ty: ty,
       field: field,
-      stream_bound: 0
+      stream_bound: 0,
+      is_param: false,
+      loop_depth: 0,
+      incarnation_of: None
     }
   }
 
@@ -45,6 +57,12 @@ impl VarInfo {
     VarInfo::new(name, kind, ty, None)
   }
 
+  pub fn param(name: Ident, kind: Kind, ty: JType) -> Self {
+    let mut info = VarInfo::local(name, kind, ty);
+    info.is_param = true;
+    info
+  }
+
   pub fn field(name: Ident, kind: Kind, ty: JType,
     visibility: JVisibility, is_ref: Option<Span>) -> Self
   {
@@ -95,6 +113,8 @@ pub struct ModuleInfo {
   /// Contains the position and UID of the `ref` variables of this module.
   pub constructor: Vec<(usize, usize)>,
   pub cons_len: usize,
+  /// The module fields initialized with `new`, their `ref` fields are bound to fields of this module.
+  pub module_fields: Vec<LocalModuleVarInfo>
 }
 
 impl ModuleInfo {
@@ -102,13 +122,19 @@ impl ModuleInfo {
     ModuleInfo {
       name: name,
       constructor: vec![],
-      cons_len: 0
+      cons_len: 0,
+      module_fields: vec![]
     }
   }
 
   pub fn has_refs(&self) -> bool {
     !self.constructor.is_empty()
   }
+
+  /// The variables bound to the `ref` fields of the module field `target`, `None` if it is not initialized with `new`.
+  pub fn module_field_refs<'a>(&'a self, target: usize) -> Option<&'a LocalModuleVarInfo> {
+    self.module_fields.iter().find(|m| m.target == target)
+  }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -219,6 +245,18 @@ impl Context {
     self.alloc_var(binding, info)
   }
 
+  /// Allocate a new incarnation of the variable declared by `binding`, which keeps the information of the original variable.
+  pub fn alloc_incarnation(&mut self, binding: &mut Binding) -> usize {
+    let mut info = self.var_by_uid(binding.uid);
+    info.incarnation_of = Some(self.source_uid(binding.uid));
+    self.alloc_var(binding, info)
+  }
+
+  pub fn alloc_param(&mut self, binding: &mut Binding) -> usize {
+    let info = VarInfo::param(binding.name.clone(), binding.kind, binding.ty.clone());
+    self.alloc_var(binding, info)
+  }
+
   pub fn alloc_field(&mut self, field: &mut ModuleField) -> usize {
     let info = VarInfo::field(field.binding.name.clone(),
       field.binding.kind, field.binding.ty.clone(), field.visibility, field.is_ref);
@@ -230,6 +268,11 @@ impl Context {
     self.vars[uid].clone()
   }
 
+  /// The UID of the variable as declared in the source code.
+  pub fn source_uid(&self, uid: usize) -> usize {
+    self.var_by_uid(uid).incarnation_of.unwrap_or(uid)
+  }
+
   pub fn var_by_uid_mut<'b>(&'b mut self, uid: usize) -> &'b mut VarInfo {
     assert!(self.vars.len() > uid, "var_by_uid_mut: Variable not declared.");
     &mut self.vars[uid]
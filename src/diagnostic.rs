@@ -0,0 +1,125 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Diagnostics as values, independently of how they are printed.
+/// They are returned by `libbonsai::compile` (collected with `CollectEmitter`), and printed by `JsonEmitter`.
+///
+/// Lines and columns start at 1, the end column is exclusive.
+
+use syntex_syntax::codemap::{Pos, CodeMap};
+use syntex_errors::{DiagnosticBuilder, SubDiagnostic};
+use syntex_errors::emitter::Emitter;
+use syntex_pos::{MultiSpan, SpanLabel};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic
+{
+  /// `error`, `warning`, `note` or `help`.
+  pub level: String,
+  pub code: Option<String>,
+  pub message: String,
+  pub spans: Vec<DiagnosticSpan>,
+  /// The notes and helps attached to this diagnostic.
+  pub children: Vec<Diagnostic>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiagnosticSpan
+{
+  pub file: String,
+  pub line_start: usize,
+  pub column_start: usize,
+  pub line_end: usize,
+  pub column_end: usize,
+  pub is_primary: bool,
+  pub label: Option<String>
+}
+
+impl Diagnostic
+{
+  pub fn new(db: &DiagnosticBuilder, codemap: &CodeMap) -> Self {
+    Diagnostic {
+      level: format!("{}", db.level),
+      code: db.code.clone(),
+      message: db.message.clone(),
+      spans: Diagnostic::spans(&db.span, codemap),
+      children: db.children.iter()
+        .map(|child| Diagnostic::sub_diagnostic(child, codemap))
+        .collect()
+    }
+  }
+
+  fn sub_diagnostic(sub: &SubDiagnostic, codemap: &CodeMap) -> Self {
+    Diagnostic {
+      level: format!("{}", sub.level),
+      code: None,
+      message: sub.message.clone(),
+      spans: Diagnostic::spans(&sub.span, codemap),
+      children: vec![]
+    }
+  }
+
+  fn spans(span: &MultiSpan, codemap: &CodeMap) -> Vec<DiagnosticSpan> {
+    span.span_labels().iter()
+      .map(|label| DiagnosticSpan::new(label, codemap))
+      .collect()
+  }
+
+  pub fn is_error(&self) -> bool {
+    self.level.starts_with("error")
+  }
+}
+
+impl DiagnosticSpan
+{
+  fn new(label: &SpanLabel, codemap: &CodeMap) -> Self {
+    let lo = codemap.lookup_char_pos(label.span.lo);
+    let hi = codemap.lookup_char_pos(label.span.hi);
+    DiagnosticSpan {
+      file: lo.file.name.clone(),
+      line_start: lo.line,
+      column_start: lo.col.to_usize() + 1,
+      line_end: hi.line,
+      column_end: hi.col.to_usize() + 1,
+      is_primary: label.is_primary,
+      label: label.label.clone()
+    }
+  }
+}
+
+/// Emitter storing the diagnostics instead of printing them.
+pub struct CollectEmitter
+{
+  diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+  codemap: Rc<CodeMap>,
+}
+
+impl CollectEmitter
+{
+  pub fn new(diagnostics: Rc<RefCell<Vec<Diagnostic>>>, codemap: Rc<CodeMap>) -> Self {
+    CollectEmitter {
+      diagnostics: diagnostics,
+      codemap: codemap
+    }
+  }
+}
+
+impl Emitter for CollectEmitter
+{
+  fn emit(&mut self, db: &DiagnosticBuilder) {
+    self.diagnostics.borrow_mut().push(Diagnostic::new(db, &self.codemap));
+  }
+}
@@ -0,0 +1,80 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+import java.io.*;
+import java.lang.reflect.*;
+import bonsai.runtime.synchronous.SpaceMachine;
+import bonsai.runtime.synchronous.interfaces.*;
+
+/// Executes several processes in a single JVM, the arguments have the form `package.Class.method`.
+/// The processes of the `#[debug(...)]` tests are prefixed by `debug:` and executed in debug mode.
+/// The output of each process is captured and printed on the standard output as a record:
+///   `@@bonsai-run <index> <status> <length>\n<output>\n`
+/// where `status` is `ok` or `error` (in which case the output ends with the stack trace), and `length` is the number of bytes of the output.
+public class RunDriver
+{
+  private static final String DEBUG_PREFIX = "debug:";
+
+  public static void main(String[] args) throws UnsupportedEncodingException {
+    PrintStream stdout = System.out;
+    for (int i = 0; i < args.length; i++) {
+      ByteArrayOutputStream buffer = new ByteArrayOutputStream();
+      PrintStream captured = new PrintStream(buffer, true, "UTF-8");
+      System.setOut(captured);
+      String status = "ok";
+      try {
+        run(args[i]);
+      }
+      catch (Throwable e) {
+        status = "error";
+        captured.println();
+        e.printStackTrace(captured);
+      }
+      captured.flush();
+      System.setOut(stdout);
+      byte[] output = buffer.toByteArray();
+      stdout.print("@@bonsai-run " + i + " " + status + " " + output.length + "\n");
+      stdout.write(output, 0, output.length);
+      stdout.print("\n");
+      stdout.flush();
+    }
+  }
+
+  private static void run(String arg) throws Exception {
+    boolean debug = arg.startsWith(DEBUG_PREFIX);
+    String classMethod = debug ? arg.substring(DEBUG_PREFIX.length()) : arg;
+    int dot = classMethod.lastIndexOf('.');
+    Class<?> moduleClass = Class.forName(classMethod.substring(0, dot));
+    Method process = moduleClass.getMethod(classMethod.substring(dot + 1));
+    BModule module = (BModule) moduleClass.newInstance();
+    SpaceMachine<BModule> machine = new SpaceMachine<>(module, (m) -> invoke(process, m), debug);
+    machine.execute();
+  }
+
+  private static Statement invoke(Method process, BModule module) {
+    try {
+      return (Statement) process.invoke(module);
+    }
+    catch (InvocationTargetException e) {
+      Throwable cause = e.getCause();
+      if (cause instanceof RuntimeException) { throw (RuntimeException) cause; }
+      if (cause instanceof Error) { throw (Error) cause; }
+      throw new RuntimeException(cause);
+    }
+    catch (IllegalAccessException e) {
+      throw new RuntimeException(e);
+    }
+  }
+}
@@ -0,0 +1,57 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The Java sources generated from a bonsai project, kept in memory until they are written.
+
+use diagnostic::Diagnostic;
+use driver::file_system::FileSystem;
+use std::path::PathBuf;
+use std::io;
+
+pub struct CompiledCrate
+{
+  pub modules: Vec<CompiledModule>,
+  /// The warnings reported during the compilation (there is no error since the compilation succeeded).
+  pub warnings: Vec<Diagnostic>
+}
+
+#[derive(Clone, Debug)]
+pub struct CompiledModule
+{
+  pub mod_name: String,
+  /// Where the module is written by `write`, computed from the input and output directories of the configuration.
+  pub output_path: PathBuf,
+  pub source: String
+}
+
+impl CompiledCrate
+{
+  pub fn write(&self, fs: &FileSystem) -> io::Result<()> {
+    for module in &self.modules {
+      module.write(fs)?;
+    }
+    Ok(())
+  }
+}
+
+impl CompiledModule
+{
+  pub fn new(mod_name: String, output_path: PathBuf, source: String) -> Self {
+    CompiledModule { mod_name, output_path, source }
+  }
+
+  pub fn write(&self, fs: &FileSystem) -> io::Result<()> {
+    fs.write(&self.output_path, &self.source)
+  }
+}
@@ -15,7 +15,9 @@
 /// This module processes the command-line arguments and performs basic checks.
 
 use std::path::PathBuf;
-use clap::{App, Error, ErrorKind};
+use driver::file_system::*;
+use driver::javac::Javac;
+use clap::{App, SubCommand, ArgMatches, Error, ErrorKind};
 use ast::{ExecutionTest};
 
 pub struct Config
@@ -25,7 +27,98 @@ pub struct Config
   pub libs: Vec<PathBuf>,
   pub main_method: Option<MainMethod>,
   pub debug: bool,
-  pub testing_mode: bool
+  pub testing_mode: bool,
+  pub error_format: ErrorFormat,
+  /// Error code given to `--explain`: its explanation is printed instead of compiling `input`.
+  pub explain: Option<String>,
+  /// Set by the subcommand `bonsai fmt`: the files are formatted instead of being compiled.
+  pub fmt: Option<FmtConfig>,
+  /// Set by the subcommand `bonsai test`: the test annotations of the files are checked instead of compiling `input`.
+  pub test: Option<TestConfig>
+}
+
+#[derive(Clone, Debug)]
+pub struct FmtConfig
+{
+  /// Files and directories (searched recursively for `.bonsai.java` files) to format.
+  pub paths: Vec<PathBuf>,
+  /// Only check that the files are formatted, without modifying them.
+  pub check: bool
+}
+
+impl FmtConfig
+{
+  fn command_args(matches: &ArgMatches) -> Self {
+    FmtConfig {
+      paths: matches.values_of("path").unwrap().map(PathBuf::from).collect(),
+      check: matches.is_present("check")
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct TestConfig
+{
+  /// A `.bonsai.java` file or a directory searched recursively for them, each file is tested independently.
+  pub project: PathBuf,
+  /// Libraries available to every tested file.
+  pub libs: Vec<PathBuf>,
+  pub backend: TestBackend,
+  /// The classes of the runtime and of the Java code of the libraries, used by the `javac` backend.
+  pub classpath: Vec<PathBuf>
+}
+
+/// How the processes of the `#[run(...)]` annotations are executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestBackend
+{
+  /// The generated Java code is compiled with `javac` and executed in the JVM (see `javac.rs`).
+  Javac,
+  /// The processes are executed by the interpreter, without generating Java code.
+  Interpreter
+}
+
+impl TestConfig
+{
+  fn command_args(matches: &ArgMatches) -> Self {
+    TestConfig {
+      project: PathBuf::from(matches.value_of("project").unwrap()),
+      libs: matches.values_of("lib")
+        .map(|libs| libs.map(PathBuf::from).collect())
+        .unwrap_or(vec![]),
+      backend:
+        if matches.is_present("interpreter") { TestBackend::Interpreter }
+        else { TestBackend::Javac },
+      classpath: matches.values_of("classpath")
+        .map(|paths| paths.map(PathBuf::from).collect())
+        .unwrap_or_else(Javac::classpath_from_env)
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorFormat
+{
+  /// Colored text for the terminal.
+  Human,
+  /// One JSON object per line (see `json_emitter.rs`).
+  Json
+}
+
+impl ErrorFormat
+{
+  pub fn command_arg(format: &str) -> Self {
+    match format {
+      "human" => ErrorFormat::Human,
+      "json" => ErrorFormat::Json,
+      _ => {
+        Error::with_description(&format!(
+          "`{}` is not an error format (expected `human` or `json`). See `{} --help` for more information.",
+            format, EXEC_NAME),
+          ErrorKind::InvalidValue).exit()
+      }
+    }
+  }
 }
 
 #[derive(Clone,Debug)]
@@ -69,15 +162,40 @@ impl Config
         "-o, --output=[directory]      'Write compiled bonsai files to [directory]. The directory structure of the input project is preserved.'
         --main=[classname.method]      'Generate a method `main` in [classname] for immediate testing. Example: `--main=NQueens.solve`.'
         --debug                        'Generate code with debug facility.'
+        --error-format=[format]        'Format of the diagnostics: `human` (default) or `json` (one JSON object per line on the standard error).'
+        --explain=[code]               'Print an extended explanation of the error or warning [code] (e.g. `--explain=E0028`) and exit.'
         --lib=[directory]...           'Paths to bonsai libraries used inside this project. The code is not compiled to Java so you still have to import the .jar of these libraries in your project.'
-        <input>                        'Root of the bonsai project to compile. All files terminating with the `.bonsai` extension are compiled.'")
+        [input]                        'Root of the bonsai project to compile. All files terminating with the `.bonsai` extension are compiled.'")
+      .subcommand(SubCommand::with_name("fmt")
+        .about("Format the bonsai files in the canonical style.")
+        .args_from_usage(
+          "--check                     'Do not modify the files but fail if one of them is not formatted.'
+          <path>...                    'Files or directories to format, the directories are searched recursively for `.bonsai.java` files.'"))
+      .subcommand(SubCommand::with_name("test")
+        .about("Check the `#[error(...)]`, `#[warning(...)]` and `#[run(...)]` annotations of the bonsai files, the processes are compiled with `javac` and executed in the JVM.")
+        .args_from_usage(
+          "--lib=[directory]...        'Paths to bonsai libraries used by the tested files.'
+          --classpath=[path]...        'Compiled classes of the bonsai runtime and of the Java code of the libraries (default: `BONSAI_CLASSPATH`).'
+          --interpreter                'Execute the processes with the interpreter instead of javac and the JVM.'
+          <project>                    'File or directory to test, the directories are searched recursively for `.bonsai.java` files.'"))
       .get_matches();
 
     let libs: Vec<_> = matches.values_of("lib")
       .map(|libs| libs.map(PathBuf::from).collect())
       .unwrap_or(vec![]);
 
-    let input = PathBuf::from(matches.value_of("input").unwrap());
+    let explain = matches.value_of("explain").map(String::from);
+    let fmt = matches.subcommand_matches("fmt").map(FmtConfig::command_args);
+    let test = matches.subcommand_matches("test").map(TestConfig::command_args);
+    let input = match (matches.value_of("input"), explain.is_some() || fmt.is_some() || test.is_some()) {
+      (Some(input), _) => PathBuf::from(input),
+      (None, true) => PathBuf::new(),
+      (None, false) => {
+        Error::with_description(&format!(
+          "The input path is missing. See `{} --help` for more information.", EXEC_NAME),
+          ErrorKind::MissingRequiredArgument).exit()
+      }
+    };
     let output = matches.value_of("output")
       .map(|s| s.trim())
       .map(PathBuf::from)
@@ -88,28 +206,43 @@ impl Config
       libs: libs,
       main_method: matches.value_of("main").map(MainMethod::command_arg),
       debug: matches.is_present("debug"),
-      testing_mode: false
+      testing_mode: false,
+      error_format: matches.value_of("error-format")
+        .map(ErrorFormat::command_arg)
+        .unwrap_or(ErrorFormat::Human),
+      explain: explain,
+      fmt: fmt,
+      test: test
     };
-    config.validate();
+    if config.explain.is_none() && config.fmt.is_none() && config.test.is_none() {
+      config.validate();
+    }
     config
   }
 
   #[allow(dead_code)]
   pub fn testing_mode(file_to_test: PathBuf, output_dir: PathBuf, libs: Vec<PathBuf>) -> Config {
-    Config::check_is_dir(&output_dir, "output (test)", false);
+    if let Err(msg) = Config::check_is_dir(&DiskFileSystem, &output_dir, "output (test)", false) {
+      Config::exit(msg);
+    }
     Config {
       input: file_to_test.clone(),
       output: output_dir,
       libs: libs,
       main_method: None,
       debug: false,
-      testing_mode: true
+      testing_mode: true,
+      error_format: ErrorFormat::Human,
+      explain: None,
+      fmt: None,
+      test: None
     }
   }
 
   #[allow(dead_code)]
   pub fn configure_execution_test(&mut self, test: &ExecutionTest) {
     self.main_method = Some(test.process.clone());
+    self.debug = test.filter_debug;
   }
 
   fn default_output(input: &PathBuf) -> PathBuf {
@@ -117,22 +250,94 @@ impl Config
   }
 
   fn validate(&self) {
-    Config::check_is_dir(&self.input, "input", true);
-    Config::check_is_dir(&self.output, "output", false);
+    if let Err(msg) = self.check(&DiskFileSystem) {
+      Config::exit(msg);
+    }
+  }
+
+  /// Check that the paths of the configuration are directories of `fs`.
+  /// The output directory may not exist yet.
+  pub fn check(&self, fs: &FileSystem) -> Result<(), String> {
+    // When testing, the input is a file, it is checked by `FileFilter`.
+    if !self.testing_mode {
+      Config::check_is_dir(fs, &self.input, "input", true)?;
+    }
+    Config::check_is_dir(fs, &self.output, "output", false)?;
     for lib in &self.libs {
-      Config::check_is_dir(lib, "library", true);
+      Config::check_is_dir(fs, lib, "library", true)?;
     }
+    Ok(())
   }
 
-  fn check_is_dir(path: &PathBuf, name: &str, must_exist: bool) {
+  fn check_is_dir(fs: &FileSystem, path: &PathBuf, name: &str, must_exist: bool) -> Result<(), String> {
     // Don't generate error if the path is a directory OR if the path does not exist and is not forced to exist.
-    if !path.is_dir() {
-      if !must_exist && !path.exists() { return; }
-      Error::with_description(&format!(
-        "The {} path `{}` is not a directory. See `{} --help` for more information.",
-          name, path.display(), EXEC_NAME),
-        ErrorKind::ValueValidation)
-      .exit();
+    if !fs.is_dir(path) && (must_exist || fs.exists(path)) {
+      return Err(format!("The {} path `{}` is not a directory.", name, path.display()));
+    }
+    Ok(())
+  }
+
+  fn exit(msg: String) -> ! {
+    Error::with_description(&format!(
+      "{} See `{} --help` for more information.", msg, EXEC_NAME),
+      ErrorKind::ValueValidation)
+    .exit()
+  }
+}
+
+/// Build a configuration programmatically, for example to embed the compiler with `libbonsai::compile`.
+/// Contrarily to `Config::new`, nothing is read from the command line and invalid paths are reported by `compile` as diagnostics.
+///
+///   let config = ConfigBuilder::new("examples/NQueens")
+///     .output("target/NQueens")
+///     .main_method("NQueens", "solve")
+///     .build();
+pub struct ConfigBuilder
+{
+  config: Config
+}
+
+impl ConfigBuilder
+{
+  pub fn new<P: Into<PathBuf>>(input: P) -> Self {
+    let input = input.into();
+    ConfigBuilder {
+      config: Config {
+        output: Config::default_output(&input),
+        input: input,
+        libs: vec![],
+        main_method: None,
+        debug: false,
+        testing_mode: false,
+        error_format: ErrorFormat::Human,
+        explain: None,
+        fmt: None,
+        test: None
+      }
     }
   }
+
+  pub fn output<P: Into<PathBuf>>(mut self, output: P) -> Self {
+    self.config.output = output.into();
+    self
+  }
+
+  pub fn lib<P: Into<PathBuf>>(mut self, lib: P) -> Self {
+    self.config.libs.push(lib.into());
+    self
+  }
+
+  pub fn main_method(mut self, class: &str, method: &str) -> Self {
+    self.config.main_method = Some(MainMethod::new(String::from(class), String::from(method)));
+    self
+  }
+
+  pub fn debug(mut self, debug: bool) -> Self {
+    self.config.debug = debug;
+    self
+  }
+
+  pub fn build(self) -> Config {
+    self.config
+  }
 }
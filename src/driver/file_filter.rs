@@ -16,13 +16,12 @@
 
 use driver::Config;
 use driver::module_file::ModuleFile;
+use driver::file_system::FileSystem;
 use std::path::{PathBuf};
-use std::fs::*;
 use std::io;
 use std::iter::IntoIterator;
 use std::vec;
 use std::collections::HashMap;
-use clap::{Error, ErrorKind};
 
 #[derive(Debug, Clone)]
 pub struct FileFilter
@@ -32,68 +31,68 @@ pub struct FileFilter
 
 impl FileFilter
 {
-  pub fn new(config: &Config) -> Self {
+  pub fn new(config: &Config, fs: &FileSystem) -> Result<Self, String> {
     let mut package = FileFilter {
       mod_to_files: HashMap::new()
     };
-    let err_msg = "Failed to collect bonsai files.";
     for lib in &config.libs {
-      package.collect_bonsai_files(config, true, lib.clone())
-        .expect(&format!("{:?}: {}", lib, err_msg));
+      package.collect_bonsai_files(fs, config, true, lib.clone())?;
     }
     // When testing, the input in `config` is a file, not a directory.
     if config.testing_mode {
-      let file =  ModuleFile::new(config, config.input.clone(), false)
-      .expect(&format!("Testing file {:?} is not a `.bonsai.java` file.", config.input));
-      package.add_mod_file(file);
+      let file = ModuleFile::new(config, config.input.clone(), false)
+        .ok_or(format!("Testing file {:?} is not a `.bonsai.java` file.", config.input))?;
+      package.add_mod_file(file)?;
     }
     else {
-      package.collect_bonsai_files(config, false, config.input.clone())
-        .expect(&format!("{:?}: {}", config.input, err_msg));
+      package.collect_bonsai_files(fs, config, false, config.input.clone())?;
     }
-    package
+    Ok(package)
   }
 
-  fn collect_bonsai_files(&mut self, config: &Config, lib: bool,
-    current_dir: PathBuf) -> io::Result<()>
+  fn collect_bonsai_files(&mut self, fs: &FileSystem, config: &Config, lib: bool,
+    current_dir: PathBuf) -> Result<(), String>
   {
-    for entry in read_dir(current_dir)? {
-      let entry = entry?.path();
-      if entry.is_dir() {
-        self.collect_bonsai_files(config, lib, entry)?;
+    let entries = fs.read_dir(&current_dir).map_err(|e: io::Error|
+      format!("{:?}: Failed to collect bonsai files ({}).", current_dir, e))?;
+    for entry in entries {
+      if fs.is_dir(&entry) {
+        self.collect_bonsai_files(fs, config, lib, entry)?;
+      }
+      // When testing, the file under test might be in a library (e.g. the project of `bonsai test`).
+      else if lib && config.testing_mode && entry == config.input {
+        continue;
       }
       else {
         if let Some(mod_file) = ModuleFile::new(config, entry, lib) {
-          self.add_mod_file(mod_file)
+          self.add_mod_file(mod_file)?;
         }
       }
     }
     Ok(())
   }
 
-  fn add_mod_file(&mut self, mod_file: ModuleFile) {
+  fn add_mod_file(&mut self, mod_file: ModuleFile) -> Result<(), String> {
     let mod_name = mod_file.mod_name();
     if self.mod_to_files.contains_key(&mod_name) {
-      self.conflicting_module_error(mod_name, mod_file);
+      Err(self.conflicting_module_error(mod_name, mod_file))
     }
     else {
       self.mod_to_files.insert(mod_name, mod_file);
+      Ok(())
     }
   }
 
-  fn conflicting_module_error(&self, conflict_mod: String, mod_file: ModuleFile) {
+  fn conflicting_module_error(&self, conflict_mod: String, mod_file: ModuleFile) -> String {
     let existing_file = self.mod_to_files[&conflict_mod].clone();
-    Error::with_description(
-      &format!("Module {} already imported. Conflicting modules:\n\
-                 {} ({})\n\
-                 {} ({})\n\
-               Explanation: Modules must have a distinct name because bonsai does not have a namespace mechanism.\n\
-               Solution: Rename one of these modules.",
-        conflict_mod,
-        conflict_mod, mod_file.input_path_str(),
-        conflict_mod, existing_file.input_path_str()),
-      ErrorKind::ValueValidation
-    ).exit();
+    format!("Module {} already imported. Conflicting modules:\n\
+               {} ({})\n\
+               {} ({})\n\
+             Explanation: Modules must have a distinct name because bonsai does not have a namespace mechanism.\n\
+             Solution: Rename one of these modules.",
+      conflict_mod,
+      conflict_mod, mod_file.input_path_str(),
+      conflict_mod, existing_file.input_path_str())
   }
 }
 
@@ -0,0 +1,172 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The file system seen by the compiler: the sources are read and the generated Java files are written through the trait `FileSystem`.
+/// `DiskFileSystem` is the real file system, and `VirtualFileSystem` keeps the files in memory, optionally on top of the disk (e.g. for the unsaved buffers of an editor).
+
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::io;
+use std::io::prelude::*;
+use std::fs::{self, OpenOptions, DirBuilder};
+
+/// The methods take `&self` because the file system is shared between the session and its creator, who can retrieve the generated files afterwards.
+pub trait FileSystem
+{
+  fn read_to_string(&self, path: &Path) -> io::Result<String>;
+  fn exists(&self, path: &Path) -> bool;
+  fn is_dir(&self, path: &Path) -> bool;
+  /// The paths of the entries of the directory `path`.
+  fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+  /// Write `contents` to the file `path`, the parent directories are created if needed.
+  fn write(&self, path: &Path, contents: &str) -> io::Result<()>;
+}
+
+pub struct DiskFileSystem;
+
+impl FileSystem for DiskFileSystem
+{
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    let mut src = String::new();
+    fs::File::open(path)?.read_to_string(&mut src)?;
+    Ok(src)
+  }
+
+  fn exists(&self, path: &Path) -> bool {
+    path.exists()
+  }
+
+  fn is_dir(&self, path: &Path) -> bool {
+    path.is_dir()
+  }
+
+  fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = vec![];
+    for entry in fs::read_dir(path)? {
+      entries.push(entry?.path());
+    }
+    Ok(entries)
+  }
+
+  fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir_path) = path.parent() {
+      DirBuilder::new()
+        .recursive(true)
+        .create(dir_path)?;
+    }
+    let mut file = OpenOptions::new()
+      .write(true)
+      .truncate(true)
+      .create(true)
+      .open(path)?;
+    file.write_fmt(format_args!("{}", contents))
+  }
+}
+
+/// The directories are not stored: a path is a directory if it is the ancestor of a file.
+/// The generated files are collected in `generated` and are not visible to `read_to_string`, so the same directory can be used for input and output.
+pub struct VirtualFileSystem
+{
+  files: BTreeMap<PathBuf, String>,
+  generated: RefCell<BTreeMap<PathBuf, String>>,
+  /// The files that are not in `files` are read from the disk.
+  disk: Option<DiskFileSystem>
+}
+
+impl VirtualFileSystem
+{
+  /// A file system existing only in memory.
+  pub fn new() -> Self {
+    VirtualFileSystem {
+      files: BTreeMap::new(),
+      generated: RefCell::new(BTreeMap::new()),
+      disk: None
+    }
+  }
+
+  /// The disk on which the files added with `add_file` shadow those with the same path.
+  pub fn overlay() -> Self {
+    VirtualFileSystem {
+      disk: Some(DiskFileSystem),
+      .. VirtualFileSystem::new()
+    }
+  }
+
+  /// Add or replace the file `path` (for example with an unsaved buffer).
+  pub fn add_file<P: Into<PathBuf>>(&mut self, path: P, contents: String) {
+    self.files.insert(path.into(), contents);
+  }
+
+  pub fn remove_file(&mut self, path: &Path) -> Option<String> {
+    self.files.remove(path)
+  }
+
+  /// The files written by the compiler, indexed by their paths.
+  pub fn generated(&self) -> BTreeMap<PathBuf, String> {
+    self.generated.borrow().clone()
+  }
+
+  fn is_virtual_dir(&self, path: &Path) -> bool {
+    self.files.keys().any(|file| file != path && file.starts_with(path))
+  }
+}
+
+impl FileSystem for VirtualFileSystem
+{
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    match (self.files.get(path), &self.disk) {
+      (Some(contents), _) => Ok(contents.clone()),
+      (None, &Some(ref disk)) => disk.read_to_string(path),
+      (None, &None) => Err(io::Error::new(io::ErrorKind::NotFound,
+        format!("{} is not in the virtual file system", path.display())))
+    }
+  }
+
+  fn exists(&self, path: &Path) -> bool {
+    self.files.contains_key(path)
+    || self.is_virtual_dir(path)
+    || self.disk.as_ref().map_or(false, |disk| disk.exists(path))
+  }
+
+  fn is_dir(&self, path: &Path) -> bool {
+    self.is_virtual_dir(path)
+    || self.disk.as_ref().map_or(false, |disk| disk.is_dir(path))
+  }
+
+  fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries: Vec<PathBuf> = match self.disk {
+      Some(ref disk) if disk.is_dir(path) => disk.read_dir(path)?,
+      _ if self.is_virtual_dir(path) => vec![],
+      _ => return Err(io::Error::new(io::ErrorKind::NotFound,
+        format!("{} is not a directory of the virtual file system", path.display())))
+    };
+    for file in self.files.keys() {
+      if let Ok(suffix) = file.strip_prefix(path) {
+        if let Some(entry) = suffix.components().next() {
+          let entry = path.join(entry.as_os_str());
+          if !entries.contains(&entry) {
+            entries.push(entry);
+          }
+        }
+      }
+    }
+    Ok(entries)
+  }
+
+  fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+    self.generated.borrow_mut().insert(path.to_path_buf(), String::from(contents));
+    Ok(())
+  }
+}
@@ -0,0 +1,264 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// `Javac` compiles the generated sources of several bonsai files in a single `javac` invocation, and executes all their processes in one JVM through the driver `RunDriver.java` of this directory.
+/// It is the runner of `bonsai test` and of the `BONSAI_RUNNER=javac` mode of the test suite of the compiler.
+/// The runtime and the standard library must be compiled beforehand (`mvn compile` in `runtime/` and `libstd/`), or their classes given in `BONSAI_CLASSPATH` (with the path separator of the platform).
+/// A Java file with compilation errors only fails its own tests: it is excluded from the batch which is compiled again (see `compile_excluding_errors`).
+
+use ast::ExecutionTest;
+use context::Context;
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::env;
+use std::process::{Command, Stdio, Output};
+use std::io;
+
+static RUN_DRIVER: &str = include_str!("RunDriver.java");
+static RUN_DRIVER_FILE: &str = "RunDriver.java";
+static RUN_HEADER: &str = "@@bonsai-run ";
+static DEBUG_PREFIX: &str = "debug:";
+
+pub struct Javac {
+  sandbox: PathBuf,
+  classpath: Vec<PathBuf>
+}
+
+/// The output of a process executed by the driver, `Err` if it threw an exception.
+pub type ProcessOutput = Result<String, String>;
+
+/// A process executed by the driver, `class` is the fully qualified name of its module.
+#[derive(Clone, Debug)]
+pub struct JavaProcess {
+  pub class: String,
+  pub method: String,
+  pub debug: bool
+}
+
+impl JavaProcess {
+  /// The process of `test`, its class is qualified with the package of the module in `context`.
+  /// `None` if the class of the test is not a module of the crate.
+  pub fn new(context: &Context, test: &ExecutionTest) -> Option<Self> {
+    context.ast.modules.iter()
+      .find(|module| module.file.mod_name() == test.process.class)
+      .map(|module| {
+        let class =
+          if module.host.package.is_empty() { test.process.class.clone() }
+          else { format!("{}.{}", module.host.package, test.process.class) };
+        JavaProcess { class, method: test.process.method.clone(), debug: test.filter_debug }
+      })
+  }
+
+  fn driver_arg(&self) -> String {
+    format!("{}{}.{}", if self.debug { DEBUG_PREFIX } else { "" }, self.class, self.method)
+  }
+}
+
+impl Javac {
+  /// The generated sources, the driver and the classes are written in `sandbox`.
+  pub fn new(sandbox: PathBuf, classpath: Vec<PathBuf>) -> Self {
+    Javac { sandbox, classpath }
+  }
+
+  /// The paths of `BONSAI_CLASSPATH`, or the classes compiled by Maven in the repository of bonsai.
+  pub fn classpath_from_env() -> Vec<PathBuf> {
+    match env::var_os("BONSAI_CLASSPATH") {
+      Some(paths) => env::split_paths(&paths).collect(),
+      None => vec![PathBuf::from("runtime/target/classes"), PathBuf::from("libstd/target/classes")]
+    }
+  }
+
+  /// The directory where the Java code of the modules must be generated.
+  pub fn source_path(&self) -> PathBuf {
+    self.sandbox.join("src/")
+  }
+
+  fn classes_path(&self) -> PathBuf {
+    self.sandbox.join("classes/")
+  }
+
+  fn driver_path(&self) -> PathBuf {
+    self.sandbox.join(RUN_DRIVER_FILE)
+  }
+
+  pub fn delete_source_files(&self) {
+    if self.sandbox.exists() {
+      let _ = fs::remove_dir_all(self.sandbox.clone());
+    }
+  }
+
+  pub fn driver_file_name() -> String {
+    String::from(RUN_DRIVER_FILE)
+  }
+
+  fn file_name(path: &Path) -> String {
+    path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned())
+  }
+
+  fn full_classpath(&self) -> io::Result<OsString> {
+    let paths = Some(self.classes_path()).into_iter().chain(self.classpath.iter().cloned());
+    env::join_paths(paths)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid classpath: {}", e)))
+  }
+
+  /// The driver and the generated sources, except the files named in `excluded`.
+  fn java_sources(&self, excluded: &HashSet<String>) -> io::Result<Vec<PathBuf>> {
+    fs::write(self.driver_path(), RUN_DRIVER)?;
+    let mut sources = vec![self.driver_path()];
+    for entry in fs::read_dir(self.source_path())? {
+      let path = entry?.path();
+      if path.extension().map_or(false, |ext| ext == "java")
+       && !excluded.contains(&Self::file_name(&path))
+      {
+        sources.push(path);
+      }
+    }
+    Ok(sources)
+  }
+
+  // javac -encoding UTF-8 -d <classes> -cp <classpath> <sources>
+  pub fn compile_batch(&self, excluded: &HashSet<String>) -> io::Result<Output> {
+    let sources = self.java_sources(excluded)?;
+    let classpath = self.full_classpath()?;
+    // The classes of a previous (failed) compilation are not reused.
+    if self.classes_path().exists() {
+      fs::remove_dir_all(self.classes_path())?;
+    }
+    fs::create_dir_all(self.classes_path())?;
+    let child = Command::new("javac")
+      .args(&["-nowarn", "-encoding", "UTF-8", "-d"])
+      .arg(self.classes_path())
+      .arg("-cp").arg(classpath)
+      .args(&sources)
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()?;
+    child.wait_with_output()
+  }
+
+  /// Compiles the batch until it succeeds, the Java files with errors are added to `excluded` with the output of `javac`.
+  /// Returns the output of `javac` if the errors cannot be attributed to a generated file (e.g. the driver does not compile).
+  pub fn compile_excluding_errors(&self, excluded: &mut HashMap<String, Output>) -> io::Result<Option<Output>> {
+    let driver = Self::driver_file_name();
+    loop {
+      let names = excluded.keys().cloned().collect();
+      let compile_output = self.compile_batch(&names)?;
+      if compile_output.status.success() {
+        return Ok(None);
+      }
+      let files = Self::files_with_errors(&compile_output.stderr);
+      if files.is_empty() || files.contains(&driver) || files.iter().any(|f| excluded.contains_key(f)) {
+        return Ok(Some(compile_output));
+      }
+      for file in files {
+        excluded.insert(file, compile_output.clone());
+      }
+    }
+  }
+
+  /// The names of the Java files in the errors reported by `javac` (`<path>.java:<line>: error: <message>`).
+  pub fn files_with_errors(stderr: &[u8]) -> HashSet<String> {
+    let stderr = String::from_utf8_lossy(stderr);
+    let mut files = HashSet::new();
+    for line in stderr.lines() {
+      if let Some(pos) = line.find(".java:") {
+        let (path, rest) = line.split_at(pos + ".java".len());
+        let rest = rest[1..].trim_left_matches(|c: char| c.is_digit(10));
+        if rest.starts_with(": error:") {
+          files.insert(Self::file_name(Path::new(path)));
+        }
+      }
+    }
+    files
+  }
+
+  // java -cp <classpath> RunDriver [debug:]<package.class.method>...
+  pub fn execute_batch(&self, processes: &[JavaProcess]) -> io::Result<Output> {
+    let classpath = self.full_classpath()?;
+    let child = Command::new("java")
+      .arg("-Dfile.encoding=UTF-8")
+      .arg("-cp").arg(classpath)
+      .arg("RunDriver")
+      .args(processes.iter().map(JavaProcess::driver_arg))
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()?;
+    child.wait_with_output()
+  }
+
+  /// Splits the output of the driver into the output of each of the `num_processes` processes.
+  /// The processes without record (if the JVM crashed) are `None`.
+  pub fn split_output(stdout: &[u8], num_processes: usize) -> Vec<Option<ProcessOutput>> {
+    let mut outputs = vec![None; num_processes];
+    let mut rest = stdout;
+    while let Some((index, output, next)) = Self::next_record(rest) {
+      if index < num_processes {
+        outputs[index] = Some(output);
+      }
+      rest = next;
+    }
+    outputs
+  }
+
+  /// Parses `@@bonsai-run <index> <status> <length>\n<output>\n`.
+  fn next_record(stdout: &[u8]) -> Option<(usize, ProcessOutput, &[u8])> {
+    let header_end = stdout.iter().position(|&b| b == b'\n')?;
+    let header = String::from_utf8_lossy(&stdout[..header_end]).into_owned();
+    if !header.starts_with(RUN_HEADER) {
+      return None;
+    }
+    let fields: Vec<&str> = header[RUN_HEADER.len()..].split(' ').collect();
+    if fields.len() != 3 {
+      return None;
+    }
+    let index = fields[0].parse::<usize>().ok()?;
+    let len = fields[2].parse::<usize>().ok()?;
+    let start = header_end + 1;
+    if stdout.len() < start + len {
+      return None;
+    }
+    let output = String::from_utf8_lossy(&stdout[start..start + len]).into_owned();
+    let output = if fields[1] == "ok" { Ok(output) } else { Err(output) };
+    let next = &stdout[(start + len + 1).min(stdout.len())..];
+    Some((index, output, next))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn attribute_javac_errors() {
+    let stderr = b"data/test/sandbox/batch/src/PruneT.java:12: error: cannot find symbol\n\
+      \x20   x.foo();\n\
+      \x20    ^\n\
+      data/test/sandbox/batch/src/WhenT.java:3: warning: [unchecked] unchecked call\n\
+      data/test/sandbox/batch/RunDriver.java:40: error: ';' expected\n\
+      2 errors\n";
+    let files = Javac::files_with_errors(stderr);
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort();
+    assert_eq!(files, vec![String::from("PruneT.java"), String::from("RunDriver.java")]);
+  }
+
+  #[test]
+  fn split_driver_output() {
+    let stdout = b"@@bonsai-run 0 ok 2\n12\n@@bonsai-run 2 error 3\nexc\n";
+    let outputs = Javac::split_output(stdout, 3);
+    assert_eq!(outputs, vec![Some(Ok(String::from("12"))), None, Some(Err(String::from("exc")))]);
+  }
+}
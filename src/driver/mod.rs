@@ -14,53 +14,153 @@
 
 pub mod config;
 pub mod module_file;
+pub mod compiled_crate;
+pub mod file_system;
+pub mod javac;
 mod file_filter;
 
 pub use self::config::*;
+pub use self::compiled_crate::*;
+pub use self::file_system::*;
 use self::file_filter::*;
 use self::module_file::*;
 use session::*;
+use partial::*;
 use front;
 use middle;
 use back;
+use fmt;
+use testing;
 use context::Context;
-use ast::{JModule, JCrate, TestAnnotation};
+use ast::{JModule, JCrate};
+use errors;
+use diagnostic::*;
+use clap::{Error, ErrorKind};
+use syntex_syntax::codemap::CodeMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::mem;
+use std::process;
 
 static ABORT_MSG: &'static str = "stop due to compilation errors";
 
 pub fn run() {
-  let session = Session::new(Config::new());
+  let config = Config::new();
+  if let Some(code) = config.explain.clone() {
+    explain(&code);
+    return;
+  }
+  if config.fmt.is_some() {
+    if !fmt::run(config) {
+      process::exit(1);
+    }
+    return;
+  }
+  if config.test.is_some() {
+    if !testing::run(config) {
+      process::exit(1);
+    }
+    return;
+  }
+  let session = Session::new(config);
   front_mid_run(session)
     .and_next(run_back)
     .expect(ABORT_MSG);
 }
 
+/// Compiles the project described by `config` without printing the diagnostics, writing the generated files or exiting the process.
+/// On success, the generated modules are returned in memory with the warnings; otherwise all the diagnostics are returned.
+pub fn compile(config: Config) -> Result<CompiledCrate, Vec<Diagnostic>> {
+  compile_with_file_system(config, Rc::new(DiskFileSystem))
+}
+
+/// Same as `compile` but the sources are read from `fs`.
+pub fn compile_with_file_system(config: Config, fs: Rc<FileSystem>) -> Result<CompiledCrate, Vec<Diagnostic>> {
+  let (modules, _, diagnostics) = collect_diagnostics(config, fs,
+    |session| front_mid_run(session).and_next(generate_modules));
+  match modules {
+    Partial::Value((_, modules)) => Ok(CompiledCrate { modules, warnings: diagnostics }),
+    _ => Err(diagnostics)
+  }
+}
+
+/// Executes `phases` in a session reading from `fs`, where the diagnostics are collected instead of being printed.
+/// The codemap is returned to locate the spans of the result.
+pub fn collect_diagnostics<T, F>(config: Config, fs: Rc<FileSystem>, phases: F)
+  -> (Partial<T>, Rc<CodeMap>, Vec<Diagnostic>) where
+  F: FnOnce(Session) -> Env<T>
+{
+  let diagnostics = Rc::new(RefCell::new(vec![]));
+  let codemap = Rc::new(CodeMap::new());
+  let emitter = Box::new(CollectEmitter::new(diagnostics.clone(), codemap.clone()));
+  let session = Session::with_emitter(config, codemap.clone(), emitter)
+    .with_file_system(fs);
+  let checked = session.config().check(&*session.file_system);
+  let env = match checked {
+    Ok(()) => phases(session),
+    Err(msg) => {
+      session.err(&msg);
+      Env::nothing(session)
+    }
+  };
+  let (_, result) = env.decompose();
+  // The diagnostics are moved out of the cell since the emitter might still share it.
+  let diagnostics = mem::replace(&mut *diagnostics.borrow_mut(), vec![]);
+  (result, codemap, diagnostics)
+}
+
+/// Prints the extended explanation of the error or warning `code`, or exits with an error if it does not exist.
+pub fn explain(code: &str) {
+  match errors::explain(code) {
+    Some(explanation) => print!("{}", explanation),
+    None => {
+      Error::with_description(&format!(
+        "`{}` is not a bonsai error code. The known codes are: {}.",
+          code, errors::codes().join(", ")),
+        ErrorKind::InvalidValue).exit()
+    }
+  }
+}
+
+/// The front-end gives back `Nothing` if a module contains errors, and the middle-end is then not executed.
 pub fn front_mid_run<'a>(session: Session) -> Env<Context> {
   let env = run_front(session)
-    .map(|jcrate| Context::new(jcrate))
-    .ensure(ABORT_MSG);
+    .map(|jcrate| Context::new(jcrate));
   run_middle(env)
 }
 
 fn run_front(session: Session) -> Env<JCrate> {
-  FileFilter::new(session.config())
-    .into_iter()
-    .fold(Env::value(session, JCrate::new()), run_front_module)
+  let files = FileFilter::new(session.config(), &*session.file_system);
+  match files {
+    Ok(files) => files.into_iter()
+      .fold(Env::value(session, JCrate::new()), run_front_module),
+    Err(msg) => {
+      session.err(&msg);
+      Env::nothing(session)
+    }
+  }
 }
 
+/// Every module is parsed, even if a previous one contains syntax errors, in order to report all the errors at once.
 fn run_front_module(env: Env<JCrate>, file: ModuleFile) -> Env<JCrate> {
-  env.and_then(|mut session, mut jcrate| {
-    let content = session.load_file(file.input_path());
-    let ast = front::parse_bonsai(content).expect(ABORT_MSG);
-    for test in ast.tests.clone() {
-      match test {
-        TestAnnotation::Compiler(test) => session.push_compiler_test(test),
-        TestAnnotation::Execution(test) => session.push_execution_test(test)
-      }
+  let (mut session, jcrate) = env.decompose();
+  let content = session.load_file(file.input_path());
+  let content = match content {
+    Ok(content) => content,
+    Err(e) => {
+      session.err(&format!("Could not read the module `{}`: {}.", file.input_path_str(), e));
+      return Env::nothing(session);
     }
-    jcrate.modules.push(JModule::new(file, ast));
-    Env::value(session, jcrate)
-  })
+  };
+  let (session, ast) = front::parse_bonsai(session, content).decompose();
+  let jcrate = match (jcrate, ast) {
+    (Partial::Value(mut jcrate), Partial::Value(ast)) => {
+      jcrate.modules.push(JModule::new(file, ast));
+      Partial::Value(jcrate)
+    }
+    _ => Partial::Nothing
+  };
+  Env::new(session, jcrate)
 }
 
 fn run_middle<'a>(env: Env<Context>) -> Env<Context> {
@@ -68,17 +168,40 @@ fn run_middle<'a>(env: Env<Context>) -> Env<Context> {
 }
 
 pub fn run_back(session: Session, context: Context) -> Env<Context> {
-  assert_eq!(session.has_errors(), false);
+  generate_modules(session, context)
+    .and_next(|session, (context, modules)| {
+      for module in modules {
+        let written = module.write(&*session.file_system);
+        if let Err(e) = written {
+          session.err(&format!("Could not write the module `{}` to `{}`: {}.",
+            module.mod_name, module.output_path.display(), e));
+          return Env::nothing(session);
+        }
+      }
+      Env::value(session, context)
+    })
+    .ensure(ABORT_MSG)
+}
+
+/// Generates the Java code of the modules of the project in memory (the libraries are not compiled).
+pub fn generate_modules(session: Session, context: Context) -> Env<(Context, Vec<CompiledModule>)> {
+  if session.has_errors() {
+    session.err("internal error: the code generation is executed on a crate with errors (this is a bug).");
+    return Env::nothing(session);
+  }
   context.ast.modules.clone()
     .into_iter()
     .filter(|module| !module.file.is_lib())
-    .fold(Env::value(session, context), |env, module| {
-      let file = module.file.clone();
-      back::compile_module(env, module)
-        .map(|(context, output)| {
-          file.write_output(output);
-          context
-        })
-        .ensure(ABORT_MSG)
+    .fold(Env::value(session, (context, vec![])), |env, module| {
+      env.and_next(|session, (context, mut modules)| {
+        let file = module.file.clone();
+        back::compile_module(Env::value(session, context), module)
+          .map(move |(context, source)| {
+            let output_path = file.output_path().expect(
+              "Try to compile a library file (this is a bug).");
+            modules.push(CompiledModule::new(file.mod_name(), output_path, source));
+            (context, modules)
+          })
+      })
     })
 }
@@ -14,8 +14,6 @@
 
 use driver::Config;
 use std::path::{Path, PathBuf};
-use std::io::prelude::*;
-use std::fs::{OpenOptions, DirBuilder};
 
 #[derive(Clone, Debug)]
 pub struct ModuleFile
@@ -100,25 +98,8 @@ impl ModuleFile
     self.input_path.as_path()
   }
 
-  pub fn write_output(&self, output: String) {
-    let output_path = self.output_path.clone().expect(
-      "Try to compile a library file (this is a bug).");
-    self.build_output_directory(output_path.clone());
-    let mut file = OpenOptions::new()
-     .write(true)
-     .truncate(true)
-     .create(true)
-     .open(output_path.clone())
-     .expect(&format!("Output file ({})", output_path.to_str().unwrap_or("<invalid UTF8>")));
-    file.write_fmt(format_args!("{}", output)).unwrap();
-  }
-
-  fn build_output_directory(&self, output_path: PathBuf) {
-    if let Some(dir_path) = output_path.parent() {
-      DirBuilder::new()
-      .recursive(true)
-      .create(dir_path)
-      .expect("Recursive creation of directory for the output file.");
-    }
+  /// `None` for the modules of a library, which are not compiled.
+  pub fn output_path(&self) -> Option<PathBuf> {
+    self.output_path.clone()
   }
 }
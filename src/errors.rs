@@ -12,42 +12,581 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#![allow(non_snake_case)]
-
-register_long_diagnostics! {
-E0001: r##"Unknown bonsai module."##,
-E0002: r##"Duplicate field in a module."##,
-E0003: r##"Duplicate local variable in a process."##,
-E0004: r##"Duplicate process in a module."##,
-E0005: r##"`ref` variable occurrence in field initialization."##,
-E0006: r##"Undeclared variable (local to the module)."##,
-E0007: r##"Undeclared process (local to the module)."##,
-E0008: r##"Access to an unknown field (external to the current module)."##,
-E0009: r##"Invocation of an unknown process (external to the current module)."##,
-E0010: r##"Process call on a foreign object (from the host language)."##,
-E0011: r##"`ref` field must not be initialized."##,
-E0012: r##"Multiple constructor in a module with `ref` fields."##,
-E0013: r##"Missing constructor in a module with `ref` fields."##,
-E0014: r##"Missing parameter initializing a `ref` field in the constructor of the module."##,
-E0015: r##"Mismatch between constructor parameter and `ref` field of the module."##,
-E0016: r##"Writing on a `pre` variable. For example: `pre x <- e`."##,
-E0017: r##"Illegal kind of a variable under a `pre` operator."##,
-E0018: r##"Local variables of kind `module` with `ref` fields must be instantiated with the `new` operator."##,
-E0019: r##"Local variables of kind `module` must always be initialized."##,
-E0020: r##"Illegal kind of `ref` field (must be of the `spacetime` kind)."##,
-E0021: r##"Field of kind `module` where the module has `ref` fields must not be initialized."##,
-E0022: r##"`ref` argument when calling a module constructor must be a variable."##,
-E0023: r##"`ref` argument must match the type and kind of the called constructor's parameters."##,
-E0024: r##"Constructor's parameters list and instantiation list differ in size."##,
-E0025: r##"Missing spacetime specifier for local host variable."##,
-E0026: r##"Variable accessed with an illegal permission in the current context."##,
-E0027: r##"Illegal host function call in a read only context (e.g. an entailment expression)."##,
-E0028: r##"`loop` statement with an instantaneous body."##,
-E0029: r##"`space` statement with a body that is not instantaneous."##,
-E0030: r##"Recursive process calls are forbidden."##,
-E0031: r##"Search statement (`space` and `prune`) in the process `p` of a `space p end` statement."##,
-E0032: r##"Two readwrite accesses on the same variable."##,
-E0033: r##"Non causal program: A constraint model generated by the causality analysis is unsatisfiable."##,
-E0034: r##"Forbidden permission on host paths."##,
-W0001: r##"Private process that is never called."##
+/// Extended explanations of the error and warning codes, printed with `bonsai --explain EXXXX`.
+/// The first line of every explanation is the short description of the code.
+
+/// Returns the extended explanation of `code` (e.g. `E0028`), or `None` if this code does not exist.
+pub fn explain(code: &str) -> Option<&'static str> {
+  let code = code.trim().to_uppercase();
+  DIAGNOSTICS.iter()
+    .find(|&&(c, _)| c == code)
+    .map(|&(_, explanation)| explanation)
+}
+
+/// All the codes documented, in increasing order.
+pub fn codes() -> Vec<&'static str> {
+  DIAGNOSTICS.iter().map(|&(code, _)| code).collect()
+}
+
+static DIAGNOSTICS: &'static [(&'static str, &'static str)] = &[
+("E0001", r##"Unknown bonsai module.
+
+A variable of kind `module` has a type that is not a bonsai module of the
+project or of the libraries given with `--lib`.
+
+Erroneous code example:
+
+```
+public class CannotFindModule
+{
+  module NotFound m;
+}
+```
+
+Fix: check the spelling of the module, or give the path of the library
+defining it with `--lib=[directory]`.
+"##),
+("E0002", r##"Duplicate field in a module.
+
+Two fields of a module have the same name.
+
+Erroneous code example:
+
+```
+public class DuplicateField
+{
+  single_time Dup d;
+  single_time Dup d;
+}
+```
+
+Fix: rename one of the fields.
+"##),
+("E0003", r##"Duplicate local variable in a process.
+
+A local variable is declared twice in the same process, or it shadows a field
+of the module.
+
+Erroneous code example:
+
+```
+public proc test() =
+  single_time Dup d;
+  single_time Dup d;
+end
+```
+
+Fix: rename one of the variables.
+"##),
+("E0004", r##"Duplicate process in a module.
+
+Two processes of a module have the same name. Processes cannot be overloaded.
+
+Erroneous code example:
+
+```
+public proc test() = nothing
+public proc test() = pause
+```
+
+Fix: rename one of the processes.
+"##),
+("E0005", r##"`ref` variable occurrence in field initialization.
+
+The `ref` fields are initialized in the constructor of the module, which is
+executed after the initialization of the other fields. Therefore, a `ref` field
+cannot appear in the initializer of another field.
+
+Erroneous code example:
+
+```
+ref single_space T a;
+single_space T b = new T(a);
+```
+
+Fix: initialize `b` in a process, or in the constructor after `a`.
+"##),
+("E0006", r##"Undeclared variable (local to the module).
+
+The variable is not a field of the module or a local variable visible at this
+point. Local variables are only visible in the statement following their
+declaration.
+
+Erroneous code example:
+
+```
+public proc test() =
+  single_space T t4 = new T();
+  nothing
+end
+public proc test2() = System.out.println(t4)
+```
+
+Fix: declare `t4` as a field, or declare it in `test2`.
+"##),
+("E0007", r##"Undeclared process (local to the module).
+
+The process called with `run` is not declared in the current module.
+
+Erroneous code example:
+
+```
+public proc test() = run test2()
+```
+
+Fix: declare `test2` in the module, or call a process of a module variable
+with `run m.test2()`.
+"##),
+("E0008", r##"Access to an unknown field (external to the current module).
+
+The field accessed through a variable of kind `module` does not exist in this
+module.
+
+Erroneous code example:
+
+```
+module Module m = new Module();
+public proc test() = m.c
+```
+
+Fix: check the fields of `Module`; only the fields declared there can be
+accessed.
+"##),
+("E0009", r##"Invocation of an unknown process (external to the current module).
+
+The process called through a variable of kind `module` does not exist in this
+module.
+
+Erroneous code example:
+
+```
+module Module m = new Module();
+public proc test() = run m.wrong()
+```
+
+Fix: check the processes of `Module`.
+"##),
+("E0010", r##"Process call on a foreign object (from the host language).
+
+A process can only be called on a bonsai module. The variable is a field of
+the module of a spacetime kind, therefore it is a Java object without
+processes.
+
+Erroneous code example:
+
+```
+module Module m = new Module();
+public proc test() = run m.a.foreign()
+```
+
+Fix: call a Java method as an expression instead, e.g. `m.a.foreign();`.
+"##),
+("E0011", r##"`ref` field must not be initialized.
+
+A `ref` field is a reference to a variable of the caller, it is initialized by
+the constructor of the module.
+
+Erroneous code example:
+
+```
+ref single_space T a = new T();
+```
+
+Fix: remove the initializer and pass the variable to the constructor:
+
+```
+ref single_space T a;
+public Module(T a) { this.a = a; }
+```
+"##),
+("E0012", r##"Multiple constructor in a module with `ref` fields.
+
+A module with `ref` fields must have exactly one constructor, which is used to
+initialize these fields.
+
+Erroneous code example:
+
+```
+public class MultipleConstructor
+{
+  ref single_space T a;
+  public MultipleConstructor(T a) { this.a = a; }
+  public MultipleConstructor(T a, int i) { this.a = a; }
+}
+```
+
+Fix: keep a single constructor.
+"##),
+("E0013", r##"Missing constructor in a module with `ref` fields.
+
+A module with `ref` fields must have a constructor taking these fields as
+parameters.
+
+Erroneous code example:
+
+```
+public class MissingConstructor
+{
+  ref single_space T a;
+  public proc test() = nothing
+}
+```
+
+Fix: add the constructor `public MissingConstructor(T a) { this.a = a; }`.
+"##),
+("E0014", r##"Missing parameter initializing a `ref` field in the constructor of the module.
+
+Every `ref` field must be initialized by a parameter of the constructor with
+the same name.
+
+Erroneous code example:
+
+```
+public class MissingRefParam
+{
+  ref single_space T a;
+  public MissingRefParam() {}
+}
+```
+
+Fix: add the parameter `a`: `public MissingRefParam(T a) { this.a = a; }`.
+"##),
+("E0015", r##"Mismatch between constructor parameter and `ref` field of the module.
+
+The parameter of the constructor initializing a `ref` field must have the same
+type as this field.
+
+Erroneous code example:
+
+```
+ref single_space T a;
+public MismatchRefType(U a) { this.a = a; }
+```
+
+Fix: change the type of the parameter to `T`.
+"##),
+("E0016", r##"Writing on a `pre` variable. For example: `pre x <- e`.
+
+The `pre` operator gives the value of the variable at the previous instant,
+which cannot be modified anymore.
+
+Erroneous code example:
+
+```
+public proc test() = pre a <- 1
+```
+
+Fix: write on the current value of the variable: `a <- 1`.
+"##),
+("E0017", r##"Illegal kind of a variable under a `pre` operator.
+
+Only the variables of kind `single_space` and `world_line` have a previous
+value. It is not the case of `single_time` variables (reinitialized at each
+instant), `module` variables and host variables.
+
+Erroneous code example:
+
+```
+public proc test() =
+  single_time N b;
+  ok <- pre b;
+end
+```
+
+Fix: declare `b` as a `single_space` variable, or remove `pre`.
+"##),
+("E0018", r##"Local variables of kind `module` with `ref` fields must be instantiated with the `new` operator.
+
+The `ref` fields of the module must be bound to variables of the caller, this
+is only possible with the `new` operator.
+
+Erroneous code example:
+
+```
+module Module2 m = Module2.create();
+```
+
+Fix: `module Module2 m = new Module2(a);` where `a` is a spacetime variable.
+"##),
+("E0019", r##"Local variables of kind `module` must always be initialized.
+
+Erroneous code example:
+
+```
+public proc test() =
+  module Module2 m;
+  run m.test();
+end
+```
+
+Fix: `module Module2 m = new Module2(a);`.
+"##),
+("E0020", r##"Illegal kind of `ref` field or process parameter (must be of the `spacetime` kind).
+
+A `ref` field references a spacetime variable; it cannot be a `module` or a
+host variable.
+
+Erroneous code example:
+
+```
+ref module Module m;
+```
+
+Fix: reference the spacetime variables of `Module` instead, e.g.
+`ref single_space T a;`.
+"##),
+("E0021", r##"Field of kind `module` where the module has `ref` fields must be initialized with `new` and fields of the current module.
+
+The `ref` fields of a module field are bound to the fields of the current
+module passed as arguments, the module field and the current module then
+share these variables. Without initializer, the `ref` fields of the module
+field are treated as normal fields.
+
+Erroneous code example:
+
+```
+public single_space T a;
+public module Module m1 = new Module();
+public module Module2 m2 = Module2.create(a);
+public module Module2 m3 = new Module2(m1.a);
+```
+
+Fix: initialize the module field with `new` and a field of the current module:
+`public module Module2 m2 = new Module2(a);`.
+If the argument is not a field of the current module, declare `m2` as a
+local variable in a process.
+"##),
+("E0022", r##"`ref` argument when calling a module constructor or a process must be a variable (without `pre`).
+
+The argument is bound to a `ref` field, therefore it must be a variable that
+can be referenced.
+
+Erroneous code example:
+
+```
+module Module2 m = new Module2(new T());
+```
+
+Fix: declare the variable first:
+
+```
+single_space T a = new T();
+module Module2 m = new Module2(a);
+```
+"##),
+("E0023", r##"`ref` argument must match the type and kind of the called constructor's or process's parameters.
+
+Erroneous code example:
+
+```
+public single_time T c;
+public proc test() =
+  module Module2 m = new Module2(c); // expects a `single_space T`.
+end
+```
+
+Fix: pass a variable with the same type and spacetime as the `ref` field.
+"##),
+("E0024", r##"Constructor's or process's parameters list and instantiation list differ in size.
+
+Erroneous code example:
+
+```
+module Module2 m = new Module2(a, b); // Module2 has a single `ref` field.
+```
+
+Fix: pass exactly one argument per `ref` field: `new Module2(a)`.
+"##),
+("E0025", r##"Missing spacetime specifier for local host variable.
+
+A local variable must have a spacetime (`single_space`, `single_time` or
+`world_line`) or be a `module`. Host variables are only allowed as fields.
+
+Erroneous code example:
+
+```
+public proc test() =
+  T ko = new T();
+  nothing
+end
+```
+
+Fix: `single_space T ok = new T();`, or declare `T ko` as a field.
+"##),
+("E0026", r##"Variable accessed with an illegal permission in the current context.
+
+The left side of `<-` is written (`write`), and the variables of an entailment
+or a condition are only read (`read`).
+
+Erroneous code example:
+
+```
+public proc test() =
+  read a <- 3;
+  when write a |= b then nothing end
+end
+```
+
+Fix: remove the permission, it is inferred: `a <- 3; when a |= b then nothing end`.
+"##),
+("E0027", r##"Illegal host function call in a read only context (e.g. an entailment expression).
+
+A host function might modify its arguments, this is not allowed in a condition
+since it is only supposed to read the variables.
+
+Erroneous code example:
+
+```
+when f(a) |= b then nothing end
+```
+
+Fix: compute the value beforehand:
+
+```
+single_time LMax c = f(a);
+when c |= b then nothing end
+```
+"##),
+("E0028", r##"`loop` statement with an instantaneous body.
+
+The body of a loop must always consume at least one instant (e.g. with
+`pause`), otherwise the loop would be executed infinitely in the same instant.
+
+Erroneous code example:
+
+```
+loop
+  when a |= b then pause end
+end
+```
+
+Fix: ensure that every branch pauses:
+
+```
+loop
+  when a |= b then pause else pause end
+end
+```
+"##),
+("E0029", r##"`space` statement with a body that is not instantaneous.
+
+The body of `space` is executed at each node of the search tree, within the
+current instant. It must terminate instantaneously.
+
+Erroneous code example:
+
+```
+space pause end
+```
+
+Fix: remove the statements that delay the execution (`pause`, `stop`,
+`loop`, `suspend`, ...) from the body of `space`.
+"##),
+("E0030", r##"Recursive process calls are forbidden.
+
+The processes are inlined during compilation, a recursive call (direct or
+through another process) cannot be expanded.
+
+Erroneous code example:
+
+```
+public proc p() = run q()
+public proc q() = run p()
+```
+
+Fix: use a `loop` statement instead of recursion.
+"##),
+("E0031", r##"Search statement (`space` and `prune`) in the process `p` of a `space p end` statement.
+
+The body of `space` describes a branch of the search tree; it cannot create
+new branches itself, directly or through a process call.
+
+Erroneous code example:
+
+```
+public proc test1() = space nothing end
+public proc test() = space run test1() end
+```
+
+Fix: move the nested `space` or `prune` outside of the body of `space`.
+"##),
+("E0032", r##"Two readwrite accesses on the same variable.
+
+Two `readwrite` accesses to a variable can be executed in any order, which
+makes the program non-deterministic.
+
+Erroneous code example:
+
+```
+f(readwrite a, readwrite a);
+```
+
+Fix: sequence the accesses, or declare that one of them is only `read`.
+"##),
+("E0033", r##"Non causal program: A constraint model generated by the causality analysis is unsatisfiable.
+
+In an instant, a variable is first written, then read-written and finally
+read. The causality analysis did not find an order of the accesses satisfying
+this rule.
+
+Erroneous code example:
+
+```
+single_space Trilean r = bot;
+r <- a |= 2;
+a <- 4; // `a |= 2` might not hold anymore.
+```
+
+Fix: write `a` before reading it, or delay one of the accesses to the next
+instant with `pause`:
+
+```
+a <- 4;
+r <- a |= 2;
+```
+"##),
+("E0034", r##"Forbidden permission on host paths.
+
+The permissions `read`, `write` and `readwrite` are only meaningful on
+spacetime variables, not on host objects.
+
+Erroneous code example:
+
+```
+read System.out.println("a");
+```
+
+Fix: remove the permission: `System.out.println("a");`.
+"##),
+("E0035", r##"Syntax error: the program does not respect the grammar of spacetime.
+
+The error points to the first token that could not be parsed, along with the
+tokens expected at this position.
+
+Erroneous code example:
+
+```
+public proc test() =
+  x <- ;
+end
+```
+
+Fix: complete the statement, e.g. `x <- 1;`.
+"##),
+("W0001", r##"Private process that is never called.
+
+A process without `public` can only be called in its module; if it is never
+called, it is dead code.
+
+Example:
+
+```
+public class M
+{
+  proc unused() = nothing
 }
+```
+
+Fix: call the process, make it `public`, or remove it.
+"##),
+];
@@ -0,0 +1,90 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The comments are skipped by the grammar, so the formatter extracts them directly from the source text.
+/// They are located with byte offsets in the source, in order to be printed back between the nodes of the AST.
+
+#[derive(Clone, Debug)]
+pub struct Comment {
+  pub lo: usize,
+  pub hi: usize,
+  pub text: String
+}
+
+impl Comment {
+  pub fn is_single_line(&self) -> bool {
+    !self.text.contains('\n')
+  }
+}
+
+/// Extracts the comments of `source` starting from `start`.
+/// The ranges in `excluded` (sorted) are not scanned, they contain Java code which is printed verbatim with its comments.
+pub fn extract_comments(source: &str, start: usize, excluded: &Vec<(usize, usize)>) -> Vec<Comment> {
+  let bytes = source.as_bytes();
+  let mut comments = vec![];
+  let mut excluded = excluded.iter().peekable();
+  let mut i = start;
+  while i < bytes.len() {
+    if let Some(&&(lo, hi)) = excluded.peek() {
+      if i >= lo {
+        excluded.next();
+        i = if hi > i { hi } else { i };
+        continue;
+      }
+    }
+    // We work on bytes because `i` might not be on a character boundary, the delimiters are ASCII characters.
+    if bytes[i] == b'"' {
+      i = find_byte(bytes, i + 1, |b| b == b'"').map_or(bytes.len(), |end| end + 1);
+    }
+    else if bytes[i..].starts_with(b"//") {
+      let hi = find_byte(bytes, i, |b| b == b'\n' || b == b'\r').unwrap_or(bytes.len());
+      comments.push(Comment { lo: i, hi: hi, text: String::from(source[i..hi].trim_right()) });
+      i = hi;
+    }
+    else if bytes[i..].starts_with(b"/*") {
+      let hi = multiline_comment_end(bytes, i);
+      comments.push(Comment { lo: i, hi: hi, text: String::from(&source[i..hi]) });
+      i = hi;
+    }
+    else {
+      i += 1;
+    }
+  }
+  comments
+}
+
+/// Multiline comments can be nested (see `multiline_comment` in the grammar).
+fn multiline_comment_end(bytes: &[u8], lo: usize) -> usize {
+  let mut depth = 0;
+  let mut i = lo;
+  while i < bytes.len() {
+    if bytes[i..].starts_with(b"/*") {
+      depth += 1;
+      i += 2;
+    }
+    else if bytes[i..].starts_with(b"*/") {
+      depth -= 1;
+      i += 2;
+      if depth == 0 { return i; }
+    }
+    else {
+      i += 1;
+    }
+  }
+  bytes.len()
+}
+
+fn find_byte<P: Fn(u8) -> bool>(bytes: &[u8], from: usize, pred: P) -> Option<usize> {
+  bytes[from..].iter().position(|&b| pred(b)).map(|i| from + i)
+}
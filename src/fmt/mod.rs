@@ -0,0 +1,268 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// `bonsai fmt` rewrites the `.bonsai.java` files in the canonical layout printed by `printer.rs`.
+/// With `--check`, the files are not modified and the command fails if one of them is not formatted.
+
+pub mod printer;
+mod comments;
+
+use driver::config::*;
+use driver::module_file::ModuleFile;
+use driver::file_system::FileSystem;
+use session::*;
+use partial::*;
+use front;
+use ast::TestAnnotation;
+use syntex_syntax::codemap::FileMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Formats the files of `config.fmt`, the errors are reported in the session.
+/// Returns `false` if a file could not be formatted or, in the check mode, if a file is not formatted.
+pub fn run(config: Config) -> bool {
+  let fmt = config.fmt.clone().expect("fmt::run: the configuration of `bonsai fmt` is missing.");
+  let mut session = Session::new(config);
+  let mut files = vec![];
+  for path in &fmt.paths {
+    if let Err(msg) = collect_files(&*session.file_system, path, &mut files) {
+      session.err(&msg);
+      return false;
+    }
+  }
+  let mut success = true;
+  for file in files {
+    let (s, formatted) = format_file(session, &file, fmt.check);
+    session = s;
+    success = success && formatted;
+  }
+  success
+}
+
+/// The files are sorted so the diagnostics are reported in a deterministic order.
+pub fn collect_files(fs: &FileSystem, path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+  if fs.is_dir(path) {
+    let mut entries = fs.read_dir(path).map_err(|e|
+      format!("{:?}: Failed to collect bonsai files ({}).", path, e))?;
+    entries.sort();
+    for entry in entries {
+      if fs.is_dir(&entry) || ModuleFile::extract_mod_name(entry.clone()).is_some() {
+        collect_files(fs, &entry, files)?;
+      }
+    }
+    Ok(())
+  }
+  else if fs.exists(path) {
+    files.push(path.to_path_buf());
+    Ok(())
+  }
+  else {
+    Err(format!("The path `{}` does not exist.", path.display()))
+  }
+}
+
+fn format_file(mut session: Session, path: &Path, check: bool) -> (Session, bool) {
+  let input = match session.load_file(path) {
+    Ok(input) => input,
+    Err(e) => {
+      session.err(&format!("Could not read the file `{}`: {}.", path.display(), e));
+      return (session, false);
+    }
+  };
+  let source = input.src.clone().expect("format_file: the source of the file is not loaded.");
+  let (session, formatted) = format_filemap(session, input).decompose();
+  let formatted = match formatted {
+    Partial::Value(formatted) => formatted,
+    _ => return (session, false)
+  };
+  if *source == formatted {
+    (session, true)
+  }
+  else if check {
+    print_first_difference(path, &source, &formatted);
+    (session, false)
+  }
+  else {
+    let written = session.file_system.write(path, &formatted);
+    match written {
+      Ok(()) => (session, true),
+      Err(e) => {
+        session.err(&format!("Could not write the file `{}`: {}.", path.display(), e));
+        (session, false)
+      }
+    }
+  }
+}
+
+/// Formats `input` and verifies that the result is parsed back and is stable, so a bug in the printer never overwrites a file with broken code.
+pub fn format_filemap(session: Session, input: Rc<FileMap>) -> Env<String> {
+  front::parse_program(session, input.clone())
+    .and_next(|session, program| {
+      let formatted = printer::format_program(&program, &input);
+      let output = session.codemap.new_filemap(
+        format!("{} (formatted)", input.name), formatted.clone());
+      let (session, reformatted) = front::parse_program(session, output.clone())
+        .map(|program| printer::format_program(&program, &output))
+        .decompose();
+      let moved_tests = program.tests.iter().any(|test| match test {
+        &TestAnnotation::Compiler(_)
+      | &TestAnnotation::Label(_, _) => true,
+        _ => false
+      });
+      if moved_tests && input.src.as_ref().map_or(false, |src| **src != formatted) {
+        session.warn(&format!("The code of `{}` is moved by the formatter, the lines and columns of its `#[error]` annotations might need to be updated.", input.name));
+      }
+      match reformatted {
+        Partial::Value(ref reformatted) if *reformatted == formatted => Env::value(session, formatted),
+        _ => {
+          session.err(&format!("[BUG] The formatting of `{}` is not stable, the file is left unchanged. Please report this issue.", input.name));
+          Env::nothing(session)
+        }
+      }
+    })
+}
+
+fn print_first_difference(path: &Path, source: &str, formatted: &str) {
+  let source_lines: Vec<&str> = source.lines().collect();
+  let formatted_lines: Vec<&str> = formatted.lines().collect();
+  let lines = source_lines.len().max(formatted_lines.len());
+  let line = (0..lines).find(|&i| source_lines.get(i) != formatted_lines.get(i))
+    .unwrap_or(lines);
+  println!("Diff in {} at line {}:", path.display(), line + 1);
+  println!("-{}", source_lines.get(line).unwrap_or(&""));
+  println!("+{}", formatted_lines.get(line).unwrap_or(&""));
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use super::comments::extract_comments;
+  use ast::{Item, Program};
+  use std::env;
+  use std::fs;
+  use std::process;
+
+  fn session() -> Session {
+    Session::new(ConfigBuilder::new("data/test").build())
+  }
+
+  /// Parses `source`, `None` if it contains syntax errors.
+  fn parse(session: Session, name: String, source: String) -> (Session, Option<(Program, Rc<FileMap>)>) {
+    let input = session.codemap.new_filemap(name, source);
+    let (session, program) = front::parse_program(session, input.clone()).decompose();
+    match program {
+      Partial::Value(program) => (session, Some((program, input))),
+      _ => (session, None)
+    }
+  }
+
+  fn java_bodies(program: &Program) -> Vec<String> {
+    program.items.iter()
+      .filter_map(|item| match item {
+        &Item::JavaMethod(ref method) => Some(method.body.clone()),
+        &Item::JavaConstructor(ref constructor) => Some(constructor.body.clone()),
+        _ => None
+      })
+      .collect()
+  }
+
+  /// The comments of the source, outside of the header and of the Java code (which are printed verbatim).
+  fn comments(program: &Program, source: &str) -> Vec<String> {
+    let mut java_ranges: Vec<(usize, usize)> = java_bodies(program).iter()
+      .map(|body| {
+        let lo = source.find(body.as_str()).unwrap();
+        (lo, lo + body.len())
+      })
+      .collect();
+    java_ranges.sort();
+    extract_comments(source, program.header.len(), &java_ranges).into_iter()
+      .map(|comment| comment.text)
+      .collect()
+  }
+
+  /// The formatter re-indents the lines and removes trailing spaces.
+  fn trim_lines(text: &str) -> String {
+    text.lines().map(|line| line.trim()).collect::<Vec<_>>().join("\n")
+  }
+
+  fn bonsai_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+      if path.is_dir() {
+        bonsai_files(&path, files);
+      }
+      else if ModuleFile::extract_mod_name(path.clone()).is_some() {
+        files.push(path);
+      }
+    }
+  }
+
+  /// Every program of `data/test` is formatted into a program that is parsed back, formatted identically, and keeps the comments and Java code.
+  /// The programs with syntax errors are the compile-fail tests of `E0035`.
+  #[test]
+  fn format_test_files() {
+    let mut files = vec![];
+    bonsai_files(Path::new("data/test"), &mut files);
+    files.sort();
+    assert!(!files.is_empty());
+    for file in files {
+      let name = format!("{}", file.display());
+      let source = fs::read_to_string(&file).unwrap();
+      let (session, program) = parse(session(), name.clone(), source.clone());
+      let (program, input) = match program {
+        Some(program) => program,
+        None => {
+          assert!(name.contains("compile-fail/E0035"), "`{}` is not parsed.", name);
+          continue;
+        }
+      };
+      let formatted = printer::format_program(&program, &input);
+      let (_, reparsed) = parse(session, format!("{} (formatted)", name), formatted.clone());
+      let (reparsed, output) = reparsed.expect(&format!("The formatted `{}` is not parsed.", name));
+      assert_eq!(printer::format_program(&reparsed, &output), formatted,
+        "The formatting of `{}` is not idempotent.", name);
+      assert!(formatted.starts_with(program.header.trim_right()), "The header of `{}` is modified.", name);
+      let trimmed = trim_lines(&formatted);
+      for comment in comments(&program, &source) {
+        assert!(trimmed.contains(&trim_lines(&comment)),
+          "The comment `{}` of `{}` is lost.", comment, name);
+      }
+      for body in java_bodies(&program) {
+        assert!(formatted.contains(&body), "The Java code `{}` of `{}` is modified.", body, name);
+      }
+    }
+  }
+
+  #[test]
+  fn check_unformatted_file() {
+    let dir = env::temp_dir().join(format!("bonsai-fmt-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("Unformatted.bonsai.java");
+    let unformatted = "package test;\npublic class Unformatted {\npublic proc test() = nothing\n}\n";
+    fs::write(&file, unformatted).unwrap();
+    let config = |check| {
+      let mut config = ConfigBuilder::new(dir.clone()).build();
+      config.fmt = Some(FmtConfig { paths: vec![file.clone()], check: check });
+      config
+    };
+    let checked = run(config(true));
+    let unchanged = fs::read_to_string(&file).unwrap();
+    let formatted = run(config(false));
+    let checked_formatted = run(config(true));
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(!checked, "`--check` must fail on an unformatted file.");
+    assert_eq!(unchanged, unformatted, "`--check` must not modify the file.");
+    assert!(formatted && checked_formatted, "`--check` must succeed once the file is formatted.");
+  }
+}
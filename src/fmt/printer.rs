@@ -0,0 +1,586 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Prints a program (as returned by `front::parse_program`) back to source code in the canonical layout of Bonsai.
+/// The comments and blank lines are recovered from the source text since they are not part of the AST.
+/// The Java methods and constructors are printed verbatim, and the header before the test annotations is left untouched.
+
+use ast::*;
+use back::code_formatter::*;
+use fmt::comments::*;
+use syntex_syntax::codemap::{Pos, FileMap};
+use syntex_pos::BytePos;
+
+pub fn format_program(program: &Program, input: &FileMap) -> String {
+  let source = input.src.clone().expect("format_program: the source of the file is not loaded.");
+  let mut printer = Printer::new(program, &source, input.start_pos.to_usize());
+  printer.print_program(program);
+  printer.fmt.unwrap()
+}
+
+struct Printer<'a> {
+  source: &'a str,
+  base: usize,
+  comments: Vec<Comment>,
+  next_comment: usize,
+  /// Position in the source of the last element printed, used to detect blank lines.
+  last: usize,
+  fmt: CodeFormatter
+}
+
+impl<'a> Printer<'a>
+{
+  fn new(program: &Program, source: &'a str, base: usize) -> Self {
+    let mut printer = Printer {
+      source: source,
+      base: base,
+      comments: vec![],
+      next_comment: 0,
+      last: program.header.len(),
+      fmt: CodeFormatter::new()
+    };
+    let java_ranges: Vec<_> = program.items.iter()
+      .filter_map(|item| printer.java_range(item))
+      .collect();
+    printer.comments = extract_comments(source, program.header.len(), &java_ranges);
+    printer
+  }
+
+  fn pos(&self, span: Span) -> usize {
+    span.lo.to_usize() - self.base
+  }
+
+  fn end(&self, span: Span) -> usize {
+    span.hi.to_usize() - self.base
+  }
+
+  /// The range of a Java method or constructor, from its visibility to the closing brace of its body.
+  fn java_range(&self, item: &Item) -> Option<(usize, usize)> {
+    let (span, body) = match item {
+      &Item::JavaMethod(ref method) => (method.span, &method.body),
+      &Item::JavaConstructor(ref constructor) => (constructor.span, &constructor.body),
+      _ => return None
+    };
+    let lo = self.pos(span);
+    let body_lo = self.source[lo..].find(body.as_str())
+      .expect("java_range: the body of a Java method must appear in its span.");
+    Some((lo, lo + body_lo + body.len()))
+  }
+
+  fn item_range(&self, item: &Item) -> (usize, usize) {
+    match item {
+      &Item::Field(ref field) => (self.pos(field.span), self.end(field.span)),
+      &Item::Proc(ref process) => (self.pos(process.span), self.end(process.body.span)),
+      &Item::JavaMethod(_)
+    | &Item::JavaConstructor(_) => self.java_range(item).unwrap(),
+      &Item::SyntaxError(_) => unreachable!("[BUG] The formatter must not be called on a program with syntax errors.")
+    }
+  }
+
+  fn print_program(&mut self, program: &Program) {
+    let header = program.header.trim_right();
+    if !header.is_empty() {
+      self.fmt.push_block(String::from(header));
+      self.fmt.blank_line();
+    }
+    if !program.tests.is_empty() {
+      for test in &program.tests {
+        let test = self.test_annotation(test);
+        self.fmt.push_line(&test);
+      }
+      self.fmt.blank_line();
+    }
+    let package_lo = self.pos(program.package.span);
+    let class_lo = self.pos(program.class_name.span);
+    self.gap(package_lo, false, false);
+    self.fmt.push(&format!("package {};", program.package));
+    let limit = program.imports.first().map_or(class_lo, |import| self.pos(import.span));
+    self.end_line(limit);
+    self.fmt.blank_line();
+    if !program.imports.is_empty() {
+      for (i, import) in program.imports.iter().enumerate() {
+        let lo = self.pos(import.span);
+        self.gap(lo, false, false);
+        self.fmt.push(&format!("import {};", import));
+        let limit = program.imports.get(i+1).map_or(class_lo, |next| self.pos(next.span));
+        self.end_line(limit);
+      }
+      self.fmt.blank_line();
+    }
+    self.print_class(program);
+  }
+
+  fn test_annotation(&self, test: &TestAnnotation) -> String {
+    match test {
+      &TestAnnotation::Compiler(ref test) => {
+        let level = match test.level {
+          Level::Fatal => String::from("fatal"),
+          Level::Error => String::from("error"),
+          Level::Warning => String::from("warning"),
+          Level::Note => String::from("note"),
+          Level::Help => String::from("help"),
+          ref level => format!("{}", level)
+        };
+        match test.message {
+          Some(ref message) => format!("#[{}({}, {}, {}, \"{}\")]", level, test.code, test.line, test.column, message),
+          None => format!("#[{}({}, {}, {})]", level, test.code, test.line, test.column)
+        }
+      }
+      &TestAnnotation::Label(_, ref label) => {
+        match label.message {
+          Some(ref message) => format!("#[label({}, {}, \"{}\")]", label.line, label.column, message),
+          None => format!("#[label({}, {})]", label.line, label.column)
+        }
+      }
+      &TestAnnotation::Execution(ref test) => {
+        let mode = if test.filter_debug { "debug" } else { "run" };
+        // The regex is enclosed in `^...$` by the parser.
+        let regex = test.output_regex.as_str();
+        let regex = &regex[1..regex.len()-1];
+        format!("#[{}({}.{}, \"{}\")]", mode, test.process.class, test.process.method, regex)
+      }
+    }
+  }
+
+  fn print_class(&mut self, program: &Program) {
+    let class_lo = self.pos(program.class_name.span);
+    self.gap(class_lo, false, false);
+    let mut class = format!("public class {}", program.class_name);
+    if !program.interfaces.is_empty() {
+      let interfaces: Vec<String> = program.interfaces.iter().map(|ty| format!("{}", ty)).collect();
+      class.push_str(&format!(" implements {}", interfaces.join(", ")));
+    }
+    self.fmt.push_line(&class);
+    self.fmt.open_block();
+    let class_end = self.class_end();
+    for (i, item) in program.items.iter().enumerate() {
+      let (lo, hi) = self.item_range(item);
+      let force_blank = i > 0 && !(is_field(item) && is_field(&program.items[i-1]));
+      self.gap(lo, i > 0, force_blank);
+      let limit = program.items.get(i+1)
+        .map_or(class_end, |next| self.item_range(next).0);
+      self.print_item(item, limit);
+      self.last = self.last.max(hi);
+    }
+    self.gap(class_end, false, false);
+    self.fmt.close_block();
+    let end = self.source.len();
+    self.gap(end, true, false);
+  }
+
+  /// The position of the closing brace of the class: it is the last one of the file outside of comments.
+  fn class_end(&self) -> usize {
+    let mut end = self.source.len();
+    loop {
+      let brace = self.source[..end].rfind('}')
+        .expect("class_end: a class is closed by a brace.");
+      match self.comments.iter().find(|c| c.lo <= brace && brace < c.hi) {
+        Some(comment) => end = comment.lo,
+        None => return brace
+      }
+    }
+  }
+
+  fn print_item(&mut self, item: &Item, limit: usize) {
+    match item {
+      &Item::Field(ref field) => self.print_field(field, limit),
+      &Item::Proc(ref process) => self.print_process(process, limit),
+      &Item::JavaMethod(_)
+    | &Item::JavaConstructor(_) => self.print_java(item, limit),
+      &Item::SyntaxError(_) => unreachable!("[BUG] The formatter must not be called on a program with syntax errors.")
+    }
+  }
+
+  fn print_field(&mut self, field: &ModuleField, limit: usize) {
+    let mut code = String::new();
+    let is_java = field.binding.is_host();
+    if is_java && field.is_final {
+      code.push_str("final ");
+    }
+    code.push_str(&self.visibility(field.visibility, field.span, field.binding.name.span));
+    if field.is_ref.is_some() {
+      code.push_str("ref ");
+    }
+    if is_java && field.is_static {
+      code.push_str("static ");
+    }
+    code.push_str(&self.binding(&field.binding));
+    self.fmt.push(&code);
+    self.fmt.push(";");
+    self.end_line(limit);
+  }
+
+  /// The visibility is printed if it is not the default one (`private`) or if it was explicitly written.
+  fn visibility(&self, visibility: JVisibility, item_span: Span, name_span: Span) -> String {
+    let prefix = &self.source[self.pos(item_span)..self.pos(name_span)];
+    if visibility != JVisibility::Private || prefix.split_whitespace().any(|w| w == "private") {
+      format!("{} ", visibility)
+    }
+    else {
+      String::new()
+    }
+  }
+
+  fn print_process(&mut self, process: &Process, limit: usize) {
+    let (keyword, body) = match flow_body(&process.body) {
+      Some((kw_lo, body)) if kw_lo < process.name.span.lo => ("flow", body),
+      _ => ("proc", &process.body)
+    };
+    let params: Vec<String> = process.params.iter().map(|p| self.binding(p)).collect();
+    let header = format!("{}{} {}({}) =",
+      self.visibility(process.visibility, process.span, process.name.span),
+      keyword, process.name, params.join(", "));
+    self.fmt.push(&header);
+    let body_lo = self.pos(body.span);
+    self.end_line(body_lo);
+    let body_end = self.end(process.body.span);
+    self.print_block(body, body_end, false);
+    self.fmt.push("end");
+    self.end_line(limit);
+  }
+
+  fn print_java(&mut self, item: &Item, limit: usize) {
+    let (lo, hi) = self.java_range(item).unwrap();
+    let code = String::from(&self.source[lo..hi]);
+    let mut lines = code.lines();
+    self.fmt.push(lines.next().unwrap());
+    for line in lines {
+      self.fmt.newline();
+      self.fmt.push_raw(line);
+    }
+    self.last = hi;
+    self.end_line(limit);
+  }
+
+  /// Prints the statements of `body` in a block indented once, where `end` is the position of the end of the block in the source.
+  /// In a branch of `par`, the first statement is printed on the current line and the last one is not terminated by a semicolon.
+  fn print_block(&mut self, body: &Stmt, end: usize, in_par: bool) {
+    let stmts = block_stmts(body);
+    let indent = if in_par { 3 } else { 2 };
+    self.fmt.indent_by(indent);
+    for (i, stmt) in stmts.iter().enumerate() {
+      let lo = self.pos(stmt.span);
+      self.gap(lo, i > 0, false);
+      self.print_stmt(stmt);
+      let is_last = i + 1 == stmts.len();
+      if !is_last || (!in_par && !ends_with_end(stmt)) {
+        self.fmt.push(";");
+      }
+      self.last = self.last.max(self.end(stmt.span));
+      let limit = stmts.get(i+1).map_or(end, |next| self.pos(next.span));
+      self.end_line(limit);
+    }
+    self.gap(end, false, false);
+    self.fmt.unindent_by(indent);
+  }
+
+  /// Prints a statement `header`, the statements of `body` and `end`.
+  fn print_compound(&mut self, header: &str, body: &Stmt, stmt: &Stmt) {
+    self.fmt.push(header);
+    let body_lo = self.pos(body.span);
+    self.end_line(body_lo);
+    let end = self.end(stmt.span);
+    self.print_block(body, end, false);
+    self.fmt.push("end");
+  }
+
+  fn print_stmt(&mut self, stmt: &Stmt) {
+    use ast::StmtKind::*;
+    match &stmt.node {
+      &Seq(_) => unreachable!("[BUG] Sequences are printed by `print_block`."),
+      &OrPar(ref branches) => self.print_par("<>", branches, stmt),
+      &AndPar(ref branches) => self.print_par("||", branches, stmt),
+      &Space(ref body) => self.print_compound("space", body, stmt),
+      &Prune => self.fmt.push("prune"),
+      &Let(ref let_stmt) => {
+        let binding = self.binding(&let_stmt.binding);
+        self.fmt.push(&binding);
+      }
+      &When(ref condition, ref then_branch, ref else_branch) => {
+        let header = format!("when {} then", self.expr(condition));
+        self.fmt.push(&header);
+        let then_lo = self.pos(then_branch.span);
+        self.end_line(then_lo);
+        let has_else = !(else_branch.is_nothing() && else_branch.span.lo == else_branch.span.hi);
+        let then_end =
+          if has_else { self.pos(else_branch.span) }
+          else { self.end(stmt.span) };
+        self.print_block(then_branch, then_end, false);
+        if has_else {
+          self.fmt.push("else");
+          self.end_line(then_end);
+          let end = self.end(stmt.span);
+          self.print_block(else_branch, end, false);
+        }
+        self.fmt.push("end");
+      }
+      &Suspend(ref suspend) => {
+        let header = format!("suspend when {} in", self.expr(&suspend.condition));
+        self.print_compound(&header, &suspend.body, stmt);
+      }
+      &Abort(ref abort) => {
+        let weak = if abort.is_weak() { "weak " } else { "" };
+        let header = format!("{}abort when {} in", weak, self.expr(&abort.condition));
+        self.print_compound(&header, &abort.body, stmt);
+      }
+      &Tell(ref var, ref expr) => {
+        let code = format!("{} <- {}", self.variable(var), self.expr(expr));
+        self.fmt.push(&code);
+      }
+      &DelayStmt(ref delay) => {
+        self.fmt.push(match delay.kind {
+          DelayKind::Pause => "pause",
+          DelayKind::PauseUp => "pause up",
+          DelayKind::Stop => "stop"
+        });
+      }
+      &Loop(ref body) => {
+        match flow_body(stmt) {
+          Some((_, body)) => self.print_compound("flow", body, stmt),
+          None => self.print_compound("loop", body, stmt)
+        }
+      }
+      &ProcCall(ref target, ref process, ref args) => {
+        let target = target.as_ref().map_or(String::new(), |t| format!("{}.", self.variable(t)));
+        let args: Vec<String> = args.iter().map(|a| self.variable(a)).collect();
+        let code = format!("run {}{}({})", target, process, args.join(", "));
+        self.fmt.push(&code);
+      }
+      &ExprStmt(ref expr) => {
+        let code = self.expr(expr);
+        self.fmt.push(&code);
+      }
+      &QFUniverse(ref body) => self.print_compound("universe", body, stmt),
+      &Universe(ref queue, ref body) => {
+        let header = format!("universe with {} in", self.variable(queue));
+        self.print_compound(&header, body, stmt);
+      }
+      &Nothing => self.fmt.push("nothing"),
+      &SyntaxError => unreachable!("[BUG] The formatter must not be called on a program with syntax errors.")
+    }
+  }
+
+  /// Each branch starts with its separator on a new line, so every branch is aligned:
+  ///
+  ///   par
+  ///   || s1;
+  ///      s2
+  ///   || s3
+  ///   end
+  fn print_par(&mut self, separator: &str, branches: &Vec<Stmt>, stmt: &Stmt) {
+    self.fmt.push("par");
+    let first_lo = self.pos(branches[0].span);
+    self.end_line(first_lo);
+    let end = self.end(stmt.span);
+    for (i, branch) in branches.iter().enumerate() {
+      let lo = self.pos(branch.span);
+      self.gap(lo, false, false);
+      self.fmt.push(&format!("{} ", separator));
+      let branch_end = branches.get(i+1).map_or(end, |next| self.pos(next.span));
+      self.print_block(branch, branch_end, true);
+    }
+    self.gap(end, false, false);
+    self.fmt.push("end");
+  }
+
+  fn binding(&self, binding: &Binding) -> String {
+    let mut code = String::new();
+    if !binding.is_host() {
+      code.push_str(&format!("{} ", binding.kind));
+    }
+    code.push_str(&format!("{} {}", binding.ty, binding.name));
+    if let Some(ref expr) = binding.expr {
+      code.push_str(&format!(" = {}", self.expr(expr)));
+    }
+    code
+  }
+
+  fn variable(&self, var: &Variable) -> String {
+    let mut code = String::new();
+    if var.past > 0 {
+      for _ in 0..var.past {
+        code.push_str("pre ");
+      }
+    }
+    else if let Some(permission) = var.permission {
+      code.push_str(&format!("{} ", permission));
+    }
+    if var.with_this {
+      code.push_str("this.");
+    }
+    code.push_str(&format!("{}", var.path));
+    code
+  }
+
+  fn expr(&self, expr: &Expr) -> String {
+    use ast::ExprKind::*;
+    match &expr.node {
+      &Number(n) => format!("{}", n),
+      &StringLiteral(ref lit) => format!("\"{}\"", lit),
+      &NewInstance(ref new_instance) =>
+        format!("new {}({})", new_instance.ty, self.exprs(&new_instance.args)),
+      &Call(ref call) => {
+        let target = match call.target {
+          Some(ref target) => format!("{}.", self.variable(target)),
+          None if self.starts_with_this(call.span) => String::from("this."),
+          None => String::new()
+        };
+        format!("{}{}({})", target, call.method, self.exprs(&call.args))
+      }
+      &Var(ref var) => self.variable(var),
+      &Bottom => String::from("bot"),
+      &Top => String::from("top"),
+      &Trilean(SKleene::True) => String::from("true"),
+      &Trilean(SKleene::False) => String::from("false"),
+      &Trilean(SKleene::Unknown) => String::from("unknown"),
+      // `or` and `and` are right associative: only the left operand might need parenthesis.
+      &Or(ref left, ref right) => format!("{} or {}", self.operand(left), self.expr(right)),
+      &And(ref left, ref right) => format!("{} and {}", self.operand(left), self.expr(right)),
+      &Not(ref expr) => format!("not {}", self.atom(expr)),
+      &Entailment(ref rel) => {
+        let op = match rel.op {
+          EntailmentKind::Entailment => "|=",
+          EntailmentKind::StrictEntailment => "|<",
+          EntailmentKind::Equality => "=="
+        };
+        format!("{} {} {}", self.atom(&rel.left), op, self.atom(&rel.right))
+      }
+    }
+  }
+
+  fn exprs(&self, exprs: &Vec<Expr>) -> String {
+    let exprs: Vec<String> = exprs.iter().map(|e| self.expr(e)).collect();
+    exprs.join(", ")
+  }
+
+  fn operand(&self, expr: &Expr) -> String {
+    match &expr.node {
+      &ExprKind::Or(_,_)
+    | &ExprKind::And(_,_) => format!("({})", self.expr(expr)),
+      _ => self.expr(expr)
+    }
+  }
+
+  fn atom(&self, expr: &Expr) -> String {
+    match &expr.node {
+      &ExprKind::Or(_,_)
+    | &ExprKind::And(_,_)
+    | &ExprKind::Not(_)
+    | &ExprKind::Entailment(_) => format!("({})", self.expr(expr)),
+      _ => self.expr(expr)
+    }
+  }
+
+  /// A call `this.m()` is parsed as a call to `m()` without target.
+  fn starts_with_this(&self, span: Span) -> bool {
+    let code = &self.source[self.pos(span)..];
+    code.starts_with("this") &&
+      code["this".len()..].trim_left().starts_with(".")
+  }
+
+  /// Prints the comments located before `lo` on their own lines.
+  /// A blank line of the source is kept before these comments and before `lo` if `keep_blank` is true, it is always added if `force_blank` is true.
+  fn gap(&mut self, lo: usize, keep_blank: bool, force_blank: bool) {
+    let mut keep_blank = keep_blank;
+    let mut force_blank = force_blank;
+    while self.next_comment < self.comments.len() && self.comments[self.next_comment].lo < lo {
+      let comment = self.comments[self.next_comment].clone();
+      if force_blank || (keep_blank && self.blank_line_between(comment.lo)) {
+        self.fmt.blank_line();
+      }
+      let mut lines = comment.text.lines();
+      self.fmt.push(lines.next().unwrap_or(""));
+      for line in lines {
+        self.fmt.newline();
+        self.fmt.push_raw(line);
+      }
+      self.fmt.newline();
+      self.next_comment += 1;
+      self.last = comment.hi;
+      keep_blank = true;
+      force_blank = false;
+    }
+    if force_blank || (keep_blank && self.blank_line_between(lo)) {
+      self.fmt.blank_line();
+    }
+  }
+
+  /// Terminates the current line with the comments written at the end of this line in the source, and located before `limit`.
+  fn end_line(&mut self, limit: usize) {
+    while self.next_comment < self.comments.len() {
+      let comment = self.comments[self.next_comment].clone();
+      if comment.lo >= limit || !comment.is_single_line() || !self.after_code(comment.lo) {
+        break;
+      }
+      self.fmt.push(&format!(" {}", comment.text));
+      self.next_comment += 1;
+      self.last = comment.hi;
+    }
+    self.fmt.newline();
+  }
+
+  /// True if a blank line separates `self.last` and `lo` in the source.
+  fn blank_line_between(&self, lo: usize) -> bool {
+    if self.last >= lo {
+      return false;
+    }
+    let lines: Vec<&str> = self.source[self.last..lo].split('\n').collect();
+    lines.len() > 2 && lines[1..lines.len()-1].iter().any(|l| l.trim().is_empty())
+  }
+
+  /// True if some code precedes the position `lo` on its line.
+  fn after_code(&self, lo: usize) -> bool {
+    let line_start = self.source[..lo].rfind('\n').map_or(0, |i| i + 1);
+    !self.source[line_start..lo].trim().is_empty()
+  }
+}
+
+fn is_field(item: &Item) -> bool {
+  match item {
+    &Item::Field(_) => true,
+    _ => false
+  }
+}
+
+/// `flow p end` is desugared by the parser into `loop p; pause end` where the span of `pause` is the one of the keyword `flow`.
+/// We return the position of the keyword and `p`.
+fn flow_body(stmt: &Stmt) -> Option<(BytePos, &Stmt)> {
+  if let StmtKind::Loop(ref body) = stmt.node {
+    if let StmtKind::Seq(ref seq) = body.node {
+      if seq.len() == 2 && seq[1].span.lo < seq[0].span.lo {
+        if let StmtKind::DelayStmt(Delay { kind: DelayKind::Pause, .. }) = seq[1].node {
+          return Some((seq[1].span.lo, &seq[0]));
+        }
+      }
+    }
+  }
+  None
+}
+
+fn block_stmts(body: &Stmt) -> Vec<Stmt> {
+  match &body.node {
+    &StmtKind::Seq(ref seq) => seq.iter().flat_map(block_stmts).collect(),
+    _ => vec![body.clone()]
+  }
+}
+
+/// True if the statement is terminated by the keyword `end`.
+fn ends_with_end(stmt: &Stmt) -> bool {
+  use ast::StmtKind::*;
+  match &stmt.node {
+    &OrPar(_) | &AndPar(_) | &Space(_) | &When(_,_,_) | &Suspend(_) | &Abort(_)
+  | &Loop(_) | &QFUniverse(_) | &Universe(_,_) => true,
+    _ => false
+  }
+}
@@ -47,7 +47,8 @@ fn lift_stmt(stmt: Stmt) -> Stmt {
       When(condition, Box::new(lift_stmt(*then_branch)), Box::new(lift_stmt(*else_branch))),
     Suspend(suspend) =>
       Suspend(SuspendStmt::new(suspend.condition, Box::new(lift_stmt(*suspend.body)))),
-    Abort(condition, body) => Abort(condition, Box::new(lift_stmt(*body))),
+    Abort(abort) =>
+      Abort(AbortStmt::new(abort.condition, Box::new(lift_stmt(*abort.body)), abort.kind)),
     Loop(body) => Loop(Box::new(lift_stmt(*body))),
     Let(mut decl) => {
       decl.body = Box::new(lift_stmt(*decl.body));
@@ -62,6 +63,7 @@ fn lift_stmt(stmt: Stmt) -> Stmt {
     ProcCall(target, name, args) => ProcCall(target, name, args),
     ExprStmt(e) => ExprStmt(e),
     Nothing => Nothing,
+    SyntaxError => SyntaxError,
   };
   Stmt::new(stmt.span, node)
 }
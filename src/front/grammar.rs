@@ -28,20 +28,26 @@ grammar! bonsai {
 
   program = .. pre_header header java_class > make_java_program
 
-  test_annotation = HASH LBRACKET (execution_test_attr / compiler_test_attr) RBRACKET
+  test_annotation = HASH LBRACKET (execution_test_attr / label_test_attr / compiler_test_attr) RBRACKET
 
   execution_test_attr = (mode spacing) LPAREN string_identifier_os DOT string_identifier COMMA string_literal RPAREN > make_execution_test
 
-  compiler_test_attr = string_identifier LPAREN string_identifier COMMA number COMMA number RPAREN > make_compiler_test
+  compiler_test_attr = string_identifier LPAREN string_identifier COMMA number COMMA number (COMMA string_literal)? RPAREN > make_compiler_test
+
+  label_test_attr = (.. "label") spacing LPAREN number COMMA number (COMMA string_literal)? RPAREN > make_label_test
 
   mode
     = "run" > make_false
     / "debug" > make_true
 
   fn make_compiler_test(level: String, code: String,
-   line: i64, column: i64) -> TestAnnotation
+   line: i64, column: i64, message: Option<String>) -> TestAnnotation
   {
-    TestAnnotation::Compiler(CompilerTest::new(level, code, line as usize, column as usize))
+    TestAnnotation::Compiler(CompilerTest::new(level, code, line as usize, column as usize).with_message(message))
+  }
+
+  fn make_label_test(span: Span, line: i64, column: i64, message: Option<String>) -> TestAnnotation {
+    TestAnnotation::Label(span, LabelTest::new(line as usize, column as usize, message))
   }
 
   fn make_execution_test(filter_debug: bool, class_name: String, process_name: String, regex: String) -> TestAnnotation
@@ -69,6 +75,13 @@ grammar! bonsai {
 
   pre_header = (!(test_annotation* PACKAGE) .)* > to_string
 
+  /// Only parse the test annotations of the file, it is used to recover them when the program fails to parse.
+  prelude_tests = pre_header test_annotation* > make_prelude_tests
+
+  fn make_prelude_tests(_pre_header: String, tests: Vec<TestAnnotation>) -> Vec<TestAnnotation> {
+    tests
+  }
+
   header = test_annotation* java_package java_import*
 
   java_import
@@ -106,12 +119,27 @@ grammar! bonsai {
   }
 
   item
+    = strict_item
+    / .. item_error > make_item_error
+
+  strict_item
     = module_field
-    / (.. java_visibility? proc_or_flow identifier java_param_list?) EQ open_sequence > make_process_item
+    / (.. java_visibility? proc_or_flow identifier proc_param_list?) EQ open_sequence > make_process_item
     / java_field
     / java_method
     / java_constructor
 
+  // Error recovery: an item that cannot be parsed is skipped until the beginning of the next item or the end of the class.
+  // The syntax errors are reported by the front-end (see `front/mod.rs`).
+  item_error = !class_end (item_start / error_token_os spacing) (!item_sync error_token_os spacing)* -> ()
+  item_sync = item_start / class_end
+  item_start = ((PUBLIC / PRIVATE / PROTECTED) (PROC / FLOW / REF)? / PROC / REF) -> ()
+  class_end = RBRACE !. -> ()
+
+  fn make_item_error(span: Span) -> Item {
+    Item::SyntaxError(span)
+  }
+
   proc_or_flow = (.. (PROC > make_false / FLOW > make_true))
 
   module_field = (.. java_visibility? (.. REF)? bonsai_binding) SEMI_COLON > make_module_field
@@ -124,7 +152,7 @@ grammar! bonsai {
   }
 
   fn make_process_item(span: Span, visibility: Option<JVisibility>, flow_kw_sp: Span, is_flow: bool,
-    name: Ident, params: Option<JParameters>, body: Stmt) -> Item
+    name: Ident, params: Option<Vec<Binding>>, body: Stmt) -> Item
   {
     let body = if is_flow {
       let sp = body.span.clone();
@@ -134,6 +162,22 @@ grammar! bonsai {
     Item::Proc(Process::new(span, visibility, name, params.unwrap_or(vec![]), body))
   }
 
+  proc_param_list
+    = LPAREN proc_param (COMMA proc_param)* RPAREN > make_proc_param_list
+    / LPAREN RPAREN > empty_proc_param_list
+
+  proc_param = (.. kind? java_ty identifier_os) spacing > make_proc_param
+
+  fn make_proc_param_list(first: Binding, rest: Vec<Binding>) -> Vec<Binding> {
+    extend_front(first, rest)
+  }
+
+  fn empty_proc_param_list() -> Vec<Binding> { vec![] }
+
+  fn make_proc_param(span: Span, kind: Option<Kind>, ty: JType, name: Ident) -> Binding {
+    Binding::new(span, name, kind.unwrap_or(Kind::Host), ty, None)
+  }
+
   java_method
     = .. java_visibility (STATIC->())? !PROC java_ty identifier java_param_list java_block kw_tail > make_java_method
 
@@ -224,6 +268,10 @@ grammar! bonsai {
     = (.. stmt_kind_os) spacing > make_stmt
 
   stmt_kind_os
+    = strict_stmt_kind_os
+    / stmt_error_os > make_stmt_error
+
+  strict_stmt_kind_os
     = PAR BARBAR? close_sequence (BARBAR close_sequence)* END_OS > make_and_par
     / PAR DIAMOND? close_sequence (DIAMOND close_sequence)* END_OS > make_or_par
     / SPACE close_sequence END_OS > make_space
@@ -231,19 +279,35 @@ grammar! bonsai {
     / WHEN expr THEN close_sequence (ELSE close_sequence)? END_OS > make_when
     / SUSPEND WHEN expr IN close_sequence END_OS > make_suspend
     / ABORT WHEN expr IN close_sequence END_OS > make_abort
+    / WEAK ABORT WHEN expr IN close_sequence END_OS > make_weak_abort
     / PAUSEUP_OS > make_pause_up
     / STOP_OS > make_stop
     / PAUSE_OS > make_pause
     / NOTHING_OS > make_nothing
     / LOOP close_sequence END_OS > make_loop
     / (.. FLOW) close_sequence END_OS > make_flow
-    / UNIVERSE close_sequence END_OS > make_qf_universe
+    // `universe with` must be tried first: the body of `universe ... end` would otherwise recover from `with q in` as a statement in error.
     / UNIVERSE WITH variable IN close_sequence END_OS > make_universe
+    / UNIVERSE close_sequence END_OS > make_qf_universe
     / RUN proc_call_os > make_proc_call
     / binding_os > make_let_stmt
     / variable LEFT_ARROW expr > make_tell
     / expr > make_expr_stmt
 
+  // Error recovery: a statement that cannot be parsed is skipped until the next delimiter of a statement.
+  stmt_error_os = !stmt_sync error_token_os (spacing !stmt_sync error_token_os)* -> ()
+  stmt_sync = (SEMI_COLON / END / ELSE / BARBAR / DIAMOND / item_sync) -> ()
+
+  // We skip identifiers and strings as a whole to avoid synchronizing in the middle of them.
+  error_token_os
+    = ident_char+ -> ()
+    / "\"" (!"\"" .)* "\"" -> ()
+    / !blank . -> ()
+
+  fn make_stmt_error() -> StmtKind {
+    StmtKind::SyntaxError
+  }
+
   fn make_stmt(span: Span, stmt_kind: StmtKind) -> Stmt {
     Stmt::new(span, stmt_kind)
   }
@@ -304,7 +368,11 @@ grammar! bonsai {
   }
 
   fn make_abort(condition: Expr, body: Stmt) -> StmtKind {
-    StmtKind::Abort(condition, Box::new(body))
+    StmtKind::Abort(AbortStmt::new(condition, Box::new(body), AbortKind::Strong))
+  }
+
+  fn make_weak_abort(condition: Expr, body: Stmt) -> StmtKind {
+    StmtKind::Abort(AbortStmt::new(condition, Box::new(body), AbortKind::Weak))
   }
 
   fn make_pause() -> StmtKind {
@@ -458,11 +526,6 @@ grammar! bonsai {
   }
 
   fn make_entailment_rel(span: Span, left: Expr, op: EntailmentKind, right: Expr) -> Expr {
-    match op {
-      EntailmentKind::StrictEntailment => { panic!("|< is not yet implemented."); }
-      EntailmentKind::Equality => { panic!("== is not yet implemented."); }
-      _ => ()
-    };
     let e = EntailmentRel { left, right, op };
     make_expr(span, ExprKind::Entailment(Box::new(e)))
   }
@@ -621,6 +684,8 @@ grammar! bonsai {
   THEN = "then" kw_tail
   SUSPEND = "suspend" kw_tail
   ABORT = "abort" kw_tail
+  // `weak` is a contextual keyword, it can still be used as an identifier.
+  WEAK = "weak" kw_tail &ABORT
   LOOP = "loop" kw_tail
   FLOW = "flow" kw_tail
   IN = "in" kw_tail
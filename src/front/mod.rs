@@ -14,24 +14,63 @@
 
 pub mod grammar;
 mod functionalize;
+mod recovery;
 
 use self::grammar::*;
 use self::functionalize::*;
-use ast::Program;
-use partial::*;
+use self::recovery::*;
+use ast::*;
+use session::*;
 use oak_runtime::*;
 use oak_runtime::file_map_stream::*;
 use std::rc::Rc;
 
-pub fn parse_bonsai(input: Rc<FileMap>) -> Partial<Program> {
-  let state = bonsai::parse_program(input.into_state());
-  let ast = match state.into_result() {
-    ParseResult::Success(ast) => Partial::Value(ast),
+/// Parse the file `input` and register its test annotations in the session.
+/// The grammar recovers from syntax errors at the boundaries of items and statements, so several errors can be reported for a single file (see `recovery.rs`).
+/// If the recovery fails, a diagnostic is reported at the farthest position reached by the parser.
+pub fn parse_bonsai(session: Session, input: Rc<FileMap>) -> Env<Program> {
+  parse_program(session, input).map(let_lifting)
+}
+
+/// Same as `parse_bonsai` but the program is kept as written in `input` (it is used by the formatter).
+/// The nodes `SyntaxError` created by the recovery are removed from the program (see `report_syntax_errors`).
+pub fn parse_program(mut session: Session, input: Rc<FileMap>) -> Env<Program> {
+  let state = bonsai::parse_program(input.clone().into_state());
+  match state.into_result() {
+    ParseResult::Success(mut ast) => {
+      register_tests(&mut session, ast.tests.clone());
+      if report_syntax_errors(&mut session, &input, &mut ast) {
+        Env::nothing(session)
+      }
+      else {
+        Env::value(session, ast)
+      }
+    }
     ParseResult::Partial(_, expectation)
   | ParseResult::Failure(expectation) => {
-      println!("{:?}", expectation);
-      Partial::Nothing
+      // The test annotations are still registered so a compile-fail test can expect a parsing error.
+      register_tests(&mut session, parse_tests(input.clone()));
+      let pos = expectation_pos(&expectation);
+      err_parse(&mut session, &input, pos, expectation.expected_items());
+      Env::nothing(session)
+    }
+  }
+}
+
+fn parse_tests(input: Rc<FileMap>) -> Vec<TestAnnotation> {
+  match bonsai::parse_prelude_tests(input.into_state()).into_result() {
+    ParseResult::Success(tests)
+  | ParseResult::Partial(tests, _) => tests,
+    ParseResult::Failure(_) => vec![]
+  }
+}
+
+fn register_tests(session: &mut Session, tests: Vec<TestAnnotation>) {
+  for test in tests {
+    match test {
+      TestAnnotation::Compiler(test) => session.push_compiler_test(test),
+      TestAnnotation::Label(span, label) => session.push_label_test(span, label),
+      TestAnnotation::Execution(test) => session.push_execution_test(test)
     }
-  };
-  ast.map(let_lifting)
+  }
 }
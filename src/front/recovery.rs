@@ -0,0 +1,160 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The grammar recovers from syntax errors by skipping the text of an item or a statement that cannot be parsed (see `item_error` and `stmt_error_os` in `grammar.rs`).
+/// The skipped text is represented by the nodes `Item::SyntaxError` and `StmtKind::SyntaxError`.
+/// To report a precise diagnostic, we parse again the skipped text alone with the strict rule (without recovery) of the item or statement.
+/// The position of the failure and the expected tokens are then mapped back into the original file.
+
+use front::grammar::*;
+use context::*;
+use session::*;
+use oak_runtime::*;
+use oak_runtime::file_map_stream::*;
+use syntex_syntax::codemap::CodeMap;
+use syntex_pos::BytePos;
+use std::rc::Rc;
+
+/// Report all the syntax errors recovered by the parser in `program`, and remove them from the program.
+/// The items in error are removed and the statements in error are replaced by `nothing`, so the nodes `SyntaxError` never leave the front-end.
+/// Returns `true` if at least one error has been reported.
+pub fn report_syntax_errors(session: &mut Session, input: &Rc<FileMap>, program: &mut Program) -> bool {
+  let mut errors = SyntaxErrors::new();
+  for item in program.items.iter_mut() {
+    match item {
+      &mut Item::SyntaxError(span) => errors.regions.push((span, Region::Item)),
+      &mut Item::Proc(ref mut process) => errors.visit_stmt(&mut process.body),
+      _ => ()
+    }
+  }
+  program.items.retain(|item| match item {
+    &Item::SyntaxError(_) => false,
+    _ => true
+  });
+  errors.regions.sort_by_key(|&(span, _)| span.lo);
+  for &(span, region) in &errors.regions {
+    report_region(session, input, span, region);
+  }
+  !errors.regions.is_empty()
+}
+
+/// The position of the farthest failure of the parser.
+pub fn expectation_pos<'a>(expectation: &ParseExpectation<FileMapStream<'a>>) -> BytePos {
+  let failed_at = expectation.failed_at();
+  (failed_at.clone()..failed_at.clone()).stream_span().lo
+}
+
+pub fn err_parse(session: &mut Session, input: &Rc<FileMap>, pos: BytePos, expected: String) {
+  let (pos, token) = token_at(input, pos);
+  let span = mk_sp(pos, pos + BytePos(token.len() as u32));
+  let found =
+    if token.is_empty() { format!("end of file") }
+    else { format!("`{}`", token) };
+  session.struct_span_err_with_code(span,
+    &format!("syntax error: unexpected {}.", found),
+    "E0035")
+  .span_label(span, &format!("expected {}", expected))
+  .emit();
+}
+
+#[derive(Clone, Copy)]
+enum Region {
+  Item,
+  Stmt
+}
+
+fn report_region(session: &mut Session, input: &Rc<FileMap>, span: Span, region: Region) {
+  let text = source_of(input, span.lo, span.hi);
+  let codemap = CodeMap::new();
+  let filemap = codemap.new_filemap(format!("<syntax error>"), text);
+  let expectation = match region {
+    Region::Item => expectation_of(bonsai::parse_strict_item(filemap.clone().into_state()).into_result()),
+    Region::Stmt => expectation_of(bonsai::parse_strict_stmt_kind_os(filemap.clone().into_state()).into_result())
+  };
+  match expectation {
+    Some(expectation) => {
+      let pos = span.lo + (expectation_pos(&expectation) - filemap.start_pos);
+      err_parse(session, input, pos, expectation.expected_items());
+    }
+    // The text parses alone, the error depends on the context, so we can only report the whole region.
+    None => {
+      let expected = match region {
+        Region::Item => "a field, a process or a Java method",
+        Region::Stmt => "a statement"
+      };
+      err_parse(session, input, span.lo, format!("{}", expected));
+    }
+  }
+}
+
+fn expectation_of<S, T>(result: ParseResult<S, T>) -> Option<ParseExpectation<S>> {
+  match result {
+    ParseResult::Success(_) => None,
+    ParseResult::Partial(_, expectation)
+  | ParseResult::Failure(expectation) => Some(expectation)
+  }
+}
+
+fn source_of(input: &Rc<FileMap>, lo: BytePos, hi: BytePos) -> String {
+  let src = input.src.as_ref().expect("[BUG] The source of a parsed file must be available.");
+  let lo = (lo - input.start_pos).0 as usize;
+  let hi = (hi - input.start_pos).0 as usize;
+  src[lo..hi].to_string()
+}
+
+/// The first token following `pos` (blanks are skipped) with its position.
+/// A token is an identifier or a single character, it is empty at the end of the file.
+fn token_at(input: &Rc<FileMap>, pos: BytePos) -> (BytePos, String) {
+  let rest = source_of(input, pos, input.end_pos);
+  let token = rest.trim_left();
+  let pos = pos + BytePos((rest.len() - token.len()) as u32);
+  let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+  let ident: String = token.chars().take_while(|c| is_ident_char(*c)).collect();
+  if ident.is_empty() {
+    (pos, token.chars().take(1).collect())
+  }
+  else {
+    (pos, ident)
+  }
+}
+
+/// Collect the statements that could not be parsed, and replace them by `nothing`.
+struct SyntaxErrors {
+  regions: Vec<(Span, Region)>
+}
+
+impl SyntaxErrors {
+  fn new() -> Self {
+    SyntaxErrors {
+      regions: vec![]
+    }
+  }
+}
+
+impl VisitorMut<JClass> for SyntaxErrors
+{
+  fn visit_stmt(&mut self, child: &mut Stmt) {
+    let is_error = match child.node {
+      StmtKind::SyntaxError => true,
+      _ => false
+    };
+    if is_error {
+      self.regions.push((child.span, Region::Stmt));
+      child.node = StmtKind::Nothing;
+    }
+    else {
+      walk_stmt_mut(self, child)
+    }
+  }
+}
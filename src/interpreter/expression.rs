@@ -0,0 +1,386 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Expressions of the interpreter: the accesses to the spacetime variables, the calls waiting for their accesses to be ready, and the entailment conditions of `when`, `suspend` and `abort`.
+/// The host expressions are evaluated directly on the AST, the method calls are dispatched on the built-in values.
+
+use ast::*;
+use trilean::SKleene;
+use interpreter::value::*;
+use interpreter::memory::Env;
+use interpreter::RtResult;
+
+/// An access to the spacetime variable `uid` through `var`.
+/// Without permission, the access is free: it is not counted and is always ready (it is used by the conditions).
+#[derive(Clone)]
+pub struct Access {
+  pub var: Variable,
+  pub uid: String,
+  pub permission: Option<Permission>
+}
+
+impl Access {
+  pub fn new(var: Variable, uid: String, permission: Option<Permission>) -> Self {
+    Access { var, uid, permission }
+  }
+
+  fn can_instant(&self, env: &mut Env) -> RtResult<()> {
+    let var = env.lookup(&self.uid)?;
+    let mut var = var.borrow_mut();
+    match self.permission {
+      Some(Permission::Read) => var.join_read(),
+      Some(Permission::ReadWrite) => var.join_readwrite(),
+      Some(Permission::Write) => var.join_write(),
+      None => ()
+    }
+    Ok(())
+  }
+
+  fn terminate(&self, env: &mut Env) -> RtResult<()> {
+    let var = env.lookup(&self.uid)?;
+    let mut var = var.borrow_mut();
+    match self.permission {
+      Some(Permission::Read) => var.meet_read(),
+      Some(Permission::ReadWrite) => { var.meet_readwrite(); env.mark_progress(); }
+      Some(Permission::Write) => { var.meet_write(); env.mark_progress(); }
+      None => ()
+    }
+    Ok(())
+  }
+
+  fn is_ready(&self, env: &Env) -> RtResult<bool> {
+    let var = env.lookup(&self.uid)?;
+    let var = var.borrow();
+    Ok(match self.permission {
+      Some(Permission::Read) => var.is_readable(),
+      Some(Permission::ReadWrite) => var.is_readwritable(),
+      Some(Permission::Write) | None => true
+    })
+  }
+
+  /// A free access is read-only if no other process can modify the variable in this instant, or if we make the hypothesis it is the case.
+  fn is_read_only(&self, env: &Env, hypothesis: &str) -> RtResult<bool> {
+    let readable = env.lookup(&self.uid)?.borrow().is_readable();
+    Ok(readable || self.uid == hypothesis)
+  }
+
+  fn can_write_on(&self, uid: &str) -> bool {
+    self.uid == uid && match self.permission {
+      Some(Permission::ReadWrite) | Some(Permission::Write) => true,
+      _ => false
+    }
+  }
+}
+
+/// An expression executed once all its accesses are ready, the accesses are released just after.
+#[derive(Clone)]
+pub struct NAryCall {
+  accesses: Vec<Access>,
+  expr: Expr,
+  ty: Option<JType>
+}
+
+impl NAryCall {
+  pub fn new(accesses: Vec<Access>, expr: Expr, ty: Option<JType>) -> Self {
+    NAryCall { accesses, expr, ty }
+  }
+
+  pub fn can_instant(&self, env: &mut Env) -> RtResult<()> {
+    for access in &self.accesses {
+      access.can_instant(env)?;
+    }
+    Ok(())
+  }
+
+  pub fn terminate(&self, env: &mut Env) -> RtResult<()> {
+    for access in &self.accesses {
+      access.terminate(env)?;
+    }
+    Ok(())
+  }
+
+  /// Evaluates the expression if the accesses are ready.
+  pub fn execute(&self, env: &mut Env) -> RtResult<Option<Value>> {
+    for access in &self.accesses {
+      if !access.is_ready(env)? {
+        return Ok(None);
+      }
+    }
+    let value = eval(env, &self.accesses, &self.expr, self.ty.as_ref())?;
+    Ok(Some(value))
+  }
+
+  pub fn can_write_on(&self, uid: &str) -> bool {
+    self.accesses.iter().any(|a| a.can_write_on(uid))
+  }
+}
+
+/// The initializing expression of a variable declaration.
+#[derive(Clone)]
+pub enum Expression {
+  Call(NAryCall),
+  Condition(Condition)
+}
+
+impl Expression {
+  pub fn can_instant(&mut self, env: &mut Env) -> RtResult<()> {
+    match *self {
+      Expression::Call(ref call) => call.can_instant(env),
+      Expression::Condition(ref mut cond) => { cond.can_instant(); Ok(()) }
+    }
+  }
+
+  pub fn terminate(&self, env: &mut Env) -> RtResult<()> {
+    match *self {
+      Expression::Call(ref call) => call.terminate(env),
+      Expression::Condition(_) => Ok(())
+    }
+  }
+
+  pub fn execute(&mut self, env: &mut Env) -> RtResult<Option<Value>> {
+    match *self {
+      Expression::Call(ref call) => call.execute(env),
+      Expression::Condition(ref mut cond) => Ok(cond.execute(env)?.map(Value::ES))
+    }
+  }
+
+  pub fn can_write_on(&self, uid: &str) -> bool {
+    match *self {
+      Expression::Call(ref call) => call.can_write_on(uid),
+      Expression::Condition(_) => false
+    }
+  }
+}
+
+#[derive(Clone)]
+enum CondTree {
+  Entailment(Vec<Access>, Vec<Access>, EntailmentRel),
+  And(Box<CondTree>, Box<CondTree>),
+  Or(Box<CondTree>, Box<CondTree>),
+  Not(Box<CondTree>)
+}
+
+/// A condition is decided as soon as no other process can change its result in the current instant.
+/// `result` is the committed result of the instant, it can also be committed by a hypothesis when the program is blocked.
+#[derive(Clone)]
+pub struct Condition {
+  tree: CondTree,
+  result: Option<Kleene>
+}
+
+impl Condition {
+  pub fn entailment(left: Vec<Access>, right: Vec<Access>, rel: EntailmentRel) -> Self {
+    Condition::new(CondTree::Entailment(left, right, rel))
+  }
+
+  pub fn and(c1: Condition, c2: Condition) -> Self {
+    Condition::new(CondTree::And(Box::new(c1.tree), Box::new(c2.tree)))
+  }
+
+  pub fn or(c1: Condition, c2: Condition) -> Self {
+    Condition::new(CondTree::Or(Box::new(c1.tree), Box::new(c2.tree)))
+  }
+
+  pub fn not(c: Condition) -> Self {
+    Condition::new(CondTree::Not(Box::new(c.tree)))
+  }
+
+  fn new(tree: CondTree) -> Self {
+    Condition { tree, result: None }
+  }
+
+  pub fn can_instant(&mut self) {
+    self.result = None;
+  }
+
+  pub fn execute(&mut self, env: &mut Env) -> RtResult<Option<Kleene>> {
+    if self.result.is_none() {
+      self.result = Condition::decide(&self.tree, env, "")?;
+      if self.result.is_some() {
+        env.mark_progress();
+      }
+    }
+    Ok(self.result)
+  }
+
+  /// The result of the condition if we suppose that `hypothesis` cannot be modified anymore in this instant.
+  pub fn hypothesis(&self, env: &mut Env, hypothesis: &str) -> RtResult<Option<Kleene>> {
+    Condition::decide(&self.tree, env, hypothesis)
+  }
+
+  pub fn commit(&mut self, result: Kleene, env: &mut Env) {
+    self.result = Some(result);
+    env.mark_progress();
+  }
+
+  fn decide(tree: &CondTree, env: &mut Env, hypothesis: &str) -> RtResult<Option<Kleene>> {
+    let promoted = match *tree {
+      CondTree::Entailment(ref left, ref right, ref rel) => {
+        let left_ro = Condition::read_only(left, env, hypothesis)?;
+        let right_ro = Condition::read_only(right, env, hypothesis)?;
+        let mut accesses = left.clone();
+        accesses.extend(right.iter().cloned());
+        let result = entailment(env, &accesses, rel)?;
+        return Ok(promote(result, rel.op == EntailmentKind::Equality, left_ro, right_ro));
+      }
+      CondTree::And(ref c1, ref c2) => {
+        let r1 = Condition::decide(c1, env, hypothesis)?;
+        let r2 = Condition::decide(c2, env, hypothesis)?;
+        from_promoted(r1).and(from_promoted(r2))
+      }
+      CondTree::Or(ref c1, ref c2) => {
+        let r1 = Condition::decide(c1, env, hypothesis)?;
+        let r2 = Condition::decide(c2, env, hypothesis)?;
+        from_promoted(r1).or(from_promoted(r2))
+      }
+      CondTree::Not(ref c) => from_promoted(Condition::decide(c, env, hypothesis)?).not()
+    };
+    Ok(to_promoted(promoted))
+  }
+
+  fn read_only(accesses: &[Access], env: &Env, hypothesis: &str) -> RtResult<bool> {
+    for access in accesses {
+      if !access.is_read_only(env, hypothesis)? {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+}
+
+/// An entailment `a |= b` is `true` as soon as `b` is read-only (`a` can only increase) and `false` as soon as `a` is read-only.
+fn promote(result: Kleene, is_equality: bool, left_ro: bool, right_ro: bool) -> Option<Kleene> {
+  if is_equality {
+    if left_ro && right_ro {
+      Some(if result == Kleene::Unknown { Kleene::False } else { result })
+    }
+    else { None }
+  }
+  else {
+    match result {
+      Kleene::Unknown if left_ro && right_ro => Some(Kleene::False),
+      Kleene::True if right_ro => Some(Kleene::True),
+      Kleene::False if left_ro => Some(Kleene::False),
+      _ => None
+    }
+  }
+}
+
+fn from_promoted(result: Option<Kleene>) -> Kleene {
+  result.unwrap_or(Kleene::Unknown)
+}
+
+fn to_promoted(result: Kleene) -> Option<Kleene> {
+  match result {
+    Kleene::Unknown => None,
+    k => Some(k)
+  }
+}
+
+fn entailment(env: &mut Env, accesses: &[Access], rel: &EntailmentRel) -> RtResult<Kleene> {
+  let left = eval(env, accesses, &rel.left, None)?;
+  let right = eval(env, accesses, &rel.right, None)?;
+  match rel.op {
+    EntailmentKind::Entailment => left.entails(&right),
+    EntailmentKind::StrictEntailment => left.strict_entail(&right),
+    EntailmentKind::Equality => left.equals_lattice(&right)
+  }
+}
+
+fn lookup_access<'a>(accesses: &'a [Access], var: &Variable) -> Option<&'a Access> {
+  accesses.iter().find(|a| &a.var == var)
+}
+
+/// Evaluates `expr` where the spacetime variables are retrieved through `accesses`.
+/// `ty` is the type expected by the context, it is used by `bot` and `top`.
+pub fn eval(env: &mut Env, accesses: &[Access], expr: &Expr, ty: Option<&JType>) -> RtResult<Value> {
+  match expr.node {
+    ExprKind::Number(n) => Ok(Value::Int(n)),
+    ExprKind::StringLiteral(ref s) => Ok(Value::Str(s.clone())),
+    ExprKind::NewInstance(ref new_instance) => {
+      let args = eval_args(env, accesses, &new_instance.args)?;
+      Value::new_instance(new_instance.ty.name.as_str(), args)
+    }
+    ExprKind::Call(ref call) => eval_call(env, accesses, call),
+    ExprKind::Var(ref var) => {
+      let uid = access_uid(accesses, var)?;
+      let var = env.lookup(&uid)?;
+      let value = var.borrow().value();
+      value
+    }
+    ExprKind::Bottom => Value::bottom_of(type_name(ty, "bot")?.as_str()),
+    ExprKind::Top => Value::top_of(type_name(ty, "top")?.as_str()),
+    ExprKind::Trilean(k) => Ok(Value::ES(match k {
+      SKleene::True => Kleene::True,
+      SKleene::False => Kleene::False,
+      SKleene::Unknown => Kleene::Unknown
+    })),
+    ExprKind::Or(ref e1, ref e2) => {
+      let k1 = eval(env, accesses, e1, None)?.to_kleene()?;
+      let k2 = eval(env, accesses, e2, None)?.to_kleene()?;
+      Ok(Value::ES(k1.or(k2)))
+    }
+    ExprKind::And(ref e1, ref e2) => {
+      let k1 = eval(env, accesses, e1, None)?.to_kleene()?;
+      let k2 = eval(env, accesses, e2, None)?.to_kleene()?;
+      Ok(Value::ES(k1.and(k2)))
+    }
+    ExprKind::Not(ref e) => Ok(Value::ES(eval(env, accesses, e, None)?.to_kleene()?.not())),
+    ExprKind::Entailment(ref rel) => Ok(Value::ES(entailment(env, accesses, rel)?))
+  }
+}
+
+fn type_name(ty: Option<&JType>, literal: &str) -> RtResult<String> {
+  ty.map(|ty| ty.name.unwrap())
+    .ok_or_else(|| format!("The type of `{}` cannot be inferred by the interpreter.", literal))
+}
+
+fn eval_args(env: &mut Env, accesses: &[Access], args: &[Expr]) -> RtResult<Vec<Value>> {
+  let mut values = vec![];
+  for arg in args {
+    values.push(eval(env, accesses, arg, None)?);
+  }
+  Ok(values)
+}
+
+fn access_uid(accesses: &[Access], var: &Variable) -> RtResult<String> {
+  lookup_access(accesses, var)
+    .map(|a| a.uid.clone())
+    .ok_or_else(|| format!("The host variable `{}` is not supported by the interpreter.", var.path))
+}
+
+fn eval_call(env: &mut Env, accesses: &[Access], call: &MethodCall) -> RtResult<Value> {
+  let args = eval_args(env, accesses, &call.args)?;
+  match call.target {
+    Some(ref target) => {
+      let path = format!("{}", target.path);
+      let method = call.method.as_str();
+      if path == "System.out" && (method == "print" || method == "println") {
+        for arg in &args {
+          env.print(&format!("{}", arg));
+        }
+        if method == "println" {
+          env.print("\n");
+        }
+        return Ok(Value::Unit);
+      }
+      let uid = access_uid(accesses, target)?;
+      let var = env.lookup(&uid)?;
+      let mut var = var.borrow_mut();
+      let res = var.value_mut()?.call_method(method, args);
+      res
+    }
+    None => Err(format!("The static method `{}` is not supported by the interpreter.", call.method))
+  }
+}
@@ -0,0 +1,435 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Builds the runtime statement of a process from the analysed AST, as the generated Java code does:
+/// the modules are instantiated, the process calls are inlined with their parameters bound to the arguments, and the loops get two incarnations of their body.
+/// Every variable declaration receives a fresh UID, so the same AST variable can be bound to several runtime variables.
+
+use std::collections::HashMap;
+use context::*;
+use back::free_variables::free_variables;
+use interpreter::statement::{Statement, Sequence, Parallel, Loop, WhenElse, SuspendWhen, AbortWhen};
+use interpreter::statement::{Delay, ProcedureCall, VarDecl, SpaceStmt, Universe};
+use interpreter::expression::*;
+use interpreter::RtResult;
+
+/// Process calls are inlined, this bound prevents the instantiation of unbounded recursive processes.
+static MAX_CALL_DEPTH: usize = 512;
+
+#[derive(Clone)]
+enum Binder {
+  Var(String),
+  Object(usize)
+}
+
+struct Object {
+  module: Ident,
+  /// Maps the UID of the fields in `Context` to their runtime variable or object.
+  fields: HashMap<usize, Binder>
+}
+
+/// The names visible in the process being instantiated.
+struct Frame {
+  this: usize,
+  process: ProcessUID,
+  locals: HashMap<usize, Binder>
+}
+
+pub fn instantiate(context: &Context, class: &str, method: &str) -> RtResult<Statement> {
+  Instantiate::new(context).root(class, method)
+}
+
+struct Instantiate<'a> {
+  context: &'a Context,
+  objects: Vec<Object>,
+  instances: HashMap<String, usize>,
+  fresh: usize,
+  depth: usize
+}
+
+impl<'a> Instantiate<'a> {
+  fn new(context: &'a Context) -> Self {
+    Instantiate {
+      context,
+      objects: vec![],
+      instances: HashMap::new(),
+      fresh: 0,
+      depth: 0
+    }
+  }
+
+  fn find_mod(&self, name: &Ident) -> RtResult<Module<JClass>> {
+    self.context.ast.find_mod_by_name(name)
+      .ok_or_else(|| format!("The module `{}` does not exist.", name))
+  }
+
+  /// The process `class.method` wrapped in the fields of the root module.
+  fn root(mut self, class: &str, method: &str) -> RtResult<Statement> {
+    let mod_name = Ident::gen(class);
+    let module = self.find_mod(&mod_name)?;
+    let process = module.find_process_by_name(&Ident::gen(method))
+      .ok_or_else(|| format!("The process `{}` does not exist in the module `{}`.", method, class))?;
+    if !process.params.is_empty() {
+      return Err(format!("The process `{}.{}` has parameters and cannot be executed directly.", class, method));
+    }
+    let root = self.new_object(mod_name.clone(), &HashMap::new())?;
+    let mut frame = Frame {
+      this: root,
+      process: ProcessUID::new(mod_name, process.name.clone()),
+      locals: HashMap::new()
+    };
+    let body = self.stmt(&mut frame, &process.body)?;
+    self.wrap_fields(root, true, body)
+  }
+
+  fn fresh_uid(&mut self, name: &Ident) -> String {
+    self.fresh += 1;
+    format!("{}#{}", name, self.fresh)
+  }
+
+  /// Creates an object of `module`, the `ref` fields in `refs` are bound to existing variables.
+  fn new_object(&mut self, module: Ident, refs: &HashMap<usize, String>) -> RtResult<usize> {
+    let m = self.find_mod(&module)?;
+    let instance = {
+      let counter = self.instances.entry(module.unwrap()).or_insert(0);
+      *counter += 1;
+      *counter - 1
+    };
+    let mut fields = HashMap::new();
+    for field in m.fields.iter().filter(|field| !field.binding.is_module()) {
+      let binder =
+        if let Some(uid) = refs.get(&field.binding.uid) {
+          Binder::Var(uid.clone())
+        }
+        else {
+          Binder::Var(format!("{}.{}.{}", module, instance, field.binding.name))
+        };
+      fields.insert(field.binding.uid, binder);
+    }
+    // The `ref` fields of the module fields are bound to the variables of the fields of this object.
+    let mod_info = self.context.module_by_name(module.clone());
+    for field in m.fields.iter().filter(|field| field.binding.is_module()) {
+      let mut field_refs = HashMap::new();
+      if let Some(info) = mod_info.module_field_refs(field.binding.uid) {
+        for (field_uid, var) in &info.instantiated_refs {
+          match fields.get(&var.last_uid()) {
+            Some(&Binder::Var(ref uid)) => { field_refs.insert(*field_uid, uid.clone()); }
+            _ => return Err(format!("The `ref` argument `{}` of the module field `{}` is not a field.", var, field.binding.name))
+          }
+        }
+      }
+      let obj = self.new_object(field.binding.ty.name.clone(), &field_refs)?;
+      fields.insert(field.binding.uid, Binder::Object(obj));
+    }
+    self.objects.push(Object { module, fields });
+    Ok(self.objects.len() - 1)
+  }
+
+  /// Declares the fields of `obj` around `body`, see `__wrap_process` in the generated code.
+  fn wrap_fields(&mut self, obj: usize, root: bool, body: Statement) -> RtResult<Statement> {
+    let module = self.find_mod(&self.objects[obj].module)?;
+    let mut stmt = body;
+    for field in module.fields.iter().rev() {
+      if field.binding.is_module() {
+        let binder = self.objects[obj].fields.get(&field.binding.uid).cloned();
+        if let Some(Binder::Object(sub)) = binder {
+          let has_refs = self.context.module_by_name(field.binding.ty.name.clone()).has_refs();
+          stmt = self.wrap_fields(sub, has_refs && field.binding.expr.is_none(), stmt)?;
+        }
+      }
+      else if field.is_ref.is_none() {
+        stmt = self.field_decl(obj, field, stmt)?;
+      }
+    }
+    if root {
+      for field in module.fields.iter().rev() {
+        if !field.binding.is_module() && field.is_ref.is_some() {
+          stmt = self.field_decl(obj, field, stmt)?;
+        }
+      }
+    }
+    Ok(stmt)
+  }
+
+  fn field_decl(&self, obj: usize, field: &ModuleField, body: Statement) -> RtResult<Statement> {
+    let spacetime = match field.binding.kind {
+      Kind::Spacetime(spacetime) => spacetime,
+      // Host fields are not managed by the runtime.
+      _ => return Ok(body)
+    };
+    let uid = match self.objects[obj].fields.get(&field.binding.uid) {
+      Some(&Binder::Var(ref uid)) => uid.clone(),
+      _ => return Err(format!("[BUG] The field `{}` is not bound to a variable.", field.binding.name))
+    };
+    let expr = field.binding.expr.clone()
+      .unwrap_or_else(|| Expr::new(field.binding.span, ExprKind::Bottom));
+    let init = Expression::Call(NAryCall::new(vec![], expr, Some(field.binding.ty.clone())));
+    Ok(Statement::VarDecl(Box::new(VarDecl::new(uid, spacetime, init, body))))
+  }
+
+  fn resolve(&self, frame: &Frame, var: &Variable) -> RtResult<Binder> {
+    if var.past > 0 {
+      return Err(format!("The stream access `pre {}` is not supported by the interpreter.", var.path));
+    }
+    let uids = &var.path.uids;
+    let not_found = || format!("The variable `{}` cannot be resolved by the interpreter.", var.path);
+    let mut binder = frame.locals.get(&uids[0])
+      .or_else(|| self.objects[frame.this].fields.get(&uids[0]))
+      .cloned()
+      .ok_or_else(not_found)?;
+    for uid in &uids[1..] {
+      binder = match binder {
+        Binder::Object(obj) => self.objects[obj].fields.get(uid).cloned().ok_or_else(not_found)?,
+        Binder::Var(_) => return Err(not_found())
+      };
+    }
+    Ok(binder)
+  }
+
+  fn resolve_var(&self, frame: &Frame, var: &Variable) -> RtResult<String> {
+    match self.resolve(frame, var)? {
+      Binder::Var(uid) => Ok(uid),
+      Binder::Object(_) => Err(format!("The module `{}` is used where a variable is expected.", var.path))
+    }
+  }
+
+  /// The spacetime variables of `expr`, in the same order as `collect_variables` in the code generator.
+  fn collect_variables(&self, variables: &mut Vec<Variable>, expr: &Expr) {
+    match expr.node {
+      ExprKind::NewInstance(ref new_instance) => {
+        for arg in &new_instance.args {
+          self.collect_variables(variables, arg);
+        }
+      }
+      ExprKind::Call(ref call) => {
+        if let Some(ref target) = call.target {
+          if self.context.var_by_uid(target.last_uid()).is_spacetime() {
+            variables.push(target.clone());
+          }
+        }
+        for arg in &call.args {
+          self.collect_variables(variables, arg);
+        }
+      }
+      ExprKind::Var(ref var) => {
+        if self.context.var_by_uid(var.last_uid()).is_spacetime() {
+          variables.push(var.clone());
+        }
+      }
+      ExprKind::And(ref e1, ref e2)
+    | ExprKind::Or(ref e1, ref e2) => {
+        self.collect_variables(variables, e1);
+        self.collect_variables(variables, e2);
+      }
+      ExprKind::Not(ref e) => self.collect_variables(variables, e),
+      ExprKind::Entailment(ref rel) => {
+        self.collect_variables(variables, &rel.left);
+        self.collect_variables(variables, &rel.right);
+      }
+      ExprKind::Trilean(_)
+    | ExprKind::Bottom | ExprKind::Top
+    | ExprKind::Number(_) | ExprKind::StringLiteral(_) => ()
+    }
+  }
+
+  /// The accesses of `expr`, they are free (not counted) if `free` is true.
+  fn accesses(&self, frame: &Frame, expr: &Expr, free: bool) -> RtResult<Vec<Access>> {
+    let mut variables = vec![];
+    self.collect_variables(&mut variables, expr);
+    let mut accesses = vec![];
+    for var in variables {
+      let uid = self.resolve_var(frame, &var)?;
+      let permission = if free { None } else { var.permission };
+      accesses.push(Access::new(var, uid, permission));
+    }
+    Ok(accesses)
+  }
+
+  fn nary_call(&self, frame: &Frame, expr: Expr, ty: Option<JType>) -> RtResult<NAryCall> {
+    let accesses = self.accesses(frame, &expr, false)?;
+    Ok(NAryCall::new(accesses, expr, ty))
+  }
+
+  fn condition(&self, frame: &Frame, expr: &Expr) -> RtResult<Condition> {
+    match expr.node {
+      ExprKind::And(ref e1, ref e2) => Ok(Condition::and(self.condition(frame, e1)?, self.condition(frame, e2)?)),
+      ExprKind::Or(ref e1, ref e2) => Ok(Condition::or(self.condition(frame, e1)?, self.condition(frame, e2)?)),
+      ExprKind::Not(ref e) => Ok(Condition::not(self.condition(frame, e)?)),
+      ExprKind::Entailment(ref rel) => self.entailment(frame, (**rel).clone()),
+      _ => {
+        // A condition `e` is `e |= true`.
+        self.entailment(frame, EntailmentRel {
+          left: expr.clone(),
+          right: Expr::new(DUMMY_SP, ExprKind::Trilean(SKleene::True)),
+          op: EntailmentKind::Entailment
+        })
+      }
+    }
+  }
+
+  fn entailment(&self, frame: &Frame, rel: EntailmentRel) -> RtResult<Condition> {
+    let left = self.accesses(frame, &rel.left, true)?;
+    let right = self.accesses(frame, &rel.right, true)?;
+    Ok(Condition::entailment(left, right, rel))
+  }
+
+  fn init_expr(&self, frame: &Frame, binding: &Binding) -> RtResult<Expression> {
+    let expr = binding.expr.clone()
+      .unwrap_or_else(|| Expr::new(binding.span, ExprKind::Bottom));
+    match expr.node {
+      ExprKind::Entailment(ref rel) =>
+        return Ok(Expression::Condition(self.entailment(frame, (**rel).clone())?)),
+      _ => ()
+    }
+    Ok(Expression::Call(self.nary_call(frame, expr, Some(binding.ty.clone()))?))
+  }
+
+  fn stmts(&mut self, frame: &mut Frame, stmts: &[Stmt]) -> RtResult<Vec<Statement>> {
+    let mut res = vec![];
+    for stmt in stmts {
+      res.push(self.stmt(frame, stmt)?);
+    }
+    Ok(res)
+  }
+
+  fn par(&mut self, frame: &mut Frame, branches: &[Stmt], conjunctive: bool) -> RtResult<Statement> {
+    let mut branches = self.stmts(frame, branches)?;
+    if branches.len() == 1 {
+      Ok(branches.remove(0))
+    }
+    else {
+      Ok(Statement::Par(Box::new(Parallel::new(conjunctive, branches))))
+    }
+  }
+
+  fn stmt(&mut self, frame: &mut Frame, stmt: &Stmt) -> RtResult<Statement> {
+    match stmt.node {
+      StmtKind::Seq(ref branches) => {
+        let mut seq = self.stmts(frame, branches)?;
+        if seq.len() == 1 { Ok(seq.remove(0)) }
+        else { Ok(Statement::Seq(Box::new(Sequence::new(seq)))) }
+      }
+      StmtKind::OrPar(ref branches) => self.par(frame, branches, true),
+      StmtKind::AndPar(ref branches) => self.par(frame, branches, false),
+      StmtKind::Space(ref branch) => {
+        let mut captured = vec![];
+        for var in free_variables(self.context, frame.process.module.clone(), (**branch).clone()) {
+          captured.push(self.resolve_var(frame, &var)?);
+        }
+        captured.sort();
+        let branch = self.stmt(frame, branch)?;
+        Ok(Statement::Space(Box::new(SpaceStmt::new(captured, branch))))
+      }
+      StmtKind::Prune => Ok(Statement::Prune),
+      StmtKind::Let(ref let_stmt) => self.let_decl(frame, let_stmt),
+      StmtKind::When(ref cond, ref then, ref els) => {
+        let cond = self.condition(frame, cond)?;
+        let then = self.stmt(frame, then)?;
+        let els = self.stmt(frame, els)?;
+        Ok(Statement::When(Box::new(WhenElse::new(cond, then, els))))
+      }
+      StmtKind::Suspend(ref suspend) => {
+        let cond = self.condition(frame, &suspend.condition)?;
+        let body = self.stmt(frame, &suspend.body)?;
+        Ok(Statement::Suspend(Box::new(SuspendWhen::new(cond, body))))
+      }
+      StmtKind::Abort(ref abort) => {
+        let cond = self.condition(frame, &abort.condition)?;
+        let body = self.stmt(frame, &abort.body)?;
+        Ok(Statement::Abort(Box::new(AbortWhen::new(cond, body, abort.kind == AbortKind::Weak))))
+      }
+      StmtKind::Tell(ref var, ref expr) => {
+        let call = MethodCall::new(expr.span, Some(var.clone()), Ident::gen("join_in_place"), vec![expr.clone()]);
+        let call = self.nary_call(frame, Expr::new(stmt.span, ExprKind::Call(call)), None)?;
+        Ok(Statement::Call(ProcedureCall::new(call)))
+      }
+      StmtKind::DelayStmt(ref delay) => Ok(Statement::Delay(Delay::new(delay.kind.clone()))),
+      StmtKind::Loop(ref body) => {
+        let body_stmt = self.stmt(frame, body)?;
+        let surface_body = self.stmt(frame, body)?;
+        Ok(Statement::Loop(Box::new(Loop::new(body_stmt, surface_body))))
+      }
+      StmtKind::ProcCall(ref target, ref name, ref args) => self.proc_call(frame, target, name, args),
+      StmtKind::ExprStmt(ref expr) => {
+        let call = self.nary_call(frame, expr.clone(), None)?;
+        Ok(Statement::Call(ProcedureCall::new(call)))
+      }
+      StmtKind::QFUniverse(ref body) => {
+        let body = self.stmt(frame, body)?;
+        Ok(Statement::Universe(Box::new(Universe::new(None, body))))
+      }
+      StmtKind::Universe(ref queue, ref body) => {
+        let queue = self.resolve_var(frame, queue)?;
+        let body = self.stmt(frame, body)?;
+        Ok(Statement::Universe(Box::new(Universe::new(Some(queue), body))))
+      }
+      StmtKind::Nothing => Ok(Statement::Nothing),
+      StmtKind::SyntaxError => Err(String::from("[BUG] A program with syntax errors cannot be interpreted."))
+    }
+  }
+
+  fn let_decl(&mut self, frame: &mut Frame, let_stmt: &LetStmt) -> RtResult<Statement> {
+    let binding = &let_stmt.binding;
+    match binding.kind {
+      Kind::Spacetime(spacetime) => {
+        let init = self.init_expr(frame, binding)?;
+        let uid = self.fresh_uid(&binding.name);
+        frame.locals.insert(binding.uid, Binder::Var(uid.clone()));
+        let body = self.stmt(frame, &let_stmt.body)?;
+        Ok(Statement::VarDecl(Box::new(VarDecl::new(uid, spacetime, init, body))))
+      }
+      Kind::Product => {
+        let process = self.context.process_by_uid(frame.process.clone());
+        let mut refs = HashMap::new();
+        if let Some(info) = process.local_module_vars.iter().find(|m| m.target == binding.uid) {
+          for (field_uid, var) in &info.instantiated_refs {
+            refs.insert(*field_uid, self.resolve_var(frame, var)?);
+          }
+        }
+        let obj = self.new_object(binding.ty.name.clone(), &refs)?;
+        frame.locals.insert(binding.uid, Binder::Object(obj));
+        let body = self.stmt(frame, &let_stmt.body)?;
+        self.wrap_fields(obj, false, body)
+      }
+      Kind::Host => Err(format!("The host variable `{}` cannot be declared in a process by the interpreter.", binding.name))
+    }
+  }
+
+  /// The process is inlined, its parameters are bound to the variables passed as arguments.
+  fn proc_call(&mut self, frame: &mut Frame, target: &Option<Variable>, name: &Ident,
+    args: &[Variable]) -> RtResult<Statement>
+  {
+    let this = match *target {
+      None => frame.this,
+      Some(ref var) => match self.resolve(frame, var)? {
+        Binder::Object(obj) => obj,
+        Binder::Var(_) => return Err(format!("The process `{}` is called on `{}` which is not a module.", name, var.path))
+      }
+    };
+    let (uid, process) = self.context.find_proc_from_call(
+      frame.process.module.clone(), name.clone(), target.clone());
+    let mut locals = HashMap::new();
+    for (param, arg) in process.params.iter().zip(args.iter()) {
+      locals.insert(param.uid, self.resolve(frame, arg)?);
+    }
+    if self.depth >= MAX_CALL_DEPTH {
+      return Err(format!("The process `{}` is called recursively, it cannot be instantiated.", uid));
+    }
+    self.depth += 1;
+    let mut callee = Frame { this, process: uid, locals };
+    let body = self.stmt(&mut callee, &process.body);
+    self.depth -= 1;
+    body
+  }
+}
@@ -0,0 +1,144 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The space machine executes the instants of each layer, it follows `bonsai.runtime.synchronous.SpaceMachine`.
+
+use std::collections::HashMap;
+use interpreter::statement::*;
+use interpreter::memory::Env;
+use interpreter::RtResult;
+
+pub struct SpaceMachine {
+  program: Statement,
+  env: Env
+}
+
+impl SpaceMachine {
+  pub fn new(mut program: Statement) -> Self {
+    program.prepare();
+    let env = Env::new(program.count_layers() + 1);
+    SpaceMachine { program, env }
+  }
+
+  /// Executes the program until it is paused (through `stop` or `pause up`) or terminated.
+  /// Returns `true` if the program is paused, and the output printed so far.
+  pub fn execute(mut self) -> (RtResult<bool>, String) {
+    let res = execute_layer(&mut self.program, &mut self.env, 0)
+      .map(|res| res.k != CompletionCode::Terminate);
+    (res, self.env.output())
+  }
+}
+
+fn causal_error(msg: &str) -> String {
+  format!("Causality error: {}", msg)
+}
+
+fn execute_layer(program: &mut Statement, env: &mut Env, target: usize) -> RtResult<StmtResult> {
+  env.set_target(target)?;
+  let mut res = StmtResult::new(CompletionCode::Pause);
+  while res.k == CompletionCode::Pause {
+    pop_queues(program, env, target)?;
+    program.can_instant(target, env)?;
+    res = execute_instant(program, env, target)?;
+    if res.k == CompletionCode::PauseDown {
+      let sub_res = execute_layer(program, env, target + 1)?;
+      env.set_target(target)?;
+      if sub_res.k.is_internal() {
+        return Err(causal_error("A layer cannot complete its execution on an internal completion code."));
+      }
+      // The sub-layer might have written on variables of the current layer.
+      let remaining = execute_instant(program, env, target)?;
+      res.sequence(remaining);
+      if res.k.is_internal() {
+        return Err(causal_error("The sub-layer has been activated once, but the current instant is still blocked."));
+      }
+    }
+    let k = res.k;
+    let futures = res.unwrap();
+    push_queues(env, target, futures)?;
+    res = StmtResult::new(k);
+    if res.k != CompletionCode::Terminate {
+      res.k = program.end_of_instant(target, env)?;
+    }
+  }
+  Ok(res)
+}
+
+fn push_queues(env: &mut Env, target: usize, futures: HashMap<String, Vec<Future>>) -> RtResult<()> {
+  if futures.is_empty() {
+    return Ok(());
+  }
+  if target == 0 {
+    return Err(String::from("A `space` statement cannot be executed in the top layer, it must be inside a `universe` with a queue."));
+  }
+  env.push(futures)
+}
+
+/// Pops one future from each active queue and executes them, in parallel, in the space they captured.
+fn pop_queues(program: &mut Statement, env: &mut Env, target: usize) -> RtResult<()> {
+  let mut queues: Vec<String> = program.active_queues(target).into_iter().collect();
+  if queues.is_empty() {
+    return Ok(());
+  }
+  queues.sort();
+  let future = Future::merge(env.pop(queues)?);
+  future.space.restore()?;
+  let mut body = future.body;
+  let res = env.with_encapsulated_layer(future.space.space, |env| {
+    body.prepare();
+    body.can_instant(0, env)?;
+    execute_instant(&mut body, env, 0)
+  })?;
+  if res.k != CompletionCode::Terminate {
+    Err(format!("A space statement did not terminate. (code: {})", res.k))
+  }
+  else if !res.branches.is_empty() {
+    Err(String::from("A space statement contains nested space statement."))
+  }
+  else {
+    Ok(())
+  }
+}
+
+/// Executes the program until it is blocked or completes the current instant.
+/// When every process is blocked, we commit the conditions that are decided under the hypothesis that a variable cannot be written anymore.
+fn execute_instant(program: &mut Statement, env: &mut Env, lr: usize) -> RtResult<StmtResult> {
+  let mut res = StmtResult::new(CompletionCode::Wait);
+  env.take_progress();
+  while res.k.is_internal() {
+    let r = program.execute(lr, env)?;
+    res.sequence(r);
+    if res.k.is_internal() && !env.take_progress() && !unblock(program, env, lr)? {
+      if res.k != CompletionCode::PauseDown {
+        return Err(causal_error("The current layer is blocked (every process waits for an event) and no sub-universe can be executed."));
+      }
+      break;
+    }
+  }
+  Ok(res)
+}
+
+fn unblock(program: &mut Statement, env: &mut Env, lr: usize) -> RtResult<bool> {
+  let mut unblocked = false;
+  for uid in env.space().not_readable() {
+    let mut hypothesis = Unblock::new(None);
+    if !program.can_write_on(lr, env, &uid, true, &mut hypothesis)?.can_write && hypothesis.subscribed > 0 {
+      let mut commit = Unblock::new(Some(hypothesis.subscribed - 1));
+      program.can_write_on(lr, env, &uid, true, &mut commit)?;
+      unblocked = true;
+    }
+  }
+  env.take_progress();
+  Ok(unblocked)
+}
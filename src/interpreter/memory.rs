@@ -0,0 +1,345 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The memory of the interpreter: the spacetime variables with their read/write counters, the spaces of each layer and the spaces captured by the futures.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+use interpreter::value::*;
+use interpreter::statement::Future;
+use interpreter::RtResult;
+
+pub type VarRef = Rc<RefCell<Variable>>;
+
+/// Counts the accesses of the processes that can still happen in the current instant.
+#[derive(Clone, Copy, Debug, Default)]
+struct RWCounter {
+  write: i32,
+  readwrite: i32,
+  read: i32
+}
+
+pub struct Variable {
+  uid: String,
+  rw: RWCounter,
+  value: Option<Value>,
+  scopes: usize
+}
+
+impl Variable {
+  fn new(uid: String) -> Self {
+    Variable { uid, rw: RWCounter::default(), value: None, scopes: 0 }
+  }
+
+  pub fn uid(&self) -> &String {
+    &self.uid
+  }
+
+  pub fn value(&self) -> RtResult<Value> {
+    self.value.clone().ok_or_else(|| format!("The variable `{}` is used before its initialization.", self.uid))
+  }
+
+  pub fn value_mut(&mut self) -> RtResult<&mut Value> {
+    let uid = &self.uid;
+    self.value.as_mut().ok_or_else(|| format!("The variable `{}` is used before its initialization.", uid))
+  }
+
+  pub fn set_value(&mut self, value: Value) {
+    self.value = Some(value);
+  }
+
+  pub fn is_readable(&self) -> bool {
+    self.rw.write == 0 && self.rw.readwrite == 0
+  }
+
+  pub fn is_readwritable(&self) -> bool {
+    self.rw.write == 0
+  }
+
+  pub fn join_read(&mut self) { self.rw.read += 1; }
+  pub fn join_readwrite(&mut self) { self.rw.readwrite += 1; }
+  pub fn join_write(&mut self) { self.rw.write += 1; }
+  pub fn meet_read(&mut self) { self.rw.read -= 1; }
+  pub fn meet_readwrite(&mut self) { self.rw.readwrite -= 1; }
+  pub fn meet_write(&mut self) { self.rw.write -= 1; }
+
+  fn enter_scope(&mut self, value: Value) {
+    if self.value.is_none() {
+      self.value = Some(value);
+    }
+    self.scopes += 1;
+  }
+
+  fn exit_scope(&mut self) {
+    if self.scopes > 0 {
+      self.scopes -= 1;
+    }
+  }
+
+  fn is_in_scope(&self) -> bool {
+    self.scopes > 0
+  }
+}
+
+#[derive(Clone, Default)]
+pub struct Space {
+  memory: HashMap<String, VarRef>
+}
+
+impl Space {
+  pub fn new() -> Self {
+    Space::default()
+  }
+
+  pub fn lookup(&self, uid: &str) -> RtResult<VarRef> {
+    self.memory.get(uid).cloned()
+      .ok_or_else(|| format!("[BUG] The variable `{}` is not registered in the current space.", uid))
+  }
+
+  /// Registers a fresh variable `uid`, if `overwrite` is false, a variable already registered is kept.
+  pub fn register(&mut self, uid: &str, overwrite: bool) {
+    if overwrite || !self.memory.contains_key(uid) {
+      self.memory.insert(uid.to_string(), Rc::new(RefCell::new(Variable::new(uid.to_string()))));
+    }
+  }
+
+  pub fn enter_scope(&mut self, uid: &str, value: Value) -> RtResult<()> {
+    self.lookup(uid)?.borrow_mut().enter_scope(value);
+    Ok(())
+  }
+
+  pub fn exit_scope(&mut self, uid: &str) -> RtResult<()> {
+    let var = self.lookup(uid)?;
+    let in_scope = {
+      let mut var = var.borrow_mut();
+      var.exit_scope();
+      var.is_in_scope()
+    };
+    if !in_scope {
+      self.memory.remove(uid);
+    }
+    Ok(())
+  }
+
+  /// The variables `uids` shared with the spaces captured in this instant.
+  pub fn project(&self, uids: &[String]) -> Space {
+    let memory = uids.iter()
+      .filter_map(|uid| self.memory.get(uid).map(|v| (uid.clone(), v.clone())))
+      .collect();
+    Space { memory }
+  }
+
+  pub fn merge(&mut self, other: &Space) {
+    for (uid, var) in &other.memory {
+      self.memory.entry(uid.clone()).or_insert_with(|| var.clone());
+    }
+  }
+
+  /// The variables which might still be written in the current instant, sorted by UID for determinism.
+  pub fn not_readable(&self) -> Vec<String> {
+    let mut uids: Vec<String> = self.memory.iter()
+      .filter(|&(_, v)| !v.borrow().is_readable())
+      .map(|(uid, _)| uid.clone())
+      .collect();
+    uids.sort();
+    uids
+  }
+}
+
+/// A space captured by a `space` statement: the variables with, for the `world_line` variables, the value they had at capture time.
+#[derive(Clone, Default)]
+pub struct CapturedSpace {
+  pub space: Space,
+  labels: HashMap<String, Value>
+}
+
+impl CapturedSpace {
+  pub fn new(space: Space) -> Self {
+    CapturedSpace { space, labels: HashMap::new() }
+  }
+
+  /// Saves the current value of a `world_line` variable, except if it went out of scope without being captured.
+  pub fn register_wl(&mut self, var: &VarRef, exit_scope: bool) -> RtResult<()> {
+    let var = var.borrow();
+    let uid = var.uid().clone();
+    if !exit_scope || self.space.memory.contains_key(&uid) {
+      let value = var.value()?;
+      self.labels.insert(uid, value);
+    }
+    Ok(())
+  }
+
+  /// Restores the `world_line` variables to the values they had at capture time.
+  pub fn restore(&self) -> RtResult<()> {
+    for (uid, value) in &self.labels {
+      if let Some(var) = self.space.memory.get(uid) {
+        var.borrow_mut().set_value(value.clone());
+      }
+    }
+    Ok(())
+  }
+
+  pub fn merge(&mut self, other: &CapturedSpace) {
+    self.space.merge(&other.space);
+    for (uid, value) in &other.labels {
+      self.labels.entry(uid.clone()).or_insert_with(|| value.clone());
+    }
+  }
+}
+
+pub struct Layer {
+  space: Space,
+  current_queue: Option<String>
+}
+
+impl Layer {
+  fn new(space: Space) -> Self {
+    Layer { space, current_queue: None }
+  }
+}
+
+/// The layers of the space machine, `target` is the layer currently executed.
+/// `progress` records that a process might have been unblocked since it was last reset.
+pub struct Env {
+  layers: Vec<Layer>,
+  target: usize,
+  progress: bool,
+  output: String
+}
+
+impl Env {
+  pub fn new(num_layers: usize) -> Self {
+    Env {
+      layers: (0..num_layers).map(|_| Layer::new(Space::new())).collect(),
+      target: 0,
+      progress: false,
+      output: String::new()
+    }
+  }
+
+  pub fn target(&self) -> usize {
+    self.target
+  }
+
+  pub fn set_target(&mut self, target: usize) -> RtResult<()> {
+    if target >= self.layers.len() {
+      return Err(format!("[BUG] The layer {} does not exist (there are {} layers).", target, self.layers.len()));
+    }
+    self.target = target;
+    Ok(())
+  }
+
+  /// Executes `f` on a fresh layer built from `space`, it is used to run the body of a future popped from a queue.
+  pub fn with_encapsulated_layer<F, R>(&mut self, space: Space, f: F) -> RtResult<R> where
+   F: FnOnce(&mut Env) -> RtResult<R>
+  {
+    let previous = self.target;
+    self.layers.push(Layer::new(space));
+    self.target = self.layers.len() - 1;
+    let res = f(self);
+    self.layers.pop();
+    self.target = previous;
+    res
+  }
+
+  pub fn space(&mut self) -> &mut Space {
+    &mut self.layers[self.target].space
+  }
+
+  pub fn lookup(&self, uid: &str) -> RtResult<VarRef> {
+    self.layers[self.target].space.lookup(uid)
+  }
+
+  pub fn current_queue(&self) -> Option<String> {
+    self.layers[self.target].current_queue.clone()
+  }
+
+  pub fn enter_queue(&mut self, uid: &str) -> RtResult<()> {
+    let layer = &mut self.layers[self.target];
+    if layer.current_queue.is_some() {
+      return Err(format!("[BUG] Only one queue per layer is supported (entering `{}`).", uid));
+    }
+    layer.current_queue = Some(uid.to_string());
+    Ok(())
+  }
+
+  pub fn exit_queue(&mut self) {
+    self.layers[self.target].current_queue = None;
+  }
+
+  /// The queue `uid` is stored in the parent of the target layer.
+  fn parent_queue(&self, uid: &str) -> RtResult<VarRef> {
+    if self.target == 0 {
+      return Err(format!("[BUG] The queue `{}` cannot be accessed from the top layer.", uid));
+    }
+    self.layers[self.target - 1].space.lookup(uid)
+  }
+
+  pub fn is_queue_empty(&self, uid: &str) -> RtResult<bool> {
+    let var = self.parent_queue(uid)?;
+    let var = var.borrow();
+    let empty = match var.value {
+      Some(Value::Queue(ref q)) => q.is_empty(),
+      _ => return Err(format!("The variable `{}` used by a universe is not a queue.", uid))
+    };
+    Ok(empty)
+  }
+
+  /// Pushes the futures of each queue in the parent layer.
+  pub fn push(&mut self, futures: HashMap<String, Vec<Future>>) -> RtResult<()> {
+    for (uid, futures) in futures {
+      let var = self.parent_queue(&uid)?;
+      let mut var = var.borrow_mut();
+      match var.value {
+        Some(Value::Queue(ref mut q)) => q.push(futures),
+        _ => return Err(format!("The variable `{}` used by a universe is not a queue.", uid))
+      }
+    }
+    Ok(())
+  }
+
+  /// Pops one future of each queue in `uids`.
+  pub fn pop(&mut self, uids: Vec<String>) -> RtResult<Vec<Future>> {
+    let mut futures = vec![];
+    for uid in uids {
+      let var = self.parent_queue(&uid)?;
+      let mut var = var.borrow_mut();
+      let future = match var.value {
+        Some(Value::Queue(ref mut q)) => q.pop(),
+        _ => return Err(format!("The variable `{}` used by a universe is not a queue.", uid))
+      };
+      futures.push(future.ok_or_else(|| format!("Pop on the empty queue `{}`.", uid))?);
+    }
+    Ok(futures)
+  }
+
+  pub fn mark_progress(&mut self) {
+    self.progress = true;
+  }
+
+  pub fn take_progress(&mut self) -> bool {
+    let progress = self.progress;
+    self.progress = false;
+    progress
+  }
+
+  pub fn print(&mut self, text: &str) {
+    self.output.push_str(text);
+  }
+
+  pub fn output(self) -> String {
+    self.output
+  }
+}
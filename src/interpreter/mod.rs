@@ -0,0 +1,228 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+/// The interpreter executes a process of the analysed `Context` without generating Java code, nor running the JVM.
+/// It follows the synchronous semantics of the Java runtime (`bonsai.runtime.synchronous`), and provides Rust versions of the core lattices (`LMin`, `LMax`, `ES`, `L` and the flat lattices).
+/// The only host method supported is `System.out.print(ln)`, which is captured in the output of `run_process`.
+
+pub mod value;
+pub mod memory;
+pub mod expression;
+pub mod statement;
+pub mod machine;
+pub mod instantiate;
+
+use std::fmt::{Display, Error, Formatter};
+use context::Context;
+use driver::config::MainMethod;
+use interpreter::machine::SpaceMachine;
+
+pub type RtResult<T> = Result<T, String>;
+
+/// A runtime error, with the output printed before it occurred.
+#[derive(Clone, Debug)]
+pub struct InterpreterError {
+  pub message: String,
+  pub output: String
+}
+
+impl Display for InterpreterError {
+  fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+    write!(fmt, "{}", self.message)
+  }
+}
+
+/// Executes the process `main` until it terminates or is paused, and returns what it printed.
+pub fn run_process(context: &Context, main: &MainMethod) -> Result<String, InterpreterError> {
+  let program = instantiate::instantiate(context, &main.class, &main.method)
+    .map_err(|message| InterpreterError { message, output: String::new() })?;
+  let (res, output) = SpaceMachine::new(program).execute();
+  match res {
+    Ok(_) => Ok(output),
+    Err(message) => Err(InterpreterError { message, output })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::rc::Rc;
+  use partial::*;
+  use driver::{collect_diagnostics, front_mid_run};
+  use driver::config::ConfigBuilder;
+  use driver::file_system::VirtualFileSystem;
+
+  /// Executes the process `process` of a module `T` containing `procs`.
+  fn run(procs: &str, process: &str) -> Result<String, InterpreterError> {
+    let mut vfs = VirtualFileSystem::new();
+    vfs.add_file("/interpreter/test/T.bonsai.java", format!(
+      "package test;\n\n\
+       import java.lang.System;\n\
+       import bonsai.runtime.lattices.*;\n\
+       import bonsai.runtime.queueing.*;\n\n\
+       public class T\n{{\n{}\n}}\n", procs));
+    let config = ConfigBuilder::new("/interpreter")
+      .output("/interpreter/out")
+      .build();
+    let (context, _, diagnostics) = collect_diagnostics(config, Rc::new(vfs), front_mid_run);
+    match context {
+      Partial::Value(context) =>
+        run_process(&context, &MainMethod::new(String::from("T"), String::from(process))),
+      _ => panic!("`T.{}` does not compile: {:?}", process, diagnostics)
+    }
+  }
+
+  fn output(procs: &str, process: &str) -> String {
+    run(procs, process).unwrap_or_else(|e|
+      panic!("`T.{}` failed: {} (output: `{}`)", process, e.message, e.output))
+  }
+
+  #[test]
+  fn pause() {
+    let procs = "
+      public proc p() =
+        System.out.print(1);
+        pause;
+        System.out.print(2);
+        pause; pause;
+        System.out.print(3)
+      end";
+    assert_eq!(output(procs, "p"), "123");
+  }
+
+  #[test]
+  fn stop() {
+    let procs = "
+      public proc p() =
+        System.out.print(1);
+        pause;
+        System.out.print(2);
+        stop;
+        System.out.print(3)
+      end";
+    assert_eq!(output(procs, "p"), "12");
+  }
+
+  #[test]
+  fn pause_up() {
+    let procs = "
+      public proc top() = pause up; System.out.print(1) end
+      public proc inUniverse() =
+        universe
+          System.out.print(1);
+          pause up;
+          System.out.print(2)
+        end;
+        System.out.print(3)
+      end";
+    assert_eq!(output(procs, "top"), "");
+    assert_eq!(output(procs, "inUniverse"), "123");
+  }
+
+  #[test]
+  fn par() {
+    let procs = "
+      public proc conj() =
+        single_time LMax x = new LMax(0);
+        par System.out.print(read x) || readwrite x.inc() end;
+        System.out.print(read x)
+      end
+      public proc conjPause() =
+        single_space LMax x = new LMax(0);
+        par System.out.print(read x) || pause; readwrite x.inc() end;
+        System.out.print(read x)
+      end
+      public proc disjPause() =
+        single_space LMax x = new LMax(0);
+        par System.out.print(read x) <> pause; readwrite x.inc() end;
+        System.out.print(read x)
+      end
+      public proc stopInPar() =
+        par System.out.print(1) || stop end;
+        System.out.print(2)
+      end";
+    assert_eq!(output(procs, "conj"), "11");
+    assert_eq!(output(procs, "conjPause"), "01");
+    assert_eq!(output(procs, "disjPause"), "00");
+    assert_eq!(output(procs, "stopInPar"), "1");
+  }
+
+  #[test]
+  fn space() {
+    let procs = "
+      public proc children() =
+        single_space StackLR stack = new StackLR();
+        universe with stack in
+          single_space LMax count = new LMax(0);
+          space readwrite count.inc() end;
+          space readwrite count.inc() end;
+          System.out.print(read count);
+          pause;
+          System.out.print(read count);
+          pause;
+          System.out.print(read count);
+        end
+      end
+      public proc noChild() =
+        single_space StackLR stack = new StackLR();
+        universe with stack in
+          pause;
+          System.out.print(\"unreachable\")
+        end;
+        System.out.print(1)
+      end";
+    assert_eq!(output(procs, "children"), "012");
+    assert_eq!(output(procs, "noChild"), "1");
+  }
+
+  #[test]
+  fn prune() {
+    let procs = "
+      public proc pruneOnly() =
+        single_space StackLR stack = new StackLR();
+        universe with stack in
+          prune; System.out.print(1); prune
+        end
+      end
+      public proc spacePrune() =
+        single_space StackLR stack = new StackLR();
+        universe with stack in
+          space System.out.print(1) end; prune;
+          pause;
+          System.out.print(2);
+          pause;
+          System.out.print(\"unreachable\")
+        end
+      end
+      public proc parSpacePrune() =
+        single_space StackLR stack = new StackLR();
+        universe with stack in
+          par space System.out.print(1) end <> prune end;
+          pause;
+          System.out.print(\"unreachable\")
+        end
+      end";
+    assert_eq!(output(procs, "pruneOnly"), "1");
+    assert_eq!(output(procs, "spacePrune"), "12");
+    assert_eq!(output(procs, "parSpacePrune"), "");
+  }
+
+  #[test]
+  fn process_with_parameters() {
+    let procs = "proc p(single_space LMax x) = readwrite x.inc() end";
+    let err = run(procs, "p").unwrap_err();
+    assert!(err.message.contains("has parameters"), "{}", err.message);
+  }
+}
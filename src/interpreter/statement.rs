@@ -0,0 +1,1645 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The statements of the interpreter, each one follows the class of the same name in `bonsai.runtime.synchronous.statements`.
+/// A statement is executed in the layer `layers_remaining` below it, `0` being the layer of the statement itself.
+/// In contrast to the Java runtime, `execute` returns only the branches created during the call, the parents accumulate them.
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::fmt::{Display, Error, Formatter};
+use std::mem;
+use ast::{DelayKind, Spacetime};
+use interpreter::value::{Kleene, Value};
+use interpreter::memory::{Env, Space, CapturedSpace, VarRef};
+use interpreter::expression::*;
+use interpreter::RtResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompletionCode {
+  PauseDown,
+  Wait,
+  Stop,
+  PauseUp,
+  Pause,
+  Terminate
+}
+
+impl CompletionCode {
+  pub fn merge(self, other: CompletionCode) -> Self {
+    self.min(other)
+  }
+
+  pub fn is_internal(self) -> bool {
+    self == CompletionCode::Wait || self == CompletionCode::PauseDown
+  }
+}
+
+impl Display for CompletionCode {
+  fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+    let code = match *self {
+      CompletionCode::PauseDown => "PAUSE_DOWN",
+      CompletionCode::Wait => "WAIT",
+      CompletionCode::Stop => "STOP",
+      CompletionCode::PauseUp => "PAUSE_UP",
+      CompletionCode::Pause => "PAUSE",
+      CompletionCode::Terminate => "TERMINATE"
+    };
+    fmt.write_str(code)
+  }
+}
+
+/// A statement captured by `space` together with the variables it can access.
+#[derive(Clone)]
+pub struct Future {
+  pub body: Statement,
+  pub space: CapturedSpace
+}
+
+impl Future {
+  pub fn merge(futures: Vec<Future>) -> Future {
+    let mut space = CapturedSpace::default();
+    let mut processes = vec![];
+    for future in futures {
+      space.merge(&future.space);
+      processes.push(future.body);
+    }
+    let body =
+      if processes.len() > 1 { Statement::Par(Box::new(Parallel::new(false, processes))) }
+      else { processes.pop().expect("[BUG] Future::merge: no future to merge.") };
+    Future { body, space }
+  }
+}
+
+/// A sequence of branches where `None` is a pruned branch.
+#[derive(Clone, Default)]
+pub struct BranchAlgebra {
+  branches: Vec<Option<Statement>>,
+  space: CapturedSpace
+}
+
+impl BranchAlgebra {
+  pub fn space_branch(branch: Statement, space: CapturedSpace) -> Self {
+    BranchAlgebra { branches: vec![Some(branch)], space }
+  }
+
+  pub fn pruned_branch() -> Self {
+    BranchAlgebra { branches: vec![None], space: CapturedSpace::default() }
+  }
+
+  fn concat(&mut self, right: BranchAlgebra) {
+    self.branches.extend(right.branches);
+    self.space.merge(&right.space);
+  }
+
+  fn merge(mut processes: Vec<BranchAlgebra>, conjunctive: bool) -> BranchAlgebra {
+    let mut captured_space = CapturedSpace::default();
+    for ba in &processes {
+      captured_space.merge(&ba.space);
+    }
+    processes.retain(|ba| !ba.branches.is_empty());
+    let max = processes.iter().map(|ba| ba.branches.len()).max().unwrap_or(0);
+    for ba in &mut processes {
+      while ba.branches.len() < max {
+        let last = ba.branches[ba.branches.len() - 1].clone();
+        ba.branches.push(last);
+      }
+    }
+    let mut res = BranchAlgebra::default();
+    for i in 0..max {
+      let pruned =
+        if conjunctive { processes.iter().any(|ba| ba.branches[i].is_none()) }
+        else { processes.iter().all(|ba| ba.branches[i].is_none()) };
+      if pruned {
+        res.concat(BranchAlgebra { branches: vec![None], space: captured_space.clone() });
+      }
+      else {
+        let par = processes.iter()
+          .filter_map(|ba| ba.branches[i].clone())
+          .collect();
+        let par = Statement::Par(Box::new(Parallel::new(conjunctive, par)));
+        res.concat(BranchAlgebra::space_branch(par, captured_space.clone()));
+      }
+    }
+    res
+  }
+
+  /// The futures of the branches that are not pruned.
+  fn unwrap(self) -> Vec<Future> {
+    let space = self.space;
+    self.branches.into_iter()
+      .filter_map(|b| b.map(|body| Future { body, space: space.clone() }))
+      .collect()
+  }
+}
+
+#[derive(Clone)]
+pub struct StmtResult {
+  pub k: CompletionCode,
+  pub branches: HashMap<String, BranchAlgebra>
+}
+
+impl StmtResult {
+  pub fn new(k: CompletionCode) -> Self {
+    StmtResult { k, branches: HashMap::new() }
+  }
+
+  fn with_branch(k: CompletionCode, queue: String, ba: BranchAlgebra) -> Self {
+    let mut res = StmtResult::new(k);
+    res.branches.insert(queue, ba);
+    res
+  }
+
+  fn register_wl(&mut self, queue: String, var: &VarRef, exit_scope: bool) -> RtResult<()> {
+    self.branches.entry(queue).or_insert_with(BranchAlgebra::default)
+      .space.register_wl(var, exit_scope)
+  }
+
+  /// `self` is followed by `res`: the completion code of `res` is kept and the branches are concatenated.
+  pub fn sequence(&mut self, res: StmtResult) {
+    self.k = res.k;
+    for (queue, ba) in res.branches {
+      match self.branches.entry(queue) {
+        Entry::Occupied(mut left) => left.get_mut().concat(ba),
+        Entry::Vacant(entry) => { entry.insert(ba); }
+      }
+    }
+  }
+
+  /// Merges the results of parallel processes, the branches are merged only when all processes have completed the instant.
+  fn par(processes: &mut Vec<StmtResult>, conjunctive: bool) -> StmtResult {
+    let mut res = StmtResult::new(CompletionCode::Terminate);
+    let mut queues = HashSet::new();
+    for process in processes.iter() {
+      res.k = res.k.merge(process.k);
+      queues.extend(process.branches.keys().cloned());
+    }
+    if !res.k.is_internal() {
+      for queue in queues {
+        let bas = processes.iter_mut()
+          .filter_map(|p| p.branches.remove(&queue))
+          .collect();
+        res.branches.insert(queue, BranchAlgebra::merge(bas, conjunctive));
+      }
+    }
+    res
+  }
+
+  /// The futures to push on each queue.
+  pub fn unwrap(self) -> HashMap<String, Vec<Future>> {
+    self.branches.into_iter()
+      .map(|(queue, ba)| (queue, ba.unwrap()))
+      .collect()
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CanWriteOn {
+  pub can_terminate: bool,
+  pub can_write: bool
+}
+
+impl CanWriteOn {
+  pub fn new(can_terminate: bool, can_write: bool) -> Self {
+    CanWriteOn { can_terminate, can_write }
+  }
+
+  fn join(self, other: CanWriteOn) -> Self {
+    CanWriteOn::new(self.can_terminate && other.can_terminate, self.can_write || other.can_write)
+  }
+}
+
+/// Records the conditions unblocked by the hypothesis that a variable cannot be written anymore.
+/// Only the last condition subscribed is committed, as in `Scheduler.subscribeUnblocked`.
+pub struct Unblock {
+  pub subscribed: usize,
+  commit: Option<usize>
+}
+
+impl Unblock {
+  pub fn new(commit: Option<usize>) -> Self {
+    Unblock { subscribed: 0, commit }
+  }
+
+  fn subscribe(&mut self, cond: &mut Condition, result: Kleene, env: &mut Env) {
+    if self.commit == Some(self.subscribed) {
+      cond.commit(result, env);
+    }
+    self.subscribed += 1;
+  }
+}
+
+fn check_no_sub_layer(layers_remaining: usize, stmt: &str) -> RtResult<()> {
+  if layers_remaining > 0 {
+    Err(format!("[BUG] The statement `{}` does not have sub-layers (layers remaining: {}).", stmt, layers_remaining))
+  }
+  else { Ok(()) }
+}
+
+fn bug_eoi(stmt: &str) -> String {
+  format!("[BUG] `{}` cannot be active at the end of the instant in its current state.", stmt)
+}
+
+#[derive(Clone)]
+pub enum Statement {
+  Nothing,
+  Delay(Delay),
+  Call(ProcedureCall),
+  Seq(Box<Sequence>),
+  Par(Box<Parallel>),
+  Loop(Box<Loop>),
+  When(Box<WhenElse>),
+  Suspend(Box<SuspendWhen>),
+  Abort(Box<AbortWhen>),
+  VarDecl(Box<VarDecl>),
+  Space(Box<SpaceStmt>),
+  Prune,
+  Universe(Box<Universe>)
+}
+
+macro_rules! dispatch {
+  ($stmt:expr, $s:ident => $e:expr, $nothing:expr) => {
+    match $stmt {
+      Statement::Nothing | Statement::Prune => $nothing,
+      Statement::Delay(ref mut $s) => $e,
+      Statement::Call(ref mut $s) => $e,
+      Statement::Seq(ref mut $s) => $e,
+      Statement::Par(ref mut $s) => $e,
+      Statement::Loop(ref mut $s) => $e,
+      Statement::When(ref mut $s) => $e,
+      Statement::Suspend(ref mut $s) => $e,
+      Statement::Abort(ref mut $s) => $e,
+      Statement::VarDecl(ref mut $s) => $e,
+      Statement::Space(ref mut $s) => $e,
+      Statement::Universe(ref mut $s) => $e
+    }
+  }
+}
+
+macro_rules! dispatch_ref {
+  ($stmt:expr, $s:ident => $e:expr, $nothing:expr) => {
+    match $stmt {
+      Statement::Nothing | Statement::Prune => $nothing,
+      Statement::Delay(ref $s) => $e,
+      Statement::Call(ref $s) => $e,
+      Statement::Seq(ref $s) => $e,
+      Statement::Par(ref $s) => $e,
+      Statement::Loop(ref $s) => $e,
+      Statement::When(ref $s) => $e,
+      Statement::Suspend(ref $s) => $e,
+      Statement::Abort(ref $s) => $e,
+      Statement::VarDecl(ref $s) => $e,
+      Statement::Space(ref $s) => $e,
+      Statement::Universe(ref $s) => $e
+    }
+  }
+}
+
+impl Statement {
+  pub fn prepare(&mut self) {
+    dispatch!(*self, s => s.prepare(), ())
+  }
+
+  pub fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    dispatch!(*self, s => s.can_instant(lr, env), check_no_sub_layer(lr, "nothing"))
+  }
+
+  pub fn active_queues(&self, lr: usize) -> HashSet<String> {
+    dispatch_ref!(*self, s => s.active_queues(lr), HashSet::new())
+  }
+
+  pub fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    dispatch!(*self, s => s.end_of_instant(lr, env), Err(bug_eoi("nothing")))
+  }
+
+  pub fn can_terminate(&self) -> bool {
+    dispatch_ref!(*self, s => s.can_terminate(), true)
+  }
+
+  pub fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    dispatch!(*self, s => s.abort(env), Ok(()))
+  }
+
+  pub fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    dispatch!(*self, s => s.suspend(env), Ok(()))
+  }
+
+  pub fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    match *self {
+      Statement::Nothing => {
+        check_no_sub_layer(lr, "nothing")?;
+        Ok(StmtResult::new(CompletionCode::Terminate))
+      }
+      Statement::Prune => {
+        check_no_sub_layer(lr, "prune")?;
+        let queue = current_queue(env, "prune")?;
+        Ok(StmtResult::with_branch(CompletionCode::Terminate, queue, BranchAlgebra::pruned_branch()))
+      }
+      _ => dispatch!(*self, s => s.execute(lr, env), unreachable!())
+    }
+  }
+
+  pub fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    dispatch!(*self, s => s.can_write_on(lr, env, uid, in_surface, unblock), Ok(CanWriteOn::new(true, false)))
+  }
+
+  pub fn count_layers(&self) -> usize {
+    dispatch_ref!(*self, s => s.count_layers(), 0)
+  }
+}
+
+fn current_queue(env: &Env, stmt: &str) -> RtResult<String> {
+  env.current_queue().ok_or_else(|| format!(
+    "`{}` must be executed inside a `universe` with a queue, and not inside a future.", stmt))
+}
+
+#[derive(Clone)]
+pub struct Delay {
+  kind: CompletionCode,
+  k: CompletionCode,
+  next_instant: bool
+}
+
+impl Delay {
+  pub fn new(kind: DelayKind) -> Self {
+    let kind = match kind {
+      DelayKind::Pause => CompletionCode::Pause,
+      DelayKind::PauseUp => CompletionCode::PauseUp,
+      DelayKind::Stop => CompletionCode::Stop
+    };
+    Delay { kind, k: CompletionCode::Wait, next_instant: false }
+  }
+
+  fn prepare(&mut self) {
+    self.k = CompletionCode::Wait;
+    self.next_instant = false;
+  }
+
+  fn can_instant(&mut self, lr: usize, _env: &mut Env) -> RtResult<()> {
+    check_no_sub_layer(lr, "delay")?;
+    self.next_instant = true;
+    Ok(())
+  }
+
+  fn active_queues(&self, _lr: usize) -> HashSet<String> { HashSet::new() }
+
+  fn end_of_instant(&mut self, lr: usize, _env: &mut Env) -> RtResult<CompletionCode> {
+    check_no_sub_layer(lr, "delay")?;
+    Ok(self.k)
+  }
+
+  fn can_terminate(&self) -> bool {
+    self.k == CompletionCode::Terminate || (self.k == self.kind && self.next_instant)
+  }
+
+  fn abort(&mut self, _env: &mut Env) -> RtResult<()> { Ok(()) }
+  fn suspend(&mut self, _env: &mut Env) -> RtResult<()> { Ok(()) }
+
+  fn execute(&mut self, lr: usize, _env: &mut Env) -> RtResult<StmtResult> {
+    check_no_sub_layer(lr, "delay")?;
+    if self.k == CompletionCode::Wait {
+      self.k = self.kind;
+    }
+    else if self.k == self.kind {
+      self.k = CompletionCode::Terminate;
+    }
+    Ok(StmtResult::new(self.k))
+  }
+
+  fn can_write_on(&mut self, _lr: usize, _env: &mut Env, _uid: &str, _in_surface: bool,
+    _unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    Ok(CanWriteOn::new(false, false))
+  }
+
+  fn count_layers(&self) -> usize { 0 }
+}
+
+#[derive(Clone)]
+pub struct ProcedureCall {
+  call: NAryCall,
+  k: CompletionCode
+}
+
+impl ProcedureCall {
+  pub fn new(call: NAryCall) -> Self {
+    ProcedureCall { call, k: CompletionCode::Wait }
+  }
+
+  fn prepare(&mut self) {
+    self.k = CompletionCode::Wait;
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    check_no_sub_layer(lr, "procedure call")?;
+    self.call.can_instant(env)
+  }
+
+  fn active_queues(&self, _lr: usize) -> HashSet<String> { HashSet::new() }
+
+  fn end_of_instant(&mut self, _lr: usize, _env: &mut Env) -> RtResult<CompletionCode> {
+    Err(bug_eoi("procedure call"))
+  }
+
+  fn can_terminate(&self) -> bool { true }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    if self.k == CompletionCode::Wait {
+      self.call.terminate(env)?;
+    }
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    self.abort(env)
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    check_no_sub_layer(lr, "procedure call")?;
+    if self.k == CompletionCode::Wait {
+      if self.call.execute(env)?.is_some() {
+        self.call.terminate(env)?;
+        self.k = CompletionCode::Terminate;
+        env.mark_progress();
+      }
+    }
+    Ok(StmtResult::new(self.k))
+  }
+
+  fn can_write_on(&mut self, _lr: usize, _env: &mut Env, uid: &str, _in_surface: bool,
+    _unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    let can_write = self.k == CompletionCode::Wait && self.call.can_write_on(uid);
+    Ok(CanWriteOn::new(true, can_write))
+  }
+
+  fn count_layers(&self) -> usize { 0 }
+}
+
+#[derive(Clone)]
+pub struct Sequence {
+  seq: Vec<Statement>,
+  pc: usize,
+  reachable: usize,
+  k: CompletionCode
+}
+
+impl Sequence {
+  pub fn new(seq: Vec<Statement>) -> Self {
+    Sequence { seq, pc: 0, reachable: 0, k: CompletionCode::Terminate }
+  }
+
+  fn prepare(&mut self) {
+    for s in &mut self.seq {
+      s.prepare();
+    }
+    self.pc = 0;
+    self.reachable = 0;
+    self.k = CompletionCode::Terminate;
+  }
+
+  fn terminated(&self) -> bool {
+    self.pc >= self.seq.len()
+  }
+
+  /// Applies `f` on the statements from `pc` that can be reached in the current instant, and returns the index of the last one.
+  fn reachable_subsequence<F>(&mut self, env: &mut Env, mut f: F) -> RtResult<usize> where
+   F: FnMut(&mut Statement, &mut Env) -> RtResult<()>
+  {
+    let mut last = self.pc;
+    for i in self.pc..self.seq.len() {
+      last = i;
+      f(&mut self.seq[i], env)?;
+      if !self.seq[i].can_terminate() {
+        break;
+      }
+    }
+    Ok(last)
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    if self.terminated() { return Ok(()); }
+    if lr == 0 {
+      self.reachable = self.reachable_subsequence(env, |s, env| s.can_instant(0, env))?;
+      Ok(())
+    }
+    else {
+      self.seq[self.pc].can_instant(lr, env)
+    }
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    if lr == 0 || self.terminated() { HashSet::new() }
+    else { self.seq[self.pc].active_queues(lr) }
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    if self.terminated() {
+      return Err(bug_eoi("sequence"));
+    }
+    if lr == 0 {
+      let k = self.seq[self.pc].end_of_instant(0, env)?;
+      if k == CompletionCode::Terminate {
+        self.pc += 1;
+        if self.terminated() {
+          self.k = CompletionCode::Terminate;
+        }
+        else {
+          self.k = self.seq[self.pc].end_of_instant(0, env)?;
+          for i in self.pc..(self.reachable + 1).min(self.seq.len()) {
+            self.seq[i].abort(env)?;
+          }
+        }
+      }
+      Ok(self.k)
+    }
+    else {
+      self.seq[self.pc].end_of_instant(lr, env)
+    }
+  }
+
+  fn can_terminate(&self) -> bool {
+    let mut can_terminate = true;
+    for s in &self.seq[self.pc.min(self.seq.len())..] {
+      can_terminate = s.can_terminate();
+      if !can_terminate {
+        break;
+      }
+    }
+    can_terminate
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    self.reachable_subsequence(env, |s, env| s.abort(env))?;
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    self.reachable_subsequence(env, |s, env| s.suspend(env))?;
+    Ok(())
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr == 0 {
+      let mut res = StmtResult::new(self.k);
+      while !self.terminated() {
+        let r = self.seq[self.pc].execute(0, env)?;
+        res.sequence(r);
+        if res.k == CompletionCode::Terminate {
+          self.pc += 1;
+        }
+        else {
+          break;
+        }
+      }
+      self.k = res.k;
+      Ok(res)
+    }
+    else if self.terminated() {
+      Err(String::from("[BUG] A terminated sequence cannot be executed in a sub-layer."))
+    }
+    else {
+      self.seq[self.pc].execute(lr, env)
+    }
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    if self.terminated() {
+      return Ok(CanWriteOn::new(true, false));
+    }
+    if lr == 0 {
+      let mut res = self.seq[self.pc].can_write_on(0, env, uid, in_surface, unblock)?;
+      let mut i = self.pc + 1;
+      while i < self.seq.len() && res.can_terminate && !res.can_write {
+        res = res.join(self.seq[i].can_write_on(0, env, uid, false, unblock)?);
+        i += 1;
+      }
+      Ok(res)
+    }
+    else {
+      self.seq[self.pc].can_write_on(lr, env, uid, in_surface, unblock)
+    }
+  }
+
+  fn count_layers(&self) -> usize {
+    self.seq.iter().map(|s| s.count_layers()).max().unwrap_or(0)
+  }
+}
+
+/// The state of a parallel statement in one layer: `members` are the indexes of the processes having this layer.
+#[derive(Clone)]
+struct ParLayer {
+  members: Vec<usize>,
+  results: Vec<StmtResult>,
+  active: Vec<usize>
+}
+
+impl ParLayer {
+  fn new(members: Vec<usize>) -> Self {
+    let mut layer = ParLayer { members, results: vec![], active: vec![] };
+    layer.init();
+    layer
+  }
+
+  fn init(&mut self) {
+    self.results = self.members.iter().map(|_| StmtResult::new(CompletionCode::Wait)).collect();
+    self.active = (0..self.members.len()).collect();
+  }
+
+  fn merge_k(&self) -> CompletionCode {
+    self.results.iter().fold(CompletionCode::Terminate, |k, r| k.merge(r.k))
+  }
+}
+
+/// `p1 <> ... <> pN` (conjunctive) and `p1 || ... || pN` (disjunctive), with one state for each layer as in `LayeredParallel`.
+#[derive(Clone)]
+pub struct Parallel {
+  conjunctive: bool,
+  processes: Vec<Statement>,
+  layers: Vec<ParLayer>
+}
+
+impl Parallel {
+  pub fn new(conjunctive: bool, processes: Vec<Statement>) -> Self {
+    let num_layers = processes.iter().map(|s| s.count_layers()).max().unwrap_or(0);
+    let layers = (0..(num_layers + 1))
+      .map(|idx| ParLayer::new((0..processes.len())
+        .filter(|&i| processes[i].count_layers() >= idx)
+        .collect()))
+      .collect();
+    Parallel { conjunctive, processes, layers }
+  }
+
+  fn layer(&self, lr: usize) -> RtResult<&ParLayer> {
+    self.layers.get(lr).ok_or_else(|| format!("[BUG] The parallel statement does not have the layer {}.", lr))
+  }
+
+  fn prepare(&mut self) {
+    for p in &mut self.processes {
+      p.prepare();
+    }
+    for layer in &mut self.layers {
+      layer.init();
+    }
+  }
+
+  /// The indexes of the active processes in the layer `lr`.
+  fn active(&self, lr: usize) -> Vec<(usize, usize)> {
+    let layer = &self.layers[lr];
+    layer.active.iter().map(|&m| (m, layer.members[m])).collect()
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    self.layer(lr)?;
+    {
+      let layer = &mut self.layers[lr];
+      let results = &layer.results;
+      layer.active = (0..layer.members.len())
+        .filter(|&m| results[m].k != CompletionCode::Terminate)
+        .collect();
+    }
+    for (_, p) in self.active(lr) {
+      self.processes[p].can_instant(lr, env)?;
+    }
+    Ok(())
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    let mut queues = HashSet::new();
+    if lr < self.layers.len() {
+      for (_, p) in self.active(lr) {
+        queues.extend(self.processes[p].active_queues(lr));
+      }
+    }
+    queues
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    self.layer(lr)?;
+    for (m, p) in self.active(lr) {
+      let k = self.processes[p].end_of_instant(lr, env)?;
+      self.layers[lr].results[m].k = k;
+    }
+    let layer = &mut self.layers[lr];
+    let k = layer.merge_k();
+    if self.conjunctive && layer.results.iter().any(|r| r.k == CompletionCode::Terminate) {
+      for r in &mut layer.results {
+        r.k = CompletionCode::Terminate;
+      }
+      layer.active.clear();
+    }
+    Ok(k)
+  }
+
+  fn can_terminate(&self) -> bool {
+    self.active(0).into_iter().all(|(_, p)| self.processes[p].can_terminate())
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    for (_, p) in self.active(0) {
+      self.processes[p].abort(env)?;
+    }
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    for (_, p) in self.active(0) {
+      self.processes[p].suspend(env)?;
+    }
+    Ok(())
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    self.layer(lr)?;
+    for (m, p) in self.active(lr) {
+      let res = self.processes[p].execute(lr, env)?;
+      self.layers[lr].results[m].sequence(res);
+    }
+    let layer = &mut self.layers[lr];
+    {
+      let results = &layer.results;
+      layer.active.retain(|&m| results[m].k.is_internal());
+    }
+    Ok(StmtResult::par(&mut layer.results, self.conjunctive))
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    self.layer(lr)?;
+    let mut res = CanWriteOn::new(true, false);
+    for (_, p) in self.active(lr) {
+      res = res.join(self.processes[p].can_write_on(lr, env, uid, in_surface, unblock)?);
+    }
+    Ok(res)
+  }
+
+  fn count_layers(&self) -> usize {
+    self.layers.len() - 1
+  }
+}
+
+#[derive(Clone)]
+pub struct Loop {
+  body: Statement,
+  surface_body: Statement,
+  k: CompletionCode
+}
+
+impl Loop {
+  /// `body` and `surface_body` are two incarnations of the loop body, they are swapped at each iteration.
+  pub fn new(body: Statement, surface_body: Statement) -> Self {
+    Loop { body, surface_body, k: CompletionCode::Wait }
+  }
+
+  fn prepare(&mut self) {
+    self.body.prepare();
+    self.surface_body.prepare();
+    self.k = CompletionCode::Wait;
+  }
+
+  fn go_to_beginning(&mut self) {
+    mem::swap(&mut self.body, &mut self.surface_body);
+    self.surface_body.prepare();
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    self.k = CompletionCode::Wait;
+    self.body.can_instant(lr, env)?;
+    if lr == 0 && self.body.can_terminate() {
+      self.surface_body.can_instant(lr, env)?;
+    }
+    Ok(())
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    self.body.active_queues(lr)
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    let k = self.body.end_of_instant(lr, env)?;
+    if lr == 0 && k == CompletionCode::Terminate {
+      self.go_to_beginning();
+      Ok(CompletionCode::Pause)
+    }
+    else { Ok(k) }
+  }
+
+  fn can_terminate(&self) -> bool {
+    self.k == CompletionCode::Terminate
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    self.k = CompletionCode::Terminate;
+    self.body.abort(env)
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    self.body.suspend(env)
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr == 0 {
+      let mut res = StmtResult::new(self.k);
+      if self.k != CompletionCode::Terminate {
+        res.sequence(self.body.execute(0, env)?);
+        if res.k == CompletionCode::Terminate {
+          self.go_to_beginning();
+          res.sequence(self.body.execute(0, env)?);
+        }
+      }
+      self.k = res.k;
+      Ok(res)
+    }
+    else {
+      self.body.execute(lr, env)
+    }
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    let res = self.body.can_write_on(lr, env, uid, in_surface, unblock)?;
+    if lr == 0 && res.can_terminate {
+      Ok(res.join(self.surface_body.can_write_on(lr, env, uid, false, unblock)?))
+    }
+    else { Ok(res) }
+  }
+
+  fn count_layers(&self) -> usize {
+    self.body.count_layers()
+  }
+}
+
+/// The state of the condition of `when`, `suspend` and `abort` in the current instant.
+#[derive(Clone)]
+struct CondState {
+  cond: Condition,
+  result: Option<Kleene>
+}
+
+impl CondState {
+  fn new(cond: Condition) -> Self {
+    CondState { cond, result: None }
+  }
+
+  fn value(&self) -> Kleene {
+    self.result.unwrap_or(Kleene::Unknown)
+  }
+
+  fn reset(&mut self) {
+    self.result = None;
+    self.cond.can_instant();
+  }
+
+  fn execute(&mut self, env: &mut Env) -> RtResult<bool> {
+    self.result = self.cond.execute(env)?;
+    Ok(self.result.is_some())
+  }
+
+  /// The result of the condition under the hypothesis that `uid` cannot be written anymore, the condition is subscribed to be committed.
+  fn hypothesis(&mut self, env: &mut Env, uid: &str, unblock: &mut Unblock) -> RtResult<Option<Kleene>> {
+    let k = self.cond.hypothesis(env, uid)?;
+    if let Some(k) = k {
+      unblock.subscribe(&mut self.cond, k, env);
+    }
+    Ok(k)
+  }
+}
+
+#[derive(Clone)]
+pub struct WhenElse {
+  cond: CondState,
+  then: Statement,
+  els: Statement,
+  k: CompletionCode
+}
+
+impl WhenElse {
+  pub fn new(cond: Condition, then: Statement, els: Statement) -> Self {
+    WhenElse { cond: CondState::new(cond), then, els, k: CompletionCode::Wait }
+  }
+
+  fn state1(&self) -> bool { self.k != CompletionCode::Terminate && self.cond.value() == Kleene::Unknown }
+  fn state2a(&self) -> bool { self.k != CompletionCode::Terminate && self.cond.value() == Kleene::True }
+  fn state2b(&self) -> bool { self.k != CompletionCode::Terminate && self.cond.value() == Kleene::False }
+
+  fn branch(&mut self) -> Option<&mut Statement> {
+    if self.state2a() { Some(&mut self.then) }
+    else if self.state2b() { Some(&mut self.els) }
+    else { None }
+  }
+
+  fn branch_ref(&self) -> Option<&Statement> {
+    if self.state2a() { Some(&self.then) }
+    else if self.state2b() { Some(&self.els) }
+    else { None }
+  }
+
+  fn prepare(&mut self) {
+    self.then.prepare();
+    self.els.prepare();
+    self.cond.result = None;
+    self.k = CompletionCode::Wait;
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    let can_cond = lr == 0 && self.state1();
+    if can_cond {
+      self.cond.cond.can_instant();
+    }
+    if can_cond || self.state2a() {
+      self.then.can_instant(lr, env)?;
+    }
+    if can_cond || self.state2b() {
+      self.els.can_instant(lr, env)?;
+    }
+    Ok(())
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    self.branch_ref().map(|s| s.active_queues(lr)).unwrap_or_default()
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    if self.state1() || self.k == CompletionCode::Terminate {
+      return Err(bug_eoi("when"));
+    }
+    let k = self.branch().expect("unreachable").end_of_instant(lr, env)?;
+    if lr == 0 {
+      self.k = k;
+    }
+    Ok(k)
+  }
+
+  fn can_terminate(&self) -> bool {
+    if self.state1() { self.then.can_terminate() || self.els.can_terminate() }
+    else { self.branch_ref().map(|s| s.can_terminate()).unwrap_or(true) }
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    let state1 = self.state1();
+    if state1 || self.state2a() { self.then.abort(env)?; }
+    if state1 || self.state2b() { self.els.abort(env)?; }
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    let state1 = self.state1();
+    if state1 || self.state2a() { self.then.suspend(env)?; }
+    if state1 || self.state2b() { self.els.suspend(env)?; }
+    Ok(())
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr == 0 {
+      if self.state1() && self.cond.execute(env)? {
+        if self.state2a() { self.els.abort(env)?; }
+        else { self.then.abort(env)?; }
+      }
+      match self.branch() {
+        Some(s) => {
+          let res = s.execute(0, env)?;
+          self.k = res.k;
+          Ok(res)
+        }
+        None => Ok(StmtResult::new(self.k))
+      }
+    }
+    else {
+      match self.branch() {
+        Some(s) => s.execute(lr, env),
+        None => Err(String::from("[BUG] The condition of `when` must be decided before executing a sub-layer."))
+      }
+    }
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    if lr == 0 && self.state1() {
+      if in_surface {
+        match self.cond.hypothesis(env, uid, unblock)? {
+          Some(Kleene::True) => return self.then.can_write_on(0, env, uid, false, unblock),
+          Some(_) => return self.els.can_write_on(0, env, uid, false, unblock),
+          None => ()
+        }
+      }
+      let then = self.then.can_write_on(0, env, uid, in_surface, unblock)?;
+      Ok(then.join(self.els.can_write_on(0, env, uid, in_surface, unblock)?))
+    }
+    else {
+      match self.branch() {
+        Some(s) => s.can_write_on(lr, env, uid, in_surface, unblock),
+        None => Ok(CanWriteOn::new(true, false))
+      }
+    }
+  }
+
+  fn count_layers(&self) -> usize {
+    self.then.count_layers().max(self.els.count_layers())
+  }
+}
+
+#[derive(Clone)]
+pub struct SuspendWhen {
+  cond: CondState,
+  body: Statement,
+  k: CompletionCode
+}
+
+impl SuspendWhen {
+  pub fn new(cond: Condition, body: Statement) -> Self {
+    SuspendWhen { cond: CondState::new(cond), body, k: CompletionCode::Wait }
+  }
+
+  fn state1(&self) -> bool { self.k != CompletionCode::Terminate && self.cond.value() == Kleene::Unknown }
+  fn state2a(&self) -> bool { self.k != CompletionCode::Terminate && self.cond.value() == Kleene::True }
+  fn state2b(&self) -> bool { self.k != CompletionCode::Terminate && self.cond.value() == Kleene::False }
+  fn state3(&self) -> bool { self.k == CompletionCode::Terminate }
+
+  fn prepare(&mut self) {
+    self.body.prepare();
+    self.cond.result = None;
+    self.k = CompletionCode::Wait;
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    if lr == 0 {
+      if !self.state3() {
+        self.k = CompletionCode::Wait;
+        self.cond.reset();
+        self.body.can_instant(lr, env)?;
+      }
+    }
+    else if self.state2b() {
+      self.body.can_instant(lr, env)?;
+    }
+    Ok(())
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    if lr == 0 || self.state2b() { self.body.active_queues(lr) }
+    else { HashSet::new() }
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    if lr == 0 {
+      if self.state3() || self.state1() {
+        return Err(bug_eoi("suspend"));
+      }
+      if self.state2b() {
+        self.k = self.body.end_of_instant(lr, env)?;
+      }
+      Ok(self.k)
+    }
+    else if self.state2b() {
+      self.body.end_of_instant(lr, env)
+    }
+    else {
+      Ok(CompletionCode::PauseUp)
+    }
+  }
+
+  fn can_terminate(&self) -> bool {
+    if self.state1() || self.state2b() { self.body.can_terminate() }
+    else { self.state3() }
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    if self.state1() || self.state2b() { self.body.abort(env)?; }
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    if self.state1() || self.state2b() { self.body.suspend(env)?; }
+    Ok(())
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr == 0 {
+      if self.state1() && self.cond.execute(env)? && self.state2a() {
+        self.body.suspend(env)?;
+        self.k = CompletionCode::Pause;
+      }
+      if self.state2b() {
+        let res = self.body.execute(lr, env)?;
+        self.k = res.k;
+        return Ok(res);
+      }
+      Ok(StmtResult::new(self.k))
+    }
+    else if self.state1() {
+      Err(String::from("[BUG] The condition of `suspend` must be decided before executing a sub-layer."))
+    }
+    else if self.state2b() {
+      self.body.execute(lr, env)
+    }
+    else {
+      Ok(StmtResult::new(CompletionCode::PauseUp))
+    }
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    if lr == 0 && self.state1() {
+      if in_surface {
+        match self.cond.hypothesis(env, uid, unblock)? {
+          Some(Kleene::True) => return Ok(CanWriteOn::new(false, false)),
+          Some(_) => return self.body.can_write_on(lr, env, uid, false, unblock),
+          None => ()
+        }
+      }
+      self.body.can_write_on(lr, env, uid, in_surface, unblock)
+    }
+    else if self.state2b() {
+      self.body.can_write_on(lr, env, uid, in_surface, unblock)
+    }
+    else {
+      Ok(CanWriteOn::new(self.state3(), false))
+    }
+  }
+
+  fn count_layers(&self) -> usize {
+    self.body.count_layers()
+  }
+}
+
+#[derive(Clone)]
+pub struct AbortWhen {
+  cond: CondState,
+  body: Statement,
+  weak: bool,
+  k: CompletionCode,
+  /// `true` if the condition of a weak abortion was entailed in the previous instant.
+  preempted: bool
+}
+
+impl AbortWhen {
+  pub fn new(cond: Condition, body: Statement, weak: bool) -> Self {
+    AbortWhen { cond: CondState::new(cond), body, weak, k: CompletionCode::Wait, preempted: false }
+  }
+
+  fn state1(&self) -> bool {
+    self.k != CompletionCode::Terminate && !self.preempted && self.cond.value() == Kleene::Unknown
+  }
+
+  fn state2a(&self) -> bool {
+    self.k != CompletionCode::Terminate &&
+      (self.preempted || (!self.weak && self.cond.value() == Kleene::True))
+  }
+
+  fn state2b(&self) -> bool {
+    self.k != CompletionCode::Terminate && !self.preempted &&
+      (self.cond.value() == Kleene::False || (self.weak && self.cond.value() == Kleene::True))
+  }
+
+  fn state3(&self) -> bool { self.k == CompletionCode::Terminate }
+
+  fn prepare(&mut self) {
+    self.body.prepare();
+    self.cond.result = None;
+    self.k = CompletionCode::Wait;
+    self.preempted = false;
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    if lr == 0 {
+      if !self.state3() {
+        self.k = CompletionCode::Wait;
+        if !self.preempted {
+          self.cond.reset();
+        }
+        self.body.can_instant(lr, env)?;
+      }
+    }
+    else if self.state2b() {
+      self.body.can_instant(lr, env)?;
+    }
+    Ok(())
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    if lr == 0 || self.state2b() { self.body.active_queues(lr) }
+    else { HashSet::new() }
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    if lr == 0 {
+      if self.state3() || self.state1() {
+        return Err(bug_eoi("abort"));
+      }
+      self.k = self.body.end_of_instant(lr, env)?;
+      if self.weak && self.cond.value() == Kleene::True && !self.state3() {
+        self.preempted = true;
+      }
+      Ok(self.k)
+    }
+    else if self.state2b() {
+      self.body.end_of_instant(lr, env)
+    }
+    else {
+      Ok(self.k)
+    }
+  }
+
+  fn can_terminate(&self) -> bool {
+    if self.state1() { !self.weak || self.body.can_terminate() }
+    else if self.state2b() { self.body.can_terminate() }
+    else { true }
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    if !self.state3() { self.body.abort(env)?; }
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    if !self.state3() { self.body.suspend(env)?; }
+    Ok(())
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr == 0 {
+      if self.state1() {
+        self.cond.execute(env)?;
+      }
+      if self.state2a() {
+        self.body.abort(env)?;
+        self.k = CompletionCode::Terminate;
+      }
+      else if self.state2b() {
+        let res = self.body.execute(lr, env)?;
+        self.k = res.k;
+        return Ok(res);
+      }
+      Ok(StmtResult::new(self.k))
+    }
+    else if self.state1() {
+      Err(String::from("[BUG] The condition of `abort` must be decided before executing a sub-layer."))
+    }
+    else if self.state2b() {
+      self.body.execute(lr, env)
+    }
+    else {
+      Ok(StmtResult::new(CompletionCode::Terminate))
+    }
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    if lr == 0 && self.state1() {
+      if in_surface {
+        match self.cond.hypothesis(env, uid, unblock)? {
+          Some(Kleene::True) if !self.weak => return Ok(CanWriteOn::new(true, false)),
+          Some(_) => return self.body.can_write_on(lr, env, uid, false, unblock),
+          None => ()
+        }
+      }
+      let mut res = self.body.can_write_on(lr, env, uid, in_surface, unblock)?;
+      if !self.weak {
+        res.can_terminate = true;
+      }
+      Ok(res)
+    }
+    else if self.state2b() {
+      self.body.can_write_on(lr, env, uid, in_surface, unblock)
+    }
+    else {
+      Ok(CanWriteOn::new(true, false))
+    }
+  }
+
+  fn count_layers(&self) -> usize {
+    self.body.count_layers()
+  }
+}
+
+/// The declaration of a spacetime variable `uid` initialized with `init` and visible in `body`.
+#[derive(Clone)]
+pub struct VarDecl {
+  uid: String,
+  spacetime: Spacetime,
+  init: Expression,
+  body: Statement,
+  k: CompletionCode,
+  value: Option<Value>
+}
+
+impl VarDecl {
+  pub fn new(uid: String, spacetime: Spacetime, init: Expression, body: Statement) -> Self {
+    VarDecl { uid, spacetime, init, body, k: CompletionCode::Wait, value: None }
+  }
+
+  fn state1(&self) -> bool { self.k != CompletionCode::Terminate && self.value.is_none() }
+  fn state2(&self) -> bool { self.k != CompletionCode::Terminate && self.value.is_some() }
+  fn state3(&self) -> bool { self.k == CompletionCode::Terminate }
+
+  fn prepare(&mut self) {
+    self.k = CompletionCode::Wait;
+    self.value = None;
+    self.body.prepare();
+  }
+
+  fn exit_scope(&mut self, env: &mut Env) -> RtResult<()> {
+    self.value = None;
+    env.space().exit_scope(&self.uid)
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    if lr == 0 {
+      if self.spacetime == Spacetime::SingleTime {
+        env.space().register(&self.uid, true);
+        self.init.can_instant(env)?;
+        self.value = None;
+      }
+      else if self.state1() {
+        self.init.can_instant(env)?;
+        env.space().register(&self.uid, false);
+      }
+    }
+    self.body.can_instant(lr, env)
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    if lr == 0 { HashSet::new() }
+    else { self.body.active_queues(lr) }
+  }
+
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    if self.state1() || self.state3() {
+      return Err(bug_eoi("variable declaration"));
+    }
+    let k = self.body.end_of_instant(lr, env)?;
+    if lr == 0 {
+      self.k = k;
+      if self.spacetime == Spacetime::SingleTime && self.state2() {
+        self.exit_scope(env)?;
+      }
+    }
+    Ok(k)
+  }
+
+  fn can_terminate(&self) -> bool {
+    self.body.can_terminate()
+  }
+
+  fn abort(&mut self, env: &mut Env) -> RtResult<()> {
+    if !self.state3() {
+      if self.state1() {
+        self.init.terminate(env)?;
+      }
+      self.body.abort(env)?;
+      if self.state2() {
+        self.exit_scope(env)?;
+      }
+      self.k = CompletionCode::Terminate;
+    }
+    Ok(())
+  }
+
+  fn suspend(&mut self, env: &mut Env) -> RtResult<()> {
+    if !self.state3() {
+      if self.state1() {
+        self.init.terminate(env)?;
+      }
+      self.body.suspend(env)?;
+    }
+    Ok(())
+  }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr > 0 {
+      return if self.state2() { self.body.execute(lr, env) }
+        else { Err(String::from("[BUG] A variable must be initialized before executing a sub-layer.")) };
+    }
+    let var = env.lookup(&self.uid)?;
+    if self.state1() {
+      if let Some(value) = self.init.execute(env)? {
+        self.init.terminate(env)?;
+        env.space().enter_scope(&self.uid, value.clone())?;
+        self.value = Some(value);
+        env.mark_progress();
+      }
+    }
+    let mut res = StmtResult::new(self.k);
+    if self.state2() {
+      res = self.body.execute(0, env)?;
+      self.k = res.k;
+      if self.state3() {
+        self.exit_scope(env)?;
+      }
+    }
+    if self.spacetime == Spacetime::WorldLine && !res.k.is_internal() {
+      if let Some(queue) = env.current_queue() {
+        res.register_wl(queue, &var, self.state3())?;
+      }
+    }
+    Ok(res)
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    if lr == 0 && self.state1() && self.init.can_write_on(uid) {
+      Ok(CanWriteOn::new(self.can_terminate(), true))
+    }
+    else if lr > 0 || !self.state3() {
+      self.body.can_write_on(lr, env, uid, in_surface, unblock)
+    }
+    else {
+      Ok(CanWriteOn::new(true, false))
+    }
+  }
+
+  fn count_layers(&self) -> usize {
+    self.body.count_layers()
+  }
+}
+
+/// `space p end`: the statement `p` is captured with its free variables `captured` and pushed on the current queue.
+#[derive(Clone)]
+pub struct SpaceStmt {
+  captured: Vec<String>,
+  branch: Statement
+}
+
+impl SpaceStmt {
+  pub fn new(captured: Vec<String>, branch: Statement) -> Self {
+    SpaceStmt { captured, branch }
+  }
+
+  fn prepare(&mut self) {}
+
+  fn can_instant(&mut self, lr: usize, _env: &mut Env) -> RtResult<()> {
+    check_no_sub_layer(lr, "space")
+  }
+
+  fn active_queues(&self, _lr: usize) -> HashSet<String> { HashSet::new() }
+
+  fn end_of_instant(&mut self, _lr: usize, _env: &mut Env) -> RtResult<CompletionCode> {
+    Err(bug_eoi("space"))
+  }
+
+  fn can_terminate(&self) -> bool { true }
+  fn abort(&mut self, _env: &mut Env) -> RtResult<()> { Ok(()) }
+  fn suspend(&mut self, _env: &mut Env) -> RtResult<()> { Ok(()) }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    check_no_sub_layer(lr, "space")?;
+    let queue = current_queue(env, "space")?;
+    let space = CapturedSpace::new(env.space().project(&self.captured));
+    let ba = BranchAlgebra::space_branch(self.branch.clone(), space);
+    Ok(StmtResult::with_branch(CompletionCode::Terminate, queue, ba))
+  }
+
+  fn can_write_on(&mut self, _lr: usize, _env: &mut Env, _uid: &str, _in_surface: bool,
+    _unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    Ok(CanWriteOn::new(true, false))
+  }
+
+  fn count_layers(&self) -> usize { 0 }
+}
+
+/// `universe in q p end` executes `p` in a sub-layer, one instant of the sub-layer per instant of the current layer.
+/// Without a queue (`universe p end`), the futures cannot be pushed nor popped.
+#[derive(Clone)]
+pub struct Universe {
+  queue: Option<String>,
+  body: Statement,
+  k: CompletionCode,
+  body_k: CompletionCode,
+  first_instant: bool
+}
+
+impl Universe {
+  pub fn new(queue: Option<String>, body: Statement) -> Self {
+    Universe { queue, body, k: CompletionCode::PauseDown, body_k: CompletionCode::Wait, first_instant: true }
+  }
+
+  fn prepare_instant(&mut self) {
+    self.k = CompletionCode::PauseDown;
+    self.body_k = CompletionCode::Wait;
+  }
+
+  fn prepare(&mut self) {
+    self.prepare_instant();
+    self.first_instant = true;
+    self.body.prepare();
+  }
+
+  fn can_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<()> {
+    if lr == 0 {
+      self.prepare_instant();
+      Ok(())
+    }
+    else {
+      self.body.can_instant(lr - 1, env)
+    }
+  }
+
+  fn active_queues(&self, lr: usize) -> HashSet<String> {
+    match self.queue {
+      Some(ref queue) if lr == 1 => {
+        let mut queues = HashSet::new();
+        if self.k != CompletionCode::Terminate && !self.first_instant {
+          queues.insert(queue.clone());
+        }
+        queues
+      }
+      _ if lr > 1 => self.body.active_queues(lr - 1),
+      _ => HashSet::new()
+    }
+  }
+
+  /// With a queue, the sub-layer directly below completes with the result of the body and the universe terminates once the queue is empty.
+  fn end_of_instant(&mut self, lr: usize, env: &mut Env) -> RtResult<CompletionCode> {
+    if self.k == CompletionCode::Terminate {
+      return Err(bug_eoi("universe"));
+    }
+    match self.queue.clone() {
+      Some(queue) => {
+        self.first_instant = false;
+        if lr > 1 {
+          self.body.end_of_instant(lr - 1, env)
+        }
+        else if lr == 1 {
+          if env.is_queue_empty(&queue)? {
+            self.body_k = CompletionCode::Terminate;
+            self.k = CompletionCode::Terminate;
+          }
+          Ok(self.body_k)
+        }
+        else { Ok(self.k) }
+      }
+      None => {
+        if lr == 0 { Ok(self.k) }
+        else { self.body.end_of_instant(lr - 1, env) }
+      }
+    }
+  }
+
+  fn can_terminate(&self) -> bool {
+    self.body.can_terminate()
+  }
+
+  fn abort(&mut self, _env: &mut Env) -> RtResult<()> {
+    self.k = CompletionCode::Terminate;
+    self.body_k = CompletionCode::Terminate;
+    Ok(())
+  }
+
+  fn suspend(&mut self, _env: &mut Env) -> RtResult<()> { Ok(()) }
+
+  fn execute(&mut self, lr: usize, env: &mut Env) -> RtResult<StmtResult> {
+    if lr == 0 {
+      match self.body_k {
+        CompletionCode::PauseUp => self.k = CompletionCode::Pause,
+        CompletionCode::Stop => self.k = CompletionCode::Stop,
+        CompletionCode::Terminate => self.k = CompletionCode::Terminate,
+        _ => ()
+      }
+      return Ok(StmtResult::new(self.k));
+    }
+    let in_queue = lr == 1 && self.queue.is_some();
+    if in_queue {
+      env.enter_queue(self.queue.as_ref().expect("unreachable"))?;
+    }
+    let res = self.body.execute(lr - 1, env);
+    if in_queue {
+      env.exit_queue();
+    }
+    let res = res?;
+    self.body_k = res.k;
+    Ok(res)
+  }
+
+  fn can_write_on(&mut self, lr: usize, env: &mut Env, uid: &str, in_surface: bool,
+    unblock: &mut Unblock) -> RtResult<CanWriteOn>
+  {
+    if lr == 0 { Ok(CanWriteOn::new(self.can_terminate(), false)) }
+    else { self.body.can_write_on(lr - 1, env, uid, in_surface, unblock) }
+  }
+
+  fn count_layers(&self) -> usize {
+    1 + self.body.count_layers()
+  }
+}
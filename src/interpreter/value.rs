@@ -0,0 +1,591 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Values manipulated by the interpreter: the host values (numbers and strings) and the built-in lattices of the runtime (`LMax`, `LMin`, `ES`, `L` and the queues).
+/// The operations follow the Java classes of `bonsai.runtime.lattice` and `bonsai.runtime.queueing`.
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Error, Formatter};
+use interpreter::statement::Future;
+use interpreter::RtResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kleene {
+  True,
+  False,
+  Unknown
+}
+
+impl Kleene {
+  pub fn from_bool(b: bool) -> Self {
+    if b { Kleene::True } else { Kleene::False }
+  }
+
+  pub fn not(self) -> Self {
+    match self {
+      Kleene::True => Kleene::False,
+      Kleene::False => Kleene::True,
+      Kleene::Unknown => Kleene::Unknown
+    }
+  }
+
+  pub fn and(self, other: Kleene) -> Self {
+    use self::Kleene::*;
+    match (self, other) {
+      (False, _) | (_, False) => False,
+      (True, True) => True,
+      _ => Unknown
+    }
+  }
+
+  pub fn or(self, other: Kleene) -> Self {
+    use self::Kleene::*;
+    match (self, other) {
+      (True, _) | (_, True) => True,
+      (False, False) => False,
+      _ => Unknown
+    }
+  }
+
+  fn index(self) -> usize {
+    match self {
+      Kleene::True => 0,
+      Kleene::False => 1,
+      Kleene::Unknown => 2
+    }
+  }
+}
+
+impl Display for Kleene {
+  fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+    match *self {
+      Kleene::True => fmt.write_str("true"),
+      Kleene::False => fmt.write_str("false"),
+      Kleene::Unknown => fmt.write_str("unknown")
+    }
+  }
+}
+
+// Tables of `ES` indexed by [TRUE, FALSE, UNKNOWN].
+static ES_JOIN: [[Kleene; 3]; 3] = [
+  [Kleene::True, Kleene::False, Kleene::True],
+  [Kleene::False, Kleene::False, Kleene::False],
+  [Kleene::True, Kleene::False, Kleene::Unknown]];
+
+static ES_MEET: [[Kleene; 3]; 3] = [
+  [Kleene::True, Kleene::True, Kleene::Unknown],
+  [Kleene::True, Kleene::False, Kleene::Unknown],
+  [Kleene::Unknown, Kleene::Unknown, Kleene::Unknown]];
+
+static ES_ENTAIL: [[Kleene; 3]; 3] = [
+  [Kleene::True, Kleene::False, Kleene::True],
+  [Kleene::True, Kleene::True, Kleene::True],
+  [Kleene::False, Kleene::False, Kleene::True]];
+
+/// Bounds of `LMax` (`LMin` uses them in the reverse order), they match the range of a Java `int`.
+pub const INT_MIN: i64 = -2147483648;
+pub const INT_MAX: i64 = 2147483647;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueKind {
+  StackLR,
+  StackRL,
+  QueueLR,
+  QueueRL
+}
+
+impl QueueKind {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "StackLR" => Some(QueueKind::StackLR),
+      "StackRL" => Some(QueueKind::StackRL),
+      "QueueLR" => Some(QueueKind::QueueLR),
+      "QueueRL" => Some(QueueKind::QueueRL),
+      _ => None
+    }
+  }
+}
+
+impl Display for QueueKind {
+  fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+    fmt.write_fmt(format_args!("{:?}", self))
+  }
+}
+
+/// The queues store the futures created by `space` statements until they are popped by a `universe` statement.
+#[derive(Clone)]
+pub struct Queue {
+  pub kind: QueueKind,
+  data: VecDeque<Future>
+}
+
+impl Queue {
+  pub fn new(kind: QueueKind) -> Self {
+    Queue { kind, data: VecDeque::new() }
+  }
+
+  pub fn push(&mut self, store: Vec<Future>) {
+    match self.kind {
+      QueueKind::StackLR => {
+        for future in store.into_iter().rev() {
+          self.data.push_front(future);
+        }
+      }
+      QueueKind::StackRL => {
+        for future in store {
+          self.data.push_front(future);
+        }
+      }
+      QueueKind::QueueLR => {
+        self.data.extend(store);
+      }
+      QueueKind::QueueRL => {
+        self.data.extend(store.into_iter().rev());
+      }
+    }
+  }
+
+  pub fn pop(&mut self) -> Option<Future> {
+    self.data.pop_front()
+  }
+
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+}
+
+/// The flat lattice `L<T>`: `bottom < T < top` where the inner values are incomparable.
+#[derive(Clone)]
+pub enum Flat {
+  Bottom,
+  Inner(Box<Value>),
+  Top
+}
+
+#[derive(Clone)]
+pub enum Value {
+  Unit,
+  Int(i64),
+  Str(String),
+  Bool(bool),
+  LMax(i64),
+  LMin(i64),
+  ES(Kleene),
+  Flat(Flat),
+  Queue(Queue)
+}
+
+impl Value {
+  pub fn type_name(&self) -> String {
+    match *self {
+      Value::Unit => String::from("void"),
+      Value::Int(_) => String::from("Integer"),
+      Value::Str(_) => String::from("String"),
+      Value::Bool(_) => String::from("Boolean"),
+      Value::LMax(_) => String::from("LMax"),
+      Value::LMin(_) => String::from("LMin"),
+      Value::ES(_) => String::from("ES"),
+      Value::Flat(_) => String::from("L"),
+      Value::Queue(ref q) => format!("{}", q.kind)
+    }
+  }
+
+  /// Creates the value of `new ty(args)`, only the built-in classes are supported.
+  pub fn new_instance(ty: &str, args: Vec<Value>) -> RtResult<Value> {
+    if let Some(kind) = QueueKind::from_name(ty) {
+      return if args.is_empty() { Ok(Value::Queue(Queue::new(kind))) }
+        else { Err(format!("`{}` has no constructor with arguments.", ty)) };
+    }
+    match (ty, args.as_slice()) {
+      ("LMax", &[]) => Ok(Value::LMax(INT_MIN)),
+      ("LMax", &[Value::Int(v)])
+    | ("LMax", &[Value::LMax(v)]) => Ok(Value::LMax(v)),
+      ("LMin", &[]) => Ok(Value::LMin(INT_MAX)),
+      ("LMin", &[Value::Int(v)])
+    | ("LMin", &[Value::LMin(v)]) => Ok(Value::LMin(v)),
+      ("ES", &[]) => Ok(Value::ES(Kleene::Unknown)),
+      ("ES", &[Value::ES(k)]) => Ok(Value::ES(k)),
+      ("L", &[]) => Ok(Value::Flat(Flat::Bottom)),
+      ("L", &[ref v]) => Ok(Value::Flat(Flat::Inner(Box::new(v.clone())))),
+      ("Integer", &[Value::Int(v)]) => Ok(Value::Int(v)),
+      ("String", &[]) => Ok(Value::Str(String::new())),
+      ("String", &[Value::Str(ref s)]) => Ok(Value::Str(s.clone())),
+      _ => Err(format!("`new {}(...)` with {} argument(s) is not supported by the interpreter: \
+        only the built-in lattices and queues of the runtime are available.", ty, args.len()))
+    }
+  }
+
+  pub fn bottom_of(ty: &str) -> RtResult<Value> {
+    match ty {
+      "LMax" => Ok(Value::LMax(INT_MIN)),
+      "LMin" => Ok(Value::LMin(INT_MAX)),
+      "ES" => Ok(Value::ES(Kleene::Unknown)),
+      "L" => Ok(Value::Flat(Flat::Bottom)),
+      _ => Value::new_instance(ty, vec![])
+    }
+  }
+
+  pub fn top_of(ty: &str) -> RtResult<Value> {
+    match ty {
+      "LMax" => Ok(Value::LMax(INT_MAX)),
+      "LMin" => Ok(Value::LMin(INT_MIN)),
+      "ES" => Ok(Value::ES(Kleene::False)),
+      "L" => Ok(Value::Flat(Flat::Top)),
+      _ => Err(format!("`top` is not defined for the type `{}` in the interpreter.", ty))
+    }
+  }
+
+  pub fn to_kleene(&self) -> RtResult<Kleene> {
+    match *self {
+      Value::ES(k) => Ok(k),
+      Value::Bool(b) => Ok(Kleene::from_bool(b)),
+      _ => Err(format!("A value of type `{}` cannot be used as a Kleene value.", self.type_name()))
+    }
+  }
+
+  /// Structural equality of the values, similar to `equals` in Java.
+  pub fn equals(&self, other: &Value) -> bool {
+    match (self, other) {
+      (&Value::Unit, &Value::Unit) => true,
+      (&Value::Int(a), &Value::Int(b))
+    | (&Value::LMax(a), &Value::LMax(b))
+    | (&Value::LMin(a), &Value::LMin(b)) => a == b,
+      (&Value::Str(ref a), &Value::Str(ref b)) => a == b,
+      (&Value::Bool(a), &Value::Bool(b)) => a == b,
+      (&Value::ES(a), &Value::ES(b)) => a == b,
+      (&Value::Flat(Flat::Bottom), &Value::Flat(Flat::Bottom))
+    | (&Value::Flat(Flat::Top), &Value::Flat(Flat::Top)) => true,
+      (&Value::Flat(Flat::Inner(ref a)), &Value::Flat(Flat::Inner(ref b))) => a.equals(b),
+      _ => false
+    }
+  }
+
+  fn not_a_lattice(&self) -> String {
+    format!("A value of type `{}` is not a lattice.", self.type_name())
+  }
+
+  fn incompatible(&self, other: &Value) -> String {
+    format!("The lattice `{}` cannot be compared with a value of type `{}`.",
+      self.type_name(), other.type_name())
+  }
+
+  /// The integer of a total order, the integers are implicitly wrapped.
+  fn total_order_value(&self, other: &Value) -> RtResult<i64> {
+    match *other {
+      Value::Int(v) | Value::LMax(v) | Value::LMin(v) => Ok(v),
+      _ => Err(self.incompatible(other))
+    }
+  }
+
+  /// The inner value of a flat lattice, the raw values are implicitly wrapped.
+  fn flat_value(other: &Value) -> Flat {
+    match *other {
+      Value::Flat(ref f) => f.clone(),
+      ref v => Flat::Inner(Box::new(v.clone()))
+    }
+  }
+
+  pub fn entails(&self, other: &Value) -> RtResult<Kleene> {
+    match *self {
+      Value::LMax(v) => Ok(Kleene::from_bool(v >= self.total_order_value(other)?)),
+      Value::LMin(v) => Ok(Kleene::from_bool(v <= self.total_order_value(other)?)),
+      Value::ES(k) => {
+        let o = other.to_kleene().map_err(|_| self.incompatible(other))?;
+        Ok(ES_ENTAIL[k.index()][o.index()])
+      }
+      Value::Flat(ref f) => {
+        match Value::flat_value(other) {
+          Flat::Bottom => Ok(Kleene::True),
+          Flat::Top => Ok(Kleene::from_bool(match *f { Flat::Top => true, _ => false })),
+          Flat::Inner(o) => Ok(match *f {
+            Flat::Bottom => Kleene::False,
+            Flat::Top => Kleene::True,
+            Flat::Inner(ref v) => if v.equals(&o) { Kleene::True } else { Kleene::Unknown }
+          })
+        }
+      }
+      _ => Err(self.not_a_lattice())
+    }
+  }
+
+  pub fn strict_entail(&self, other: &Value) -> RtResult<Kleene> {
+    match *self {
+      Value::LMax(v) | Value::LMin(v) => {
+        let o = self.total_order_value(other)?;
+        Ok(self.entails(other)?.and(Kleene::from_bool(v != o)))
+      }
+      _ => {
+        let equal = self.default_equals(other)?;
+        Ok(self.entails(other)?.and(Kleene::from_bool(!equal)))
+      }
+    }
+  }
+
+  pub fn equals_lattice(&self, other: &Value) -> RtResult<Kleene> {
+    match *self {
+      Value::LMax(v) | Value::LMin(v) => Ok(Kleene::from_bool(v == self.total_order_value(other)?)),
+      _ => Ok(self.entails(other)?.and(self.lattice_of(other).entails(self)?))
+    }
+  }
+
+  fn default_equals(&self, other: &Value) -> RtResult<bool> {
+    Ok(self.entails(other)? == Kleene::True
+      && self.lattice_of(other).entails(self)? == Kleene::True)
+  }
+
+  /// Wraps `other` in the lattice type of `self` if it is a raw value.
+  fn lattice_of(&self, other: &Value) -> Value {
+    match (self, other) {
+      (&Value::Flat(_), &Value::Flat(_)) => other.clone(),
+      (&Value::Flat(_), v) => Value::Flat(Flat::Inner(Box::new(v.clone()))),
+      (&Value::ES(_), &Value::Bool(b)) => Value::ES(Kleene::from_bool(b)),
+      _ => other.clone()
+    }
+  }
+
+  pub fn join(&self, other: &Value) -> RtResult<Value> {
+    match *self {
+      Value::LMax(v) => Ok(Value::LMax(v.max(self.total_order_value(other)?))),
+      Value::LMin(v) => Ok(Value::LMin(v.min(self.total_order_value(other)?))),
+      Value::ES(k) => {
+        let o = other.to_kleene().map_err(|_| self.incompatible(other))?;
+        Ok(Value::ES(ES_JOIN[k.index()][o.index()]))
+      }
+      Value::Flat(ref f) => {
+        let res = match Value::flat_value(other) {
+          Flat::Bottom => f.clone(),
+          Flat::Top => Flat::Top,
+          Flat::Inner(o) => match *f {
+            Flat::Bottom => Flat::Inner(o),
+            Flat::Top => Flat::Top,
+            Flat::Inner(ref v) => if v.equals(&o) { Flat::Inner(o) } else { Flat::Top }
+          }
+        };
+        Ok(Value::Flat(res))
+      }
+      _ => Err(self.not_a_lattice())
+    }
+  }
+
+  pub fn meet(&self, other: &Value) -> RtResult<Value> {
+    match *self {
+      Value::LMax(v) => Ok(Value::LMax(v.min(self.total_order_value(other)?))),
+      Value::LMin(v) => Ok(Value::LMin(v.max(self.total_order_value(other)?))),
+      Value::ES(k) => {
+        let o = other.to_kleene().map_err(|_| self.incompatible(other))?;
+        Ok(Value::ES(ES_MEET[k.index()][o.index()]))
+      }
+      Value::Flat(ref f) => {
+        let res = match Value::flat_value(other) {
+          Flat::Bottom => Flat::Bottom,
+          Flat::Top => f.clone(),
+          Flat::Inner(o) => match *f {
+            Flat::Bottom => Flat::Bottom,
+            Flat::Top => Flat::Inner(o),
+            Flat::Inner(ref v) => if v.equals(&o) { Flat::Inner(o) } else { Flat::Bottom }
+          }
+        };
+        Ok(Value::Flat(res))
+      }
+      _ => Err(self.not_a_lattice())
+    }
+  }
+
+  fn is_bottom(&self) -> RtResult<bool> {
+    match *self {
+      Value::LMax(v) => Ok(v == INT_MIN),
+      Value::LMin(v) => Ok(v == INT_MAX),
+      Value::ES(k) => Ok(k == Kleene::Unknown),
+      Value::Flat(Flat::Bottom) => Ok(true),
+      Value::Flat(_) => Ok(false),
+      _ => Err(self.not_a_lattice())
+    }
+  }
+
+  fn is_top(&self) -> RtResult<bool> {
+    match *self {
+      Value::LMax(v) => Ok(v == INT_MAX),
+      Value::LMin(v) => Ok(v == INT_MIN),
+      Value::ES(k) => Ok(k == Kleene::False),
+      Value::Flat(Flat::Top) => Ok(true),
+      Value::Flat(_) => Ok(false),
+      _ => Err(self.not_a_lattice())
+    }
+  }
+
+  /// Calls the method `name` on `self`, the methods ending with `_in_place` (and `inc`/`dec`) modify `self`.
+  pub fn call_method(&mut self, name: &str, args: Vec<Value>) -> RtResult<Value> {
+    let type_name = self.type_name();
+    match (name, args.len()) {
+      ("join_in_place", 1) => { *self = self.join(&args[0])?; Ok(Value::Unit) }
+      ("meet_in_place", 1) => { *self = self.meet(&args[0])?; Ok(Value::Unit) }
+      ("join", 1) => self.join(&args[0]),
+      ("meet", 1) => self.meet(&args[0]),
+      ("entails", 1) => Ok(Value::ES(self.entails(&args[0])?)),
+      ("strict_entail", 1) => Ok(Value::ES(self.strict_entail(&args[0])?)),
+      ("equals_lattice", 1) => Ok(Value::ES(self.equals_lattice(&args[0])?)),
+      ("equals", 1) => Ok(Value::Bool(self.equals(&args[0]))),
+      ("bottom", 0) => Value::bottom_of(&self.type_name()),
+      ("top", 0) => Value::top_of(&self.type_name()),
+      ("isBottom", 0) => Ok(Value::Bool(self.is_bottom()?)),
+      ("isTop", 0) => Ok(Value::Bool(self.is_top()?)),
+      ("copy", 0) => Ok(self.clone()),
+      ("toString", 0) => Ok(Value::Str(format!("{}", self))),
+      _ => {
+        match (self, name) {
+          (&mut Value::LMax(ref mut v), "inc") => { if *v < INT_MAX { *v += 1; } Ok(Value::Unit) }
+          (&mut Value::LMin(ref mut v), "dec") => { if *v > INT_MIN { *v -= 1; } Ok(Value::Unit) }
+          (&mut Value::LMax(v), "unwrap")
+        | (&mut Value::LMin(v), "unwrap") => Ok(Value::Int(v)),
+          (&mut Value::ES(k), "unwrap") => Ok(Value::ES(k)),
+          (&mut Value::Flat(Flat::Inner(ref v)), "unwrap") => Ok((**v).clone()),
+          (&mut Value::Queue(ref q), "size") => Ok(Value::Int(q.len() as i64)),
+          (&mut Value::Queue(ref q), "isEmpty") => Ok(Value::Bool(q.is_empty())),
+          _ => Err(format!("The method `{}` with {} argument(s) of `{}` is not supported by the interpreter.",
+            name, args.len(), type_name))
+        }
+      }
+    }
+  }
+}
+
+impl Display for Value {
+  fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+    match *self {
+      Value::Unit => Ok(()),
+      Value::Int(v) => fmt.write_fmt(format_args!("{}", v)),
+      Value::Str(ref s) => fmt.write_str(s),
+      Value::Bool(b) => fmt.write_fmt(format_args!("{}", b)),
+      Value::LMax(v) | Value::LMin(v) => {
+        if self.is_bottom() == Ok(true) { fmt.write_str("bot") }
+        else if self.is_top() == Ok(true) { fmt.write_str("top") }
+        else { fmt.write_fmt(format_args!("{}", v)) }
+      }
+      Value::ES(k) => fmt.write_fmt(format_args!("{}", k)),
+      Value::Flat(Flat::Bottom) => fmt.write_str("bottom"),
+      Value::Flat(Flat::Top) => fmt.write_str("top"),
+      Value::Flat(Flat::Inner(ref v)) => fmt.write_fmt(format_args!("{}", v)),
+      Value::Queue(ref q) => fmt.write_fmt(format_args!("{}", q.kind))
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use super::Kleene::*;
+
+  fn es(k: Kleene) -> Value { Value::ES(k) }
+  fn inner(v: i64) -> Value { Value::Flat(Flat::Inner(Box::new(Value::Int(v)))) }
+
+  fn show(v: RtResult<Value>) -> String {
+    format!("{}", v.unwrap())
+  }
+
+  #[test]
+  fn lmax() {
+    let a = Value::LMax(1);
+    let b = Value::LMax(3);
+    assert_eq!(show(a.join(&b)), "3");
+    assert_eq!(show(a.meet(&b)), "1");
+    assert_eq!(a.entails(&b), Ok(False));
+    assert_eq!(b.entails(&a), Ok(True));
+    assert_eq!(b.entails(&Value::Int(3)), Ok(True));
+    assert_eq!(b.strict_entail(&b), Ok(False));
+    assert_eq!(b.strict_entail(&a), Ok(True));
+    assert_eq!(show(Value::bottom_of("LMax")), "bot");
+    assert_eq!(show(Value::top_of("LMax")), "top");
+    let mut top = Value::LMax(INT_MAX);
+    top.call_method("inc", vec![]).unwrap();
+    assert!(top.equals(&Value::LMax(INT_MAX)));
+  }
+
+  #[test]
+  fn lmin() {
+    let a = Value::LMin(1);
+    let b = Value::LMin(3);
+    assert_eq!(show(a.join(&b)), "1");
+    assert_eq!(show(a.meet(&b)), "3");
+    assert_eq!(a.entails(&b), Ok(True));
+    assert_eq!(b.entails(&a), Ok(False));
+    assert_eq!(a.equals_lattice(&Value::Int(1)), Ok(True));
+    assert_eq!(show(Value::bottom_of("LMin")), "bot");
+    assert_eq!(show(Value::top_of("LMin")), "top");
+    let mut c = b.clone();
+    c.call_method("dec", vec![]).unwrap();
+    assert!(c.equals(&Value::LMin(2)));
+    assert!(Value::LMin(2).join(&Value::Str(String::from("2"))).is_err());
+  }
+
+  #[test]
+  fn es_lattice() {
+    // `unknown` is the bottom and `false` the top of `ES`.
+    assert_eq!(show(es(Unknown).join(&es(True))), "true");
+    assert_eq!(show(es(True).join(&es(False))), "false");
+    assert_eq!(show(es(Unknown).join(&es(Unknown))), "unknown");
+    assert_eq!(show(es(True).meet(&es(False))), "true");
+    assert_eq!(show(es(False).meet(&es(Unknown))), "unknown");
+    assert_eq!(es(False).entails(&es(True)), Ok(True));
+    assert_eq!(es(True).entails(&es(False)), Ok(False));
+    assert_eq!(es(Unknown).entails(&es(True)), Ok(False));
+    assert_eq!(es(True).entails(&Value::Bool(true)), Ok(True));
+    assert_eq!(es(True).strict_entail(&es(Unknown)), Ok(True));
+    assert_eq!(es(True).strict_entail(&es(True)), Ok(False));
+    assert_eq!(show(Value::bottom_of("ES")), "unknown");
+    assert_eq!(show(Value::top_of("ES")), "false");
+  }
+
+  #[test]
+  fn kleene() {
+    assert_eq!(Unknown.and(False), False);
+    assert_eq!(Unknown.and(True), Unknown);
+    assert_eq!(Unknown.or(True), True);
+    assert_eq!(Unknown.not(), Unknown);
+    assert_eq!(True.not(), False);
+  }
+
+  #[test]
+  fn flat() {
+    let bot = Value::Flat(Flat::Bottom);
+    let top = Value::Flat(Flat::Top);
+    assert_eq!(show(bot.join(&inner(1))), "1");
+    assert_eq!(show(inner(1).join(&inner(1))), "1");
+    assert_eq!(show(inner(1).join(&inner(2))), "top");
+    assert_eq!(show(inner(1).meet(&inner(2))), "bottom");
+    assert_eq!(show(top.meet(&inner(2))), "2");
+    // The raw values are implicitly wrapped.
+    assert_eq!(show(bot.join(&Value::Int(4))), "4");
+    assert_eq!(inner(1).entails(&bot), Ok(True));
+    assert_eq!(inner(1).entails(&inner(1)), Ok(True));
+    assert_eq!(inner(1).entails(&inner(2)), Ok(Unknown));
+    assert_eq!(bot.entails(&inner(2)), Ok(False));
+    assert_eq!(top.entails(&inner(2)), Ok(True));
+    assert_eq!(inner(1).entails(&top), Ok(False));
+    assert_eq!(inner(1).equals_lattice(&Value::Int(1)), Ok(True));
+    assert_eq!(inner(1).strict_entail(&bot), Ok(True));
+    assert_eq!(show(Value::new_instance("L", vec![])), "bottom");
+    assert_eq!(show(Value::top_of("L")), "top");
+  }
+
+  #[test]
+  fn not_a_lattice() {
+    assert!(Value::Int(1).join(&Value::Int(2)).is_err());
+    assert!(Value::Str(String::from("a")).entails(&Value::Unit).is_err());
+    assert!(Value::top_of("String").is_err());
+  }
+}
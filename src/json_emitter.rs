@@ -0,0 +1,96 @@
+// Copyright 2018 Pierre Talbot
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Emitter of diagnostics in JSON, selected with `--error-format=json`.
+/// Each diagnostic is printed as one JSON object per line on the standard error, for example:
+///
+/// {"children":[{"level":"help","message":"...","spans":[]}],"code":"E0035","level":"error","message":"...",
+///   "spans":[{"column_end":6,"column_start":5,"file":"A.bonsai.java","is_primary":true,"label":"expected ...","line_end":3,"line_start":3}]}
+///
+/// Lines and columns start at 1, the end column is exclusive.
+
+use diagnostic::*;
+use syntex_syntax::codemap::CodeMap;
+use syntex_errors::DiagnosticBuilder;
+use syntex_errors::emitter::Emitter;
+use serde_json::Value;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+pub struct JsonEmitter
+{
+  dst: Box<Write>,
+  codemap: Rc<CodeMap>,
+}
+
+impl JsonEmitter
+{
+  pub fn stderr(codemap: Rc<CodeMap>) -> Self {
+    JsonEmitter::new(Box::new(io::stderr()), codemap)
+  }
+
+  pub fn new(dst: Box<Write>, codemap: Rc<CodeMap>) -> Self {
+    JsonEmitter {
+      dst: dst,
+      codemap: codemap
+    }
+  }
+}
+
+impl Emitter for JsonEmitter
+{
+  fn emit(&mut self, db: &DiagnosticBuilder) {
+    let json = diagnostic(&Diagnostic::new(db, &self.codemap));
+    if let Err(e) = writeln!(self.dst, "{}", json) {
+      panic!("failed to print diagnostics: {:?}", e);
+    }
+  }
+}
+
+fn diagnostic(diagnostic: &Diagnostic) -> Value {
+  let children: Vec<Value> = diagnostic.children.iter()
+    .map(sub_diagnostic)
+    .collect();
+  json!({
+    "level": diagnostic.level,
+    "code": diagnostic.code,
+    "message": diagnostic.message,
+    "spans": spans(&diagnostic.spans),
+    "children": children
+  })
+}
+
+fn sub_diagnostic(sub: &Diagnostic) -> Value {
+  json!({
+    "level": sub.level,
+    "message": sub.message,
+    "spans": spans(&sub.spans)
+  })
+}
+
+fn spans(spans: &Vec<DiagnosticSpan>) -> Value {
+  Value::Array(spans.iter().map(span).collect())
+}
+
+fn span(span: &DiagnosticSpan) -> Value {
+  json!({
+    "file": span.file,
+    "line_start": span.line_start,
+    "column_start": span.column_start,
+    "line_end": span.line_end,
+    "column_end": span.column_end,
+    "is_primary": span.is_primary,
+    "label": span.label
+  })
+}
@@ -22,14 +22,21 @@ extern crate syntex_pos;
 extern crate syntex_syntax;
 extern crate syntex_errors;
 extern crate regex;
+#[cfg(feature = "pcp_check")]
 extern crate pcp;
 extern crate gcollections;
+#[cfg(feature = "pcp_check")]
 extern crate interval;
 extern crate trilean;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate serde_json;
 
 pub mod session;
+pub mod errors;
+pub mod diagnostic;
+pub mod json_emitter;
 pub mod ast;
 pub mod visitor;
 pub mod context;
@@ -37,3 +44,10 @@ pub mod driver;
 pub mod front;
 pub mod middle;
 pub mod back;
+pub mod fmt;
+pub mod interpreter;
+pub mod testing;
+
+pub use driver::{compile, compile_with_file_system, Config, ConfigBuilder, CompiledCrate, CompiledModule};
+pub use driver::{FileSystem, DiskFileSystem, VirtualFileSystem};
+pub use diagnostic::{Diagnostic, DiagnosticSpan};
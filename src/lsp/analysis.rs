@@ -0,0 +1,82 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Analysis of the project with the front and middle phases of the compiler (see `front_mid_run`), the Java code is not generated.
+
+use index::*;
+use libbonsai::context::*;
+use libbonsai::driver::*;
+use libbonsai::diagnostic::Diagnostic;
+use std::rc::Rc;
+
+pub struct Analysis {
+  pub diagnostics: Vec<Diagnostic>,
+  /// `None` if the project could not be parsed.
+  pub context: Option<Context>,
+  pub index: Index
+}
+
+impl Analysis {
+  pub fn run(config: Config, fs: Rc<FileSystem>) -> Self {
+    let (context, codemap, diagnostics) = collect_diagnostics(config, fs, front_mid_run);
+    let context = match context {
+      Partial::Value(context)
+    | Partial::Fake(context) => Some(context),
+      Partial::Nothing => None
+    };
+    let index = match context {
+      Some(ref context) => Index::new(context, &codemap),
+      None => Index::empty()
+    };
+    Analysis { diagnostics, context, index }
+  }
+
+  /// The markdown description of the variable accessed or declared by `occurrence`.
+  pub fn hover(&self, occurrence: &Occurrence) -> Option<String> {
+    let context = self.context.as_ref()?;
+    let var = context.var_by_uid(occurrence.uid);
+    let decl = match var.kind {
+      Kind::Host => format!("{} {}", var.ty, var.name),
+      kind => format!("{} {} {}", kind, var.ty, var.name)
+    };
+    let mut lines = vec![format!("```java\n{}\n```", decl)];
+    lines.push(format!("- kind: {}", kind_name(var.kind)));
+    if let Kind::Spacetime(spacetime) = var.kind {
+      lines.push(format!("- spacetime: {}", spacetime));
+    }
+    lines.push(format!("- type: `{}`", var.ty));
+    if let Some(permission) = occurrence.permission {
+      lines.push(format!("- permission: {}", permission));
+    }
+    lines.push(format!("- declared as: {}", declared_as(&var)));
+    Some(lines.join("\n"))
+  }
+}
+
+fn kind_name(kind: Kind) -> &'static str {
+  match kind {
+    Kind::Spacetime(_) => "spacetime variable",
+    Kind::Product => "module",
+    Kind::Host => "host variable"
+  }
+}
+
+fn declared_as(var: &VarInfo) -> String {
+  match var.field {
+    Some(ref field) if field.is_ref.is_some() => format!("{} ref field", field.visibility),
+    Some(ref field) => format!("{} field", field.visibility),
+    None if var.is_param => format!("process parameter"),
+    None => format!("local variable")
+  }
+}
@@ -0,0 +1,194 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The index locates the variables and the items of the analysed crate in the source files.
+/// The occurrences of a variable are identified by its UID in `Context::vars`, so a reference is resolved to its declaration across modules (e.g. `m.x` where `x` is a field of the module `m`).
+
+use libbonsai::context::*;
+use syntex_syntax::codemap::{CodeMap, Pos};
+
+/// Lines and characters start at 0 as in LSP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+  pub line: usize,
+  pub character: usize
+}
+
+impl Position {
+  pub fn new(line: usize, character: usize) -> Self {
+    Position { line, character }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+  pub file: String,
+  pub start: Position,
+  pub end: Position
+}
+
+impl Location {
+  /// `None` if the span was generated by the compiler.
+  pub fn from_span(codemap: &CodeMap, span: Span) -> Option<Self> {
+    if span == DUMMY_SP {
+      return None;
+    }
+    let lo = codemap.lookup_char_pos(span.lo);
+    let hi = codemap.lookup_char_pos(span.hi);
+    Some(Location {
+      file: lo.file.name.clone(),
+      start: Position::new(lo.line - 1, lo.col.to_usize()),
+      end: Position::new(hi.line - 1, hi.col.to_usize())
+    })
+  }
+
+  pub fn contains(&self, file: &str, pos: Position) -> bool {
+    self.file == file && self.start <= pos && pos <= self.end
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct Occurrence {
+  pub location: Location,
+  /// The UID of the variable as declared in the source code.
+  pub uid: usize,
+  /// The permission inferred for this access, `None` for a declaration.
+  pub permission: Option<Permission>,
+  pub is_declaration: bool
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+  Module,
+  Field,
+  Process
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+  pub name: String,
+  pub kind: SymbolKind,
+  pub location: Location,
+  /// The module containing a field or a process.
+  pub container: Option<String>
+}
+
+pub struct Index {
+  occurrences: Vec<Occurrence>,
+  symbols: Vec<Symbol>
+}
+
+impl Index {
+  pub fn empty() -> Self {
+    Index { occurrences: vec![], symbols: vec![] }
+  }
+
+  pub fn new(context: &Context, codemap: &CodeMap) -> Self {
+    let mut builder = IndexBuilder {
+      context: context,
+      codemap: codemap,
+      index: Index::empty(),
+      module: None
+    };
+    builder.visit_crate(context.clone_ast());
+    builder.index
+  }
+
+  pub fn occurrence_at(&self, file: &str, pos: Position) -> Option<&Occurrence> {
+    self.occurrences.iter()
+      .find(|occ| occ.location.contains(file, pos))
+  }
+
+  pub fn declaration(&self, uid: usize) -> Option<&Occurrence> {
+    self.occurrences.iter()
+      .find(|occ| occ.uid == uid && occ.is_declaration)
+  }
+
+  pub fn references(&self, uid: usize, include_declaration: bool) -> Vec<&Occurrence> {
+    self.occurrences.iter()
+      .filter(|occ| occ.uid == uid && (include_declaration || !occ.is_declaration))
+      .collect()
+  }
+
+  pub fn symbols(&self, file: &str) -> Vec<&Symbol> {
+    self.symbols.iter()
+      .filter(|symbol| symbol.location.file == file)
+      .collect()
+  }
+}
+
+struct IndexBuilder<'a> {
+  context: &'a Context,
+  codemap: &'a CodeMap,
+  index: Index,
+  module: Option<String>
+}
+
+impl<'a> IndexBuilder<'a> {
+  fn occurrence(&mut self, span: Span, uid: usize, permission: Option<Permission>, is_declaration: bool) {
+    // The UID `0` is shared by all the external variables.
+    if uid == 0 { return; }
+    if let Some(location) = Location::from_span(self.codemap, span) {
+      let uid = self.context.source_uid(uid);
+      self.index.occurrences.push(Occurrence { location, uid, permission, is_declaration });
+    }
+  }
+
+  fn symbol(&mut self, name: &Ident, kind: SymbolKind) {
+    if let Some(location) = Location::from_span(self.codemap, name.span) {
+      let container = match kind {
+        SymbolKind::Module => None,
+        _ => self.module.clone()
+      };
+      self.index.symbols.push(Symbol { name: name.unwrap(), kind, location, container });
+    }
+  }
+}
+
+impl<'a> Visitor<JClass> for IndexBuilder<'a>
+{
+  fn visit_module(&mut self, module: JModule) {
+    let mod_name = module.mod_name();
+    self.symbol(&mod_name, SymbolKind::Module);
+    self.module = Some(mod_name.unwrap());
+    walk_fields(self, module.fields);
+    walk_processes(self, module.processes);
+  }
+
+  fn visit_field(&mut self, field: ModuleField) {
+    self.symbol(&field.binding.name, SymbolKind::Field);
+    self.visit_binding(field.binding)
+  }
+
+  fn visit_process(&mut self, process: Process) {
+    self.symbol(&process.name, SymbolKind::Process);
+    for param in process.params {
+      self.visit_binding(param);
+    }
+    self.visit_stmt(process.body)
+  }
+
+  fn visit_binding(&mut self, binding: Binding) {
+    self.occurrence(binding.name.span, binding.uid, None, true);
+    walk_binding(self, binding)
+  }
+
+  fn visit_var(&mut self, var: Variable) {
+    let last = var.path.len() - 1;
+    for (i, (fragment, uid)) in var.path.fragments.iter().zip(var.path.uids.iter()).enumerate() {
+      let permission = if i == last { var.permission } else { None };
+      self.occurrence(fragment.span, *uid, permission, false);
+    }
+  }
+}
@@ -0,0 +1,46 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Language server of bonsai, communicating with the editor by JSON-RPC on the standard input and output.
+/// It publishes the diagnostics of the project whenever a document changes, and answers the requests for hover, go-to-definition, references and document symbols.
+///
+/// The project is the root directory given by the editor in `initialize`, the libraries can be given with `"initializationOptions": {"libs": ["path/to/lib"]}`.
+/// Columns are counted in characters, which coincide with the UTF-16 code units of LSP for the characters of the basic multilingual plane.
+
+extern crate libbonsai;
+extern crate syntex_syntax;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate log;
+extern crate env_logger;
+
+mod rpc;
+mod uri;
+mod index;
+mod analysis;
+mod server;
+
+use server::Server;
+use std::io;
+use std::process;
+
+fn main() {
+  env_logger::init();
+  let stdin = io::stdin();
+  let stdout = io::stdout();
+  let mut server = Server::new(stdout.lock());
+  let code = server.run(&mut stdin.lock());
+  process::exit(code);
+}
@@ -0,0 +1,53 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Framing of the JSON-RPC messages: each message is preceded by a `Content-Length` header and an empty line.
+
+use serde_json::{self, Value};
+use std::io::{self, BufRead, Write};
+
+/// Reads the next message, `None` if the input is closed.
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+  let mut content_length = None;
+  loop {
+    let mut header = String::new();
+    if input.read_line(&mut header)? == 0 {
+      return Ok(None);
+    }
+    let header = header.trim();
+    if header.is_empty() {
+      break;
+    }
+    let mut parts = header.splitn(2, ':');
+    let name = parts.next().unwrap_or("").trim();
+    let value = parts.next().unwrap_or("").trim();
+    if name.eq_ignore_ascii_case("Content-Length") {
+      content_length = Some(value.parse::<usize>().map_err(|e|
+        io::Error::new(io::ErrorKind::InvalidData, format!("Content-Length `{}`: {}", value, e)))?);
+    }
+  }
+  let len = content_length.ok_or(
+    io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+  let mut content = vec![0; len];
+  input.read_exact(&mut content)?;
+  serde_json::from_slice(&content)
+    .map(Some)
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+  let content = message.to_string();
+  write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+  output.flush()
+}
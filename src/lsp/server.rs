@@ -0,0 +1,487 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The server keeps the open documents in memory and analyses the whole project again when one of them changes.
+/// The documents shadow the files on the disk, so the diagnostics are computed on the unsaved buffers.
+
+use rpc;
+use uri;
+use index::*;
+use analysis::Analysis;
+use libbonsai::driver::*;
+use libbonsai::diagnostic::{Diagnostic, DiagnosticSpan};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Error codes of JSON-RPC.
+static PARSE_ERROR: i64 = -32700;
+static INVALID_PARAMS: i64 = -32602;
+static METHOD_NOT_FOUND: i64 = -32601;
+
+/// Types of the messages of `window/logMessage`.
+static ERROR_MESSAGE: i64 = 1;
+static INFO_MESSAGE: i64 = 3;
+
+type Response = Result<Value, (i64, String)>;
+
+pub struct Server<W: Write> {
+  output: W,
+  root: Option<PathBuf>,
+  libs: Vec<PathBuf>,
+  /// The content of the open documents, indexed by their paths.
+  documents: HashMap<PathBuf, String>,
+  /// The last analysis that parsed the project, it is kept when the current project has syntax errors.
+  analysis: Option<Analysis>,
+  /// The files for which diagnostics were published, they must be cleared if they do not have diagnostics anymore.
+  published: HashSet<String>,
+  shutdown: bool
+}
+
+impl<W: Write> Server<W> {
+  pub fn new(output: W) -> Self {
+    Server {
+      output: output,
+      root: None,
+      libs: vec![],
+      documents: HashMap::new(),
+      analysis: None,
+      published: HashSet::new(),
+      shutdown: false
+    }
+  }
+
+  /// Serves the messages of `input` until `exit` is received, and returns the exit code of the process.
+  pub fn run<R: BufRead>(&mut self, input: &mut R) -> i32 {
+    loop {
+      match rpc::read_message(input) {
+        Ok(Some(message)) => {
+          if let Some(code) = self.handle(message) {
+            return code;
+          }
+        }
+        Ok(None) => {
+          warn!("the input is closed before the `exit` notification.");
+          return 1;
+        }
+        Err(e) => {
+          self.send(json!({"jsonrpc": "2.0", "id": Value::Null,
+            "error": {"code": PARSE_ERROR, "message": format!("{}", e)}}));
+        }
+      }
+    }
+  }
+
+  fn handle(&mut self, message: Value) -> Option<i32> {
+    let method = message["method"].as_str().map(String::from);
+    let params = message["params"].clone();
+    match (method, message.get("id").cloned()) {
+      (Some(method), Some(id)) => {
+        let response = self.request(&method, params);
+        self.respond(id, response);
+        None
+      }
+      (Some(method), None) => self.notification(&method, params),
+      // The server does not send requests, so it does not expect responses.
+      (None, _) => None
+    }
+  }
+
+  fn request(&mut self, method: &str, params: Value) -> Response {
+    match method {
+      "initialize" => Ok(self.initialize(params)),
+      "shutdown" => {
+        self.shutdown = true;
+        Ok(Value::Null)
+      }
+      "textDocument/hover" => self.hover(params),
+      "textDocument/definition" => self.definition(params),
+      "textDocument/references" => self.references(params),
+      "textDocument/documentSymbol" => self.document_symbols(params),
+      _ => Err((METHOD_NOT_FOUND, format!("unsupported request `{}`", method)))
+    }
+  }
+
+  fn notification(&mut self, method: &str, params: Value) -> Option<i32> {
+    match method {
+      "exit" => return Some(if self.shutdown { 0 } else { 1 }),
+      "textDocument/didOpen" => {
+        if let Some(path) = document_path(&params) {
+          let text = params["textDocument"]["text"].as_str().unwrap_or("");
+          self.documents.insert(path, String::from(text));
+          self.analyse();
+        }
+      }
+      "textDocument/didChange" => {
+        // We only support the synchronization of the full content (`TextDocumentSyncKind.Full`).
+        let text = params["contentChanges"].as_array()
+          .and_then(|changes| changes.last())
+          .and_then(|change| change["text"].as_str())
+          .map(String::from);
+        if let (Some(path), Some(text)) = (document_path(&params), text) {
+          self.documents.insert(path, text);
+          self.analyse();
+        }
+      }
+      "textDocument/didClose" => {
+        if let Some(path) = document_path(&params) {
+          self.documents.remove(&path);
+          self.analyse();
+        }
+      }
+      "textDocument/didSave" => self.analyse(),
+      _ => debug!("notification `{}` ignored.", method)
+    }
+    None
+  }
+
+  fn initialize(&mut self, params: Value) -> Value {
+    self.root = params["rootUri"].as_str().and_then(uri::to_path)
+      .or(params["rootPath"].as_str().map(PathBuf::from));
+    self.libs = params["initializationOptions"]["libs"].as_array()
+      .map(|libs| libs.iter().filter_map(|lib| lib.as_str()).map(PathBuf::from).collect())
+      .unwrap_or(vec![]);
+    json!({
+      "capabilities": {
+        "textDocumentSync": 1,
+        "hoverProvider": true,
+        "definitionProvider": true,
+        "referencesProvider": true,
+        "documentSymbolProvider": true
+      }
+    })
+  }
+
+  fn analyse(&mut self) {
+    let root = match self.root.clone() {
+      Some(root) => root,
+      None => {
+        self.log("bonsai-lsp: no root directory was given in `initialize`, the project is not analysed.");
+        return;
+      }
+    };
+    let mut fs = VirtualFileSystem::overlay();
+    for (path, text) in &self.documents {
+      fs.add_file(path.clone(), text.clone());
+    }
+    let config = self.libs.iter()
+      .fold(ConfigBuilder::new(root), |config, lib| config.lib(lib.clone()))
+      .build();
+    let fs: Rc<FileSystem> = Rc::new(fs);
+    // A bug in the compiler must not stop the server, the previous analysis is kept.
+    let analysis = match panic::catch_unwind(AssertUnwindSafe(|| Analysis::run(config, fs))) {
+      Ok(analysis) => analysis,
+      Err(cause) => {
+        let cause = cause.downcast_ref::<&str>().map(|s| String::from(*s))
+          .or(cause.downcast_ref::<String>().cloned())
+          .unwrap_or(String::from("unknown cause"));
+        self.log_message(ERROR_MESSAGE, &format!(
+          "bonsai-lsp: the analysis of the project failed because of a bug in the compiler ({}).", cause));
+        return;
+      }
+    };
+    self.publish_diagnostics(&analysis.diagnostics);
+    if analysis.context.is_some() || self.analysis.is_none() {
+      self.analysis = Some(analysis);
+    }
+  }
+
+  fn publish_diagnostics(&mut self, diagnostics: &Vec<Diagnostic>) {
+    let mut by_file: HashMap<String, Vec<Value>> = HashMap::new();
+    for diagnostic in diagnostics {
+      let primary = diagnostic.spans.iter()
+        .find(|span| span.is_primary)
+        .or(diagnostic.spans.first());
+      match primary {
+        Some(span) => {
+          by_file.entry(span.file.clone()).or_insert(vec![])
+            .push(lsp_diagnostic(diagnostic, span));
+        }
+        None => self.log(&format!("{}: {}", diagnostic.level, diagnostic.message))
+      }
+    }
+    let mut files: HashSet<String> = self.published.drain().collect();
+    files.extend(self.documents.keys().map(|path| format!("{}", path.display())));
+    files.extend(by_file.keys().cloned());
+    for file in files {
+      let diagnostics = by_file.remove(&file).unwrap_or(vec![]);
+      if !diagnostics.is_empty() {
+        self.published.insert(file.clone());
+      }
+      self.send(json!({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+        "params": {"uri": uri::from_path(&PathBuf::from(file)), "diagnostics": diagnostics}}));
+    }
+  }
+
+  fn hover(&self, params: Value) -> Response {
+    let (file, pos) = text_position(&params)?;
+    Ok(self.analysis.as_ref()
+      .and_then(|analysis| analysis.index.occurrence_at(&file, pos)
+        .and_then(|occ| analysis.hover(occ)
+          .map(|text| json!({
+            "contents": {"kind": "markdown", "value": text},
+            "range": lsp_range(&occ.location)
+          }))))
+      .unwrap_or(Value::Null))
+  }
+
+  fn definition(&self, params: Value) -> Response {
+    let (file, pos) = text_position(&params)?;
+    Ok(self.analysis.as_ref()
+      .and_then(|analysis| analysis.index.occurrence_at(&file, pos)
+        .and_then(|occ| analysis.index.declaration(occ.uid))
+        .map(|decl| lsp_location(&decl.location)))
+      .unwrap_or(Value::Null))
+  }
+
+  fn references(&self, params: Value) -> Response {
+    let (file, pos) = text_position(&params)?;
+    let include_declaration = params["context"]["includeDeclaration"].as_bool().unwrap_or(true);
+    let locations: Vec<Value> = self.analysis.as_ref()
+      .and_then(|analysis| analysis.index.occurrence_at(&file, pos)
+        .map(|occ| analysis.index.references(occ.uid, include_declaration).into_iter()
+          .map(|reference| lsp_location(&reference.location))
+          .collect()))
+      .unwrap_or(vec![]);
+    Ok(Value::Array(locations))
+  }
+
+  fn document_symbols(&self, params: Value) -> Response {
+    let file = document_path(&params)
+      .map(|path| format!("{}", path.display()))
+      .ok_or(invalid_params())?;
+    let symbols: Vec<Value> = self.analysis.as_ref()
+      .map(|analysis| analysis.index.symbols(&file).into_iter()
+        .map(lsp_symbol)
+        .collect())
+      .unwrap_or(vec![]);
+    Ok(Value::Array(symbols))
+  }
+
+  fn respond(&mut self, id: Value, response: Response) {
+    let message = match response {
+      Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+      Err((code, message)) => json!({"jsonrpc": "2.0", "id": id,
+        "error": {"code": code, "message": message}})
+    };
+    self.send(message);
+  }
+
+  fn log(&mut self, message: &str) {
+    self.log_message(INFO_MESSAGE, message);
+  }
+
+  fn log_message(&mut self, message_type: i64, message: &str) {
+    self.send(json!({"jsonrpc": "2.0", "method": "window/logMessage",
+      "params": {"type": message_type, "message": message}}));
+  }
+
+  fn send(&mut self, message: Value) {
+    if let Err(e) = rpc::write_message(&mut self.output, &message) {
+      error!("could not send a message to the client: {}", e);
+    }
+  }
+}
+
+fn invalid_params() -> (i64, String) {
+  (INVALID_PARAMS, format!("expected `textDocument.uri` with a `file` scheme"))
+}
+
+fn document_path(params: &Value) -> Option<PathBuf> {
+  params["textDocument"]["uri"].as_str().and_then(uri::to_path)
+}
+
+fn text_position(params: &Value) -> Result<(String, Position), (i64, String)> {
+  let file = document_path(params)
+    .map(|path| format!("{}", path.display()))
+    .ok_or(invalid_params())?;
+  let line = params["position"]["line"].as_u64();
+  let character = params["position"]["character"].as_u64();
+  match (line, character) {
+    (Some(line), Some(character)) => Ok((file, Position::new(line as usize, character as usize))),
+    _ => Err((INVALID_PARAMS, format!("expected `position.line` and `position.character`")))
+  }
+}
+
+fn lsp_range(location: &Location) -> Value {
+  json!({
+    "start": {"line": location.start.line, "character": location.start.character},
+    "end": {"line": location.end.line, "character": location.end.character}
+  })
+}
+
+fn lsp_location(location: &Location) -> Value {
+  json!({"uri": uri::from_path(&PathBuf::from(&location.file)), "range": lsp_range(location)})
+}
+
+/// The spans of the diagnostics start at 1.
+fn span_location(span: &DiagnosticSpan) -> Location {
+  Location {
+    file: span.file.clone(),
+    start: Position::new(span.line_start - 1, span.column_start - 1),
+    end: Position::new(span.line_end - 1, span.column_end - 1)
+  }
+}
+
+fn lsp_diagnostic(diagnostic: &Diagnostic, primary: &DiagnosticSpan) -> Value {
+  let severity = match diagnostic.level.as_str() {
+    "warning" => 2,
+    "note" => 3,
+    "help" => 4,
+    _ => 1
+  };
+  let mut message = diagnostic.message.clone();
+  if let Some(ref label) = primary.label {
+    message.push_str(&format!("\n{}", label));
+  }
+  for child in &diagnostic.children {
+    message.push_str(&format!("\n{}: {}", child.level, child.message));
+  }
+  let related: Vec<Value> = diagnostic.spans.iter()
+    .filter(|span| *span != primary)
+    .map(|span| json!({
+      "location": lsp_location(&span_location(span)),
+      "message": span.label.clone().unwrap_or(String::new())
+    }))
+    .collect();
+  json!({
+    "range": lsp_range(&span_location(primary)),
+    "severity": severity,
+    "code": diagnostic.code,
+    "source": "bonsai",
+    "message": message,
+    "relatedInformation": related
+  })
+}
+
+fn lsp_symbol(symbol: &Symbol) -> Value {
+  // See `SymbolKind` in the LSP specification.
+  let kind = match symbol.kind {
+    SymbolKind::Module => 5,
+    SymbolKind::Field => 8,
+    SymbolKind::Process => 6
+  };
+  json!({
+    "name": symbol.name,
+    "kind": kind,
+    "location": lsp_location(&symbol.location),
+    "containerName": symbol.container
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::env;
+  use std::io::Cursor;
+  use std::process;
+
+  static COUNTER: &'static str = "package test;
+
+public class Counter
+{
+  public single_space LMax value = new LMax(0);
+
+  public proc inc() =
+    readwrite value.inc();
+  end
+}
+";
+
+  fn frame(messages: Vec<Value>) -> Cursor<Vec<u8>> {
+    let mut input = vec![];
+    for message in messages {
+      rpc::write_message(&mut input, &message).unwrap();
+    }
+    Cursor::new(input)
+  }
+
+  fn unframe(output: Vec<u8>) -> Vec<Value> {
+    let mut output = Cursor::new(output);
+    let mut messages = vec![];
+    while let Some(message) = rpc::read_message(&mut output).unwrap() {
+      messages.push(message);
+    }
+    messages
+  }
+
+  fn response(messages: &Vec<Value>, id: u64) -> Value {
+    messages.iter()
+      .find(|message| message["id"] == json!(id))
+      .map(|message| message["result"].clone())
+      .expect(&format!("no response to the request {}", id))
+  }
+
+  /// A session of an editor opening a document of a project existing only in its buffers.
+  #[test]
+  fn scripted_session() {
+    let root = env::temp_dir().join(format!("bonsai-lsp-{}", process::id()));
+    let file = root.join("test/Counter.bonsai.java");
+    let uri = uri::from_path(&file);
+    // The position of `value` in `readwrite value.inc()`.
+    let position = json!({"textDocument": {"uri": uri}, "position": {"line": 7, "character": 15}});
+    let mut references = position.clone();
+    references["context"] = json!({"includeDeclaration": true});
+    let mut input = frame(vec![
+      json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+        "params": {"rootUri": uri::from_path(&root)}}),
+      json!({"jsonrpc": "2.0", "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": uri, "languageId": "bonsai", "version": 1, "text": COUNTER}}}),
+      json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": position}),
+      json!({"jsonrpc": "2.0", "id": 3, "method": "textDocument/definition", "params": position}),
+      json!({"jsonrpc": "2.0", "id": 4, "method": "textDocument/references", "params": references}),
+      json!({"jsonrpc": "2.0", "id": 5, "method": "textDocument/documentSymbol",
+        "params": {"textDocument": {"uri": uri}}}),
+      json!({"jsonrpc": "2.0", "id": 6, "method": "shutdown"}),
+      json!({"jsonrpc": "2.0", "method": "exit"})
+    ]);
+    let mut server = Server::new(vec![]);
+    assert_eq!(server.run(&mut input), 0);
+    let messages = unframe(server.output);
+
+    assert_eq!(response(&messages, 1)["capabilities"]["hoverProvider"], json!(true));
+
+    let published: Vec<&Value> = messages.iter()
+      .filter(|message| message["method"] == json!("textDocument/publishDiagnostics"))
+      .collect();
+    assert_eq!(published.len(), 1);
+    assert_eq!(published[0]["params"]["uri"], json!(uri));
+    assert_eq!(published[0]["params"]["diagnostics"], json!([]));
+
+    let hover = response(&messages, 2);
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("LMax value"), "hover: {}", text);
+    assert!(text.contains("- permission: readwrite"), "hover: {}", text);
+    assert!(text.contains("- declared as: public field"), "hover: {}", text);
+    assert_eq!(hover["range"]["start"], json!({"line": 7, "character": 14}));
+
+    let declaration = json!({"uri": uri, "range": {
+      "start": {"line": 4, "character": 27}, "end": {"line": 4, "character": 32}}});
+    assert_eq!(response(&messages, 3), declaration);
+
+    let references = response(&messages, 4);
+    assert_eq!(references.as_array().unwrap().len(), 2);
+    assert!(references.as_array().unwrap().contains(&declaration));
+
+    let symbols: Vec<(String, u64)> = response(&messages, 5).as_array().unwrap().iter()
+      .map(|symbol| (String::from(symbol["name"].as_str().unwrap()), symbol["kind"].as_u64().unwrap()))
+      .collect();
+    assert_eq!(symbols, vec![(String::from("Counter"), 5), (String::from("value"), 8), (String::from("inc"), 6)]);
+
+    assert_eq!(response(&messages, 6), Value::Null);
+  }
+}
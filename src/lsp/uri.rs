@@ -0,0 +1,56 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Conversion between the `file://` URIs of LSP and the paths of the file system.
+
+use std::path::{Path, PathBuf};
+
+static FILE_SCHEME: &'static str = "file://";
+
+pub fn to_path(uri: &str) -> Option<PathBuf> {
+  if uri.starts_with(FILE_SCHEME) {
+    decode(&uri[FILE_SCHEME.len()..]).map(PathBuf::from)
+  }
+  else { None }
+}
+
+pub fn from_path(path: &Path) -> String {
+  let mut uri = String::from(FILE_SCHEME);
+  for byte in format!("{}", path.display()).bytes() {
+    match byte {
+      b'a' ... b'z' | b'A' ... b'Z' | b'0' ... b'9'
+    | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+      _ => uri.push_str(&format!("%{:02X}", byte))
+    }
+  }
+  uri
+}
+
+/// Decode the percent-encoded bytes of `s`, `None` if it is malformed.
+fn decode(s: &str) -> Option<String> {
+  let mut bytes = vec![];
+  let mut chars = s.bytes();
+  while let Some(byte) = chars.next() {
+    if byte == b'%' {
+      let hex: Vec<u8> = chars.by_ref().take(2).collect();
+      if hex.len() != 2 { return None; }
+      let hex = String::from_utf8(hex).ok()?;
+      bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+    }
+    else {
+      bytes.push(byte);
+    }
+  }
+  String::from_utf8(bytes).ok()
+}
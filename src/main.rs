@@ -12,34 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#![feature(plugin, box_syntax)]
-#![plugin(oak)]
-
-extern crate oak_runtime;
-extern crate clap;
-extern crate partial;
-extern crate syntex_pos;
-extern crate syntex_syntax;
-extern crate syntex_errors;
-extern crate regex;
-extern crate pcp;
-extern crate gcollections;
-extern crate interval;
-extern crate trilean;
-#[macro_use]
-extern crate log;
+extern crate libbonsai;
 extern crate env_logger;
 
-mod session;
-mod ast;
-mod visitor;
-mod context;
-mod driver;
-mod front;
-mod middle;
-mod back;
-
 fn main() {
   env_logger::init();
-  driver::run();
+  libbonsai::driver::run();
 }
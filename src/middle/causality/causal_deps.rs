@@ -42,7 +42,7 @@ impl CausalDeps {
       Var(var) => self.visit_var(var, is_monotonic, model),
       Or(left, right)
     | And(left, right) => self.visit_bin_op(*left, *right, is_monotonic, model),
-      Not(expr) => self.visit_expr(*expr, is_monotonic, model),
+      Not(expr) => self.visit_not(*expr, is_monotonic, model),
       Entailment(rel) => self.visit_entailment(*rel, is_monotonic, model)
     }
   }
@@ -53,26 +53,33 @@ impl CausalDeps {
   fn visit_exprs_simultaneously(&self, exprs: Vec<Expr>, is_monotonic: Option<bool>, mut model: CausalModel) -> CausalModel {
     let mut op_models = vec![];
     let mut models = vec![];
+    let mut simultaneous_ops = vec![];
     for expr in exprs {
+      if let ExprKind::Var(ref var) = expr.node {
+        simultaneous_ops.push(var.op_no);
+      }
       let is_var = expr.is_var();
       let m = self.visit_expr(expr, is_monotonic, model.clone());
       if is_var { op_models.push(m); }
       else { models.push(m); }
     }
     model = model.fold(models);
-    assert!(op_models.iter().all(|m| m.latest_ops.len() == 1),
-      "visit_exprs_simultaneously: variable operation must only create one latest operation.");
-    let simultaneous_ops: Vec<usize> = op_models.iter().map(|m| m.latest_ops[0]).collect();
     model = model.fold(op_models);
     model.add_simultaneous_ops_constraint(simultaneous_ops);
     model
   }
 
-  /// We restrict ourselves to binary operations causal with a left-to-right order of evaluation.
-  /// This is to simplify the runtime.
+  /// The operands of `and` and `or` are conditions evaluated independently at runtime (see `KleeneAnd` and `KleeneOr`).
+  /// Similarly to the entailment, their variables are only read, so there is no sequential constraint between the left and right operands.
   fn visit_bin_op(&self, left: Expr, right: Expr, is_monotonic: Option<bool>, model: CausalModel) -> CausalModel {
-    let m1 = self.visit_expr(left, is_monotonic, model);
-    self.visit_expr(right, is_monotonic, m1)
+    let m1 = self.visit_expr(left, is_monotonic, model.clone());
+    let m2 = self.visit_expr(right, is_monotonic, model);
+    m1.join_constraints(m2)
+  }
+
+  /// The negation reverses the monotonicity of its operand: `not (x |= e)` can only be decided once `x` cannot be written anymore.
+  fn visit_not(&self, expr: Expr, is_monotonic: Option<bool>, model: CausalModel) -> CausalModel {
+    self.visit_expr(expr, is_monotonic.map(|m| !m), model)
   }
 
   fn visit_method_call(&self, call: MethodCall, is_monotonic: Option<bool>, model: CausalModel) -> CausalModel {
@@ -90,12 +97,14 @@ impl CausalDeps {
   /// The sub-expressions of the entailment are quite limited:
   /// By the analysis E0026 and E0027 host functions, write and readwrite are forbidden.
   /// Therefore, there is no need to generate sequential constraints between the left and right sides.
+  /// The strict entailment `|<` has the same monotonicity as `|=`, but the equality `==` is not monotonic in any of its sides.
   fn visit_entailment(&self, rel: EntailmentRel, is_monotonic: Option<bool>, model: CausalModel) -> CausalModel {
     let (l, r) =
-      match is_monotonic {
-        None => (None, None),
-        Some(true) => (Some(true), Some(false)),
-        Some(false) => (Some(false), Some(true))
+      match (is_monotonic, rel.op) {
+        (None, _) => (None, None),
+        (Some(_), EntailmentKind::Equality) => (Some(false), Some(false)),
+        (Some(true), _) => (Some(true), Some(false)),
+        (Some(false), _) => (Some(false), Some(true))
       };
     let m1 = self.visit_expr(rel.left, l, model.clone());
     let m2 = self.visit_expr(rel.right, r, model);
@@ -104,20 +113,15 @@ impl CausalDeps {
 
   pub fn visit_var(&self, var: Variable, is_monotonic: Option<bool>, mut model: CausalModel) -> CausalModel {
     debug!("visit var {}, monotonic: {:?}", var, is_monotonic);
-    model.params.activate_op(var.op_no);
     if let Some(true) = is_monotonic {
       model.params.store_monotonic_read(var.op_no);
     }
-    if var.permission.unwrap() != Permission::Read {
-      model.params.register_relaxed_op(var.op_no);
-    }
+    model.activate_op(var.op_no);
     model.add_after_latest_constraint(var.op_no);
-    model.instantaneous = true;
     model
   }
 
-  fn visit_constant(&self, mut model: CausalModel) -> CausalModel {
-    model.instantaneous = true;
+  fn visit_constant(&self, model: CausalModel) -> CausalModel {
     model
   }
 }
@@ -12,45 +12,152 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-/// Constraint model modelling the causal dependencies of an execution path in a spacetime program.
-/// The causal dependencies of the full program can modelled with `Vec<CausalModel>` (see `causal_stmt.rs`).
+/// Constraint model modelling the causal dependencies of an instant in a spacetime program.
 /// It is described in the Section 4.5 of the dissertation (Talbot, 2018).
+///
+/// The constraints are only ordering constraints between operations, so the model is a precedence graph.
+/// Instead of building one graph for each execution path of the instant, every constraint is guarded by the branches of the path in which it is created (see `Guard`).
+/// The model is satisfiable if and only if, in every execution path, no strongly connected component contains a strict edge (see `CausalModel::conflict`).
 
+use context::*;
 use middle::causality::model_parameters::*;
-use pcp::search::*;
-use pcp::concept::*;
-use pcp::propagators::*;
-use gcollections::ops::*;
-use interval::interval_set::*;
-use interval::ops::Range;
 use std::clone::Clone;
+use std::collections::VecDeque;
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OrderConstraint {
+  /// The operation `before` precedes `after` in the program (e.g. in a sequence `P; Q`).
+  Sequential(usize, usize),
+  /// The access `before` must precede `after` because of their permissions (write < readwrite < read).
+  Permission(usize, usize),
+  /// The operations are the arguments of a same call, they can be executed in any order.
+  Simultaneous(Vec<usize>)
+}
+
+/// A conjunction of branches: the index of a condition (see `CausalModel::conditions`) and whether it is `true` or `false`.
+/// The branches are sorted by condition, and a condition appears at most once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Guard {
+  branches: Vec<(usize, bool)>
+}
+
+impl Guard {
+  /// The guard satisfied by every execution path.
+  pub fn always() -> Self {
+    Guard { branches: vec![] }
+  }
+
+  pub fn branches(&self) -> &Vec<(usize, bool)> {
+    &self.branches
+  }
+
+  pub fn contains(&self, branch: (usize, bool)) -> bool {
+    self.branches.contains(&branch)
+  }
+
+  pub fn decides(&self, condition: usize) -> bool {
+    self.branches.iter().any(|&(c, _)| c == condition)
+  }
+
+  /// Precondition: `condition` does not appear in the guard.
+  pub fn take_branch(&mut self, condition: usize, taken: bool) {
+    let pos = self.branches.iter()
+      .position(|&(c, _)| c > condition)
+      .unwrap_or(self.branches.len());
+    self.branches.insert(pos, (condition, taken));
+  }
+
+  /// The conjunction of both guards, or `None` if they take different branches of a same condition.
+  pub fn and(&self, other: &Guard) -> Option<Guard> {
+    let mut res = self.clone();
+    for &(condition, taken) in &other.branches {
+      match self.branches.iter().find(|&&(c, _)| c == condition) {
+        Some(&(_, t)) if t != taken => return None,
+        Some(_) => (),
+        None => res.take_branch(condition, taken)
+      }
+    }
+    Some(res)
+  }
+
+  /// `true` if every execution path satisfying `self` also satisfies `other`.
+  pub fn implies(&self, other: &Guard) -> bool {
+    other.branches.iter().all(|b| self.branches.contains(b))
+  }
+
+  /// If both guards only differ by the branch taken on one condition, their disjunction is the guard without this condition.
+  fn resolve(&self, other: &Guard) -> Option<Guard> {
+    if self.branches.len() != other.branches.len() {
+      return None;
+    }
+    let mut diff = None;
+    for (i, (&b1, &b2)) in self.branches.iter().zip(other.branches.iter()).enumerate() {
+      if b1 != b2 {
+        if b1.0 != b2.0 || diff.is_some() {
+          return None;
+        }
+        diff = Some(i);
+      }
+    }
+    diff.map(|i| {
+      let mut res = self.clone();
+      res.branches.remove(i);
+      res
+    })
+  }
+}
+
+/// An execution path reaching the current statement: it is taken when `guard` holds, and `latest_ops` (sorted) are the last operations executed on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionPath {
+  pub guard: Guard,
+  pub latest_ops: Vec<usize>
+}
+
+impl ExecutionPath {
+  fn new(guard: Guard, mut latest_ops: Vec<usize>) -> Self {
+    latest_ops.sort();
+    latest_ops.dedup();
+    ExecutionPath { guard, latest_ops }
+  }
+}
+
+/// A cycle of constraints making an execution path unsatisfiable, as a list of `(from, to, constraint)`.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+  pub cycle: Vec<(usize, usize, OrderConstraint)>,
+  /// The branches taken by the execution path in which the cycle happens.
+  pub path: Guard
+}
+
+/// An edge `from -> to` of the precedence graph, `strict` if `from` must happen strictly before `to`.
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+  from: usize,
+  to: usize,
+  strict: bool,
+  constraint: usize
+}
+
+#[derive(Clone)]
 pub struct CausalModel {
-  pub space: FDSpace,
-  pub latest_ops: Vec<usize>,
-  pub instantaneous: bool,
-  pub order_of_op: Vec<Var<VStore>>,
+  /// The execution paths reaching the current statement, they are mutually exclusive.
+  /// It is empty if the statement is not reached in the current instant (e.g. after a `pause`).
+  pub flow: Vec<ExecutionPath>,
   pub params: ModelParameters,
+  /// Every constraint holds in the execution paths satisfying its guard.
+  pub constraints: Vec<(OrderConstraint, Guard)>,
+  /// The span of every condition appearing in the guards.
+  pub conditions: Vec<Span>,
 }
 
 impl CausalModel {
   pub fn new(params: ModelParameters) -> Self {
-    let mut m = CausalModel {
-      space: FDSpace::empty(),
-      latest_ops: vec![],
-      instantaneous: true,
-      order_of_op: vec![],
-      params
-    };
-    m.init_op_vars();
-    m
-  }
-
-  fn init_op_vars(&mut self) {
-    let n =  self.num_ops();
-    for _ in 0..n {
-      let v = Box::new(self.space.vstore.alloc(IntervalSet::new(0, (n-1) as i32))) as Var<VStore>;
-      self.order_of_op.push(v);
+    CausalModel {
+      flow: vec![ExecutionPath::new(Guard::always(), vec![])],
+      params,
+      constraints: vec![],
+      conditions: vec![]
     }
   }
 
@@ -58,72 +165,467 @@ impl CausalModel {
     self.params.num_ops()
   }
 
-  /// We join the constraint stores of `self` and `other` with the following assumptions:
-  ///   1. The variable stores are identical.
-  ///   2. The constraint stores are completely different.
-  ///   3. The model parameters can be joined (see `ModelParameters::join`).
-  /// The second assumption implies that we do not check for identical constraints.
+  /// No execution path reaches the current statement in this instant.
+  pub fn pause(&mut self) {
+    self.flow.clear();
+  }
+
+  /// Parallel composition of `self` and `other` (e.g. the branches of `par` or the operands of `x && y`).
+  /// An execution path of the composition is made of one path of each model, the models are assumed to be copies of a same base model.
   pub fn join_constraints(mut self, other: CausalModel) -> CausalModel
   {
-    assert_eq!(self.space.vstore.size(), other.space.vstore.size(),
-      "join_constraints: The variables store must be identical.");
-    self.latest_ops.extend(other.latest_ops.into_iter());
-    // NOTE: the conjunctive parallel statement <> is a weak preemption so during an instant, it behaves like ||.
-    self.instantaneous = self.instantaneous && other.instantaneous;
-    let cstore = other.space.cstore;
-    for i in 0..cstore.size() {
-      self.space.cstore.alloc(cstore[i].bclone());
+    let mut flow = vec![];
+    for p1 in &self.flow {
+      for p2 in &other.flow {
+        if let Some(guard) = p1.guard.and(&p2.guard) {
+          let mut latest_ops = p1.latest_ops.clone();
+          latest_ops.extend(p2.latest_ops.iter().cloned());
+          flow.push(ExecutionPath::new(guard, latest_ops));
+        }
+      }
     }
+    self.flow = flow;
+    self.merge_flow();
+    self.join_params_and_constraints(other)
+  }
+
+  /// Sequential composition of the branches of a conditional statement: the execution paths of `self` and `other` are exclusive.
+  pub fn join_branches(mut self, other: CausalModel) -> CausalModel
+  {
+    self.flow.extend(other.flow.iter().cloned());
+    self.merge_flow();
+    self.join_params_and_constraints(other)
+  }
+
+  /// The constraints are only appended to a model, and the joined models are usually copies of a same base model.
+  /// Hence we only add the constraints of `other` after the prefix it shares with `self`, the base constraints would be duplicated otherwise.
+  fn join_params_and_constraints(mut self, other: CausalModel) -> CausalModel {
     self.params = self.params.join(other.params);
+    let shared = self.constraints.iter()
+      .zip(other.constraints.iter())
+      .take_while(|&(c1, c2)| c1 == c2)
+      .count();
+    self.constraints.extend(other.constraints.into_iter().skip(shared));
     self
   }
 
-  pub fn cartesian_product(left: Vec<CausalModel>, right: Vec<CausalModel>)
-   -> Vec<CausalModel>
-  {
-    let mut res = vec![];
-    for s1 in left {
-      for s2 in right.clone() {
-        res.push(s1.clone().join_constraints(s2));
+  pub fn fold(self, models: Vec<CausalModel>) -> CausalModel {
+    models.into_iter().fold(self, |a, m| a.join_constraints(m))
+  }
+
+  /// Two paths ending with the same operations are merged if their guards only differ by one branch, so the number of paths does not grow with the number of conditional statements in sequence.
+  fn merge_flow(&mut self) {
+    let mut i = 0;
+    while i < self.flow.len() {
+      let mut merged = false;
+      for j in (i+1)..self.flow.len() {
+        if self.flow[i].latest_ops == self.flow[j].latest_ops {
+          let resolved =
+            if self.flow[i].guard == self.flow[j].guard { Some(self.flow[i].guard.clone()) }
+            else { self.flow[i].guard.resolve(&self.flow[j].guard) };
+          if let Some(guard) = resolved {
+            self.flow.remove(j);
+            self.flow[i].guard = guard;
+            merged = true;
+            break;
+          }
+        }
       }
+      // A merged path might be merged again with a path before it.
+      if merged { i = 0; }
+      else { i += 1; }
     }
-    res
   }
 
-  pub fn fold(self, models: Vec<CausalModel>) -> CausalModel {
-    models.into_iter().fold(self, |a, m| a.join_constraints(m))
+  /// Every execution path of the flow takes the branch `taken` of `condition`.
+  pub fn take_branch(&mut self, condition: usize, taken: bool) {
+    for path in &mut self.flow {
+      path.guard.take_branch(condition, taken);
+    }
+    self.params.commit_monotonic_reads((condition, taken));
+  }
+
+  /// The operation `op` is executed in every execution path of the flow.
+  pub fn activate_op(&mut self, op: usize) {
+    for path in &self.flow {
+      self.params.activate_op(op, path.guard.clone());
+      if self.params.var_of_op[op].permission != Some(Permission::Read) {
+        self.params.register_relaxed_op(op, &path.guard);
+      }
+    }
   }
 
   pub fn add_simultaneous_ops_constraint(&mut self, ops: Vec<usize>) {
     if ops.len() > 1 {
-      let vars: Vec<Var<VStore>> = ops.into_iter().map(|op| self.order_of_op[op].bclone()).collect();
-      let all_equal = Box::new(AllEqual::new(vars));
-      self.space.cstore.alloc(all_equal);
+      for path in &self.flow {
+        self.constraints.push((OrderConstraint::Simultaneous(ops.clone()), path.guard.clone()));
+      }
     }
   }
 
   pub fn add_after_latest_constraint(&mut self, after_op: usize) {
-    for before_op in self.latest_ops.clone() {
-      self.add_sequential_constraint(before_op, after_op);
+    for path in &mut self.flow {
+      for &before_op in &path.latest_ops {
+        self.constraints.push((OrderConstraint::Sequential(before_op, after_op), path.guard.clone()));
+      }
+      path.latest_ops = vec![after_op];
     }
-    self.latest_ops = vec![after_op];
+    self.merge_flow();
+  }
+
+  pub fn add_permission_constraint(&mut self, before_op: usize, after_op: usize, guard: Guard) {
+    self.constraints.push((OrderConstraint::Permission(before_op, after_op), guard));
   }
 
-  pub fn add_sequential_constraint(&mut self, before_op: usize, after_op: usize) {
-    let gt = Box::new(x_greater_y(
-      self.order_of_op[after_op].bclone(), self.order_of_op[before_op].bclone()));
-    self.space.cstore.alloc(gt);
+  /// The constraints holding in every execution path satisfying `guard`.
+  pub fn constraints_in(&self, guard: &Guard) -> Vec<OrderConstraint> {
+    self.constraints.iter()
+      .filter(|&&(_, ref g)| guard.implies(g))
+      .map(|&(ref c, _)| c.clone())
+      .collect()
+  }
+
+  /// Returns `None` if the model is satisfiable in every execution path, otherwise a cycle of constraints explaining why it is not.
+  /// The operations can be ordered if and only if no strongly connected component of the precedence graph contains a strict edge.
+  /// The components are computed in linear time on the graph of all the paths, and a faulty component is only split on its conditions when it exists (see `conflict_in`).
+  pub fn conflict(&self) -> Option<Conflict> {
+    let edges = self.ordering_edges();
+    self.conflict_in(&edges, Guard::always())
+  }
+
+  /// Searches a cycle in the execution paths satisfying `assignment`, the edges contradicting it are removed.
+  /// A faulty component is split on one of its conditions not yet decided, until the guards of all its edges are implied by `assignment`.
+  /// In this case, the witness is the shortest cycle going through a strict edge of the component.
+  fn conflict_in(&self, edges: &Vec<Edge>, assignment: Guard) -> Option<Conflict> {
+    let edges: Vec<Edge> = edges.iter()
+      .filter(|e| self.guard_of(e).and(&assignment).is_some())
+      .cloned()
+      .collect();
+    let succ = self.successors(&edges);
+    let component = self.strongly_connected_components(&edges, &succ);
+    let faulty = edges.iter()
+      .find(|e| e.strict && component[e.from] == component[e.to])
+      .map(|e| component[e.from]);
+    let c = match faulty {
+      None => return None,
+      Some(c) => c
+    };
+    let undecided = edges.iter()
+      .filter(|e| component[e.from] == c && component[e.to] == c)
+      .flat_map(|e| self.guard_of(e).branches().clone().into_iter())
+      .map(|(condition, _)| condition)
+      .find(|&condition| !assignment.decides(condition));
+    match undecided {
+      Some(condition) => {
+        // Both branches cover all the paths of `assignment`, including the ones of the other faulty components.
+        let mut then_a = assignment.clone();
+        then_a.take_branch(condition, true);
+        let mut else_a = assignment;
+        else_a.take_branch(condition, false);
+        self.conflict_in(&edges, then_a)
+          .or_else(|| self.conflict_in(&edges, else_a))
+      }
+      None => {
+        self.shortest_cycle(&edges, &succ, &component, c).map(|cycle| {
+          let path = cycle.iter()
+            .fold(Guard::always(), |path, &e| path.and(self.guard_of(&edges[e]))
+              .expect("[BUG] the guards of a cycle are implied by the same assignment."));
+          let cycle = cycle.into_iter()
+            .map(|e| (edges[e].from, edges[e].to, self.constraints[edges[e].constraint].0.clone()))
+            .collect();
+          Conflict { cycle, path }
+        })
+      }
+    }
+  }
+
+  fn guard_of(&self, edge: &Edge) -> &Guard {
+    &self.constraints[edge.constraint].1
+  }
+
+  /// Every constraint is turned into edges.
+  /// A simultaneous constraint is a chain of non-strict edges in both directions.
+  fn ordering_edges(&self) -> Vec<Edge> {
+    let mut edges = vec![];
+    for (c, &(ref constraint, _)) in self.constraints.iter().enumerate() {
+      match constraint {
+        &OrderConstraint::Sequential(before, after)
+      | &OrderConstraint::Permission(before, after) =>
+          edges.push(Edge { from: before, to: after, strict: true, constraint: c }),
+        &OrderConstraint::Simultaneous(ref ops) => {
+          for w in ops.windows(2) {
+            edges.push(Edge { from: w[0], to: w[1], strict: false, constraint: c });
+            edges.push(Edge { from: w[1], to: w[0], strict: false, constraint: c });
+          }
+        }
+      }
+    }
+    edges
+  }
+
+  /// The indexes of the outgoing edges of every operation.
+  fn successors(&self, edges: &Vec<Edge>) -> Vec<Vec<usize>> {
+    let mut succ = vec![vec![]; self.num_ops()];
+    for (e, edge) in edges.iter().enumerate() {
+      succ[edge.from].push(e);
+    }
+    succ
+  }
+
+  /// Iterative version of the algorithm of Tarjan, it returns the component of every operation.
+  fn strongly_connected_components(&self, edges: &Vec<Edge>, succ: &Vec<Vec<usize>>) -> Vec<usize> {
+    let n = self.num_ops();
+    let mut index = vec![None; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut component = vec![0; n];
+    let mut next_index = 0;
+    let mut next_component = 0;
+    for root in 0..n {
+      if index[root].is_some() { continue; }
+      // Each frame is an operation and the position of the next outgoing edge to explore.
+      let mut frames = vec![(root, 0)];
+      index[root] = Some(next_index);
+      lowlink[root] = next_index;
+      next_index += 1;
+      stack.push(root);
+      on_stack[root] = true;
+      loop {
+        let (op, i) = match frames.last() {
+          Some(&frame) => frame,
+          None => break
+        };
+        if i < succ[op].len() {
+          frames.last_mut().unwrap().1 += 1;
+          let to = edges[succ[op][i]].to;
+          match index[to] {
+            None => {
+              index[to] = Some(next_index);
+              lowlink[to] = next_index;
+              next_index += 1;
+              stack.push(to);
+              on_stack[to] = true;
+              frames.push((to, 0));
+            }
+            Some(to_index) if on_stack[to] => {
+              lowlink[op] = lowlink[op].min(to_index);
+            }
+            _ => ()
+          }
+        }
+        else {
+          frames.pop();
+          if let Some(&(parent, _)) = frames.last() {
+            lowlink[parent] = lowlink[parent].min(lowlink[op]);
+          }
+          if Some(lowlink[op]) == index[op] {
+            loop {
+              let member = stack.pop().unwrap();
+              on_stack[member] = false;
+              component[member] = next_component;
+              if member == op { break; }
+            }
+            next_component += 1;
+          }
+        }
+      }
+    }
+    component
+  }
+
+  /// The shortest cycle of the component `c` going through one of its strict edges, as a list of edge indexes.
+  fn shortest_cycle(&self, edges: &Vec<Edge>, succ: &Vec<Vec<usize>>,
+    component: &Vec<usize>, c: usize) -> Option<Vec<usize>>
+  {
+    let mut shortest: Option<Vec<usize>> = None;
+    for (e, edge) in edges.iter().enumerate() {
+      if edge.strict && component[edge.from] == c && component[edge.to] == c {
+        if let Some(path) = self.shortest_path(edges, succ, edge.to, edge.from) {
+          if shortest.as_ref().map_or(true, |s| path.len() + 1 < s.len()) {
+            let mut cycle = vec![e];
+            cycle.extend(path.into_iter());
+            shortest = Some(cycle);
+          }
+        }
+      }
+    }
+    shortest
+  }
+
+  /// Breadth-first search of the shortest path from `source` to `target`, returned as a list of edge indexes.
+  fn shortest_path(&self, edges: &Vec<Edge>, succ: &Vec<Vec<usize>>,
+    source: usize, target: usize) -> Option<Vec<usize>>
+  {
+    if source == target { return Some(vec![]); }
+    let mut pred: Vec<Option<usize>> = vec![None; self.num_ops()];
+    let mut visited = vec![false; self.num_ops()];
+    let mut queue = VecDeque::new();
+    visited[source] = true;
+    queue.push_back(source);
+    while let Some(op) = queue.pop_front() {
+      for &e in &succ[op] {
+        let to = edges[e].to;
+        if !visited[to] {
+          visited[to] = true;
+          pred[to] = Some(e);
+          if to == target {
+            let mut path = vec![];
+            let mut current = target;
+            while let Some(e) = pred[current] {
+              path.push(e);
+              current = edges[e].from;
+            }
+            path.reverse();
+            return Some(path);
+          }
+          queue.push_back(to);
+        }
+      }
+    }
+    None
   }
 }
 
-impl Clone for CausalModel {
-  fn clone(&self) -> Self {
-    CausalModel {
-      space: FDSpace::new(self.space.vstore.clone(), self.space.cstore.clone()),
-      latest_ops: self.latest_ops.clone(),
-      instantaneous: self.instantaneous,
-      order_of_op: self.order_of_op.iter().map(|v| v.bclone()).collect(),
-      params: self.params.clone()
+#[cfg(test)]
+mod test
+{
+  use super::*;
+  use super::OrderConstraint::*;
+
+  fn model(n: usize, constraints: Vec<(OrderConstraint, Guard)>) -> CausalModel {
+    let mut params = ModelParameters::new();
+    for _ in 0..n {
+      let mut var = Variable::access(DUMMY_SP, VarPath::gen("x"), None);
+      params.alloc_variable(&mut var);
+    }
+    let mut model = CausalModel::new(params);
+    model.constraints = constraints;
+    model
+  }
+
+  fn always(constraints: Vec<OrderConstraint>) -> Vec<(OrderConstraint, Guard)> {
+    constraints.into_iter().map(|c| (c, Guard::always())).collect()
+  }
+
+  fn guard(branches: &[(usize, bool)]) -> Guard {
+    let mut guard = Guard::always();
+    for &(condition, taken) in branches {
+      guard.take_branch(condition, taken);
     }
+    guard
+  }
+
+  fn components(model: &CausalModel) -> Vec<usize> {
+    let edges = model.ordering_edges();
+    let succ = model.successors(&edges);
+    model.strongly_connected_components(&edges, &succ)
+  }
+
+  fn cycle_of(model: &CausalModel) -> Option<Vec<(usize, usize)>> {
+    model.conflict().map(|conflict| conflict.cycle.into_iter()
+      .map(|(from, to, _)| (from, to))
+      .collect())
+  }
+
+  #[test]
+  fn tarjan_components() {
+    // {0, 1} and {2, 3} are cycles linked by 1 -> 2, and 4 is isolated.
+    let m = model(5, always(vec![
+      Sequential(0, 1), Sequential(1, 0), Sequential(1, 2),
+      Sequential(2, 3), Sequential(3, 2)]));
+    let c = components(&m);
+    assert_eq!(c[0], c[1]);
+    assert_eq!(c[2], c[3]);
+    assert!(c[0] != c[2]);
+    assert!(c[4] != c[0] && c[4] != c[2]);
+  }
+
+  #[test]
+  fn tarjan_deep_chain() {
+    // The iterative version must not overflow the stack on long chains.
+    let n = 100000;
+    let mut constraints: Vec<OrderConstraint> = (0..n-1).map(|i| Sequential(i, i+1)).collect();
+    constraints.push(Sequential(n-1, 0));
+    let c = components(&model(n, always(constraints)));
+    assert!(c.iter().all(|&comp| comp == c[0]));
+  }
+
+  #[test]
+  fn acyclic_model() {
+    let m = model(4, always(vec![
+      Sequential(0, 1), Sequential(1, 2), Permission(0, 2), Sequential(2, 3)]));
+    assert!(m.conflict().is_none());
+  }
+
+  #[test]
+  fn self_loop() {
+    let m = model(2, always(vec![Sequential(0, 1), Permission(1, 1)]));
+    assert_eq!(cycle_of(&m), Some(vec![(1, 1)]));
+  }
+
+  #[test]
+  fn all_equal_class() {
+    // The operations of a same call can be executed in any order...
+    let m = model(3, always(vec![Simultaneous(vec![0, 1, 2])]));
+    assert!(m.conflict().is_none());
+    // ...but they cannot be sequentially ordered.
+    let m = model(3, always(vec![Simultaneous(vec![0, 1, 2]), Permission(2, 0)]));
+    assert_eq!(cycle_of(&m), Some(vec![(2, 0), (0, 1), (1, 2)]));
+  }
+
+  #[test]
+  fn shortest_cycle_of_first_faulty_component() {
+    // {0, 1, 2, 3} is faulty with a cycle of length 4 and a cycle of length 2, {4, 5} only has non-strict edges.
+    let m = model(6, always(vec![
+      Sequential(0, 1), Sequential(1, 2), Sequential(2, 3), Sequential(3, 0),
+      Permission(2, 1), Simultaneous(vec![4, 5]), Sequential(3, 4)]));
+    assert_eq!(cycle_of(&m), Some(vec![(1, 2), (2, 1)]));
+  }
+
+  #[test]
+  fn multiple_components() {
+    // The first component is acyclic with non-strict edges, the second one is faulty.
+    let m = model(5, always(vec![
+      Simultaneous(vec![0, 1]), Sequential(1, 2), Sequential(2, 3), Permission(3, 4), Sequential(4, 3)]));
+    let c = components(&m);
+    assert_eq!(c[0], c[1]);
+    assert_eq!(c[3], c[4]);
+    assert!(c[0] != c[3] && c[2] != c[3]);
+    assert_eq!(cycle_of(&m), Some(vec![(3, 4), (4, 3)]));
+  }
+
+  #[test]
+  fn cycle_in_exclusive_paths() {
+    // The two constraints are never in the same execution path.
+    let m = model(2, vec![
+      (Sequential(0, 1), guard(&[(0, true)])),
+      (Permission(1, 0), guard(&[(0, false)]))]);
+    assert!(m.conflict().is_none());
+  }
+
+  #[test]
+  fn cycle_in_compatible_paths() {
+    let m = model(3, vec![
+      (Sequential(0, 1), guard(&[(0, true)])),
+      (Permission(1, 0), guard(&[(1, false)])),
+      (Permission(2, 0), guard(&[(0, false), (1, false)]))]);
+    let conflict = m.conflict().expect("a cycle in the path (0, true), (1, false).");
+    assert_eq!(conflict.path, guard(&[(0, true), (1, false)]));
+    assert_eq!(conflict.cycle.len(), 2);
+  }
+
+  #[test]
+  fn merge_exclusive_paths() {
+    let mut then_m = model(2, vec![]);
+    then_m.take_branch(0, true);
+    then_m.add_after_latest_constraint(0);
+    let mut else_m = model(2, vec![]);
+    else_m.take_branch(0, false);
+    let mut m = then_m.join_branches(else_m);
+    assert_eq!(m.flow.len(), 2);
+    m.add_after_latest_constraint(1);
+    assert_eq!(m.flow, vec![ExecutionPath::new(Guard::always(), vec![1])]);
+    assert_eq!(m.constraints, vec![(Sequential(0, 1), guard(&[(0, true)]))]);
   }
 }
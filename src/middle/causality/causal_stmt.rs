@@ -20,234 +20,160 @@ use session::*;
 use middle::causality::causal_model::*;
 use middle::causality::model_parameters::*;
 use middle::causality::causal_deps::*;
+use std::cell::RefCell;
 
+/// Builds the guarded precedence graph of the instant `program` (see `causal_model.rs`).
 pub fn build_causal_model(session: Session, context: Context, program: Stmt, params: ModelParameters)
-  -> Env<(Context,Vec<CausalModel>)>
+  -> Env<(Context,CausalModel)>
 {
   let causal_stmt = CausalStmt::new(session, context, params);
   causal_stmt.compute(program)
 }
 
-trait Continuation {
-  fn call(&self, this: &CausalStmt, model: CausalModel) -> Vec<CausalModel>;
-  fn bclone(&self) -> Box<Continuation>;
-}
-
-type Cont = Box<Continuation>;
-
-#[derive(Clone)]
-struct IdentityCont;
-
-impl Continuation for IdentityCont {
-  fn call(&self, _this: &CausalStmt, model: CausalModel) -> Vec<CausalModel> {
-    vec![model]
-  }
-
-  fn bclone(&self) -> Cont { Box::new(self.clone()) }
-}
-
-struct SequenceCont {
-  children: Vec<Stmt>,
-  continuation: Cont
-}
-
-impl SequenceCont {
-  pub fn new(children: Vec<Stmt>, continuation: Cont) -> Self {
-    SequenceCont { children, continuation }
-  }
-}
-
-impl Continuation for SequenceCont {
-  fn call(&self, this: &CausalStmt, model: CausalModel) -> Vec<CausalModel> {
-    this.visit_seq(self.children.clone(), model, self.continuation.bclone())
-  }
-
-  fn bclone(&self) -> Cont {
-    Box::new(SequenceCont::new(self.children.clone(), self.continuation.bclone()))
-  }
-}
-
 struct CausalStmt {
   session: Session,
   context: Context,
   deps: CausalDeps,
   params: ModelParameters,
+  /// The conditions are shared by the models of the branches, so they are allocated outside of the models.
+  conditions: RefCell<Vec<Span>>,
 }
 
 impl CausalStmt {
   pub fn new(session: Session, context: Context, params: ModelParameters) -> Self
   {
     let deps = CausalDeps::new();
-    CausalStmt { session, context, deps, params }
+    CausalStmt { session, context, deps, params, conditions: RefCell::new(vec![]) }
   }
 
-  fn compute(self, program: Stmt) -> Env<(Context,Vec<CausalModel>)> {
-    let models = self.causal_analysis(program);
-    Env::value(self.session, (self.context, models))
+  fn compute(self, program: Stmt) -> Env<(Context,CausalModel)> {
+    let mut model = self.causal_analysis(program);
+    model.conditions = self.conditions.into_inner();
+    Env::value(self.session, (self.context, model))
   }
 
-  fn causal_analysis(&self, program: Stmt) -> Vec<CausalModel> {
+  fn causal_analysis(&self, program: Stmt) -> CausalModel {
     let model = CausalModel::new(self.params.clone());
-    let models = self.visit_stmt(program, model, Box::new(IdentityCont));
-    models
+    self.visit_stmt(program, model)
+  }
+
+  fn alloc_condition(&self, condition: Span) -> usize {
+    let mut conditions = self.conditions.borrow_mut();
+    conditions.push(condition);
+    conditions.len() - 1
   }
 
-  fn visit_stmt(&self, stmt: Stmt, model: CausalModel,
-      continuation: Cont) -> Vec<CausalModel>
+  fn visit_stmt(&self, stmt: Stmt, model: CausalModel) -> CausalModel
   {
     use ast::StmtKind::*;
     match stmt.node {
-      DelayStmt(_) => self.visit_delay(model, continuation),
+      DelayStmt(_) => self.visit_delay(model),
       Space(_)
     | Prune
-    | Nothing => continuation.call(self, model),
-      Seq(branches) => self.visit_seq(branches, model, continuation),
-      Let(stmt) => self.visit_let(stmt, model, continuation),
-      Tell(var, expr) => self.visit_tell(var, expr, model, continuation),
+    | Nothing => model,
+      Seq(branches) => self.visit_seq(branches, model),
+      Let(stmt) => self.visit_let(stmt, model),
+      Tell(var, expr) => self.visit_tell(var, expr, model),
       When(cond, then_branch, else_branch) =>
-        self.visit_when(cond, *then_branch, *else_branch, model, continuation),
-      ExprStmt(expr) => self.visit_expr_stmt(expr, model, continuation),
-      OrPar(branches) => self.visit_or_par(branches, model, continuation),
-      AndPar(branches) => self.visit_and_par(branches, model, continuation),
-      Loop(body) => self.visit_loop(*body, model, continuation),
+        self.visit_when(cond, *then_branch, *else_branch, model),
+      ExprStmt(expr) => self.deps.visit_expr(expr, None, model),
+      OrPar(branches)
+    | AndPar(branches) => self.visit_par(branches, model),
+      Loop(body) => self.visit_stmt(*body, model),
       Universe(_, body)
-    | QFUniverse(body) => self.visit_stmt(*body, model, continuation),
-      _ => vec![]
-      // Suspend(cond, body) => self.visit_suspend(cond, *body, model, continuation),
-      // Abort(cond, body) => self.visit_abort(cond, *body, model, continuation),
-      // ProcCall(var, process, args) => self.visit_proc_call(var, process, args),
+    | QFUniverse(body) => self.visit_stmt(*body, model),
+      Suspend(suspend) => self.visit_suspend(suspend, model),
+      Abort(abort) => self.visit_abort(abort, model),
+      ProcCall(_, _, _) => unreachable!("[BUG] Process calls are inlined before the causality analysis (see `inline.rs`)."),
+      SyntaxError => unreachable!("[BUG] Syntax errors are removed in the front-end (see `front::parse_program`)."),
     }
   }
 
-  fn visit_delay(&self, mut model: CausalModel, _continuation: Cont) -> Vec<CausalModel>
+  fn visit_delay(&self, mut model: CausalModel) -> CausalModel
   {
-    model.instantaneous = false;
-    vec![model]
+    model.pause();
+    model
   }
 
-  fn visit_seq(&self, mut children: Vec<Stmt>,
-      model: CausalModel, continuation: Cont) -> Vec<CausalModel>
+  /// The statements following a delay in every execution path are not executed in this instant.
+  fn visit_seq(&self, children: Vec<Stmt>, mut model: CausalModel) -> CausalModel
   {
-    match children.len() {
-      0 => continuation.call(self, model),
-      1 => self.visit_stmt(children.remove(0), model, continuation),
-      _ => {
-        let stmt = children.remove(0);
-        self.visit_stmt(stmt, model,
-          Box::new(SequenceCont::new(children, continuation)))
+    for child in children {
+      if model.flow.is_empty() {
+        break;
       }
+      model = self.visit_stmt(child, model);
     }
+    model
   }
 
-  fn visit_let(&self, let_stmt: LetStmt,
-      model: CausalModel, continuation: Cont) -> Vec<CausalModel>
+  fn visit_let(&self, let_stmt: LetStmt, model: CausalModel) -> CausalModel
   {
     let model = match let_stmt.binding.expr {
       None => model,
       Some(expr) => self.deps.visit_expr(expr, None, model)
     };
-    self.visit_stmt(*(let_stmt.body), model, continuation)
+    self.visit_stmt(*(let_stmt.body), model)
   }
 
-  fn visit_tell(&self, var: Variable, expr: Expr,
-      model: CausalModel, continuation: Cont) -> Vec<CausalModel>
+  fn visit_tell(&self, var: Variable, expr: Expr, model: CausalModel) -> CausalModel
   {
     let m1 = self.deps.visit_expr(expr, None, model);
-    let m2 = self.deps.visit_var(var, None, m1);
-    continuation.call(self, m2)
+    self.deps.visit_var(var, None, m1)
   }
 
   fn visit_when(&self, condition: Expr, then_branch: Stmt, else_branch: Stmt,
-      model: CausalModel, continuation: Cont) -> Vec<CausalModel>
+      model: CausalModel) -> CausalModel
   {
-    let then_m = self.deps.visit_expr(condition.clone(), Some(true), model.clone());
-    let mut m1 = self.visit_stmt(then_branch, then_m, continuation.bclone());
-    let else_m = self.deps.visit_expr(condition, Some(false), model);
-    let mut m2 = self.visit_stmt(else_branch, else_m, continuation);
-    m1.append(&mut m2);
-    m1
-  }
-
-  fn visit_expr_stmt(&self, expr: Expr,
-      model: CausalModel, continuation: Cont) -> Vec<CausalModel>
+    let c = self.alloc_condition(condition.span);
+    let mut then_m = self.deps.visit_expr(condition.clone(), Some(true), model.clone());
+    then_m.take_branch(c, true);
+    let m1 = self.visit_stmt(then_branch, then_m);
+    let mut else_m = self.deps.visit_expr(condition, Some(false), model);
+    else_m.take_branch(c, false);
+    let m2 = self.visit_stmt(else_branch, else_m);
+    m1.join_branches(m2)
+  }
+
+  /// The execution paths of every branch are composed, the statement terminates in the paths where all the branches terminate.
+  /// NOTE: the conjunctive parallel statement <> is a weak preemption so during an instant, it behaves like ||.
+  fn visit_par(&self, children: Vec<Stmt>, model: CausalModel) -> CausalModel
   {
-    let m = self.deps.visit_expr(expr, None, model);
-    continuation.call(self, m)
+    let models = children.into_iter()
+      .map(|child| self.visit_stmt(child, model.clone()))
+      .collect::<Vec<_>>();
+    let mut models = models.into_iter();
+    let first = models.next().expect("[BUG] `par` has at least one branch.");
+    first.fold(models.collect())
   }
 
-  fn visit_par(&self, children: Vec<Stmt>, model: CausalModel,
-    continuation: Cont) -> Vec<CausalModel>
+  /// The body is executed if the condition is `false`, otherwise the statement pauses in the current instant.
+  fn visit_suspend(&self, suspend: SuspendStmt, model: CausalModel) -> CausalModel
   {
-    // We create the model of every branch without calling the current continuation.
-    let mut models = vec![];
-    for child in children {
-      models.push(self.visit_stmt(child, model.clone(), Box::new(IdentityCont)));
-    }
-    // We merge by Cartesian product the models created by every branch.
-    let first = models.remove(0);
-    let models = models.into_iter().fold(first, |accu, m| {
-      CausalModel::cartesian_product(accu, m)
-    });
-    // We call the continuation on models that are instantaneous.
-    let mut result = vec![];
-    for model in models {
-      if model.instantaneous {
-        let mut next = continuation.call(self, model);
-        result.append(&mut next);
-      }
-      else {
-        result.push(model);
-      }
-    }
-    result
-  }
-
-  fn visit_or_par(&self, children: Vec<Stmt>, model: CausalModel,
-    continuation: Cont) -> Vec<CausalModel>
+    let c = self.alloc_condition(suspend.condition.span);
+    let mut then_m = self.deps.visit_expr(suspend.condition.clone(), Some(false), model.clone());
+    then_m.take_branch(c, false);
+    let m1 = self.visit_stmt(*suspend.body, then_m);
+    let mut else_m = self.deps.visit_expr(suspend.condition, Some(true), model);
+    else_m.take_branch(c, true);
+    else_m.pause();
+    m1.join_branches(else_m)
+  }
+
+  /// If the condition is `true`, a strong abortion terminates immediately (without executing its body), whereas a weak abortion executes its body and terminates in the next instant.
+  /// The termination in the next instant is taken into account by the symbolic execution.
+  fn visit_abort(&self, abort: AbortStmt, model: CausalModel) -> CausalModel
   {
-    self.visit_par(children, model, continuation)
-  }
-
-  fn visit_and_par(&self, children: Vec<Stmt>, model: CausalModel,
-    continuation: Cont) -> Vec<CausalModel>
-  {
-    self.visit_par(children, model, continuation)
-  }
-
-  fn visit_loop(&self, body: Stmt, model: CausalModel,
-    continuation: Cont) -> Vec<CausalModel>
-  {
-    self.visit_stmt(body, model, continuation)
+    let c = self.alloc_condition(abort.condition.span);
+    let mut then_m = self.deps.visit_expr(abort.condition.clone(), Some(true), model.clone());
+    then_m.take_branch(c, true);
+    let mut else_m = self.deps.visit_expr(abort.condition, Some(false), model);
+    else_m.take_branch(c, false);
+    let m1 = match abort.kind {
+      AbortKind::Strong => then_m,
+      AbortKind::Weak => self.visit_stmt((*abort.body).clone(), then_m)
+    };
+    let m2 = self.visit_stmt(*abort.body, else_m);
+    m1.join_branches(m2)
   }
-
-  // fn visit_suspend(&self, condition: Expr, child: Stmt,
-  //   model: CausalModel, continuation: Cont) -> Vec<CausalModel>
-  // {
-  //   let then_m = self.visit_expr(condition, false, model.clone());
-  //   let mut m1 = self.visit_stmt(child, then_m, continuation);
-  //   let else_m = self.visit_expr(condition, true, model);
-  //   else_m.instantaneous = false;
-  //   m1.push(else_m);
-  //   m1
-  // }
-
-  // fn visit_abort(&self, condition: Expr, child: Stmt,
-  //   model: CausalModel, continuation: Cont) -> Vec<CausalModel>
-  // {
-  //   let then_m = self.visit_expr(condition, false, model.clone());
-  //   let else_m = self.visit_expr(condition, true, model);
-  //   let mut m1 = self.visit_stmt(child, then_m, continuation.clone());
-  //   let mut m2 = continuation(else_m);
-  //   m1.extend(&mut m2);
-  //   m1
-  // }
-
-  // fn visit_proc_call(&self, var: Option<Variable>, _process: Ident, args: Vec<Variable>) {
-  //   walk_proc_call(self, var, args);
-  // }
-
-  // fn visit_universe(&self, child: Stmt) {
-  //   self.visit_stmt(child)
-  // }
 }
-
@@ -12,28 +12,37 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-/// Given a progam, `Indexing` has two tasks:
-///   1. Indexing every access operation with an integer.
+/// Given a progam, the indexing has two tasks:
+///   1. Indexing every pause-like statements (`pause`,`pause up`,`stop`,`suspend`,`abort`) with an integer.
+///      This is useful to represent an instant with a compact state (instead of the full AST).
+///   2. Indexing every access operation of an instant with an integer.
 ///      For example, `read x` becomes `read^n x` where `n` is its index (field `op_no` in `Variable`).
 ///      We also create a `reversed index lookup` in `ModelParameters` where we can search a variable from an operation number.
-///   2. Indexing every pause-like statements (`pause`,`pause up`,`stop`,`suspend`) with an integer.
-///      This is useful to represent an instant with a compact state (instead of the full AST).
+///      It is done on the program of each instant because the body of a loop can appear twice in an instant (see `reduce_loop` in `symbolic_execution.rs`).
 
 use context::*;
 use session::*;
 use middle::causality::model_parameters::*;
 
-/// Returns the modified AST with indexed variable operations and the associated model parameters.
-pub fn index_ops_and_delay(session: Session, context: Context) -> Env<(Context, ModelParameters)> {
+/// Returns the modified AST with indexed delays and the associated model parameters.
+pub fn index_delay(session: Session, context: Context) -> Env<(Context, ModelParameters)> {
   let index = Indexing::new(session, context);
   index.compute()
 }
 
+/// Index the operations of the program of an instant, `params` must not contain operations yet.
+pub fn index_ops(params: ModelParameters, program: &mut Stmt) -> ModelParameters {
+  let mut index = OpsIndexing { params };
+  index.visit_stmt(program);
+  index.params
+}
+
 struct Indexing {
   session: Session,
   context: Context,
   params: ModelParameters,
-  state_num: usize
+  /// The span of the statement being visited, it is the span of the pause-like statements when a state is generated.
+  current_span: Span
 }
 
 impl Indexing {
@@ -41,7 +50,7 @@ impl Indexing {
     Indexing {
       session, context,
       params: ModelParameters::new(),
-      state_num: 0
+      current_span: DUMMY_SP
     }
   }
 
@@ -53,13 +62,17 @@ impl Indexing {
   }
 
   fn gen_state(&mut self) -> usize {
-    self.state_num += 1;
-    self.state_num - 1
+    self.params.alloc_state(self.current_span)
   }
 }
 
 impl VisitorMut<JClass> for Indexing
 {
+  fn visit_stmt(&mut self, child: &mut Stmt) {
+    self.current_span = child.span;
+    walk_stmt_mut(self, child)
+  }
+
   fn visit_delay(&mut self, delay: &mut Delay) {
     delay.state_num = self.gen_state();
   }
@@ -69,6 +82,18 @@ impl VisitorMut<JClass> for Indexing
     walk_suspend_mut(self, suspend)
   }
 
+  fn visit_abort(&mut self, abort: &mut AbortStmt) {
+    abort.state_num = self.gen_state();
+    walk_abort_mut(self, abort)
+  }
+}
+
+struct OpsIndexing {
+  params: ModelParameters
+}
+
+impl VisitorMut<JClass> for OpsIndexing
+{
   fn visit_var(&mut self, var: &mut Variable) {
     self.params.alloc_variable(var);
   }
@@ -0,0 +1,168 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The processes called with `run` are inlined in the entry points before the symbolic execution.
+/// Hence the causality analysis sees the accesses and the delays of a callee as if they were written at the call site:
+///   * The parameters are substituted by the arguments of the call.
+///   * The fields of a module called through a variable (`run m.p()`) are prefixed by this variable, and its `ref` fields are substituted by the variables given to its constructor.
+///   * The local variables of the callee are renamed with fresh UIDs, since a process can be called several times in the same instant.
+/// The call graph is not recursive (see `recursive_call.rs`), so the inlining terminates.
+
+use context::*;
+use session::*;
+use std::mem;
+
+pub fn inline_processes(session: Session, mut context: Context) -> Env<Context> {
+  let mut ast = context.clone_ast();
+  for uid in context.entry_points.clone() {
+    let module = ast.modules.iter_mut()
+      .find(|m| m.mod_name() == uid.module)
+      .expect("inline_processes: the module of an entry point is not declared.");
+    let process = module.processes.iter_mut()
+      .find(|p| p.name == uid.process)
+      .expect("inline_processes: the entry point is not declared.");
+    Inlining::new(&mut context, uid.module.clone()).visit_stmt(&mut process.body);
+  }
+  context.replace_ast(ast);
+  Env::value(session, context)
+}
+
+struct Inlining<'a> {
+  context: &'a mut Context,
+  current_mod: Ident,
+  /// The local module variables in scope, with their `ref` fields and the variables given to their constructor.
+  module_refs: Vec<(usize, Vec<(usize, Variable)>)>
+}
+
+impl<'a> Inlining<'a> {
+  fn new(context: &'a mut Context, current_mod: Ident) -> Self {
+    Inlining { context, current_mod, module_refs: vec![] }
+  }
+
+  fn inline(&mut self, target: Option<Variable>, process: Ident, args: Vec<Variable>) -> Stmt {
+    let (uid, callee) = self.context.find_proc_from_call(self.current_mod.clone(), process, target.clone());
+    let mut substitutions: Vec<(usize, Variable)> = callee.params.iter()
+      .map(|param| param.uid)
+      .zip(args.into_iter())
+      .collect();
+    if let Some(ref target) = target {
+      let refs = self.module_refs.iter().rev()
+        .find(|&&(uid, _)| target.len() == 1 && uid == target.first_uid());
+      if let Some(&(_, ref refs)) = refs {
+        substitutions.extend(refs.iter().cloned());
+      }
+    }
+    let mut body = callee.body;
+    Substitution::new(&mut *self.context, substitutions, target).visit_stmt(&mut body);
+    // The calls of the callee are resolved in its own module.
+    let caller_mod = mem::replace(&mut self.current_mod, uid.module);
+    self.visit_stmt(&mut body);
+    self.current_mod = caller_mod;
+    body
+  }
+
+  fn refs_of_module(&self, binding: &Binding) -> Vec<(usize, Variable)> {
+    let new_instance = match binding.expr {
+      Some(Expr { node: ExprKind::NewInstance(ref new_instance), .. }) => new_instance,
+      _ => return vec![]
+    };
+    self.context.module_by_name(new_instance.ty.name.clone()).constructor.into_iter()
+      .filter_map(|(pos, field_uid)| match new_instance.args.get(pos) {
+        Some(&Expr { node: ExprKind::Var(ref var), .. }) => Some((field_uid, var.clone())),
+        _ => None
+      })
+      .collect()
+  }
+}
+
+impl<'a> VisitorMut<JClass> for Inlining<'a>
+{
+  fn visit_stmt(&mut self, stmt: &mut Stmt) {
+    let call = match stmt.node {
+      StmtKind::ProcCall(ref target, ref process, ref args) =>
+        Some((target.clone(), process.clone(), args.clone())),
+      _ => None
+    };
+    match call {
+      Some((target, process, args)) => *stmt = self.inline(target, process, args),
+      None => walk_stmt_mut(self, stmt)
+    }
+  }
+
+  fn visit_let(&mut self, let_stmt: &mut LetStmt) {
+    let refs =
+      if let_stmt.binding.is_module() { self.refs_of_module(&let_stmt.binding) }
+      else { vec![] };
+    self.module_refs.push((let_stmt.binding.uid, refs));
+    self.visit_stmt(&mut *(let_stmt.body));
+    self.module_refs.pop();
+  }
+}
+
+/// Renames the variables of the body of a process inlined at a call site.
+struct Substitution<'a> {
+  context: &'a mut Context,
+  /// The UIDs of the parameters and `ref` fields, with the variables replacing them.
+  substitutions: Vec<(usize, Variable)>,
+  /// The module variable on which the process is called.
+  target: Option<Variable>,
+  /// The old and new UIDs of the local variables in scope.
+  renamings: Vec<(usize, usize)>
+}
+
+impl<'a> Substitution<'a> {
+  fn new(context: &'a mut Context, substitutions: Vec<(usize, Variable)>, target: Option<Variable>) -> Self {
+    Substitution { context, substitutions, target, renamings: vec![] }
+  }
+
+  /// Replaces the `n` first fragments of the path of `var` by the path of `prefix`.
+  fn replace_prefix(var: &mut Variable, prefix: &Variable, n: usize) {
+    let mut fragments = prefix.path.fragments.clone();
+    fragments.extend(var.path.fragments.drain(n..));
+    let mut uids = prefix.path.uids.clone();
+    uids.extend(var.path.uids.drain(n..));
+    var.path.fragments = fragments;
+    var.path.uids = uids;
+    var.with_this = prefix.with_this;
+  }
+}
+
+impl<'a> VisitorMut<JClass> for Substitution<'a>
+{
+  fn visit_let(&mut self, let_stmt: &mut LetStmt) {
+    self.visit_binding(&mut let_stmt.binding);
+    let old_uid = let_stmt.binding.uid;
+    let new_uid = self.context.alloc_incarnation(&mut let_stmt.binding);
+    self.renamings.push((old_uid, new_uid));
+    self.visit_stmt(&mut *(let_stmt.body));
+    self.renamings.pop();
+  }
+
+  fn visit_var(&mut self, var: &mut Variable) {
+    let uid = var.first_uid();
+    let renaming = self.renamings.iter().rev().find(|&&(old_uid, _)| old_uid == uid).cloned();
+    let substitution = self.substitutions.iter().find(|&&(old_uid, _)| old_uid == uid).cloned();
+    if let Some((_, new_uid)) = renaming {
+      var.path.uids[0] = new_uid;
+    }
+    else if let Some((_, arg)) = substitution {
+      Self::replace_prefix(var, &arg, 1);
+    }
+    else if let Some(target) = self.target.clone() {
+      if self.context.var_by_uid(uid).is_field() {
+        Self::replace_prefix(var, &target, 0);
+      }
+    }
+  }
+}
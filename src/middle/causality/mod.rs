@@ -12,32 +12,46 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod inline;
 mod indexing;
 mod causal_stmt;
 mod causal_deps;
 pub mod causal_model;
 mod model_parameters;
 mod solver;
+#[cfg(feature = "pcp_check")]
+mod pcp_check;
 pub mod symbolic_execution;
 
 use context::*;
 use session::*;
+use middle::causality::inline::*;
 use middle::causality::indexing::*;
 use middle::causality::solver::*;
 use middle::causality::causal_stmt::*;
 use middle::causality::symbolic_execution::*;
 use middle::causality::model_parameters::*;
 
+/// The processes are inlined and indexed only for the analysis, the original AST is restored for the code generation.
 pub fn causality_analysis(session: Session, context: Context) -> Env<Context> {
+  let ast = context.clone_ast();
   Env::value(session, context)
-    .and_then(index_ops_and_delay)
+    .and_then(inline_processes)
+    .and_then(index_delay)
     .and_then(execute_symbolically)
+    .map(move |mut context| {
+      context.replace_ast(ast);
+      context
+    })
 }
 
 fn execute_symbolically(session: Session, (context, params): (Context, ModelParameters)) -> Env<Context> {
   SymbolicExecution::for_each_instant(session, context, |env| {
-    env.and_then(|session, (context, stmt)|
-          build_causal_model(session, context, stmt, params.clone()))
-       .and_then(solve_causal_model)
+    env.and_then(|session, (context, instant)| {
+          let mut program = instant.program.clone();
+          let params = index_ops(params.clone(), &mut program);
+          build_causal_model(session, context, program, params)
+            .and_then(|session, c| solve_causal_model(session, c, instant))
+        })
     })
 }
@@ -15,27 +15,35 @@
 /// Parameters of the causality model described in the Section 4.5.2 of the dissertation (Talbot, 2018).
 
 use context::*;
+use middle::causality::causal_model::Guard;
 use std::clone::Clone;
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct ModelParameters {
   pub var_of_op: Vec<Variable>,
-  /// We keep monotonic read operations encountered in an expression.
+  /// The monotonic read operations of a condition are kept until the branch of the condition is taken (see `commit_monotonic_reads`).
+  pending_monotonic_reads: Vec<usize>,
+  /// We keep the monotonic read operations encountered in a condition with the branch in which they are monotonic.
   /// This is useful for conditional statements that should not add read/write constraints on these variables.
-  pub monotonic_read_ops: Vec<usize>,
-  /// We store the operations couple that are not subject to read/write constraints.
+  pub monotonic_read_ops: Vec<(usize, (usize, bool))>,
+  /// We store the operations couple that are not subject to read/write constraints, in the execution paths satisfying the guard.
   /// For example: when a(1) |= b then a(2) <- 1 end, `a(1)` and `a(2)` must be not be constrained (a write can happen after a read in this context).
-  pub relaxed_rw_ops: Vec<(usize, usize)>,
-  pub activated: Vec<bool>,
+  pub relaxed_rw_ops: Vec<(usize, usize, Guard)>,
+  /// The guards of the execution paths in which each operation is executed.
+  pub activations: Vec<Vec<Guard>>,
+  /// The span of the pause-like statement (`pause`, `suspend`, `abort`,...) associated to each state number.
+  pub span_of_state: Vec<Span>,
 }
 
 impl ModelParameters {
   pub fn new() -> Self {
     ModelParameters {
       var_of_op: vec![],
+      pending_monotonic_reads: vec![],
       monotonic_read_ops: vec![],
       relaxed_rw_ops: vec![],
-      activated: vec![],
+      activations: vec![],
+      span_of_state: vec![],
     }
   }
 
@@ -46,47 +54,77 @@ impl ModelParameters {
   pub fn alloc_variable(&mut self, var: &mut Variable) {
     var.op_no = self.num_ops();
     self.var_of_op.push(var.clone());
-    self.activated.push(false);
+    self.activations.push(vec![]);
   }
 
-  pub fn activate_op(&mut self, op: usize) {
-    self.activated[op] = true;
+  pub fn alloc_state(&mut self, span: Span) -> usize {
+    self.span_of_state.push(span);
+    self.span_of_state.len() - 1
+  }
+
+  pub fn activate_op(&mut self, op: usize, guard: Guard) {
+    push_unique(&mut self.activations[op], guard);
   }
 
   /// Preconditions:
   ///   1. Both model parameters have the same number of operations.
   ///   2. `var_of_op` are the same.
-  pub fn join(mut self, mut other: ModelParameters) -> ModelParameters
+  pub fn join(mut self, other: ModelParameters) -> ModelParameters
   {
     assert_eq!(self.num_ops(), other.num_ops(),
       "join: model parameters must have the same number of operations.");
     assert_eq!(self.var_of_op, other.var_of_op,
       "join: `var_of_op` must be identical.");
-    self.relaxed_rw_ops.append(&mut other.relaxed_rw_ops);
-    self.monotonic_read_ops.append(&mut other.monotonic_read_ops);
-    for i in 0..self.activated.len() {
-      self.activated[i] = self.activated[i] || other.activated[i];
+    for op in other.pending_monotonic_reads {
+      push_unique(&mut self.pending_monotonic_reads, op);
+    }
+    for read in other.monotonic_read_ops {
+      push_unique(&mut self.monotonic_read_ops, read);
+    }
+    for relaxed in other.relaxed_rw_ops {
+      push_unique(&mut self.relaxed_rw_ops, relaxed);
+    }
+    for (op, guards) in other.activations.into_iter().enumerate() {
+      for guard in guards {
+        push_unique(&mut self.activations[op], guard);
+      }
     }
     self
   }
 
   pub fn store_monotonic_read(&mut self, op: usize) {
-    self.monotonic_read_ops.push(op);
+    self.pending_monotonic_reads.push(op);
   }
 
-  pub fn register_relaxed_op(&mut self, op: usize) {
+  /// The monotonic reads of the condition are monotonic in the execution paths taking `branch`.
+  pub fn commit_monotonic_reads(&mut self, branch: (usize, bool)) {
+    for op in self.pending_monotonic_reads.drain(..) {
+      self.monotonic_read_ops.push((op, branch));
+    }
+  }
+
+  /// The operation `op` executed in the paths satisfying `guard` is not constrained with the monotonic reads of the same variable taken before on these paths.
+  pub fn register_relaxed_op(&mut self, op: usize, guard: &Guard) {
     debug!("in register_relaxed_op: {:?}", self.monotonic_read_ops);
     debug!("in register_relaxed_op: op: {}", self.var_of_op[op]);
-    for relaxed_op in &self.monotonic_read_ops {
-      debug!("in register_relaxed_op: relaxed_op: {}", self.var_of_op[*relaxed_op]);
-      if self.var_of_op[*relaxed_op].last_uid() == self.var_of_op[op].last_uid() {
-        self.relaxed_rw_ops.push((*relaxed_op, op));
-        debug!("Add relaxed ops {} / {}.", *relaxed_op, op);
+    for &(relaxed_op, branch) in &self.monotonic_read_ops {
+      debug!("in register_relaxed_op: relaxed_op: {}", self.var_of_op[relaxed_op]);
+      if guard.contains(branch) && self.var_of_op[relaxed_op].last_uid() == self.var_of_op[op].last_uid() {
+        push_unique(&mut self.relaxed_rw_ops, (relaxed_op, op, guard.clone()));
+        debug!("Add relaxed ops {} / {}.", relaxed_op, op);
       }
     }
   }
 
-  pub fn is_rw_constrained(&self, op1: usize, op2: usize) -> bool {
-    !(self.relaxed_rw_ops.contains(&(op1, op2)) || self.relaxed_rw_ops.contains(&(op2, op1)))
+  /// `true` if the operations are constrained in the execution paths satisfying `guard`.
+  pub fn is_rw_constrained(&self, op1: usize, op2: usize, guard: &Guard) -> bool {
+    !self.relaxed_rw_ops.iter().any(|&(r1, r2, ref relaxed)|
+      ((r1, r2) == (op1, op2) || (r1, r2) == (op2, op1)) && guard.implies(relaxed))
+  }
+}
+
+fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+  if !values.contains(&value) {
+    values.push(value);
   }
 }
@@ -0,0 +1,57 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Cross-check of the precedence graph with the constraint solver pcp (enabled with the feature `pcp_check`).
+/// Every operation is a variable ranging over its position in the instant, and the ordering constraints are `x_greater_y` and `AllEqual` propagators.
+
+use middle::causality::causal_model::*;
+use pcp::search::*;
+use pcp::kernel::*;
+use pcp::concept::*;
+use pcp::propagators::*;
+use gcollections::ops::*;
+use interval::interval_set::*;
+use interval::ops::Range;
+
+/// `constraints` are the constraints of the `n` operations in one execution path (see `CausalModel::constraints_in`).
+pub fn is_satisfiable(n: usize, constraints: &Vec<OrderConstraint>) -> bool {
+  if n == 0 { return true; }
+  let mut space = FDSpace::empty();
+  let order_of_op: Vec<Var<VStore>> = (0..n)
+    .map(|_| Box::new(space.vstore.alloc(IntervalSet::new(0, (n-1) as i32))) as Var<VStore>)
+    .collect();
+  for constraint in constraints {
+    match constraint {
+      &OrderConstraint::Sequential(before, after)
+    | &OrderConstraint::Permission(before, after) => {
+        space.cstore.alloc(Box::new(x_greater_y(
+          order_of_op[after].bclone(), order_of_op[before].bclone())));
+      }
+      &OrderConstraint::Simultaneous(ref ops) => {
+        let vars = ops.iter().map(|&op| order_of_op[op].bclone()).collect();
+        space.cstore.alloc(Box::new(AllEqual::new(vars)));
+      }
+    }
+  }
+  let mut search = one_solution_engine();
+  search.start(&space);
+  let (_, status) = search.enter(space);
+  match status {
+    Status::Satisfiable => true,
+    Status::Unsatisfiable => false,
+    Status::EndOfSearch
+  | Status::Unknown(_) => unreachable!(
+      "After the search step, the problem instance should be either satisfiable or unsatisfiable.")
+  }
+}
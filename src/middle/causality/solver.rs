@@ -15,34 +15,33 @@
 use session::*;
 use context::*;
 use middle::causality::causal_model::*;
-use pcp::search::*;
-use pcp::kernel::*;
+use middle::causality::symbolic_execution::Instant;
+#[cfg(feature = "pcp_check")]
+use middle::causality::pcp_check;
 
-pub fn solve_causal_model(session: Session, c: (Context, Vec<CausalModel>)) -> Env<Context> {
-  let solver = Solver::new(session, c.0, c.1);
-  solver.solve_all()
+pub fn solve_causal_model(session: Session, c: (Context, CausalModel), instant: Instant) -> Env<Context> {
+  let solver = Solver::new(session, c.0, c.1, instant);
+  solver.solve()
 }
 
 pub struct Solver {
   session: Session,
   context: Context,
-  models: Vec<CausalModel>,
+  model: CausalModel,
+  /// The instant from which the model is generated.
+  instant: Instant,
 }
 
 impl Solver {
-  pub fn new(session: Session, context: Context, models: Vec<CausalModel>) -> Self {
-    Solver { session, context, models }
+  pub fn new(session: Session, context: Context, model: CausalModel, instant: Instant) -> Self {
+    Solver { session, context, model, instant }
   }
 
-  pub fn solve_all(mut self) -> Env<Context> {
-    debug!("{} causal models\n", self.models.len());
-    debug!("{} instantaneous causal models\n", self.models.iter().filter(|m| m.instantaneous).count());
-    for model in self.models.clone() {
-      if let Some(model) = self.prepare_model(model) {
-        if !self.solve_model(model) {
-          break
-        }
-      }
+  pub fn solve(mut self) -> Env<Context> {
+    debug!("{} operations and {} conditions in the causal model\n", self.model.num_ops(), self.model.conditions.len());
+    let model = self.model.clone();
+    if let Some(model) = self.prepare_model(model) {
+      self.solve_model(model);
     }
     if self.session.has_errors() {
       Env::fake(self.session, self.context)
@@ -51,65 +50,128 @@ impl Solver {
     }
   }
 
-  fn solve_model(&mut self, model: CausalModel) -> bool {
-    // Search step.
-    let space = model.clone().space;
-    let mut search = one_solution_engine();
-    search.start(&space);
-    let (frozen_space, status) = search.enter(space);
-    let space = frozen_space.unfreeze();
-
-    // Print result.
-    match status {
-      Status::Satisfiable => {
-        trace!("{:?}\n\n{:?}", space.vstore, space.cstore);
-        true
-      },
-      Status::Unsatisfiable => {
-        self.err_unsatisfiable_model();
-        trace!("{:?}\n\n{:?}", space.vstore, space.cstore);
-        false
+  fn solve_model(&mut self, model: CausalModel) {
+    let conflict = model.conflict();
+    self.cross_check(&model, &conflict);
+    trace!("{:?}", model.constraints);
+    if let Some(conflict) = conflict {
+      self.err_unsatisfiable_model(&model, conflict);
+    }
+  }
+
+  /// With the feature `pcp_check`, the result of the graph analysis is checked against the constraint solver.
+  /// The constraints of the path of a conflict must be unsatisfiable, otherwise the constraints holding in every path must be satisfiable.
+  #[cfg(feature = "pcp_check")]
+  fn cross_check(&self, model: &CausalModel, conflict: &Option<Conflict>) {
+    let (path, satisfiable) = match conflict {
+      &Some(ref conflict) => (conflict.path.clone(), false),
+      &None => (Guard::always(), true)
+    };
+    assert_eq!(pcp_check::is_satisfiable(model.num_ops(), &model.constraints_in(&path)), satisfiable,
+      "[BUG] The precedence graph and the constraint solver disagree on the satisfiability of the causal model.");
+  }
+
+  #[cfg(not(feature = "pcp_check"))]
+  fn cross_check(&self, _model: &CausalModel, _conflict: &Option<Conflict>) {}
+
+  /// We report the shortest cycle of ordering constraints making the model unsatisfiable.
+  /// The primary span is the access of the cycle appearing last in the program, the other accesses are labelled in the order of the cycle.
+  fn err_unsatisfiable_model(&mut self, model: &CausalModel, conflict: Conflict) {
+    let cycle = conflict.cycle;
+    let ops: Vec<usize> = cycle.iter().map(|&(from, _, _)| from).collect();
+    let var_of = |op: usize| model.params.var_of_op[op].clone();
+    let primary = ops.iter().cloned()
+      .max_by_key(|&op| var_of(op).span.lo)
+      .expect("a cycle has at least one operation.");
+    let (before, after) = cycle.iter()
+      .filter_map(|&(_, _, ref c)| match c {
+        &OrderConstraint::Permission(before, after) => Some((var_of(before), var_of(after))),
+        _ => None
+      })
+      .next()
+      .unwrap_or((var_of(ops[0]), var_of(ops[ops.len()-1])));
+    let position = |op: usize| ops.iter().position(|&o| o == op).unwrap() + 1;
+    let mut db = self.session.struct_span_err_with_code(var_of(primary).span,
+      &format!("causality error: the `{}` access on `{}` cannot be scheduled before its `{}` access in this instant.",
+        Self::permission_of(&before), before.path, Self::permission_of(&after)),
+      "E0033");
+    for &op in &ops {
+      let var = var_of(op);
+      db.span_label(var.span, &format!("({}) `{} {}`{}", position(op), Self::permission_of(&var), var.path,
+        if Self::is_next_incarnation(&self.context, &var, &ops, &var_of) { " in the next iteration of the loop" } else { "" }));
+    }
+    for &(from, to, ref constraint) in &cycle {
+      let reason = match constraint {
+        &OrderConstraint::Sequential(_, _) => "it is executed before in the program",
+        &OrderConstraint::Permission(_, _) => "a variable is written, then read-written and finally read",
+        &OrderConstraint::Simultaneous(_) => "both are arguments of the same call and can be executed in any order"
+      };
+      db.note(&format!("({}) must happen before ({}): {}.", position(from), position(to), reason));
+    }
+    for &(condition, taken) in conflict.path.branches() {
+      db.span_label(model.conditions[condition], &format!("in the execution path where this condition is {}", taken));
+    }
+    if self.instant.is_first() {
+      db.note(&format!("in the first instant of the process `{}`.", self.instant.process));
+    }
+    else {
+      let mut states: Vec<usize> = self.instant.locations.iter().cloned().collect();
+      states.sort();
+      for state in states {
+        db.span_label(model.params.span_of_state[state],
+          &format!("in the instant resumed from here"));
       }
-      Status::EndOfSearch
-    | Status::Unknown(_) => unreachable!(
-        "After the search step, the problem instance should be either satisfiable or unsatisfiable.")
+      db.note(&format!("in an instant of the process `{}` resumed after the labelled statements.", self.instant.process));
     }
+    db.emit();
   }
 
-  fn err_unsatisfiable_model(&mut self) {
-    self.session.struct_span_err_with_code(DUMMY_SP,
-      &format!("causality error: a write access happens after a read access on the same variable."),
-      "E0033")
-      .help(&"Unfortunately, we just report that the program is not causal but not the reason (see issue #4).")
-      .emit();
+  /// When the body of a loop is reincarnated in an instant, the two incarnations of a variable have the same name.
+  /// The most recent incarnation is the one with the greatest UID.
+  fn is_next_incarnation<F>(context: &Context, var: &Variable, ops: &Vec<usize>, var_of: &F) -> bool
+    where F: Fn(usize) -> Variable
+  {
+    let uid = var.first_uid();
+    let source = context.source_uid(uid);
+    ops.iter().map(|&op| var_of(op).first_uid())
+      .any(|other| other < uid && context.source_uid(other) == source)
+  }
+
+  fn permission_of(var: &Variable) -> Permission {
+    var.permission.expect("every variable access must have an explicit permission (should be done in `infer_permission.rs`).")
   }
 
   /// Returns `None` if the model is detected unsatisfiable.
   /// In this case, an error is reported.
+  /// The permission constraints are added between the accesses to a same variable executed in a same path, and are guarded by this path.
   fn prepare_model(&mut self, mut model: CausalModel) -> Option<CausalModel> {
     let mut unsatisfiable = false;
     let n = model.num_ops();
     for op1 in 0..n {
       for op2 in (op1+1)..n {
-        if model.params.activated[op1] && model.params.activated[op2] {
-          let v1 = model.params.var_of_op[op1].clone();
-          let v2 = model.params.var_of_op[op2].clone();
-          if v1 == v2 && model.params.is_rw_constrained(op1, op2)
-          {
+        let v1 = model.params.var_of_op[op1].clone();
+        let v2 = model.params.var_of_op[op2].clone();
+        if v1 != v2 {
+          continue;
+        }
+        let mut reported = false;
+        for guard in Self::common_paths(&model, op1, op2) {
+          if model.params.is_rw_constrained(op1, op2, &guard) {
             debug!("{} / {} are read/write constrained.", op1, op2);
             let err_msg = "every variable access must have an explicit permission (should be done in `infer_permission.rs`).";
             let a1 = v1.permission.expect(err_msg);
             let a2 = v2.permission.expect(err_msg);
             // Enforce that every access read is done after readwrite, and in turn that every write is realized after a readwrite.
             if a1 > a2 {
-              model.add_sequential_constraint(op1, op2);
+              model.add_permission_constraint(op1, op2, guard);
             }
             else if a1 < a2 {
-              model.add_sequential_constraint(op2, op1);
+              model.add_permission_constraint(op2, op1, guard);
             }
             // Enforce that a variable is not accessed two times with readwrite.
-            else if a1 == Permission::ReadWrite && a2 == Permission::ReadWrite {
-              self.err_two_readwrite_accesses(v1, v2);
+            else if a1 == Permission::ReadWrite && a2 == Permission::ReadWrite && !reported {
+              self.err_two_readwrite_accesses(v1.clone(), v2.clone());
+              reported = true;
               unsatisfiable = true;
             }
           }
@@ -120,6 +182,21 @@ impl Solver {
     else { Some(model) }
   }
 
+  /// The guards of the execution paths in which both operations are executed.
+  fn common_paths(model: &CausalModel, op1: usize, op2: usize) -> Vec<Guard> {
+    let mut guards = vec![];
+    for g1 in &model.params.activations[op1] {
+      for g2 in &model.params.activations[op2] {
+        if let Some(guard) = g1.and(g2) {
+          if !guards.contains(&guard) {
+            guards.push(guard);
+          }
+        }
+      }
+    }
+    guards
+  }
+
   fn err_two_readwrite_accesses(&mut self, v1: Variable, v2: Variable) {
     self.session.struct_span_err_with_code(v2.span,
       &format!("second readwrite access to this variable."),
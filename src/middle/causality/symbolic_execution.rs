@@ -124,14 +124,20 @@ impl StatesSet {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Instant {
+  /// The process from which this instant is reached.
+  pub process: ProcessUID,
   pub locations: State,
   pub program: Stmt
 }
 
 impl Instant
 {
-  pub fn new(locations: State, program: Stmt) -> Self {
-    Instant { locations, program }
+  pub fn new(process: ProcessUID, locations: State, program: Stmt) -> Self {
+    Instant { process, locations, program }
+  }
+
+  pub fn is_first(&self) -> bool {
+    self.locations.is_empty()
   }
 }
 
@@ -146,28 +152,30 @@ enum ResidualStmt {
 pub struct SymbolicExecution {
   session: Session,
   context: Context,
+  process: ProcessUID,
   visited_states: Vec<State>,
   next_instants: VectorStack<Instant>
 }
 
 impl SymbolicExecution
 {
-  fn new(session: Session, context: Context) -> Self {
+  fn new(session: Session, context: Context, process: ProcessUID) -> Self {
     SymbolicExecution {
       session: session,
       context: context,
+      process: process,
       visited_states: vec![],
       next_instants: VectorStack::empty()
     }
   }
 
   pub fn for_each_instant<F>(mut session: Session, mut context: Context, f: F) -> Env<Context>
-   where F: Clone + Fn(Env<(Context, Stmt)>) -> Env<Context>
+   where F: Clone + Fn(Env<(Context, Instant)>) -> Env<Context>
   {
     let mut fake = false;
     for uid in context.entry_points.clone() {
-      let mut this = SymbolicExecution::new(session, context);
-      this.push_process(uid.clone());
+      let mut this = SymbolicExecution::new(session, context, uid.clone());
+      this.push_process(uid);
       let env = this.for_each(f.clone());
       let (s, data) = env.decompose();
       fake = fake || data.is_fake();
@@ -191,11 +199,11 @@ impl SymbolicExecution
   }
 
   fn for_each<F>(mut self, f: F) -> Env<Context>
-   where F: Fn(Env<(Context, Stmt)>) -> Env<Context>
+   where F: Fn(Env<(Context, Instant)>) -> Env<Context>
   {
     let mut fake = false;
     while let Some(instant) = self.next() {
-      let env = f(Env::value(self.session, (self.context, instant.program.clone())));
+      let env = f(Env::value(self.session, (self.context, instant)));
       let (session, data) = env.decompose();
       fake = fake || data.is_fake();
       match data {
@@ -219,7 +227,7 @@ impl SymbolicExecution
   fn push_instant(&mut self, next_program: Option<Stmt>, state: State) {
     self.visited_states.push(state.clone());
     let nothing = Stmt::new(DUMMY_SP, StmtKind::Nothing);
-    let instant = Instant::new(state, next_program.clone().unwrap_or(nothing));
+    let instant = Instant::new(self.process.clone(), state, next_program.clone().unwrap_or(nothing));
     self.next_instants.push(instant);
   }
 
@@ -267,15 +275,15 @@ impl SymbolicExecution
       Loop(body) => self.next_states_loop(*body),
       Universe(queue, body) => self.next_states_universe(queue, *body),
       QFUniverse(body) => self.next_states_qf_universe(*body),
+      Suspend(suspend) => self.next_states_suspend(suspend),
+      Abort(abort) => self.next_states_abort(abort),
       Space(_)
     | Prune
     | Nothing
     | ExprStmt(_)
     | Tell(_, _) => StatesSet::terminated_state(),
+      ProcCall(_, _, _) => unreachable!("[BUG] Process calls are inlined before the symbolic execution (see `inline.rs`)."),
       _ => StatesSet::terminated_state(),
-      // Suspend(cond, body) => self.next_states_suspend(cond, *body, model),
-      // Abort(cond, body) => self.next_states_abort(cond, *body, model),
-      // ProcCall(var, process, args) => self.next_states_proc_call(var, process, args),
     }
   }
 
@@ -337,7 +345,29 @@ impl SymbolicExecution
     self.next_states_stmt(body)
   }
 
-  fn reduce_stmt(&self, stmt: Stmt, state: State) -> ResidualStmt
+  /// The statement can either be suspended in the current instant (in this case we pause on `state_num`), or execute its body.
+  fn next_states_suspend(&self, suspend: SuspendStmt) -> StatesSet
+  {
+    let mut states_body = self.next_states_stmt(*suspend.body);
+    states_body.join(StatesSet::paused_state(suspend.state_num));
+    states_body
+  }
+
+  /// The body of the statement can be aborted in any instant:
+  ///   * A strong abortion terminates immediately.
+  ///   * A weak abortion pauses (we pause on `state_num`) and terminates at the beginning of the next instant.
+  fn next_states_abort(&self, abort: AbortStmt) -> StatesSet
+  {
+    let next_abort = match abort.kind {
+      AbortKind::Strong => StatesSet::terminated_state(),
+      AbortKind::Weak => StatesSet::paused_state(abort.state_num)
+    };
+    let mut states_body = self.next_states_stmt(*abort.body);
+    states_body.join(next_abort);
+    states_body
+  }
+
+  fn reduce_stmt(&mut self, stmt: Stmt, state: State) -> ResidualStmt
   {
     use ast::StmtKind::*;
     let span = stmt.span;
@@ -352,15 +382,15 @@ impl SymbolicExecution
       Loop(body) => self.reduce_loop(*body, state),
       Universe(queue, body) => self.reduce_universe(queue, *body, state),
       QFUniverse(body) => self.reduce_qf_universe(*body, state),
+      Suspend(suspend) => self.reduce_suspend(span, suspend, state),
+      Abort(abort) => self.reduce_abort(span, abort, state),
       Space(_)
     | Prune
     | Nothing
     | ExprStmt(_)
     | Tell(_, _) => ResidualStmt::Terminated,
+      ProcCall(_, _, _) => unreachable!("[BUG] Process calls are inlined before the symbolic execution (see `inline.rs`)."),
       _ => ResidualStmt::Terminated
-      // Suspend(cond, body) => self.reduce_suspend(cond, *body, model, state),
-      // Abort(cond, body) => self.reduce_abort(cond, *body, model, state),
-      // ProcCall(var, process, args) => self.reduce_proc_call(var, process, args),
     }
   }
 
@@ -374,7 +404,7 @@ impl SymbolicExecution
     }
   }
 
-  fn reduce_seq(&self, children: Vec<Stmt>, state: State) -> ResidualStmt
+  fn reduce_seq(&mut self, children: Vec<Stmt>, state: State) -> ResidualStmt
   {
     let mut next_stmts = vec![];
     let mut has_paused = false;
@@ -412,7 +442,7 @@ impl SymbolicExecution
     }
   }
 
-  fn reduce_let(&self, span: Span, mut let_stmt: LetStmt, state: State) -> ResidualStmt
+  fn reduce_let(&mut self, span: Span, mut let_stmt: LetStmt, state: State) -> ResidualStmt
   {
     let kind = let_stmt.kind();
     let next = self.reduce_stmt(*(let_stmt.body), state);
@@ -433,7 +463,7 @@ impl SymbolicExecution
     }
   }
 
-  fn reduce_when(&self, _condition: Expr, then_branch: Stmt, else_branch: Stmt,
+  fn reduce_when(&mut self, _condition: Expr, then_branch: Stmt, else_branch: Stmt,
       state: State) -> ResidualStmt
   {
     let next_then = self.reduce_stmt(then_branch, state.clone());
@@ -449,15 +479,15 @@ impl SymbolicExecution
     }
   }
 
-  fn reduce_or_par(&self, span: Span, children: Vec<Stmt>, state: State) -> ResidualStmt {
+  fn reduce_or_par(&mut self, span: Span, children: Vec<Stmt>, state: State) -> ResidualStmt {
     self.reduce_par(span, children, state, |next| StmtKind::OrPar(next))
   }
 
-  fn reduce_and_par(&self, span: Span, children: Vec<Stmt>, state: State) -> ResidualStmt {
+  fn reduce_and_par(&mut self, span: Span, children: Vec<Stmt>, state: State) -> ResidualStmt {
     self.reduce_par(span, children, state, |next| StmtKind::AndPar(next))
   }
 
-  fn reduce_par<F>(&self, span: Span, children: Vec<Stmt>, state: State, build_par: F) -> ResidualStmt
+  fn reduce_par<F>(&mut self, span: Span, children: Vec<Stmt>, state: State, build_par: F) -> ResidualStmt
     where F: Fn(Vec<Stmt>) -> StmtKind
   {
     let reduced: Vec<_> = children.into_iter()
@@ -482,7 +512,7 @@ impl SymbolicExecution
     }
   }
 
-  fn reduce_loop(&self, body: Stmt, state: State) -> ResidualStmt
+  fn reduce_loop(&mut self, body: Stmt, state: State) -> ResidualStmt
   {
     use middle::causality::symbolic_execution::ResidualStmt::*;
     let next_body = self.reduce_stmt(body.clone(), state.clone());
@@ -491,21 +521,96 @@ impl SymbolicExecution
       Terminated => Terminated,
       // We go back to the beginning of the loop.
       Paused => Next(body),
-      // We rewrite `P` into `P; loop P end`.
+      // We rewrite `P` into `P; loop P' end` where `P'` is a new incarnation of the body.
       Next(stmt) => {
         let sp = stmt.span;
+        let body = self.reincarnate(body);
         Next(Stmt::new(sp, StmtKind::Seq(vec![stmt, Stmt::new(sp, StmtKind::Loop(Box::new(body)))])))
       }
     }
   }
 
-  fn reduce_universe(&self, _queue: Variable, body: Stmt, state: State) -> ResidualStmt
+  /// The variables declared in the body of a loop are renamed with fresh UIDs.
+  /// Hence they are not confused with the variables of the terminating incarnation, which can still be accessed in the same instant.
+  fn reincarnate(&mut self, mut body: Stmt) -> Stmt {
+    Reincarnation::new(&mut self.context).visit_stmt(&mut body);
+    body
+  }
+
+  /// If the statement was suspended, the residual program is the statement itself.
+  /// Otherwise, the condition is checked again in the next instant, even if the body paused on its last statement.
+  fn reduce_suspend(&mut self, span: Span, suspend: SuspendStmt, state: State) -> ResidualStmt
+  {
+    use middle::causality::symbolic_execution::ResidualStmt::*;
+    if state.contains(&suspend.state_num) {
+      return Next(Stmt::new(span, StmtKind::Suspend(suspend)));
+    }
+    let SuspendStmt { condition, body, state_num } = suspend;
+    let next_body = match self.reduce_stmt(*body, state) {
+      Terminated => return Terminated,
+      Paused => Stmt::new(span, StmtKind::Nothing),
+      Next(stmt) => stmt
+    };
+    let next = SuspendStmt { condition, body: Box::new(next_body), state_num };
+    Next(Stmt::new(span, StmtKind::Suspend(next)))
+  }
+
+  /// When a weak abortion was triggered, nothing remains to be executed in the next instant.
+  /// Otherwise, similarly to `suspend`, the condition must be checked again in the next instant.
+  fn reduce_abort(&mut self, span: Span, abort: AbortStmt, state: State) -> ResidualStmt
+  {
+    use middle::causality::symbolic_execution::ResidualStmt::*;
+    if abort.is_weak() && state.contains(&abort.state_num) {
+      return Paused;
+    }
+    let AbortStmt { condition, body, kind, state_num } = abort;
+    let next_body = match self.reduce_stmt(*body, state) {
+      Terminated => return Terminated,
+      Paused => Stmt::new(span, StmtKind::Nothing),
+      Next(stmt) => stmt
+    };
+    let next = AbortStmt { condition, body: Box::new(next_body), kind, state_num };
+    Next(Stmt::new(span, StmtKind::Abort(next)))
+  }
+
+  fn reduce_universe(&mut self, _queue: Variable, body: Stmt, state: State) -> ResidualStmt
   {
     self.reduce_stmt(body, state)
   }
 
-  fn reduce_qf_universe(&self, body: Stmt, state: State) -> ResidualStmt
+  fn reduce_qf_universe(&mut self, body: Stmt, state: State) -> ResidualStmt
   {
     self.reduce_stmt(body, state)
   }
 }
+
+struct Reincarnation<'a> {
+  context: &'a mut Context,
+  /// The old and new UIDs of the variables in scope.
+  renamings: Vec<(usize, usize)>
+}
+
+impl<'a> Reincarnation<'a> {
+  fn new(context: &'a mut Context) -> Self {
+    Reincarnation { context, renamings: vec![] }
+  }
+}
+
+impl<'a> VisitorMut<JClass> for Reincarnation<'a>
+{
+  fn visit_let(&mut self, let_stmt: &mut LetStmt) {
+    self.visit_binding(&mut let_stmt.binding);
+    let old_uid = let_stmt.binding.uid;
+    let new_uid = self.context.alloc_incarnation(&mut let_stmt.binding);
+    self.renamings.push((old_uid, new_uid));
+    self.visit_stmt(&mut *(let_stmt.body));
+    self.renamings.pop();
+  }
+
+  fn visit_var(&mut self, var: &mut Variable) {
+    let uid = var.path.uids[0];
+    if let Some(&(_, new_uid)) = self.renamings.iter().rev().find(|&&(old_uid, _)| old_uid == uid) {
+      var.path.uids[0] = new_uid;
+    }
+  }
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 /// It populates the store of ProcessInfo in `context`, it is used in the backend to generate the code of processes.
+/// Similarly, the module fields initialized with `new` are stored in the `ModuleInfo` of their enclosing module.
 /// This class is based on the code of `initialization.rs`.
 
 use context::*;
@@ -64,13 +65,28 @@ impl CollectModuleInProc {
 
   fn module_initialization_list(&mut self, binding: &Binding, ty: JType, args: Vec<Expr>)
   {
+    let instantiated_refs = self.instantiated_refs(ty, args);
+    self.current_proc.push_local_module(binding.uid, instantiated_refs);
+  }
+
+  // A module field without initializer is created with `new T()` and its `ref` fields are treated as normal fields (E0021).
+  fn module_field(&mut self, binding: &Binding) {
+    if let Some(Expr { node: ExprKind::NewInstance(new_instance), .. }) = binding.expr.clone() {
+      let instantiated_refs = self.instantiated_refs(new_instance.ty, new_instance.args);
+      let mod_name = self.current_proc.uid.module.clone();
+      self.context.module_by_name_mut(mod_name).module_fields
+        .push(LocalModuleVarInfo::new(binding.uid, instantiated_refs));
+    }
+  }
+
+  fn instantiated_refs(&mut self, ty: JType, args: Vec<Expr>) -> HashMap<usize, Variable> {
     let mod_info = self.context.module_by_name(ty.name);
     let mut instantiated_refs = HashMap::new();
     for (pos, uid) in mod_info.constructor {
       let (uid, var) = self.ref_instantiation(&args[pos], uid);
       instantiated_refs.insert(uid, var);
     }
-    self.current_proc.push_local_module(binding.uid, instantiated_refs);
+    instantiated_refs
   }
 
   fn ref_instantiation(&mut self, expr: &Expr, uid: usize) -> (usize, Variable) {
@@ -85,6 +101,11 @@ impl Visitor<JClass> for CollectModuleInProc
 {
   fn visit_module(&mut self, module: JModule) {
     self.current_proc.uid.module = module.mod_name();
+    for field in &module.fields {
+      if field.binding.is_module() {
+        self.module_field(&field.binding);
+      }
+    }
     walk_processes(self, module.processes);
   }
 
@@ -14,7 +14,7 @@
 
 /// Check duplicate names of:
 ///  (1) Processes.
-///  (2) Local variables per process.
+///  (2) Local variables and parameters per process.
 ///  (3) Spacetime fields in modules.
 
 use context::*;
@@ -105,6 +105,13 @@ impl Duplicate {
     if !err { self.dup_local_vars.insert(name.unwrap(), let_stmt.span); }
   }
 
+  fn duplicate_param(&mut self, param: &Binding) {
+    let name = param.name.clone();
+    let err = Self::duplicate(self.dup_local_vars.clone(), self.session(),
+      name.clone(), "E0003", "local variable");
+    if !err { self.dup_local_vars.insert(name.unwrap(), param.span); }
+  }
+
   fn duplicate_proc(&mut self, process: &Process) {
     let name = process.name.clone();
     let err = Self::duplicate(self.dup_procs.clone(), self.session(),
@@ -129,6 +136,9 @@ impl Visitor<JClass> for Duplicate
   fn visit_process(&mut self, process: Process) {
     self.reset_dup_local_vars();
     self.duplicate_proc(&process);
+    for param in &process.params {
+      self.duplicate_param(param);
+    }
     self.visit_stmt(process.body);
   }
 
@@ -18,14 +18,18 @@
 /// By default, if not precised, the permission is `Read`.
 /// In a tell statement `x <- e`, `x` is write only.
 /// In an entailment condition `e |= e'`, every variable appearing in `e` or `e'` are supposed to be read-only.
+/// The arguments of a process call `run p(x)` inherit the permission of the corresponding parameter of `p`.
+/// It is the union of the permissions of the occurrences of this parameter in `p` (`Read` if it is never accessed).
 
-/// In addition, we detect two errors:
+/// In addition, we detect three errors:
 ///   1. We forbid `pre` on module, host and single_time variables.
 ///   2. We forbid to write on `pre` variables.
+///   3. We forbid an explicit permission on an argument weaker than the one of its parameter.
 
 use context::*;
 use session::*;
 use ast::Permission::*;
+use std::collections::HashMap;
 
 pub fn infer_permission(session: Session, context: Context) -> Env<Context> {
   let permission = InferPermission::new(session, context);
@@ -56,6 +60,8 @@ impl InferPermission {
   fn compute(mut self) -> Env<Context> {
     let mut bcrate_clone = self.context.clone_ast();
     self.visit_crate(&mut bcrate_clone);
+    let params_usage = ParamsUsage::new(&self.context).compute(bcrate_clone.clone());
+    ArgsPermission::new(&mut self.session, &self.context, params_usage).visit_crate(&mut bcrate_clone);
     self.context.replace_ast(bcrate_clone);
     if self.session.has_errors() {
       Env::fake(self.session, self.context)
@@ -229,9 +235,9 @@ impl VisitorMut<JClass> for InferPermission
     self.visit_stmt(&mut *suspend.body)
   }
 
-  fn visit_abort(&mut self, condition: &mut Expr, child: &mut Stmt) {
-    self.visit_read_only_expr(condition);
-    self.visit_stmt(child)
+  fn visit_abort(&mut self, abort: &mut AbortStmt) {
+    self.visit_read_only_expr(&mut abort.condition);
+    self.visit_stmt(&mut *abort.body)
   }
 
   fn visit_tell(&mut self, var: &mut Variable, expr: &mut Expr) {
@@ -251,6 +257,28 @@ impl VisitorMut<JClass> for InferPermission
     self.perm_context = old;
   }
 
+  /// The operands of the Kleene operators are only read.
+  fn visit_trilean_or(&mut self, left: &mut Expr, right: &mut Expr) {
+    self.visit_read_only_expr(left);
+    self.visit_read_only_expr(right);
+  }
+
+  fn visit_trilean_and(&mut self, left: &mut Expr, right: &mut Expr) {
+    self.visit_read_only_expr(left);
+    self.visit_read_only_expr(right);
+  }
+
+  fn visit_trilean_not(&mut self, expr: &mut Expr) {
+    self.visit_read_only_expr(expr);
+  }
+
+  /// The permissions of the arguments are inferred later from the parameters (see `ArgsPermission`).
+  fn visit_proc_call(&mut self, var: &mut Option<Variable>, _process: Ident, _args: &mut Vec<Variable>) {
+    if let &mut Some(ref mut var) = var {
+      self.visit_var(var);
+    }
+  }
+
   fn visit_new_instance(&mut self, _ty: JType, args: &mut Vec<Expr>) {
     if self.check_host_function() {
       walk_exprs_mut(self, args);
@@ -266,3 +294,130 @@ impl VisitorMut<JClass> for InferPermission
     }
   }
 }
+
+fn join_permission(p1: Permission, p2: Permission) -> Permission {
+  if p1 == p2 { p1 } else { ReadWrite }
+}
+
+/// Compute the permission of each process parameter, which is the union of the permissions of its occurrences.
+/// A parameter can be passed as an argument to another process, therefore we iterate until a fixpoint is reached.
+struct ParamsUsage<'a> {
+  context: &'a Context,
+  current_mod: Ident,
+  usage: HashMap<usize, Permission>,
+  changed: bool
+}
+
+impl<'a> ParamsUsage<'a> {
+  pub fn new(context: &'a Context) -> Self {
+    ParamsUsage {
+      context: context,
+      current_mod: context.dummy_ident(),
+      usage: HashMap::new(),
+      changed: false
+    }
+  }
+
+  fn compute(mut self, bcrate: JCrate) -> HashMap<usize, Permission> {
+    loop {
+      self.changed = false;
+      self.visit_crate(bcrate.clone());
+      if !self.changed { break; }
+    }
+    self.usage
+  }
+
+  fn param_uid(&self, var: &Variable) -> Option<usize> {
+    let uid = var.first_uid();
+    if var.len() == 1 && self.context.var_by_uid(uid).is_param { Some(uid) }
+    else { None }
+  }
+
+  fn use_param(&mut self, uid: usize, perm: Permission) {
+    let new = match self.usage.get(&uid) {
+      Some(&old) => join_permission(old, perm),
+      None => perm
+    };
+    if self.usage.insert(uid, new) != Some(new) {
+      self.changed = true;
+    }
+  }
+}
+
+impl<'a> Visitor<JClass> for ParamsUsage<'a>
+{
+  fn visit_module(&mut self, module: JModule) {
+    self.current_mod = module.mod_name();
+    walk_processes(self, module.processes);
+  }
+
+  fn visit_var(&mut self, var: Variable) {
+    if let (Some(uid), Some(perm)) = (self.param_uid(&var), var.permission) {
+      self.use_param(uid, perm);
+    }
+  }
+
+  fn visit_proc_call(&mut self, var: Option<Variable>, process: Ident, args: Vec<Variable>) {
+    let (_, callee) = self.context.find_proc_from_call(self.current_mod.clone(), process, var);
+    for (arg, param) in args.into_iter().zip(callee.params.into_iter()) {
+      let perm = arg.permission.or(self.usage.get(&param.uid).cloned());
+      if let (Some(uid), Some(perm)) = (self.param_uid(&arg), perm) {
+        self.use_param(uid, perm);
+      }
+    }
+  }
+}
+
+/// Set the permission of the arguments of process calls from the permission of the parameters.
+struct ArgsPermission<'a> {
+  session: &'a mut Session,
+  context: &'a Context,
+  current_mod: Ident,
+  params_usage: HashMap<usize, Permission>
+}
+
+impl<'a> ArgsPermission<'a> {
+  pub fn new(session: &'a mut Session, context: &'a Context,
+    params_usage: HashMap<usize, Permission>) -> Self
+  {
+    ArgsPermission {
+      session: session,
+      context: context,
+      current_mod: context.dummy_ident(),
+      params_usage: params_usage
+    }
+  }
+
+  fn err_arg_weaker_than_param(&mut self, arg: &Variable, perm: Permission, param: &Binding, param_perm: Permission) {
+    self.session.struct_span_err_with_code(arg.span,
+      &format!("illegal permission of the variable `{}`.", arg.last()),
+      "E0026")
+    .span_label(arg.last().span, &format!(
+      "this argument is passed with the permission `{}`", perm))
+    .span_label(param.span, &format!(
+      "but the parameter `{}` is accessed with the permission `{}`", param.name, param_perm))
+    .help(&"Solution: remove the permission of the argument, it is inferred from the process.")
+    .emit();
+  }
+}
+
+impl<'a> VisitorMut<JClass> for ArgsPermission<'a>
+{
+  fn visit_module(&mut self, module: &mut JModule) {
+    self.current_mod = module.mod_name();
+    walk_processes_mut(self, &mut module.processes);
+  }
+
+  fn visit_proc_call(&mut self, var: &mut Option<Variable>, process: Ident, args: &mut Vec<Variable>) {
+    let (_, callee) = self.context.find_proc_from_call(self.current_mod.clone(), process, var.clone());
+    for (arg, param) in args.iter_mut().zip(callee.params.iter()) {
+      let param_perm = self.params_usage.get(&param.uid).cloned().unwrap_or(Read);
+      match arg.permission.clone() {
+        Some(perm) if join_permission(perm, param_perm) != perm =>
+          self.err_arg_weaker_than_param(arg, perm, param, param_perm),
+        Some(_) => (),
+        None => arg.permission = Some(param_perm)
+      }
+    }
+  }
+}
@@ -22,8 +22,16 @@
 ///       * `ref` arguments must be variables and not expressions (E0022).
 ///       * `ref` arguments must match the types and kinds of the corresponding module fields (E0023).
 ///
-///  (d) `module` field variables: Module that contains refs variables cannot be initialized (E0021)
+///  (d) `module` field variables, if the module contains `ref` fields:
+///     (1) Without initializer, its `ref` fields are treated as normal fields, similarly to the `ref` fields of the root module.
+///     (2) Otherwise, it must be initialized with a `new` and the `ref` arguments must be fields of the current module (E0021).
+///         The `ref` arguments are checked as for local `module` variables (E0022, E0023, E0024).
 ///  (e) Ref variables must not occurred when initializing field's RHS. (E0005)
+///  (f) Process parameters:
+///     (1) They must be of the spacetime kind. (E0020)
+///     (2) Process calls must have as many arguments as parameters (E0024).
+///     (3) Arguments must be variables without `pre` (E0022).
+///     (4) Arguments must match the types and kinds of the corresponding parameters (E0023).
 ///
 ///   Design rational:
 ///     (a) `ref` variables can only be retrieved from the environment, however it is not accessible when initializing the field.
@@ -88,9 +96,29 @@ impl Initialization {
   }
 
   fn module_field(&mut self, binding: &Binding) {
-    if binding.expr.is_some() {
+    if let Some(expr) = binding.expr.clone() {
       if self.is_module_ref(binding) {
-        self.err_module_ref_in_field(binding)
+        match expr.node {
+          ExprKind::NewInstance(new_instance) => {
+            self.module_initialization_list(binding, new_instance.ty.clone(), new_instance.args.clone());
+            self.ref_args_in_field(binding, new_instance.ty, new_instance.args);
+          }
+          _ => self.err_module_ref_in_field(binding)
+        }
+      }
+    }
+  }
+
+  // The `ref` fields of a module field are bound to the fields of the current module in its `__init` method.
+  fn ref_args_in_field(&mut self, binding: &Binding, ty: JType, args: Vec<Expr>) {
+    let mod_info = self.context.module_by_name(ty.name);
+    if mod_info.cons_len == args.len() {
+      for (pos, _) in mod_info.constructor {
+        if let ExprKind::Var(ref var) = args[pos].node {
+          if var.path.len() > 1 || !self.context.var_by_uid(var.last_uid()).is_field() {
+            self.err_module_ref_arg_not_field(binding, var);
+          }
+        }
       }
     }
   }
@@ -138,6 +166,37 @@ impl Initialization {
     }
   }
 
+  fn process_params(&mut self, process: &Process) {
+    for param in &process.params {
+      match param.kind {
+        Kind::Product
+      | Kind::Host => self.err_kind_param(process, param),
+        _ => ()
+      }
+    }
+  }
+
+  fn process_call_args(&mut self, var: Option<Variable>, process: Ident, args: Vec<Variable>) {
+    let current_mod = self.context.ast.modules[self.current_mod].mod_name();
+    let (uid, callee) = self.context.find_proc_from_call(current_mod, process.clone(), var);
+    if callee.params.len() != args.len() {
+      self.err_arg_list_differ(process, &uid, callee.params.len(), args.len());
+    }
+    else {
+      for (arg, param) in args.iter().zip(callee.params.iter()) {
+        if arg.past > 0 {
+          self.err_pre_arg(arg);
+          continue;
+        }
+        let arg_info = self.context.var_by_uid(arg.last_uid());
+        let param_info = self.context.var_by_uid(param.uid);
+        if arg_info.kind != param_info.kind || arg_info.ty != param_info.ty {
+          self.err_mismatch_kind_type(arg.span, &arg_info, &param_info)
+        }
+      }
+    }
+  }
+
   fn contains_ref(&self, var: &Variable) -> Option<Ident> {
     for i in 0..var.path.len() {
       if self.context.var_by_uid(var.path.uids[i]).is_ref() {
@@ -158,6 +217,14 @@ impl Initialization {
     .emit();
   }
 
+  fn err_arg_list_differ(&mut self, process: Ident, uid: &ProcessUID, params_len: usize, args_len: usize) {
+    self.session().struct_span_err_with_code(process.span,
+      &format!("process `{}` has {} parameters but was called with {} arguments.", uid, params_len, args_len),
+      "E0024")
+    .span_label(process.span, &format!("expected {} arguments", params_len))
+    .emit();
+  }
+
   fn err_mismatch_kind_type(&mut self, sp: Span, var_info: &VarInfo, ref_info: &VarInfo) {
     let msg = self.msg_expected_var(ref_info);
     self.session().struct_span_err_with_code(sp,
@@ -178,6 +245,16 @@ impl Initialization {
     .emit();
   }
 
+  fn err_pre_arg(&mut self, arg: &Variable) {
+    self.session().struct_span_err_with_code(arg.span,
+      &format!("illegal `pre` on the argument `{}` of a process call.", arg.last()),
+      "E0022")
+    .span_help(arg.span,
+      &format!("Arguments of processes are passed by reference and must be variables.\n\
+               Use `pre` inside the called process instead."))
+    .emit();
+  }
+
   fn err_ref_var_in_field(&mut self, var: &Variable, first_ref: Ident) {
     let msg = self.msg_ref_field();
     self.session().struct_span_err_with_code(var.span,
@@ -189,14 +266,30 @@ impl Initialization {
   }
 
   fn err_module_ref_in_field(&mut self, binding: &Binding) {
-    let msg = self.msg_ref_field();
+    let msg = self.msg_module_ref_field();
     self.session().struct_span_err_with_code(binding.span,
-      &format!("forbidden initialization of the module `{}` in a field.", binding.ty.name),
+      &format!("illegal initialization of the module field `{}`.", binding.name),
       "E0021")
+    .span_label(binding.expr.as_ref().unwrap().span, &"illegal initializer")
     .help(&msg)
     .emit();
   }
 
+  fn err_module_ref_arg_not_field(&mut self, binding: &Binding, var: &Variable) {
+    let msg = self.msg_module_ref_field();
+    self.session().struct_span_err_with_code(var.span,
+      &format!("the `ref` argument `{}` of the module field `{}` is not a field of this module.", var, binding.name),
+      "E0021")
+    .help(&msg)
+    .emit();
+  }
+
+  fn msg_module_ref_field(&self) -> String {
+    format!("A module field with `ref` fields must be initialized with the `new` operator, \
+             and its `ref` fields are bound to fields of the current module.\n\
+             Without initializer, its `ref` fields are treated as normal fields.")
+  }
+
   fn msg_ref_field(&self) -> String {
     format!("At this stage, the `ref` variables are not yet initialized and are equal to `null`.\n\
              You can initialize this field in the constructor.")
@@ -221,6 +314,14 @@ impl Initialization {
     .emit();
   }
 
+  fn err_kind_param(&mut self, process: &Process, param: &Binding) {
+    self.session().struct_span_err_with_code(param.span,
+      &format!("illegal kind for the parameter `{}` of the process `{}`.", param.name, process.name),
+      "E0020")
+    .help(&"Process parameters are passed by reference and must have the spacetime kind (`single_time`, `single_space` or `world_line`).")
+    .emit();
+  }
+
   fn err_module_illegal_initializer(&mut self, binding: &Binding) {
     let msg = self.msg_module_initializer();
     self.session().struct_span_err_with_code(binding.span,
@@ -273,6 +374,15 @@ impl Visitor<JClass> for Initialization
     walk_processes(self, module.processes);
   }
 
+  fn visit_process(&mut self, process: Process) {
+    self.process_params(&process);
+    self.visit_stmt(process.body);
+  }
+
+  fn visit_proc_call(&mut self, var: Option<Variable>, process: Ident, args: Vec<Variable>) {
+    self.process_call_args(var, process, args);
+  }
+
   fn visit_binding(&mut self, binding: Binding) {
     if binding.is_module() {
       if self.visiting_fields {
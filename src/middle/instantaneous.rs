@@ -157,9 +157,13 @@ impl Visitor<JClass> for InstantaneousAnalysis
     self.can_pause = self.can_pause || true;
   }
 
-  fn visit_abort(&mut self, _condition: Expr, child: Stmt) {
-    self.visit_stmt(child);
-    self.must_pause = false;
+  fn visit_abort(&mut self, abort: AbortStmt) {
+    let is_weak = abort.is_weak();
+    self.visit_stmt(*abort.body);
+    // A weak abortion cannot prevent its body from pausing.
+    if !is_weak {
+      self.must_pause = false;
+    }
   }
 
   fn visit_proc_call(&mut self, var: Option<Variable>, process: Ident, _args: Vec<Variable>) {
@@ -23,8 +23,8 @@ mod instantaneous;
 mod causality;
 mod recursive_call;
 mod search_tree_wf;
-mod rewrite_reincarnation;
 mod collect_module_in_proc;
+mod pass_manager;
 
 use context::*;
 use session::*;
@@ -39,22 +39,27 @@ use middle::instantaneous::*;
 use middle::causality::*;
 use middle::recursive_call::*;
 use middle::search_tree_wf::*;
-use middle::rewrite_reincarnation::*;
 use middle::collect_module_in_proc::*;
+use middle::pass_manager::*;
 
+/// The analyses are executed per unit (module or process), see `pass_manager.rs`.
+/// As in the original sequence of analyses, `resolve` only needs the variables to be declared, the checks on resolved variables need a resolution without errors, and `instantaneous_analysis` and `search_tree_wf` need all the previous checks to succeed.
+/// The causality analysis and the collection of the modules (for the code generation) need the whole crate to be well-formed.
 pub fn analyse_bonsai(env: Env<Context>) -> Env<Context> {
-  env
-    .and_next(rewrite_reincarnation)
-    .and_then(duplicate)
-    .and_then(undeclared)
-    .and_then(resolve)
-    .and_then(constructor)
-    .and_then(initialization)
-    .and_then(stream_bound)
-    .and_then(infer_permission)
-    .and_then(recursive_call)
-    .and_next(instantaneous_analysis)
-    .and_next(search_tree_wf)
-    .and_next(causality_analysis)
-    .and_next(collect_module_in_proc)
+  let resolved = &["duplicate", "resolve"];
+  let checked = &["constructor", "initialization", "stream_bound", "infer_permission", "recursive_call"];
+  PassManager::new()
+    .pass("duplicate", duplicate, &[])
+    .pass("undeclared", undeclared, &[])
+    .pass("resolve", resolve, &["undeclared"])
+    .pass("constructor", constructor, resolved)
+    .pass("initialization", initialization, resolved)
+    .pass("stream_bound", stream_bound, resolved)
+    .pass("infer_permission", infer_permission, resolved)
+    .pass("recursive_call", recursive_call, resolved)
+    .pass("instantaneous_analysis", instantaneous_analysis, checked)
+    .pass("search_tree_wf", search_tree_wf, &["instantaneous_analysis"])
+    .crate_pass("causality_analysis", causality_analysis, &["search_tree_wf"])
+    .crate_pass("collect_module_in_proc", collect_module_in_proc, &["causality_analysis"])
+    .run(env)
 }
@@ -0,0 +1,314 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The pass manager executes the analyses in their order of registration.
+/// A pass reporting errors does not stop the analysis: the errors are attributed to units, and the later passes are only skipped on the units where a pass they depend on failed.
+/// A unit is a process, or a module for the errors outside of its processes (fields, constructors).
+/// This way, all the independent errors are reported in a single compilation.
+///
+/// A pass fails on a unit if it reports an error in this unit, or if it is not executed on this unit.
+/// We do not rely on the result of the pass since it is usually `Fake` as soon as the session contains an error, even if reported by a previous pass.
+/// An error that cannot be attributed to a unit (no span, or a span outside of the modules) makes the pass fail on the whole crate.
+///
+/// The excluded units are removed from the crate given to a pass, and restored afterwards.
+/// A unit referring to an excluded unit is excluded too: a module or a process through the type of its variables, and a process through its process calls.
+
+use context::*;
+use session::*;
+use std::collections::HashSet;
+use std::mem;
+
+pub type PassFn = fn(Session, Context) -> Env<Context>;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum Unit {
+  Module(String),
+  Process(String, String)
+}
+
+impl Unit {
+  fn module(module: &JModule) -> Self {
+    Unit::Module(module.mod_name().unwrap())
+  }
+
+  fn process(module: &JModule, process: &Process) -> Self {
+    Unit::Process(module.mod_name().unwrap(), process.name.unwrap())
+  }
+
+  /// The unit containing `span`, or `None` if it is outside of the modules of `bcrate`.
+  fn of_span(session: &Session, bcrate: &JCrate, span: Span) -> Option<Unit> {
+    if span == DUMMY_SP {
+      return None;
+    }
+    let file = session.codemap.lookup_char_pos(span.lo).file.name.clone();
+    bcrate.modules.iter()
+      .find(|module| module.file.input_path_str() == file)
+      .map(|module| {
+        match module.processes.iter().find(|p| p.span.lo <= span.lo && span.hi <= p.span.hi) {
+          Some(process) => Unit::process(module, process),
+          None => Unit::module(module)
+        }
+      })
+  }
+}
+
+/// The units on which a pass failed.
+#[derive(Clone, Debug)]
+struct Failure {
+  crate_wide: bool,
+  units: HashSet<Unit>
+}
+
+impl Failure {
+  fn new() -> Self {
+    Failure { crate_wide: false, units: HashSet::new() }
+  }
+
+  fn crate_wide() -> Self {
+    Failure { crate_wide: true, units: HashSet::new() }
+  }
+
+  fn is_empty(&self) -> bool {
+    !self.crate_wide && self.units.is_empty()
+  }
+
+  fn union(&mut self, other: &Failure) {
+    self.crate_wide |= other.crate_wide;
+    self.units.extend(other.units.iter().cloned());
+  }
+
+  /// Attributes the errors reported since the session had `errors` errors and `spans` error spans.
+  fn attribute_errors(&mut self, session: &Session, bcrate: &JCrate, errors: usize, spans: usize) {
+    let new_spans = &session.error_spans()[spans..];
+    if session.err_count() > errors + new_spans.len() {
+      self.crate_wide = true;
+    }
+    for span in new_spans {
+      match span.and_then(|span| Unit::of_span(session, bcrate, span)) {
+        Some(unit) => { self.units.insert(unit); }
+        None => self.crate_wide = true
+      }
+    }
+  }
+
+  /// Excludes the units referring to an excluded unit, until a fixpoint is reached.
+  fn propagate(mut self, bcrate: &JCrate) -> Self {
+    if self.units.is_empty() {
+      return self;
+    }
+    let mut units = vec![];
+    for module in &bcrate.modules {
+      let mut refs = References::new();
+      for field in &module.fields {
+        refs.visit_field(field.clone());
+      }
+      units.push((Unit::module(module), refs));
+      for process in &module.processes {
+        let mut refs = References::new();
+        refs.visit_process(process.clone());
+        units.push((Unit::process(module, process), refs));
+      }
+    }
+    loop {
+      let excluded: Vec<Unit> = units.iter()
+        .filter(|&&(ref unit, ref refs)| !self.units.contains(unit) && self.refers_to_excluded(unit, refs))
+        .map(|&(ref unit, _)| unit.clone())
+        .collect();
+      if excluded.is_empty() {
+        return self;
+      }
+      self.units.extend(excluded);
+    }
+  }
+
+  fn refers_to_excluded(&self, unit: &Unit, refs: &References) -> bool {
+    let mod_name = match unit {
+      &Unit::Module(ref mod_name)
+    | &Unit::Process(ref mod_name, _) => mod_name
+    };
+    self.units.contains(&Unit::Module(mod_name.clone()))
+    || refs.modules.iter().any(|module| self.units.contains(&Unit::Module(module.clone())))
+    || refs.processes.iter().any(|&(qualified, ref process)|
+         self.units.iter().any(|excluded| match excluded {
+           &Unit::Process(ref module, ref name) => name == process && (qualified || module == mod_name),
+           &Unit::Module(_) => false
+         }))
+  }
+
+  fn remove_from(&self, bcrate: &mut JCrate) {
+    bcrate.modules.retain(|module| !self.units.contains(&Unit::module(module)));
+    for module in &mut bcrate.modules {
+      let mod_name = module.mod_name().unwrap();
+      module.processes.retain(|process|
+        !self.units.contains(&Unit::Process(mod_name.clone(), process.name.unwrap())));
+    }
+  }
+
+  /// Puts back the excluded units of `full` in the crate of `context`, at their original positions.
+  fn restore(&self, full: JCrate, mut context: Context) -> Context {
+    let mut analysed = mem::replace(&mut context.ast.modules, vec![]);
+    for module in full.modules {
+      let restored =
+        if self.units.contains(&Unit::module(&module)) { module }
+        else {
+          let mod_name = module.mod_name().unwrap();
+          let pos = analysed.iter().position(|m| m.mod_name() == module.mod_name())
+            .expect("[BUG] A pass removed a module from the crate.");
+          let mut analysed_mod = analysed.remove(pos);
+          let mut analysed_procs = mem::replace(&mut analysed_mod.processes, vec![]);
+          for process in module.processes {
+            if self.units.contains(&Unit::Process(mod_name.clone(), process.name.unwrap())) {
+              analysed_mod.processes.push(process);
+            }
+            else {
+              let pos = analysed_procs.iter().position(|p| p.name == process.name)
+                .expect("[BUG] A pass removed a process from the crate.");
+              analysed_mod.processes.push(analysed_procs.remove(pos));
+            }
+          }
+          analysed_mod
+        };
+      context.ast.modules.push(restored);
+    }
+    context
+  }
+}
+
+/// The modules and processes a unit refers to.
+/// A process call is qualified if it is of the form `run m.p()`, we do not resolve `m` and consider every process named `p`.
+struct References {
+  modules: Vec<String>,
+  processes: Vec<(bool, String)>
+}
+
+impl References {
+  fn new() -> Self {
+    References { modules: vec![], processes: vec![] }
+  }
+}
+
+impl Visitor<JClass> for References {
+  fn visit_binding(&mut self, binding: Binding) {
+    if binding.kind == Kind::Product {
+      self.modules.push(binding.ty.name.unwrap());
+    }
+    walk_binding(self, binding)
+  }
+
+  fn visit_proc_call(&mut self, var: Option<Variable>, process: Ident, args: Vec<Variable>) {
+    self.processes.push((var.is_some(), process.unwrap()));
+    walk_proc_call(self, var, args);
+  }
+}
+
+struct Pass {
+  name: &'static str,
+  run: PassFn,
+  /// The passes that must succeed on a unit before this pass can be executed on it.
+  requires: Vec<&'static str>,
+  /// `true` if the pass can only be executed when the required passes succeeded on the whole crate.
+  whole_crate: bool
+}
+
+impl Pass {
+  /// Executes the pass on the crate without the `excluded` units.
+  fn run_on(&self, session: Session, mut context: Context, excluded: &Failure) -> (Session, Partial<Context>) {
+    if excluded.is_empty() {
+      return (self.run)(session, context).decompose();
+    }
+    let full = context.clone_ast();
+    excluded.remove_from(&mut context.ast);
+    let (session, data) = (self.run)(session, context).decompose();
+    let data = match data {
+      Partial::Value(context) => Partial::Value(excluded.restore(full, context)),
+      Partial::Fake(context) => Partial::Fake(excluded.restore(full, context)),
+      Partial::Nothing => Partial::Nothing
+    };
+    (session, data)
+  }
+}
+
+pub struct PassManager {
+  passes: Vec<Pass>
+}
+
+impl PassManager {
+  pub fn new() -> Self {
+    PassManager { passes: vec![] }
+  }
+
+  /// Register the pass `name` which is executed on the units where the passes in `requires` succeeded.
+  /// The required passes must be registered before.
+  pub fn pass(self, name: &'static str, run: PassFn, requires: &[&'static str]) -> Self {
+    self.register(name, run, requires, false)
+  }
+
+  /// Register the pass `name` which is executed only if the passes in `requires` succeeded on the whole crate.
+  pub fn crate_pass(self, name: &'static str, run: PassFn, requires: &[&'static str]) -> Self {
+    self.register(name, run, requires, true)
+  }
+
+  fn register(mut self, name: &'static str, run: PassFn, requires: &[&'static str], whole_crate: bool) -> Self {
+    for required in requires {
+      assert!(self.passes.iter().any(|p| &p.name == required),
+        "PassManager::pass: `{}` must be registered before `{}`.", required, name);
+    }
+    self.passes.push(Pass { name, run, requires: requires.to_vec(), whole_crate });
+    self
+  }
+
+  /// The result is `Fake` if one of the passes failed, and `Nothing` if a pass did not give back the context.
+  pub fn run(self, env: Env<Context>) -> Env<Context> {
+    let (mut session, data) = env.decompose();
+    let mut context = match data {
+      Partial::Value(context) => context,
+      data => return Env::new(session, data)
+    };
+    let mut failures: Vec<(&'static str, Failure)> = vec![];
+    for pass in self.passes {
+      let mut excluded = Failure::new();
+      for &(name, ref failure) in &failures {
+        if pass.requires.contains(&name) {
+          excluded.union(failure);
+        }
+      }
+      if excluded.crate_wide || (pass.whole_crate && !excluded.is_empty()) {
+        debug!("pass `{}` skipped because a pass it depends on failed.", pass.name);
+        failures.push((pass.name, Failure::crate_wide()));
+        continue;
+      }
+      let mut failure = excluded.propagate(&context.ast);
+      if !failure.is_empty() {
+        debug!("pass `{}` skipped on {:?}.", pass.name, failure.units);
+      }
+      let errors = session.err_count();
+      let spans = session.error_spans().len();
+      let (s, data) = pass.run_on(session, context, &failure);
+      session = s;
+      context = match data {
+        Partial::Value(context)
+      | Partial::Fake(context) => context,
+        Partial::Nothing => return Env::nothing(session)
+      };
+      failure.attribute_errors(&session, &context.ast, errors, spans);
+      failures.push((pass.name, failure));
+    }
+    if failures.iter().all(|&(_, ref failure)| failure.is_empty()) {
+      Env::value(session, context)
+    }
+    else {
+      Env::fake(session, context)
+    }
+  }
+}
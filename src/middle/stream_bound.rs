@@ -14,6 +14,7 @@
 
 /// For each binding, we compute its maximum stream bound.
 /// It is the maximum number of `pre` occuring before the variable.
+/// The stream bound of a process parameter is propagated to the arguments of the calls to this process.
 
 use context::*;
 use session::*;
@@ -26,29 +27,61 @@ pub fn stream_bound(session: Session, context: Context) -> Env<Context> {
 
 struct StreamBound {
   session: Session,
-  context: Context
+  context: Context,
+  current_mod: Ident,
+  changed: bool
 }
 
 impl StreamBound {
   pub fn new(session: Session, context: Context) -> Self {
-    StreamBound { session, context }
+    let current_mod = context.dummy_ident();
+    StreamBound { session, context, current_mod, changed: false }
   }
 
   fn compute(mut self) -> Env<Context> {
     let bcrate_clone = self.context.clone_ast();
-    self.visit_crate(bcrate_clone);
+    // A parameter can be passed in cascade to several processes, so we iterate until the bounds are stable.
+    loop {
+      self.changed = false;
+      self.visit_crate(bcrate_clone.clone());
+      if !self.changed { break; }
+    }
     Env::value(self.session, self.context)
   }
 
   fn bound_of<'b>(&'b mut self, uid: usize) -> &'b mut usize {
     &mut self.context.var_by_uid_mut(uid).stream_bound
   }
+
+  fn update_bound(&mut self, uid: usize, past: usize) {
+    let changed = {
+      let bound = self.bound_of(uid);
+      let old = *bound;
+      *bound = max(*bound, past);
+      old != *bound
+    };
+    self.changed |= changed;
+  }
 }
 
 impl Visitor<JClass> for StreamBound
 {
+  fn visit_module(&mut self, module: JModule) {
+    self.current_mod = module.mod_name();
+    walk_fields(self, module.fields);
+    walk_processes(self, module.processes);
+  }
+
   fn visit_var(&mut self, var: Variable) {
-    let bound = self.bound_of(var.last_uid());
-    *bound = max(*bound, var.past);
+    self.update_bound(var.last_uid(), var.past);
+  }
+
+  fn visit_proc_call(&mut self, var: Option<Variable>, process: Ident, args: Vec<Variable>) {
+    let (_, callee) = self.context.find_proc_from_call(self.current_mod.clone(), process, var.clone());
+    for (arg, param) in args.iter().zip(callee.params.iter()) {
+      let param_bound = self.context.var_by_uid(param.uid).stream_bound;
+      self.update_bound(arg.last_uid(), param_bound);
+    }
+    walk_proc_call(self, var, args);
   }
 }
@@ -16,6 +16,7 @@
 /// In addition, it computes a unique identifier (UID) for variables local to modules.
 /// It does not assign a UID to variable of the form `m.a` or `m.a.b` because the UID for each module's variables is not yet accessible (it is currently being computed).
 /// This next step is done in `resolve.rs`.
+/// We also record the number of loops enclosing each local variable (see `VarInfo::loop_depth`).
 
 use context::*;
 use session::*;
@@ -31,6 +32,7 @@ struct Undeclared {
   current_mod: Ident,
   in_scope_vars: Vec<(Ident, usize, bool)>, // (Name, UID, is_field)
   in_scope_processes: Vec<Ident>,
+  loops: usize,
 }
 
 impl Undeclared {
@@ -41,6 +43,7 @@ impl Undeclared {
       current_mod: Ident::gen("Undeclared::new: no current_mod yet"),
       in_scope_vars: Vec::new(),
       in_scope_processes: Vec::new(),
+      loops: 0,
     }
   }
 
@@ -73,6 +76,12 @@ impl Undeclared {
 
   fn enter_local_scope(&mut self, binding: &mut Binding) {
     let uid = self.context.alloc_local(binding);
+    self.context.var_by_uid_mut(uid).loop_depth = self.loops;
+    self.enter_scope(binding, uid, false);
+  }
+
+  fn enter_param_scope(&mut self, binding: &mut Binding) {
+    let uid = self.context.alloc_param(binding);
     self.enter_scope(binding, uid, false);
   }
 
@@ -175,6 +184,16 @@ impl<'a> VisitorMut<JClass> for Undeclared
     }
   }
 
+  fn visit_process(&mut self, process: &mut Process) {
+    for param in &mut process.params {
+      self.enter_param_scope(param);
+    }
+    self.visit_stmt(&mut process.body);
+    for _ in &process.params {
+      self.exit_scope();
+    }
+  }
+
   fn visit_proc_call(&mut self, var: &mut Option<Variable>, process: Ident, args: &mut Vec<Variable>) {
     match var {
       &mut Some(ref mut var) => self.visit_var(var),
@@ -203,6 +222,12 @@ impl<'a> VisitorMut<JClass> for Undeclared
     self.exit_scope();
   }
 
+  fn visit_loop(&mut self, child: &mut Stmt) {
+    self.loops += 1;
+    self.visit_stmt(child);
+    self.loops -= 1;
+  }
+
   fn visit_binding(&mut self, binding: &mut Binding) {
     self.unknown_module_ty(binding);
     walk_binding_mut(self, binding);
@@ -15,14 +15,17 @@
 #![allow(dead_code)]
 
 use driver::config::*;
-use syntex_pos::MultiSpan;
+use driver::file_system::*;
+use json_emitter::JsonEmitter;
+use syntex_pos::{MultiSpan, Span};
 use syntex_errors::DiagnosticBuilder;
 use syntex_errors::emitter::{ColorConfig, Emitter};
 use syntex_syntax::codemap::{FileMap, CodeMap};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::io;
 use std::collections::hash_set::HashSet;
-use ast::CompilerTest;
+use ast::{CompilerTest, LabelTest};
 use ast::ExecutionTest;
 use partial::*;
 
@@ -33,14 +36,18 @@ pub type Env<T> = Environment<Session, T>;
 pub struct Session {
   pub config: Config,
   pub codemap: Rc<CodeMap>,
+  /// The sources are read from this file system, and the generated files are written to it.
+  pub file_system: Rc<FileSystem>,
   pub span_diagnostic: SpanDiagnostic,
   pub compiler_tests: Vec<CompilerTest>,
   pub execution_tests: Vec<ExecutionTest>,
   // We uniquely identify an error and a warning with its span and error/warning code.
   // This is to avoid registering duplicated errors more than one time.
-  // Rational: The body of the loop statement is duplicated which can generate the same error twice. (see `rewrite_reincarnation.rs`).
+  // Rational: An analysis can meet the same statement in several instants or execution paths (e.g. E0032 in `causality/solver.rs`).
   errors: HashSet<(MultiSpan, String)>,
   warnings: HashSet<(MultiSpan, String)>,
+  /// The primary span of the reported errors, in the order of reporting.
+  error_spans: Vec<Option<Span>>,
 }
 
 impl Session
@@ -51,35 +58,55 @@ impl Session
     Session {
       config: config,
       codemap: codemap,
+      file_system: Rc::new(DiskFileSystem),
       span_diagnostic: span_diagnostic,
       compiler_tests: vec![],
       execution_tests: vec![],
       errors: HashSet::new(),
       warnings: HashSet::new(),
+      error_spans: vec![],
     }
   }
 
   pub fn new(config: Config) -> Self {
     let codemap = Rc::new(CodeMap::new());
-    let span_diagnostic = SpanDiagnostic::with_tty_emitter(
-      ColorConfig::Always, true, false, Some(codemap.clone()));
+    let span_diagnostic = Session::make_diagnostic(config.error_format, codemap.clone());
+    Session::init(config, codemap, span_diagnostic)
+  }
+
+  fn make_diagnostic(error_format: ErrorFormat, codemap: Rc<CodeMap>) -> SpanDiagnostic {
+    match error_format {
+      ErrorFormat::Human => SpanDiagnostic::with_tty_emitter(
+        ColorConfig::Always, true, false, Some(codemap)),
+      ErrorFormat::Json => SpanDiagnostic::with_emitter(
+        true, false, Box::new(JsonEmitter::stderr(codemap)))
+    }
+  }
+
+  /// The diagnostics are given to `emitter` instead of being printed according to `config.error_format`.
+  pub fn with_emitter(config: Config, codemap: Rc<CodeMap>, emitter: Box<Emitter>) -> Self {
+    let span_diagnostic = SpanDiagnostic::with_emitter(
+      true, false, emitter);
     Session::init(config, codemap, span_diagnostic)
   }
 
   pub fn testing_mode(file_to_test: PathBuf, output_dir: PathBuf, libs: Vec<PathBuf>,
     codemap: Rc<CodeMap>, emitter: Box<Emitter>) -> Self
   {
-    let span_diagnostic = SpanDiagnostic::with_emitter(
-      true, false, emitter);
-    Session::init(Config::testing_mode(file_to_test, output_dir, libs),
-      codemap, span_diagnostic)
+    Session::with_emitter(Config::testing_mode(file_to_test, output_dir, libs),
+      codemap, emitter)
+  }
+
+  pub fn with_file_system(mut self, file_system: Rc<FileSystem>) -> Self {
+    self.file_system = file_system;
+    self
   }
 
   // `reset_diagnostic` is necessary when testing because `SpanDiagnostic` might encapsulate some references to a shared object.
   // By replacing it, we decrease the reference count.
   pub fn reset_diagnostic(mut self) -> Self {
-    self.span_diagnostic = SpanDiagnostic::with_tty_emitter(
-      ColorConfig::Always, true, false, Some(self.codemap.clone()));
+    self.span_diagnostic = Session::make_diagnostic(
+      self.config.error_format, self.codemap.clone());
     self
   }
 
@@ -87,6 +114,20 @@ impl Session
     self.compiler_tests.push(test);
   }
 
+  /// The label is a secondary span of the last compiler test.
+  pub fn push_label_test(&mut self, span: Span, label: LabelTest) {
+    if let Some(test) = self.compiler_tests.last_mut() {
+      test.labels.push(label);
+      return;
+    }
+    self.struct_span_err_with_code(span,
+      &format!("syntax error: the annotation `#[label({}, {})]` does not follow an `#[error(...)]` or `#[warning(...)]` annotation.",
+        label.line, label.column),
+      "E0035")
+    .span_label(span, &format!("expected a compiler test before this label"))
+    .emit();
+  }
+
   pub fn push_execution_test(&mut self, test: ExecutionTest) {
     self.execution_tests.push(test);
   }
@@ -95,15 +136,28 @@ impl Session
     &self.config
   }
 
-  pub fn load_file(&mut self, path: &Path) -> Rc<FileMap> {
-    self.codemap.load_file(path).unwrap()
+  pub fn load_file(&mut self, path: &Path) -> io::Result<Rc<FileMap>> {
+    let src = self.file_system.read_to_string(path)?;
+    Ok(self.codemap.new_filemap(format!("{}", path.display()), src))
   }
 
   fn record_error<S: Into<MultiSpan>>(&mut self, sp: S, code: &str) -> bool {
     let code = format!("{}",code);
-    // TODO: E0033: To remove when the causality analysis is more precise on the span.
+    let sp = sp.into();
+    let primary_span = sp.primary_span();
+    // E0033: The same access can be involved in causality errors of several instants, and we report one error per instant.
     // TODO: E0014: We should aggregate the errors on reference arguments into a single error.
-    code == "E0033" || code == "E0014" || self.errors.insert((sp.into(), code.clone()))
+    let recorded = code == "E0033" || code == "E0014" || self.errors.insert((sp, code.clone()));
+    if recorded {
+      self.error_spans.push(primary_span);
+    }
+    recorded
+  }
+
+  /// The spans of the errors reported with a code (see `struct_span_err_with_code`).
+  /// The errors reported without a code are only counted in `err_count`.
+  pub fn error_spans<'a>(&'a self) -> &'a Vec<Option<Span>> {
+    &self.error_spans
   }
 
   fn record_warning<S: Into<MultiSpan>>(&mut self, sp: S, code: &str) -> bool {
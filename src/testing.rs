@@ -0,0 +1,292 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// `bonsai test` checks the test annotations of a project, as the `tests/` harness of the compiler does on `data/test`.
+/// Each `.bonsai.java` file is compiled independently with the libraries and the other files of the project: the `#[error(...)]` and `#[warning(...)]` annotations must match the diagnostics.
+/// The processes of the `#[run(...)]` annotations are compiled with `javac` and executed in a single JVM once all the files are checked (see `driver/javac.rs`), or executed by the interpreter with `--interpreter`.
+/// The files without test annotations are ignored.
+
+use driver::*;
+use driver::javac::*;
+use driver::module_file::ModuleFile;
+use session::*;
+use partial::*;
+use context::Context;
+use ast::{CompilerTest, LabelTest, ExecutionTest};
+use diagnostic::Diagnostic;
+use interpreter;
+use fmt;
+use std::path::PathBuf;
+use std::collections::HashMap;
+use std::process::{self, Output};
+use std::rc::Rc;
+use std::env;
+use std::fs::create_dir_all;
+
+pub struct TestResult
+{
+  pub name: String,
+  /// The explanation of the failure.
+  pub result: Result<(), String>
+}
+
+impl TestResult
+{
+  fn new(name: String, result: Result<(), String>) -> Self {
+    let status = if result.is_ok() { "ok" } else { "FAILED" };
+    println!("test {} ... {}", name, status);
+    TestResult { name, result }
+  }
+}
+
+/// A process waiting to be executed in the JVM with the processes of the other files (see `run_batch`).
+struct BatchTest {
+  name: String,
+  java_file: String,
+  process: JavaProcess,
+  test: ExecutionTest
+}
+
+/// Tests the files of `config.test` and prints a summary.
+/// Returns `false` if a test failed.
+pub fn run(config: Config) -> bool {
+  let test = config.test.clone().expect("testing::run: the configuration of `bonsai test` is missing.");
+  match test_project(&test) {
+    Ok(results) => print_summary(&results),
+    Err(msg) => {
+      Session::new(config).err(&msg);
+      false
+    }
+  }
+}
+
+/// Tests the files of the project, the result of each test is printed when it is known.
+pub fn test_project(test: &TestConfig) -> Result<Vec<TestResult>, String> {
+  let fs: Rc<FileSystem> = Rc::new(DiskFileSystem);
+  let mut files = vec![];
+  fmt::collect_files(&*fs, &test.project, &mut files)?;
+  let javac = Javac::new(env::temp_dir().join(format!("bonsai-test-{}", process::id())), test.classpath.clone());
+  let uses_javac = test.backend == TestBackend::Javac;
+  if uses_javac {
+    javac.delete_source_files();
+    create_dir_all(javac.source_path())
+      .map_err(|e| format!("Could not create the directory `{}`: {}.", javac.source_path().display(), e))?;
+  }
+  let mut results = vec![];
+  let mut batch = vec![];
+  for file in files {
+    test_file(test, &javac, fs.clone(), file, &mut results, &mut batch);
+  }
+  if uses_javac {
+    run_batch(&javac, batch, &mut results);
+    javac.delete_source_files();
+  }
+  Ok(results)
+}
+
+fn test_file(test: &TestConfig, javac: &Javac, fs: Rc<FileSystem>, file: PathBuf,
+  results: &mut Vec<TestResult>, batch: &mut Vec<BatchTest>)
+{
+  let name = format!("{}", file.display());
+  let uses_javac = test.backend == TestBackend::Javac;
+  // Nothing is written in the output directory when the processes are interpreted.
+  let output = if uses_javac { javac.source_path() } else { env::temp_dir() };
+  let config = Config::testing_mode(file.clone(), output, project_libs(test));
+  let (compiled, _, diagnostics) = collect_diagnostics(config, fs.clone(), |session| {
+    let (session, context) = front_mid_run(session).decompose();
+    let tests = (session.compiler_tests.clone(), session.execution_tests.clone());
+    let env = Env::new(session, context);
+    let (session, generated) =
+      if uses_javac { env.and_next(generate_modules).decompose() }
+      else { env.map(|context| (context, vec![])).decompose() };
+    Env::value(session, (generated, tests))
+  });
+  let obtained: Vec<CompilerTest> = diagnostics.iter().map(compiler_test).collect();
+  let (generated, compiler_tests, execution_tests) = match compiled {
+    Partial::Value((generated, (compiler_tests, execution_tests))) => (generated, compiler_tests, execution_tests),
+    _ => {
+      results.push(TestResult::new(name, Err(diagnostics_failure(&[], &obtained))));
+      return;
+    }
+  };
+  let no_tests = compiler_tests.is_empty() && execution_tests.is_empty();
+  if no_tests && !uses_javac {
+    return;
+  }
+  if !no_tests {
+    let checked =
+      if CompilerTest::match_all(&compiler_tests, &obtained) { Ok(()) }
+      else { Err(diagnostics_failure(&compiler_tests, &obtained)) };
+    results.push(TestResult::new(name.clone(), checked));
+  }
+  let (context, modules) = match generated {
+    Partial::Value(generated) => generated,
+    _ => {
+      for test in execution_tests {
+        results.push(TestResult::new(run_name(&name, &test),
+          Err(String::from("The process is not executed because the file does not compile."))));
+      }
+      return;
+    }
+  };
+  match test.backend {
+    TestBackend::Interpreter => {
+      for test in execution_tests {
+        results.push(TestResult::new(run_name(&name, &test), interpret_test(&context, &test)));
+      }
+    }
+    TestBackend::Javac => batch_file(fs, file, &context, modules, execution_tests, results, batch)
+  }
+}
+
+/// The libraries of `test` and the project itself, so a file can use the modules of the other files of the project.
+/// The file under test is excluded from the project when it is loaded as a library (see `FileFilter`).
+fn project_libs(test: &TestConfig) -> Vec<PathBuf> {
+  let mut libs = test.libs.clone();
+  if test.project.is_dir() {
+    libs.push(test.project.clone());
+  }
+  libs
+}
+
+fn run_name(name: &str, test: &ExecutionTest) -> String {
+  format!("{}: {}.{}", name, test.process.class, test.process.method)
+}
+
+fn interpret_test(context: &Context, test: &ExecutionTest) -> Result<(), String> {
+  let output = interpreter::run_process(context, &test.process)
+    .map_err(|e| format!("{}\n(output before the error):\n{}", e.message, e.output));
+  check_output(test, output)
+}
+
+fn check_output(test: &ExecutionTest, output: Result<String, String>) -> Result<(), String> {
+  match output {
+    Ok(ref output) if test.output_regex.is_match(output) => Ok(()),
+    Ok(output) => Err(format!("The output does not match the regex `{}`:\n{}",
+      test.output_regex.as_str(), output)),
+    Err(e) => Err(e)
+  }
+}
+
+/// Writes the Java code of the file in the sandbox of `javac`, its processes are executed later with the whole batch in `run_batch`.
+/// The files without tests are written too since the other files of the project might use their modules.
+fn batch_file(fs: Rc<FileSystem>, file: PathBuf, context: &Context, modules: Vec<CompiledModule>,
+  tests: Vec<ExecutionTest>, results: &mut Vec<TestResult>, batch: &mut Vec<BatchTest>)
+{
+  let name = format!("{}", file.display());
+  let written = CompiledCrate { modules, warnings: vec![] }.write(&*fs)
+    .map_err(|e| format!("Could not write the generated Java code: {}.", e));
+  let mod_name = ModuleFile::extract_mod_name(file).expect("bonsai file name (batch_file)");
+  for test in tests {
+    let run_name = run_name(&name, &test);
+    let process = JavaProcess::new(context, &test);
+    match (&written, process) {
+      (&Err(ref msg), _) => results.push(TestResult::new(run_name, Err(msg.clone()))),
+      (_, None) => results.push(TestResult::new(run_name,
+        Err(format!("The class `{}` is not a module of the file.", test.process.class)))),
+      (_, Some(process)) => batch.push(BatchTest {
+        name: run_name, java_file: format!("{}.java", mod_name), process, test })
+    }
+  }
+}
+
+/// Compiles the generated code of the batch with `javac`, executes every process with the driver, and compares their outputs.
+/// The Java files with compilation errors are excluded from the batch, so only the tests of these files fail.
+fn run_batch(javac: &Javac, batch: Vec<BatchTest>, results: &mut Vec<TestResult>) {
+  if batch.is_empty() {
+    return;
+  }
+  let mut excluded = HashMap::new();
+  let compiled = javac.compile_excluding_errors(&mut excluded);
+  let (batch, failed): (Vec<BatchTest>, Vec<BatchTest>) = batch.into_iter()
+    .partition(|b| !excluded.contains_key(&b.java_file));
+  for b in failed {
+    results.push(TestResult::new(b.name, Err(javac_failure(&excluded[&b.java_file]))));
+  }
+  let executed = compiled.and_then(|unattributed| {
+    match unattributed {
+      Some(compile_output) => Ok(Err(javac_failure(&compile_output))),
+      None => {
+        let processes: Vec<JavaProcess> = batch.iter().map(|b| b.process.clone()).collect();
+        javac.execute_batch(&processes).map(Ok)
+      }
+    }
+  });
+  match executed {
+    Err(e) => {
+      for b in batch {
+        results.push(TestResult::new(b.name, Err(format!("Could not execute `javac` or `java`: {}.", e))));
+      }
+    }
+    Ok(Err(msg)) => {
+      for b in batch {
+        results.push(TestResult::new(b.name, Err(msg.clone())));
+      }
+    }
+    Ok(Ok(output)) => {
+      let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+      let outputs = Javac::split_output(&output.stdout, batch.len());
+      for (b, output) in batch.into_iter().zip(outputs.into_iter()) {
+        let output = output.unwrap_or_else(||
+          Err(format!("The JVM stopped before executing this process.\n{}", stderr)));
+        results.push(TestResult::new(b.name, check_output(&b.test, output)));
+      }
+    }
+  }
+}
+
+fn javac_failure(output: &Output) -> String {
+  format!("The generated Java code does not compile:\n{}", String::from_utf8_lossy(&output.stderr))
+}
+
+/// The diagnostic in the format of the test annotations, where the columns start at 0.
+fn compiler_test(diagnostic: &Diagnostic) -> CompilerTest {
+  let (line, column) = diagnostic.spans.iter()
+    .find(|span| span.is_primary)
+    .map(|span| (span.line_start, span.column_start - 1))
+    .unwrap_or((0, 0));
+  let code = diagnostic.code.clone().unwrap_or_else(|| String::from("NoCode"));
+  let mut test = CompilerTest::new(diagnostic.level.clone(), code, line, column)
+    .with_message(Some(diagnostic.message.clone()));
+  test.labels = diagnostic.spans.iter()
+    .filter(|span| !span.is_primary)
+    .map(|span| LabelTest::new(span.line_start, span.column_start - 1, span.label.clone()))
+    .collect();
+  test
+}
+
+fn diagnostics_failure(expected: &[CompilerTest], obtained: &[CompilerTest]) -> String {
+  let mut msg = String::from("The diagnostics do not match the test annotations.\n  Expected:\n");
+  for diagnostic in expected {
+    msg.push_str(&format!("    {}\n", diagnostic));
+  }
+  msg.push_str("  Obtained:\n");
+  for diagnostic in obtained {
+    msg.push_str(&format!("    {}\n", diagnostic));
+  }
+  msg
+}
+
+fn print_summary(results: &[TestResult]) -> bool {
+  let failures: Vec<&TestResult> = results.iter().filter(|r| r.result.is_err()).collect();
+  for failure in &failures {
+    if let Err(ref msg) = failure.result {
+      println!("\n---- {} ----\n{}", failure.name, msg);
+    }
+  }
+  let status = if failures.is_empty() { "ok" } else { "FAILED" };
+  println!("\ntest result: {}. {} passed; {} failed.", status,
+    results.len() - failures.len(), failures.len());
+  failures.is_empty()
+}
@@ -77,9 +77,8 @@ pub trait Visitor<H>
     walk_suspend(self, suspend)
   }
 
-  fn visit_abort(&mut self, condition: Expr, child: Stmt) {
-    self.visit_expr(condition);
-    self.visit_stmt(child)
+  fn visit_abort(&mut self, abort: AbortStmt) {
+    walk_abort(self, abort)
   }
 
   fn visit_tell(&mut self, var: Variable, expr: Expr) {
@@ -192,7 +191,7 @@ pub fn walk_stmt<H, V: ?Sized>(visitor: &mut V, stmt: Stmt) where
     Let(stmt) => visitor.visit_let(stmt),
     When(cond, then_branch, else_branch) => visitor.visit_when(cond, *then_branch, *else_branch),
     Suspend(suspend) => visitor.visit_suspend(suspend),
-    Abort(cond, body) => visitor.visit_abort(cond, *body),
+    Abort(abort) => visitor.visit_abort(abort),
     Tell(var, expr) => visitor.visit_tell(var, expr),
     DelayStmt(delay) => visitor.visit_delay(delay),
     Loop(body) => visitor.visit_loop(*body),
@@ -201,6 +200,7 @@ pub fn walk_stmt<H, V: ?Sized>(visitor: &mut V, stmt: Stmt) where
     QFUniverse(body) => visitor.visit_qf_universe(*body),
     Universe(queue, body) => visitor.visit_universe(queue, *body),
     Nothing => visitor.visit_nothing(),
+    SyntaxError => (),
   }
 }
 
@@ -272,6 +272,13 @@ pub fn walk_suspend<H, V: ?Sized>(visitor: &mut V, suspend: SuspendStmt) where
   visitor.visit_stmt(*suspend.body)
 }
 
+pub fn walk_abort<H, V: ?Sized>(visitor: &mut V, abort: AbortStmt) where
+  V: Visitor<H>
+{
+  visitor.visit_expr(abort.condition);
+  visitor.visit_stmt(*abort.body)
+}
+
 pub trait VisitorMut<H>
 {
   fn visit_crate(&mut self, bcrate: &mut Crate<H>) {
@@ -333,9 +340,8 @@ pub trait VisitorMut<H>
     walk_suspend_mut(self, suspend)
   }
 
-  fn visit_abort(&mut self, condition: &mut Expr, child: &mut Stmt) {
-    self.visit_expr(condition);
-    self.visit_stmt(child)
+  fn visit_abort(&mut self, abort: &mut AbortStmt) {
+    walk_abort_mut(self, abort)
   }
 
   fn visit_tell(&mut self, var: &mut Variable, expr: &mut Expr) {
@@ -448,7 +454,7 @@ pub fn walk_stmt_mut<H, V: ?Sized>(visitor: &mut V, stmt: &mut Stmt) where
     &mut Let(ref mut stmt) => visitor.visit_let(stmt),
     &mut When(ref mut cond, ref mut then_branch, ref mut else_branch) => visitor.visit_when(cond, &mut **then_branch, &mut **else_branch),
     &mut Suspend(ref mut suspend) => visitor.visit_suspend(suspend),
-    &mut Abort(ref mut cond, ref mut body) => visitor.visit_abort(cond, &mut **body),
+    &mut Abort(ref mut abort) => visitor.visit_abort(abort),
     &mut Tell(ref mut var, ref mut expr) => visitor.visit_tell(var, expr),
     &mut DelayStmt(ref mut delay) => visitor.visit_delay(delay),
     &mut Loop(ref mut body) => visitor.visit_loop(&mut **body),
@@ -457,6 +463,7 @@ pub fn walk_stmt_mut<H, V: ?Sized>(visitor: &mut V, stmt: &mut Stmt) where
     &mut QFUniverse(ref mut body) => visitor.visit_qf_universe(&mut **body),
     &mut Universe(ref mut queue, ref mut body) => visitor.visit_universe(queue, &mut **body),
     &mut Nothing => visitor.visit_nothing(),
+    &mut SyntaxError => (),
   }
 }
 
@@ -531,3 +538,10 @@ pub fn walk_suspend_mut<H, V: ?Sized>(visitor: &mut V, suspend: &mut SuspendStmt
   visitor.visit_expr(&mut suspend.condition);
   visitor.visit_stmt(&mut *suspend.body)
 }
+
+pub fn walk_abort_mut<H, V: ?Sized>(visitor: &mut V, abort: &mut AbortStmt) where
+  V: VisitorMut<H>
+{
+  visitor.visit_expr(&mut abort.condition);
+  visitor.visit_stmt(&mut *abort.body)
+}
@@ -15,17 +15,18 @@
 use test::*;
 
 use libbonsai::ast::*;
-use libbonsai::context::*;
+use libbonsai::context::Partial;
 
 use std::path::{PathBuf};
 
 use ExpectedResult::*;
 use ExpectedResult;
 
-pub struct CompileTest<'a>
+/// `T` is the result of the compilation, the parallel tests only keep its status (`Partial<()>`).
+pub struct CompileTest<'a, T>
 {
   display: &'a mut Display,
-  result: Partial<Context>,
+  result: Partial<T>,
   expect: ExpectedResult,
   expected_diagnostics: Vec<CompilerTest>,
   obtained_diagnostics: Vec<CompilerTest>,
@@ -33,10 +34,10 @@ pub struct CompileTest<'a>
   intermediate_step: bool, // do not print success message because the compilation is just a step of the test.
 }
 
-impl<'a> CompileTest<'a>
+impl<'a, T> CompileTest<'a, T>
 {
   pub fn new(display: &'a mut Display,
-    result: Partial<Context>,
+    result: Partial<T>,
     expect: ExpectedResult,
     expected_diagnostics: Vec<CompilerTest>,
     obtained_diagnostics: Vec<CompilerTest>,
@@ -50,7 +51,7 @@ impl<'a> CompileTest<'a>
   }
 
   /// Returns the context if the compilation succeeded as expected.
-  pub fn diagnostic(mut self) -> Option<Context> {
+  pub fn diagnostic(mut self) -> Option<T> {
     let file_name = self.file_name();
     if self.compilation_status(file_name.clone()) {
       self.compare_diagnostics(file_name)
@@ -60,7 +61,7 @@ impl<'a> CompileTest<'a>
     }
   }
 
-  pub fn context_to_option(self) -> Option<Context> {
+  pub fn context_to_option(self) -> Option<T> {
     match self.result {
       Partial::Value(x) => Some(x),
       _ => None
@@ -82,10 +83,10 @@ impl<'a> CompileTest<'a>
     }
   }
 
-  fn compare_diagnostics(mut self, file_name: String) -> Option<Context> {
+  fn compare_diagnostics(mut self, file_name: String) -> Option<T> {
     self.obtained_diagnostics.sort();
     self.expected_diagnostics.sort();
-    if &self.obtained_diagnostics != &self.expected_diagnostics {
+    if !CompilerTest::match_all(&self.expected_diagnostics, &self.obtained_diagnostics) {
       self.display.diagnostics_failure(self.test_path, file_name,
         &self.obtained_diagnostics,
         &self.expected_diagnostics,
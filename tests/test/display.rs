@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use libbonsai::ast::*;
+use test::report::TestRecord;
 
 use std::path::{PathBuf};
 use std::process::{Output};
@@ -25,7 +26,10 @@ pub struct Display
   terminal: Box<term::StdoutTerminal>,
   num_success: u32,
   num_failure: u32,
-  num_system_failure: u32
+  num_system_failure: u32,
+  records: Vec<TestRecord>,
+  /// The messages following a failure are recorded in its report.
+  capturing: bool
 }
 
 impl Display
@@ -35,7 +39,9 @@ impl Display
       terminal: term::stdout().unwrap(),
       num_success: 0,
       num_failure: 0,
-      num_system_failure: 0
+      num_system_failure: 0,
+      records: vec![],
+      capturing: false
     }
   }
 
@@ -46,11 +52,13 @@ impl Display
   }
 
   pub fn title(&mut self, msg: &str) {
+    self.capturing = false;
     self.write_header(color::CYAN, msg);
     self.write_msg("\n\n");
   }
 
   pub fn info(&mut self, msg: String) {
+    self.capturing = false;
     self.write_line(color::CYAN, "\n[ info ] ", msg);
   }
 
@@ -64,6 +72,7 @@ impl Display
   }
 
   pub fn stats(&mut self) {
+    self.capturing = false;
     let system_failure_plural = if self.num_system_failure > 1 { "s" } else { "" };
     let msg = format!("{} passed, {} failed, {} system failure{}.",
         self.num_success, self.num_failure, self.num_system_failure,
@@ -80,7 +89,8 @@ impl Display
   fn failure(&mut self, path: PathBuf, test_name: String)
   {
     self.num_failure += 1;
-    self.write_line(color::RED, "[ failed ] ", test_name);
+    self.write_line(color::RED, "[ failed ] ", test_name.clone());
+    self.start_capture(test_name);
     self.path(path);
   }
 
@@ -138,7 +148,9 @@ impl Display
 
   pub fn success(&mut self, test_name: String) {
     self.num_success += 1;
-    self.write_line(color::GREEN, "[ passed ] ", test_name);
+    self.capturing = false;
+    self.write_line(color::GREEN, "[ passed ] ", test_name.clone());
+    self.records.push(TestRecord::success(test_name));
   }
 
   pub fn warn(&mut self, msg: String) {
@@ -153,7 +165,19 @@ impl Display
 
   pub fn system_failure(&mut self, msg: String) {
     self.num_system_failure += 1;
-    self.write_line(color::RED, "[ system error ] ", msg);
+    self.write_line(color::RED, "[ system error ] ", msg.clone());
+    self.start_capture(msg);
+  }
+
+  fn start_capture(&mut self, test_name: String) {
+    self.records.push(TestRecord::failure(test_name));
+    self.capturing = true;
+  }
+
+  /// The results of the tests displayed since the last call.
+  pub fn take_records(&mut self) -> Vec<TestRecord> {
+    self.capturing = false;
+    self.records.drain(..).collect()
   }
 
   fn write_header(&mut self, color: color::Color, header: &str) {
@@ -164,14 +188,19 @@ impl Display
 
   fn write_msg(&mut self, msg: &str) {
     (write!(self.terminal, "{}", msg)).unwrap();
+    if self.capturing {
+      if let Some(&mut TestRecord { failure: Some(ref mut failure), .. }) = self.records.last_mut() {
+        failure.push_str(msg);
+      }
+    }
   }
 
-  fn write_maven_output(&mut self, color: color::Color, header: &str, output: Vec<u8>) {
+  fn write_tool_output(&mut self, tool: &str, color: color::Color, header: &str, output: Vec<u8>) {
     if output.len() == 0 {
-      self.write_line(color, header, format!("Maven did not write on this output stream."));
+      self.write_line(color, header, format!("{} did not write on this output stream.", tool));
     }
     else {
-      self.write_line(color, header, format!("Maven produced the following output:"));
+      self.write_line(color, header, format!("{} produced the following output:", tool));
       match String::from_utf8(output.clone()) {
         Result::Ok(output) => self.write_msg(&output),
         Result::Err(_) => self.write_msg(&format!("{:?}", output))
@@ -189,11 +218,41 @@ impl Display
 
   pub fn maven_failure(&mut self, phase: &str, path: PathBuf,
     test_name: String, process_name: String, output: Output)
+  {
+    self.tool_failure("Maven", phase, path, test_name, process_name, output);
+  }
+
+  pub fn tool_failure(&mut self, tool: &str, phase: &str, path: PathBuf,
+    test_name: String, process_name: String, output: Output)
   {
     self.run_failure(path, test_name, process_name);
-    self.error(format!("Maven {} should have succeeded but failed.", phase));
-    self.write_maven_output(color::CYAN, "  [ stdout ] ", output.stdout);
-    self.write_maven_output(color::CYAN, "  [ stderr ] ", output.stderr);
+    self.error(format!("{} {} should have succeeded but failed.", tool, phase));
+    self.write_tool_output(tool, color::CYAN, "  [ stdout ] ", output.stdout);
+    self.write_tool_output(tool, color::CYAN, "  [ stderr ] ", output.stderr);
+  }
+
+  pub fn codegen_failure(&mut self, path: PathBuf, test_name: String, expected_path: PathBuf, diff: String)
+  {
+    self.failure(path, test_name);
+    self.error(format!("The generated code differs from `{}` (run with BONSAI_BLESS=1 to update it).",
+      expected_path.display()));
+    self.write_msg(&diff);
+  }
+
+  pub fn codegen_missing(&mut self, path: PathBuf, test_name: String, expected_path: PathBuf)
+  {
+    self.failure(path, test_name);
+    self.error(format!("The expected code `{}` does not exist (run with BONSAI_BLESS=1 to create it).",
+      expected_path.display()));
+  }
+
+  pub fn codegen_compile_failure(&mut self, path: PathBuf, test_name: String, errors: Vec<String>)
+  {
+    self.failure(path, test_name);
+    self.error(format!("Compilation should have succeeded but failed."));
+    for error in errors {
+      self.write_msg(&format!("    {}\n", error));
+    }
   }
 
   pub fn execution_failure(&mut self, path: PathBuf, test_name: String,
@@ -16,13 +16,22 @@ use libbonsai::session::*;
 use libbonsai::driver::*;
 use libbonsai::context::*;
 use libbonsai::driver::module_file::ModuleFile;
+use libbonsai::driver::javac::*;
+use libbonsai::interpreter;
+use libbonsai::back;
 
 use syntex_syntax::codemap::{CodeMap};
 use std::rc::Rc;
 use std::cell::RefCell;
 
 use std::path::{PathBuf, Path};
-use std::fs::read_dir;
+use std::fs::{self, read_dir};
+use std::collections::HashMap;
+use std::process::Output;
+use std::env;
+use std::cmp::max;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
 
 use test::*;
 use test::ExpectedResult::*;
@@ -32,9 +41,123 @@ pub struct Engine
 {
   test_path: PathBuf,
   test_lib: PathBuf,
+  run_lib: PathBuf,
   display: Display,
   filter_debug: bool,
-  maven: Maven
+  maven: Maven,
+  javac: Javac,
+  runner: Runner,
+  batch: Vec<BatchTest>,
+  filter: TestFilter,
+  golden: Golden,
+  skip: Vec<String>
+}
+
+/// The run-pass tests are executed with Maven, or with the Rust interpreter if `BONSAI_RUNNER=interpreter`.
+/// With `BONSAI_RUNNER=javac`, the tests are compiled with a single `javac` invocation and executed in a single JVM.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Runner {
+  Maven,
+  Interpreter,
+  Javac
+}
+
+/// A process waiting to be executed in the batch of the `javac` runner.
+struct BatchTest {
+  file_path: PathBuf,
+  java_file: String,
+  process: JavaProcess,
+  test: ExecutionTest
+}
+
+impl Runner {
+  fn from_env() -> Self {
+    match env::var("BONSAI_RUNNER") {
+      Ok(ref runner) if runner == "interpreter" => Runner::Interpreter,
+      Ok(ref runner) if runner == "javac" => Runner::Javac,
+      _ => Runner::Maven
+    }
+  }
+
+  /// The suffix of the report of the run-pass tests, so the reports of the runners do not overwrite each other.
+  fn report_suffix(&self) -> &'static str {
+    match *self {
+      Runner::Maven => "",
+      Runner::Interpreter => "-interpreter",
+      Runner::Javac => "-javac"
+    }
+  }
+}
+
+/// The test directories of `data/test`, each one is run by a distinct `#[test]`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+  CompilePass,
+  CompileFail,
+  RunPass,
+  Codegen
+}
+
+impl Category {
+  pub fn directory(&self) -> &'static str {
+    match *self {
+      Category::CompilePass => "compile-pass",
+      Category::CompileFail => "compile-fail",
+      Category::RunPass => "run-pass",
+      Category::Codegen => "codegen"
+    }
+  }
+
+  fn title(&self) -> String {
+    match *self {
+      Category::CompilePass => String::from("Compile and Pass tests."),
+      Category::CompileFail => String::from("Compile and Fail tests"),
+      Category::RunPass => String::from("Compile and Run tests"),
+      Category::Codegen => String::from("Code generation tests")
+    }
+  }
+
+  fn expect(&self) -> ExpectedResult {
+    match *self {
+      Category::CompileFail => CompileFail,
+      _ => CompileSuccess
+    }
+  }
+}
+
+/// The result of a compilation in a worker thread (`Context` cannot be sent between threads).
+struct CompileOutcome {
+  status: Partial<()>,
+  expected: Vec<CompilerTest>,
+  obtained: Vec<CompilerTest>
+}
+
+/// The number of threads compiling the files in parallel, set with `BONSAI_TEST_THREADS` (4 by default).
+fn num_threads() -> usize {
+  env::var("BONSAI_TEST_THREADS").ok()
+    .and_then(|n| n.parse::<usize>().ok())
+    .map_or(4, |n| max(n, 1))
+}
+
+/// Compiles the file (front and middle phases) and returns the diagnostics emitted.
+fn compile_file(filepath: PathBuf, output: PathBuf, libs: Vec<PathBuf>) -> (Session, Partial<Context>, Vec<CompilerTest>) {
+  let obtained_diagnostics = Rc::new(RefCell::new(vec![]));
+  let codemap = Rc::new(CodeMap::new());
+  let emitter = Box::new(TestEmitter::new(obtained_diagnostics.clone(), codemap.clone()));
+  let session = Session::testing_mode(filepath, output, libs, codemap.clone(), emitter);
+  let (session, context) = front_mid_run(session).decompose();
+  let session = session.reset_diagnostic();
+  let obtained_diagnostics = Rc::try_unwrap(obtained_diagnostics)
+    .expect("Could not extract `obtained_diagnostics`.").into_inner();
+  (session, context, obtained_diagnostics)
+}
+
+fn compilation_status(context: &Partial<Context>) -> Partial<()> {
+  match *context {
+    Partial::Value(_) => Partial::Value(()),
+    Partial::Fake(_) => Partial::Fake(()),
+    Partial::Nothing => Partial::Nothing
+  }
 }
 
 impl Engine
@@ -45,40 +168,74 @@ impl Engine
       panic!(format!("`{}` is not a valid test directory.", test_path.display()));
     }
     let maven = Maven::new(test_path.clone(), filter_debug);
+    let javac = Javac::new(test_path.join("sandbox/batch/"), Javac::classpath_from_env());
+    let run_lib = test_path.join("run-lib");
     Engine{
-      test_path, test_lib, maven, filter_debug,
-      display: Display::new()
+      test_path, test_lib, run_lib, maven, javac, filter_debug,
+      runner: Runner::from_env(),
+      batch: vec![],
+      filter: TestFilter::from_env(),
+      golden: Golden::from_env(),
+      display: Display::new(),
+      skip: vec![]
     }
   }
 
-  pub fn run(&mut self)
+  /// Executes the run-pass tests with the interpreter whatever `BONSAI_RUNNER` is.
+  /// The files in `skip` (e.g. `SharedModuleT.bonsai.java`) use features not supported by the interpreter and are not executed.
+  pub fn with_interpreter(mut self, skip: &[&str]) -> Engine {
+    self.runner = Runner::Interpreter;
+    self.skip = skip.iter().map(|file| String::from(*file)).collect();
+    self
+  }
+
+  fn is_skipped(&self, file: &Path) -> bool {
+    file.file_name()
+      .and_then(|name| name.to_str())
+      .map_or(false, |name| self.skip.iter().any(|skipped| skipped == name))
+  }
+
+  pub fn run(&mut self, category: Category)
   {
-    let test_path = self.test_path.clone();
     self.display.title("    Bonsai compiler tests suite");
     if self.filter_debug {
       self.display.title("         (debug mode)");
     }
-    if !self.filter_debug {
-      self.test_directory(format!("Compile and Pass tests."),
-        test_path.join(Path::new("compile-pass")), CompileSuccess, false);
-      self.test_directory(format!("Compile and Fail tests"),
-        test_path.join(Path::new("compile-fail")), CompileFail, false);
+    if !self.filter_debug || category == Category::RunPass {
+      self.test_directory(category);
     }
-    self.test_directory(format!("Compile and Run tests"),
-      test_path.join(Path::new("run-pass")), CompileSuccess, true);
     self.display.stats();
+    self.write_report(category);
     self.display.panic_if_failure();
   }
 
-  fn test_directory(&mut self, start_msg: String, directory: PathBuf,
-    expect: ExpectedResult, execute: bool)
+  fn write_report(&mut self, category: Category) {
+    let records = self.display.take_records();
+    let suite =
+      if category == Category::RunPass { format!("{}{}", category.directory(), self.runner.report_suffix()) }
+      else { String::from(category.directory()) };
+    if let Some(report) = Report::from_env() {
+      match report.write(&suite, &records) {
+        Ok(path) => self.display.info(format!("Report written in `{}`.", path.display())),
+        Err(ref io_err) => self.display.io_error("Can't write the test report.",
+          self.test_path.clone(), format!("{}", io_err))
+      }
+    }
+  }
+
+  fn test_directory(&mut self, category: Category)
   {
-    self.display.info(start_msg);
+    self.display.info(category.title());
+    let directory = self.test_path.join(Path::new(category.directory()));
+    let mut files = vec![];
     match read_dir(&directory) {
       Ok(dir_entries) => {
         for entry in dir_entries.map(Result::unwrap).map(|entry| entry.path()) {
           if entry.is_file() {
-            self.compile_and_run(entry, expect, execute);
+            let relative_path = Path::new(category.directory()).join(entry.file_name().unwrap());
+            if self.filter.matches(&relative_path) {
+              files.push(entry);
+            }
           } else {
             self.display.warn(format!("Entry ignored because it's not a file."));
             self.display.path(entry);
@@ -89,22 +246,133 @@ impl Engine
         self.display.io_error("Can't read directory.", directory, format!("{}", io_err));
       }
     }
+    files.sort();
+    if category == Category::Codegen {
+      // The other files of this directory are the expected outputs.
+      for file in files.into_iter().filter(|f| f.to_string_lossy().ends_with(".bonsai.java")) {
+        self.codegen_file(file);
+      }
+    }
+    else if category == Category::RunPass {
+      if self.runner == Runner::Javac {
+        self.javac.delete_source_files();
+      }
+      for file in files {
+        if self.is_skipped(&file) {
+          self.display.warn(format!("Skipped, the interpreter does not support this file."));
+          self.display.path(file);
+          continue;
+        }
+        self.compile_and_run(file, category.expect(), true);
+      }
+      if self.runner == Runner::Javac {
+        self.run_batch();
+      }
+    }
+    else {
+      self.compile_in_parallel(files, category.expect());
+    }
+  }
+
+  fn source_path(&self) -> PathBuf {
+    match self.runner {
+      Runner::Javac => self.javac.source_path(),
+      _ => self.maven.source_path()
+    }
+  }
+
+  /// Each file is compiled in its own thread with its own `Session` and `CodeMap`, the results are displayed in the order of `files`.
+  fn compile_in_parallel(&mut self, files: Vec<PathBuf>, expect: ExpectedResult) {
+    let queue = Arc::new(Mutex::new(files.clone().into_iter().enumerate().rev().collect::<Vec<_>>()));
+    let (sender, receiver) = mpsc::channel();
+    let mut workers = vec![];
+    for _ in 0..num_threads() {
+      let queue = queue.clone();
+      let sender = sender.clone();
+      let output = self.source_path();
+      let test_lib = self.test_lib.clone();
+      workers.push(thread::spawn(move || {
+        loop {
+          let next = queue.lock().unwrap().pop();
+          match next {
+            Some((i, filepath)) => {
+              let (session, context, obtained) = compile_file(filepath, output.clone(), vec![test_lib.clone()]);
+              let outcome = CompileOutcome {
+                status: compilation_status(&context),
+                expected: session.compiler_tests.clone(),
+                obtained
+              };
+              sender.send((i, outcome)).unwrap();
+            }
+            None => break
+          }
+        }
+      }));
+    }
+    drop(sender);
+    let mut outcomes: Vec<Option<CompileOutcome>> = files.iter().map(|_| None).collect();
+    for (i, outcome) in receiver.iter() {
+      outcomes[i] = Some(outcome);
+    }
+    for worker in workers {
+      let _ = worker.join();
+    }
+    for (filepath, outcome) in files.into_iter().zip(outcomes.into_iter()) {
+      println!("{:?}", filepath);
+      match outcome {
+        Some(outcome) => {
+          let compile_test = CompileTest::new(&mut self.display, outcome.status, expect,
+            outcome.expected, outcome.obtained, filepath, false);
+          compile_test.diagnostic();
+        }
+        None => {
+          self.display.io_error("The compiler panicked.", filepath,
+            String::from("See the message of the panic above."));
+        }
+      }
+    }
+  }
+
+  /// Compiles the file to Java and compares the code of its module with the expected one.
+  fn codegen_file(&mut self, filepath: PathBuf) {
+    println!("{:?}", filepath);
+    let file_name = format!("{}", filepath.file_name().unwrap().to_str().unwrap());
+    let mod_name = ModuleFile::extract_mod_name(filepath.clone()).expect("bonsai file name (codegen_file)");
+    let output = filepath.parent().unwrap().to_path_buf();
+    let config = Config::testing_mode(filepath.clone(), output, vec![self.test_lib.clone()]);
+    let module = match compile(config) {
+      Ok(compiled) => compiled.modules.into_iter().find(|m| m.mod_name == mod_name),
+      Err(diagnostics) => {
+        let errors = diagnostics.into_iter()
+          .filter(|d| d.is_error())
+          .map(|d| format!("{}: {}", d.code.unwrap_or_else(|| String::from("error")), d.message))
+          .collect();
+        self.display.codegen_compile_failure(filepath, file_name, errors);
+        return;
+      }
+    };
+    let module = match module {
+      Some(module) => module,
+      None => {
+        self.display.io_error("No Java code generated for the module.", filepath, mod_name);
+        return;
+      }
+    };
+    let expected_path = Golden::expected_path(&filepath, &mod_name);
+    match self.golden.check(&expected_path, &module.source) {
+      Ok(GoldenResult::Same) => self.display.success(file_name),
+      Ok(GoldenResult::Blessed) => self.display.success(format!("{} (blessed)", file_name)),
+      Ok(GoldenResult::Missing) => self.display.codegen_missing(filepath, file_name, expected_path),
+      Ok(GoldenResult::Different(diff)) => self.display.codegen_failure(filepath, file_name, expected_path, diff),
+      Err(ref io_err) => self.display.io_error("Can't read or write the expected code.",
+        expected_path.clone(), format!("{}", io_err))
+    }
   }
 
   fn compile_and_run(&mut self, filepath: PathBuf, expect: ExpectedResult, execute: bool) {
     println!("{:?}", filepath);
-    let obtained_diagnostics = Rc::new(RefCell::new(vec![]));
-    let codemap = Rc::new(CodeMap::new());
-    let emitter = Box::new(TestEmitter::new(obtained_diagnostics.clone(), codemap.clone()));
-    let session = Session::testing_mode(
-      filepath.clone(),
-      self.maven.source_path(),
-      vec![self.test_lib.clone()],
-      codemap.clone(), emitter);
-    let (session, context) = front_mid_run(session).decompose();
-    let session = session.reset_diagnostic();
-    let obtained_diagnostics = Rc::try_unwrap(obtained_diagnostics)
-      .expect("Could not extract `obtained_diagnostics`.").into_inner();
+    let (session, context, obtained_diagnostics) =
+      compile_file(filepath.clone(), self.source_path(), vec![self.test_lib.clone(), self.run_lib.clone()]);
     let context = {
       let compile_test = CompileTest::new(&mut self.display, context, expect, session.compiler_tests.clone(),
         obtained_diagnostics, filepath.clone(), execute);
@@ -112,7 +380,11 @@ impl Engine
     };
     if let Some(context) = context {
       if execute {
-        self.run_file(session, context, filepath);
+        match self.runner {
+          Runner::Maven => self.run_file(session, context, filepath),
+          Runner::Interpreter => self.interpret_file(session, context, filepath),
+          Runner::Javac => self.batch_file(session, context, filepath)
+        }
       }
     }
   }
@@ -128,6 +400,7 @@ impl Engine
         self.maven.delete_source_files();
         session.config.configure_execution_test(&test);
         let env = run_back(session, context)
+          .and_next(|session, context| self.generate_run_lib(session, context))
           .ensure("[Test] Could not generate the Bonsai code.");
         let (s, c) = env.decompose();
         session = s;
@@ -142,4 +415,124 @@ impl Engine
       }
     }
   }
+
+  /// Same as `run_file` but the processes are executed by the interpreter, without generating Java code.
+  fn interpret_file(&mut self, session: Session, context: Context, filepath: PathBuf) {
+    for test in session.execution_tests.clone() {
+      if !self.filter_debug || test.filter_debug {
+        let result = interpreter::run_process(&context, &test.process)
+          .map_err(|e| format!("{}\n(output before the error):\n{}", e.message, e.output));
+        let output_test = OutputTest::new(&mut self.display, "interpreter", result,
+          test.output_regex, test.process.method, filepath.clone());
+        output_test.diagnostic();
+      }
+    }
+  }
+
+  /// Generates the Java code of the module, its processes are executed later with the whole batch in `run_batch`.
+  fn batch_file(&mut self, mut session: Session, context: Context, filepath: PathBuf) {
+    let filter_debug = self.filter_debug;
+    let tests: Vec<ExecutionTest> = session.execution_tests.clone().into_iter()
+      .filter(|test| !filter_debug || test.filter_debug)
+      .collect();
+    if tests.is_empty() {
+      return;
+    }
+    let processes: Vec<Option<JavaProcess>> = tests.iter().map(|test| JavaProcess::new(&context, test)).collect();
+    // The main method is not used by the driver, so the code is generated once for all the processes.
+    session.config.configure_execution_test(&tests[0]);
+    run_back(session, context)
+      .and_next(|session, context| self.generate_run_lib(session, context))
+      .ensure("[Test] Could not generate the Bonsai code.");
+    let mod_name = ModuleFile::extract_mod_name(filepath.clone()).expect("bonsai file name (batch_file)");
+    for (test, process) in tests.into_iter().zip(processes.into_iter()) {
+      match process {
+        Some(process) => self.batch.push(BatchTest {
+          file_path: filepath.clone(), java_file: format!("{}.java", mod_name), process, test }),
+        None => {
+          let result = Err(format!("The class `{}` is not a module of the file.", test.process.class));
+          let output_test = OutputTest::new(&mut self.display, "javac runner", result,
+            test.output_regex, test.process.method, filepath.clone());
+          output_test.diagnostic();
+        }
+      }
+    }
+  }
+
+  /// The modules of `data/test/run-lib` are libraries of the run-pass tests, so `run_back` does not compile them.
+  /// Their Java code is generated next to the one of the test.
+  fn generate_run_lib(&self, session: Session, context: Context) -> Env<Context> {
+    let output = self.source_path();
+    context.ast.modules.clone()
+      .into_iter()
+      .filter(|module| module.file.input_path().starts_with(&self.run_lib))
+      .fold(Env::value(session, context), |env, module| {
+        let java_file = output.join(format!("{}.java", module.mod_name()));
+        back::compile_module(env, module)
+          .and_next(move |session, (context, source)| {
+            if let Err(e) = fs::write(&java_file, source) {
+              session.err(&format!("Could not write `{}`: {}.", java_file.display(), e));
+              return Env::nothing(session);
+            }
+            Env::value(session, context)
+          })
+      })
+  }
+
+  /// Compiles the generated code of the batch with `javac`, executes every process with the driver, and compares their outputs.
+  /// The Java files with compilation errors are excluded and the batch is compiled again, so only the tests of these files fail.
+  fn run_batch(&mut self) {
+    let batch: Vec<BatchTest> = self.batch.drain(..).collect();
+    if batch.is_empty() {
+      return;
+    }
+    let mut excluded = HashMap::new();
+    let compiled = self.javac.compile_excluding_errors(&mut excluded);
+    let result = compiled.and_then(|unattributed| {
+      match unattributed {
+        Some(compile_output) => Ok(Err(compile_output)),
+        None => {
+          let processes: Vec<JavaProcess> = batch.iter()
+            .filter(|b| !excluded.contains_key(&b.java_file))
+            .map(|b| b.process.clone())
+            .collect();
+          self.javac.execute_batch(&processes).map(Ok)
+        }
+      }
+    });
+    self.javac.delete_source_files();
+    let (compiled, failed): (Vec<BatchTest>, Vec<BatchTest>) = batch.into_iter()
+      .partition(|b| !excluded.contains_key(&b.java_file));
+    for b in failed {
+      let compile_output = excluded[&b.java_file].clone();
+      self.javac_failure(b, compile_output);
+    }
+    match result {
+      Err(ref io_err) => {
+        self.display.io_error("Failure of the javac runner.", self.test_path.clone(), format!("{}", io_err));
+      }
+      Ok(Err(compile_output)) => {
+        for b in compiled {
+          self.javac_failure(b, compile_output.clone());
+        }
+      }
+      Ok(Ok(output)) => {
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let outputs = Javac::split_output(&output.stdout, compiled.len());
+        for (b, process_output) in compiled.into_iter().zip(outputs.into_iter()) {
+          let result = process_output.unwrap_or_else(||
+            Err(format!("The JVM stopped before executing this process.\n{}", stderr)));
+          let output_test = OutputTest::new(&mut self.display, "javac runner", result,
+            b.test.output_regex, b.test.process.method, b.file_path);
+          output_test.diagnostic();
+        }
+      }
+    }
+  }
+
+  fn javac_failure(&mut self, b: BatchTest, compile_output: Output) {
+    let file_name = format!("{}", b.file_path.file_name().unwrap().to_str().unwrap());
+    self.display.tool_failure("javac", "compilation", b.file_path, file_name,
+      b.test.process.method, compile_output);
+  }
 }
@@ -122,3 +122,48 @@ impl<'a> ExecuteTest<'a>
     format!("{}", self.file_path.file_name().unwrap().to_str().unwrap())
   }
 }
+
+/// Compares the output of a process executed without Maven (by the interpreter or the `javac` runner) with the expected regex.
+pub struct OutputTest<'a>
+{
+  display: &'a mut Display,
+  runner: &'static str,
+  result: Result<String, String>,
+  expect: Regex,
+  process_name: String,
+  file_path: PathBuf
+}
+
+impl<'a> OutputTest<'a>
+{
+  pub fn new(display: &'a mut Display,
+    runner: &'static str,
+    result: Result<String, String>,
+    expect: Regex,
+    process_name: String,
+    file_path: PathBuf) -> Self
+  {
+    OutputTest {
+      display, runner, result, expect, file_path, process_name
+    }
+  }
+
+  pub fn diagnostic(self) {
+    let file_name = format!("{}", self.file_path.file_name().unwrap().to_str().unwrap());
+    match self.result {
+      Result::Ok(output) => {
+        if self.expect.is_match(&output) {
+          self.display.run_success(file_name, self.process_name);
+        }
+        else {
+          self.display.execution_failure(self.file_path, file_name, self.process_name,
+            format!("{}", self.expect.as_str()), output);
+        }
+      }
+      Result::Err(error) => {
+        self.display.io_error(&format!("Failure of the {} in `{}.{}`.", self.runner, file_name, self.process_name),
+          self.file_path, error);
+      }
+    }
+  }
+}
@@ -0,0 +1,64 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+/// `TestFilter` selects the test files with the patterns given in `BONSAI_TEST_FILTER`, separated by commas.
+/// A pattern is matched against the file name (`DelayT*`) and against the path relative to the test directory (`run-pass/Delay*`).
+/// The wildcards are `*` (any sequence of characters) and `?` (any character).
+
+use std::env;
+use std::path::Path;
+
+pub struct TestFilter {
+  patterns: Vec<String>
+}
+
+impl TestFilter {
+  pub fn from_env() -> Self {
+    let patterns = env::var("BONSAI_TEST_FILTER")
+      .map(|filter| filter.split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect())
+      .unwrap_or_else(|_| vec![]);
+    TestFilter { patterns }
+  }
+
+  pub fn is_active(&self) -> bool {
+    !self.patterns.is_empty()
+  }
+
+  /// `relative_path` is the path of the file relative to the test directory.
+  pub fn matches(&self, relative_path: &Path) -> bool {
+    if !self.is_active() {
+      return true;
+    }
+    let path = relative_path.to_string_lossy().replace('\\', "/");
+    let file_name = relative_path.file_name()
+      .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    self.patterns.iter().any(|pattern|
+      glob_match(pattern.as_bytes(), file_name.as_bytes()) || glob_match(pattern.as_bytes(), path.as_bytes()))
+  }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+  match (pattern.first(), text.first()) {
+    (None, None) => true,
+    (Some(&b'*'), _) =>
+      glob_match(&pattern[1..], text) || (!text.is_empty() && glob_match(pattern, &text[1..])),
+    (Some(&b'?'), Some(_)) => glob_match(&pattern[1..], &text[1..]),
+    (Some(p), Some(t)) if p == t => glob_match(&pattern[1..], &text[1..]),
+    _ => false
+  }
+}
@@ -0,0 +1,149 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+/// `Golden` compares the Java code generated from `codegen/X.bonsai.java` with the expected file `codegen/X.java`.
+/// With `BONSAI_BLESS=1`, the expected files are overwritten with the generated code instead of being compared.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::cmp::{max, min};
+
+/// Number of unchanged lines printed around each difference.
+static CONTEXT_LINES: usize = 2;
+
+pub enum GoldenResult {
+  Same,
+  Blessed,
+  Missing,
+  Different(String)
+}
+
+pub struct Golden {
+  bless: bool
+}
+
+impl Golden {
+  pub fn from_env() -> Self {
+    let bless = env::var("BONSAI_BLESS").map(|b| b == "1").unwrap_or(false);
+    Golden { bless }
+  }
+
+  /// `codegen/X.bonsai.java` is expected to generate `codegen/X.java`.
+  pub fn expected_path(test_path: &Path, mod_name: &str) -> PathBuf {
+    test_path.with_file_name(format!("{}.java", mod_name))
+  }
+
+  pub fn check(&self, expected_path: &Path, obtained: &str) -> io::Result<GoldenResult> {
+    if self.bless {
+      fs::write(expected_path, obtained)?;
+      return Ok(GoldenResult::Blessed);
+    }
+    if !expected_path.is_file() {
+      return Ok(GoldenResult::Missing);
+    }
+    let expected = fs::read_to_string(expected_path)?;
+    if expected == obtained {
+      Ok(GoldenResult::Same)
+    }
+    else {
+      Ok(GoldenResult::Different(line_diff(&expected, obtained)))
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+  Keep(usize, usize),
+  Remove(usize),
+  Add(usize)
+}
+
+/// A line diff in the style of `diff -u`: removed lines start with `-`, added lines with `+`, and each hunk is introduced by the line numbers in both files.
+pub fn line_diff(expected: &str, obtained: &str) -> String {
+  let old: Vec<&str> = expected.lines().collect();
+  let new: Vec<&str> = obtained.lines().collect();
+  let edits = edit_script(&old, &new);
+  let changed: Vec<usize> = edits.iter().enumerate()
+    .filter(|&(_, e)| match *e { Edit::Keep(_, _) => false, _ => true })
+    .map(|(i, _)| i)
+    .collect();
+  let mut diff = String::new();
+  let mut i = 0;
+  while i < changed.len() {
+    // Group the changes separated by at most twice the context into a hunk.
+    let mut j = i;
+    while j + 1 < changed.len() && changed[j + 1] - changed[j] <= 2 * CONTEXT_LINES + 1 {
+      j += 1;
+    }
+    let start = changed[i].saturating_sub(CONTEXT_LINES);
+    let end = min(changed[j] + CONTEXT_LINES + 1, edits.len());
+    let (old_line, new_line) = first_lines(&edits[start..]);
+    diff.push_str(&format!("@@ -{} +{} @@\n", old_line + 1, new_line + 1));
+    for edit in &edits[start..end] {
+      match *edit {
+        Edit::Keep(o, _) => diff.push_str(&format!("  {}\n", old[o])),
+        Edit::Remove(o) => diff.push_str(&format!("- {}\n", old[o])),
+        Edit::Add(n) => diff.push_str(&format!("+ {}\n", new[n]))
+      }
+    }
+    i = j + 1;
+  }
+  if diff.is_empty() {
+    diff.push_str("The files only differ by their line endings.\n");
+  }
+  diff
+}
+
+/// The line numbers of the first edit of `edits` in the old and new files.
+fn first_lines(edits: &[Edit]) -> (usize, usize) {
+  let old = edits.iter().filter_map(|e| match *e {
+    Edit::Keep(o, _) | Edit::Remove(o) => Some(o), _ => None }).next();
+  let new = edits.iter().filter_map(|e| match *e {
+    Edit::Keep(_, n) | Edit::Add(n) => Some(n), _ => None }).next();
+  (old.unwrap_or(0), new.unwrap_or(0))
+}
+
+/// The edits transforming `old` into `new`, computed from their longest common subsequence.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+  let (n, m) = (old.len(), new.len());
+  let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+  for i in (0..n).rev() {
+    for j in (0..m).rev() {
+      lcs[i][j] =
+        if old[i] == new[j] { lcs[i + 1][j + 1] + 1 }
+        else { max(lcs[i + 1][j], lcs[i][j + 1]) };
+    }
+  }
+  let mut edits = vec![];
+  let (mut i, mut j) = (0, 0);
+  while i < n || j < m {
+    if i < n && j < m && old[i] == new[j] {
+      edits.push(Edit::Keep(i, j));
+      i += 1;
+      j += 1;
+    }
+    else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+      edits.push(Edit::Remove(i));
+      i += 1;
+    }
+    else {
+      edits.push(Edit::Add(j));
+      j += 1;
+    }
+  }
+  edits
+}
@@ -18,6 +18,9 @@ pub mod expected_result;
 pub mod test_emitter;
 pub mod engine;
 pub mod maven;
+pub mod filter;
+pub mod report;
+pub mod golden;
 pub mod execute_test;
 
 pub use self::display::*;
@@ -26,4 +29,7 @@ pub use self::expected_result::*;
 pub use self::test_emitter::*;
 pub use self::engine::*;
 pub use self::maven::*;
+pub use self::filter::*;
+pub use self::report::*;
+pub use self::golden::*;
 pub use self::execute_test::*;
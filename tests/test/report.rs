@@ -0,0 +1,124 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+/// Writes the results recorded by `Display` as a JUnit XML or a TAP report.
+/// The report is written in the directory given by `BONSAI_TEST_REPORT`, one file per test category (and per runner for the run-pass tests, e.g. `run-pass-interpreter.xml`); the format is given by `BONSAI_TEST_REPORT_FORMAT` (`junit` by default, or `tap`).
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug)]
+pub struct TestRecord {
+  pub name: String,
+  /// The messages printed after the failure of the test.
+  pub failure: Option<String>
+}
+
+impl TestRecord {
+  pub fn success(name: String) -> Self {
+    TestRecord { name, failure: None }
+  }
+
+  pub fn failure(name: String) -> Self {
+    TestRecord { name, failure: Some(String::new()) }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+  JUnit,
+  TAP
+}
+
+pub struct Report {
+  directory: PathBuf,
+  format: ReportFormat
+}
+
+impl Report {
+  pub fn from_env() -> Option<Self> {
+    let directory = env::var("BONSAI_TEST_REPORT").ok()?;
+    let format = match env::var("BONSAI_TEST_REPORT_FORMAT") {
+      Ok(ref format) if format == "tap" => ReportFormat::TAP,
+      _ => ReportFormat::JUnit
+    };
+    Some(Report { directory: PathBuf::from(directory), format })
+  }
+
+  /// Writes the report of the test suite `suite` and returns the path of the file.
+  pub fn write(&self, suite: &str, records: &[TestRecord]) -> io::Result<PathBuf> {
+    fs::create_dir_all(&self.directory)?;
+    let (extension, content) = match self.format {
+      ReportFormat::JUnit => ("xml", junit(suite, records)),
+      ReportFormat::TAP => ("tap", tap(suite, records))
+    };
+    let path = self.directory.join(format!("{}.{}", suite, extension));
+    let mut file = File::create(&path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(path)
+  }
+}
+
+fn xml_escape(text: &str) -> String {
+  text.chars()
+    .filter(|&c| c == '\n' || c == '\t' || c >= ' ')
+    .map(|c| match c {
+      '&' => String::from("&amp;"),
+      '<' => String::from("&lt;"),
+      '>' => String::from("&gt;"),
+      '"' => String::from("&quot;"),
+      c => c.to_string()
+    })
+    .collect()
+}
+
+fn junit(suite: &str, records: &[TestRecord]) -> String {
+  let failures = records.iter().filter(|r| r.failure.is_some()).count();
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+  xml.push_str(&format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+    xml_escape(suite), records.len(), failures));
+  for record in records {
+    let header = format!("    <testcase classname=\"{}\" name=\"{}\"",
+      xml_escape(suite), xml_escape(&record.name));
+    match record.failure {
+      None => xml.push_str(&format!("{}/>\n", header)),
+      Some(ref msg) => {
+        xml.push_str(&format!("{}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+          header, xml_escape(msg.trim().lines().next().unwrap_or("")), xml_escape(msg)));
+      }
+    }
+  }
+  xml.push_str("  </testsuite>\n</testsuites>\n");
+  xml
+}
+
+fn tap(suite: &str, records: &[TestRecord]) -> String {
+  let mut tap = format!("TAP version 13\n1..{}\n", records.len());
+  for (i, record) in records.iter().enumerate() {
+    match record.failure {
+      None => tap.push_str(&format!("ok {} - {}/{}\n", i + 1, suite, record.name)),
+      Some(ref msg) => {
+        tap.push_str(&format!("not ok {} - {}/{}\n  ---\n  message: |\n", i + 1, suite, record.name));
+        for line in msg.lines() {
+          tap.push_str(&format!("    {}\n", line));
+        }
+        tap.push_str("  ...\n");
+      }
+    }
+  }
+  tap
+}
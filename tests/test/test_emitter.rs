@@ -45,12 +45,18 @@ impl Emitter for TestEmitter
     let primary_span: Span = db.span.primary_span()
       .expect("Diagnostic lacks a primary span.");
     let loc = self.codemap.lookup_char_pos(primary_span.lo);
-    let diagnostic = CompilerTest::new(
+    let mut diagnostic = CompilerTest::new(
       format!("{}", db.level),
       db.code.clone().unwrap_or(format!("NoCode")),
       loc.line,
       loc.col.to_usize()
-    );
+    ).with_message(Some(db.message.clone()));
+    for label in db.span.span_labels() {
+      if !label.is_primary {
+        let loc = self.codemap.lookup_char_pos(label.span.lo);
+        diagnostic.labels.push(LabelTest::new(loc.line, loc.col.to_usize(), label.label.clone()));
+      }
+    }
     self.obtained_diagnostics
       .try_borrow_mut()
       .expect("Could not mutably borrow `obtained_diagnostics`")
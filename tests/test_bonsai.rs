@@ -28,7 +28,13 @@ use test::*;
 
 use std::path::{PathBuf, Path};
 
-fn test_data_dir(filter: bool) {
+/// The run-pass files that the interpreter cannot execute.
+static INTERPRETER_SKIP: [&'static str; 1] = [
+  // `init()` is a Java method of the module.
+  "SingleTimeDeclT.bonsai.java"
+];
+
+fn test_engine(filter: bool) -> Engine {
   let data_path = Path::new("data/");
   if !data_path.is_dir() {
     panic!(format!("`{}` is not a valid data directory.", data_path.display()));
@@ -40,17 +46,47 @@ fn test_data_dir(filter: bool) {
   if !test_lib.is_dir() {
     panic!(format!("`{}` must be a directory (the bonsai library used in the test files).", test_lib.display()));
   }
-  let mut engine = Engine::new(test_path, test_lib, filter);
-  engine.run();
+  Engine::new(test_path, test_lib, filter)
+}
+
+fn test_data_dir(filter: bool, category: Category) {
+  let mut engine = test_engine(filter);
+  engine.run(category);
+}
+
+#[test]
+fn compile_pass()
+{
+  test_data_dir(false, Category::CompilePass);
+}
+
+#[test]
+fn compile_fail()
+{
+  test_data_dir(false, Category::CompileFail);
+}
+
+#[test]
+fn run_pass()
+{
+  test_data_dir(false, Category::RunPass);
+}
+
+/// The run-pass tests executed by the interpreter, it does not need Maven nor a JVM.
+#[test]
+fn run_pass_interpreter()
+{
+  let mut engine = test_engine(false).with_interpreter(&INTERPRETER_SKIP);
+  engine.run(Category::RunPass);
 }
 
 #[test]
-fn test_data_directory()
+fn codegen()
 {
-  test_data_dir(false);
+  test_data_dir(false, Category::Codegen);
 }
 
 // #[test]
 // fn debug_run() {
-//   test_data_dir(true);
+//   test_data_dir(true, Category::RunPass);
 // }
@@ -0,0 +1,71 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `libbonsai::compile` reports the errors as diagnostics instead of exiting the process.
+
+extern crate libbonsai;
+
+use libbonsai::{compile, ConfigBuilder, Diagnostic};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A fresh directory in the temporary directory of the system.
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("bonsai-{}-{}", name, process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn error_codes(diagnostics: &Vec<Diagnostic>) -> Vec<String> {
+  let mut codes: Vec<String> = diagnostics.iter()
+    .filter(|d| d.is_error())
+    .filter_map(|d| d.code.clone())
+    .collect();
+  codes.sort();
+  codes
+}
+
+#[test]
+fn missing_input_directory() {
+  let input = env::temp_dir().join(format!("bonsai-missing-{}", process::id()));
+  let config = ConfigBuilder::new(input).build();
+  match compile(config) {
+    Ok(_) => panic!("A missing input directory must be reported."),
+    Err(diagnostics) => {
+      assert_eq!(diagnostics.len(), 1);
+      assert!(diagnostics[0].is_error());
+      assert!(diagnostics[0].message.contains("is not a directory"),
+        "unexpected message: {}", diagnostics[0].message);
+    }
+  }
+}
+
+#[test]
+fn compile_fail_file() {
+  let input = temp_dir("compile-fail");
+  let file = Path::new("data/test/compile-fail/E0006_E0026.bonsai.java");
+  fs::copy(file, input.join(file.file_name().unwrap())).unwrap();
+  let config = ConfigBuilder::new(input.clone())
+    .output(input.join("out"))
+    .build();
+  let result = compile(config);
+  fs::remove_dir_all(&input).unwrap();
+  match result {
+    Ok(_) => panic!("`{}` must not compile.", file.display()),
+    Err(diagnostics) => assert_eq!(error_codes(&diagnostics), vec!["E0006", "E0026"])
+  }
+}
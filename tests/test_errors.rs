@@ -0,0 +1,82 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+extern crate libbonsai;
+extern crate regex;
+
+use libbonsai::errors;
+use regex::Regex;
+use std::env;
+use std::fs::{read_dir, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn rust_files(directory: &Path, files: &mut Vec<PathBuf>) {
+  for entry in read_dir(directory).expect("source directory") {
+    let path = entry.unwrap().path();
+    if path.is_dir() {
+      rust_files(&path, files);
+    }
+    else if path.extension().map_or(false, |ext| ext == "rs") {
+      files.push(path);
+    }
+  }
+}
+
+/// The binary `bonsai` is built by Cargo next to the directory of the test executables.
+fn bonsai_bin() -> PathBuf {
+  let mut path = env::current_exe().unwrap();
+  path.pop();
+  if path.ends_with("deps") {
+    path.pop();
+  }
+  path.join(format!("bonsai{}", env::consts::EXE_SUFFIX))
+}
+
+#[test]
+fn every_emitted_code_is_explained() {
+  let mut files = vec![];
+  rust_files(Path::new("src"), &mut files);
+  let code = Regex::new(r#""(E\d{4})""#).unwrap();
+  for file in files.into_iter().filter(|f| !f.ends_with("errors.rs")) {
+    let mut source = String::new();
+    File::open(&file).and_then(|mut f| f.read_to_string(&mut source)).unwrap();
+    for cap in code.captures_iter(&source) {
+      assert!(errors::explain(&cap[1]).is_some(),
+        "The code {} used in `{}` has no explanation in `src/errors.rs`.", &cap[1], file.display());
+    }
+  }
+}
+
+#[test]
+fn explain_known_code() {
+  let output = Command::new(bonsai_bin()).arg("--explain=E0035").output()
+    .expect("the binary `bonsai`");
+  assert!(output.status.success());
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert_eq!(Some(stdout.as_ref()), errors::explain("E0035"));
+  assert!(stdout.starts_with("Syntax error"));
+}
+
+#[test]
+fn explain_unknown_code() {
+  assert_eq!(errors::explain("E9999"), None);
+  let output = Command::new(bonsai_bin()).arg("--explain=E9999").output()
+    .expect("the binary `bonsai`");
+  assert!(!output.status.success());
+  assert!(output.stdout.is_empty());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("`E9999` is not a bonsai error code"), "unexpected error message: {}", stderr);
+}
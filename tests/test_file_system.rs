@@ -0,0 +1,144 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The virtual file system used to compile unsaved buffers, alone or on top of the disk.
+
+extern crate libbonsai;
+
+use libbonsai::{compile_with_file_system, ConfigBuilder, FileSystem, VirtualFileSystem};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+/// A fresh directory in the temporary directory of the system.
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("bonsai-{}-{}", name, process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn sorted(mut entries: Vec<PathBuf>) -> Vec<PathBuf> {
+  entries.sort();
+  entries
+}
+
+#[test]
+fn implied_directories() {
+  let mut vfs = VirtualFileSystem::new();
+  vfs.add_file("/project/test/A.bonsai.java", String::from("a"));
+  for dir in &["/project", "/project/test"] {
+    assert!(vfs.is_dir(Path::new(dir)), "`{}` is a directory.", dir);
+    assert!(vfs.exists(Path::new(dir)), "`{}` exists.", dir);
+  }
+  assert!(!vfs.is_dir(Path::new("/project/test/A.bonsai.java")));
+  assert!(vfs.exists(Path::new("/project/test/A.bonsai.java")));
+  assert!(!vfs.is_dir(Path::new("/project/lib")));
+  assert!(!vfs.exists(Path::new("/project/lib")));
+}
+
+#[test]
+fn read_virtual_dir() {
+  let mut vfs = VirtualFileSystem::new();
+  vfs.add_file("/project/test/A.bonsai.java", String::from("a"));
+  vfs.add_file("/project/test/B.bonsai.java", String::from("b"));
+  vfs.add_file("/project/README", String::from("readme"));
+  assert_eq!(sorted(vfs.read_dir(Path::new("/project")).unwrap()),
+    vec![PathBuf::from("/project/README"), PathBuf::from("/project/test")]);
+  assert_eq!(sorted(vfs.read_dir(Path::new("/project/test")).unwrap()),
+    vec![PathBuf::from("/project/test/A.bonsai.java"), PathBuf::from("/project/test/B.bonsai.java")]);
+  assert!(vfs.read_dir(Path::new("/project/lib")).is_err());
+}
+
+#[test]
+fn read_dir_merges_disk_and_virtual_entries() {
+  let dir = temp_dir("vfs-read-dir");
+  fs::write(dir.join("Disk.bonsai.java"), "disk").unwrap();
+  let mut vfs = VirtualFileSystem::overlay();
+  vfs.add_file(dir.join("Disk.bonsai.java"), String::from("buffer"));
+  vfs.add_file(dir.join("Virtual.bonsai.java"), String::from("virtual"));
+  vfs.add_file(dir.join("sub/Nested.bonsai.java"), String::from("nested"));
+  let entries = sorted(vfs.read_dir(&dir).unwrap());
+  assert!(vfs.is_dir(&dir.join("sub")));
+  fs::remove_dir_all(&dir).unwrap();
+  assert_eq!(entries, vec![dir.join("Disk.bonsai.java"), dir.join("Virtual.bonsai.java"), dir.join("sub")]);
+}
+
+#[test]
+fn overlay_shadows_disk() {
+  let dir = temp_dir("vfs-overlay");
+  let shadowed = dir.join("Shadowed.bonsai.java");
+  let on_disk = dir.join("OnDisk.bonsai.java");
+  fs::write(&shadowed, "disk").unwrap();
+  fs::write(&on_disk, "disk").unwrap();
+  let mut vfs = VirtualFileSystem::overlay();
+  vfs.add_file(shadowed.clone(), String::from("buffer"));
+  let read_shadowed = vfs.read_to_string(&shadowed).unwrap();
+  let read_on_disk = vfs.read_to_string(&on_disk).unwrap();
+  vfs.remove_file(&shadowed);
+  let read_removed = vfs.read_to_string(&shadowed).unwrap();
+  fs::remove_dir_all(&dir).unwrap();
+  assert_eq!(read_shadowed, "buffer");
+  assert_eq!(read_on_disk, "disk");
+  assert_eq!(read_removed, "disk");
+}
+
+#[test]
+fn generated_files_are_collected() {
+  let mut vfs = VirtualFileSystem::new();
+  vfs.add_file("/project/A.bonsai.java", String::from("source"));
+  vfs.write(Path::new("/project/out/A.java"), "generated").unwrap();
+  vfs.write(Path::new("/project/A.bonsai.java"), "overwritten").unwrap();
+  let generated = vfs.generated();
+  assert_eq!(generated.len(), 2);
+  assert_eq!(generated[Path::new("/project/out/A.java")], "generated");
+  // The generated files are not visible to the compiler.
+  assert_eq!(vfs.read_to_string(Path::new("/project/A.bonsai.java")).unwrap(), "source");
+  assert!(vfs.read_to_string(Path::new("/project/out/A.java")).is_err());
+  assert!(!vfs.exists(Path::new("/project/out")));
+}
+
+#[test]
+fn compile_in_memory_project() {
+  let mut vfs = VirtualFileSystem::new();
+  vfs.add_file("/project/test/Hello.bonsai.java", String::from(
+    "package test;\n\
+     \n\
+     import java.lang.System;\n\
+     \n\
+     public class Hello\n\
+     {\n\
+     \x20 public proc hello() = System.out.println(\"hello\")\n\
+     }\n"));
+  let vfs = Rc::new(vfs);
+  let config = ConfigBuilder::new("/project")
+    .output("/project/out")
+    .build();
+  let compiled = match compile_with_file_system(config, vfs.clone()) {
+    Ok(compiled) => compiled,
+    Err(diagnostics) => panic!("The project does not compile: {:?}", diagnostics)
+  };
+  assert_eq!(compiled.modules.len(), 1);
+  let module = &compiled.modules[0];
+  assert_eq!(module.mod_name, "Hello");
+  assert_eq!(module.output_path, PathBuf::from("/project/out/test/Hello.java"));
+  assert!(module.source.contains("class Hello"));
+  assert!(vfs.generated().is_empty(), "`compile` does not write the generated files.");
+  compiled.write(&*vfs).unwrap();
+  assert_eq!(vfs.generated().keys().cloned().collect::<Vec<_>>(),
+    vec![PathBuf::from("/project/out/test/Hello.java")]);
+  assert!(!Path::new("/project").exists(), "Nothing is written on the disk.");
+}
@@ -0,0 +1,107 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+extern crate libbonsai;
+extern crate syntex_syntax;
+extern crate syntex_errors;
+extern crate syntex_pos;
+extern crate serde_json;
+
+use libbonsai::json_emitter::JsonEmitter;
+use syntex_syntax::codemap::{CodeMap, mk_sp};
+use syntex_errors::Handler;
+use syntex_pos::BytePos;
+use serde_json::Value;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+/// A destination of the emitter that can be read after the emission.
+#[derive(Clone)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.borrow_mut().write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Emits an error at `x` (line 3, columns 3 to 4) with a label and a help message, and returns the lines printed.
+fn emit_error(message: &str) -> Vec<String> {
+  let codemap = Rc::new(CodeMap::new());
+  let filemap = codemap.new_filemap(String::from("A.bonsai.java"),
+    String::from("package test;\nclass A {\n  x = 1;\n}\n"));
+  let buffer = SharedBuffer(Rc::new(RefCell::new(vec![])));
+  let emitter = JsonEmitter::new(Box::new(buffer.clone()), codemap.clone());
+  let handler = Handler::with_emitter(true, false, Box::new(emitter));
+  let x = filemap.start_pos + BytePos(26);
+  handler.struct_span_err_with_code(mk_sp(x, x + BytePos(1)), message, "E0035")
+    .span_label(mk_sp(x, x + BytePos(1)), &"the label")
+    .help("the help")
+    .emit();
+  let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+  output.lines().map(String::from).collect()
+}
+
+fn parse_line(line: &str) -> Value {
+  serde_json::from_str(line).expect("an emitted line must be a JSON object")
+}
+
+#[test]
+fn one_object_per_line() {
+  let lines = emit_error("unexpected `x`.");
+  assert_eq!(lines.len(), 1);
+  let json = parse_line(&lines[0]);
+  assert_eq!(json["level"], "error");
+  assert_eq!(json["code"], "E0035");
+  assert_eq!(json["message"], "unexpected `x`.");
+}
+
+#[test]
+fn escaped_message() {
+  let message = "a \"quoted\" \\ message\non two lines\twith \u{1} control";
+  let lines = emit_error(message);
+  assert_eq!(lines.len(), 1, "the newlines of the message must be escaped");
+  assert_eq!(parse_line(&lines[0])["message"], message);
+}
+
+#[test]
+fn columns_start_at_one() {
+  let json = parse_line(&emit_error("error")[0]);
+  let span = &json["spans"][0];
+  assert_eq!(span["file"], "A.bonsai.java");
+  assert_eq!(span["line_start"], 3);
+  assert_eq!(span["column_start"], 3);
+  assert_eq!(span["line_end"], 3);
+  assert_eq!(span["column_end"], 4);
+}
+
+#[test]
+fn labels_and_children() {
+  let json = parse_line(&emit_error("error")[0]);
+  let spans = json["spans"].as_array().unwrap();
+  assert_eq!(spans.len(), 1);
+  assert_eq!(spans[0]["is_primary"], true);
+  assert_eq!(spans[0]["label"], "the label");
+  let children = json["children"].as_array().unwrap();
+  assert_eq!(children.len(), 1);
+  assert_eq!(children[0]["level"], "help");
+  assert_eq!(children[0]["message"], "the help");
+  assert_eq!(children[0]["spans"], Value::Array(vec![]));
+  assert!(children[0].get("code").is_none());
+}
@@ -0,0 +1,107 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The recovery alternatives of the grammar are always tried last, and each one creates a node `SyntaxError`.
+//! Therefore, if the AST of a program does not contain such a node, the parser took the same alternatives as a grammar without recovery.
+//! We check that it is the case for every valid program of `data/test`.
+
+extern crate libbonsai;
+extern crate oak_runtime;
+extern crate syntex_syntax;
+
+use libbonsai::front::grammar::bonsai;
+use libbonsai::context::*;
+use oak_runtime::*;
+use oak_runtime::file_map_stream::*;
+use syntex_syntax::codemap::CodeMap;
+use std::fs::{read_dir, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Count the statements `SyntaxError` in a process.
+struct SyntaxErrors {
+  count: usize
+}
+
+impl Visitor<JClass> for SyntaxErrors
+{
+  fn visit_stmt(&mut self, child: Stmt) {
+    match child.node {
+      StmtKind::SyntaxError => self.count += 1,
+      _ => walk_stmt(self, child)
+    }
+  }
+}
+
+fn parse(name: String, source: String) -> Program {
+  let codemap = CodeMap::new();
+  let filemap = codemap.new_filemap(name.clone(), source);
+  match bonsai::parse_program(filemap.into_state()).into_result() {
+    ParseResult::Success(program) => program,
+    _ => panic!("`{}` is not parsed.", name)
+  }
+}
+
+fn syntax_errors(program: Program) -> usize {
+  let mut errors = SyntaxErrors { count: 0 };
+  for item in program.items {
+    match item {
+      Item::SyntaxError(_) => errors.count += 1,
+      Item::Proc(process) => errors.visit_stmt(process.body),
+      _ => ()
+    }
+  }
+  errors.count
+}
+
+fn bonsai_files(directory: &Path) -> Vec<PathBuf> {
+  let mut files: Vec<PathBuf> = read_dir(directory)
+    .expect("test directory")
+    .map(|entry| entry.unwrap().path())
+    .filter(|path| path.to_string_lossy().ends_with(".bonsai.java"))
+    .collect();
+  files.sort();
+  files
+}
+
+#[test]
+fn valid_programs_do_not_use_recovery() {
+  let directories = ["compile-pass", "run-pass", "codegen", "lib", "run-lib"];
+  for directory in directories.iter() {
+    for file in bonsai_files(&Path::new("data/test").join(directory)) {
+      let mut source = String::new();
+      File::open(&file).and_then(|mut f| f.read_to_string(&mut source))
+        .expect("readable test file");
+      let program = parse(format!("{}", file.display()), source);
+      assert_eq!(syntax_errors(program), 0, "`{}` is parsed with recovery.", file.display());
+    }
+  }
+}
+
+#[test]
+fn universe_with_queue_is_not_recovered() {
+  let source = String::from("package test;\n\
+    public class UniverseQueue {\n\
+      proc p() = universe with q in pause end\n\
+    }\n");
+  let program = parse(String::from("UniverseQueue"), source);
+  let body = program.items.into_iter()
+    .filter_map(|item| match item { Item::Proc(process) => Some(process.body), _ => None })
+    .next()
+    .expect("the process `p`");
+  match body.node {
+    StmtKind::Universe(_, _) => (),
+    _ => panic!("`universe with q in` must be parsed as a universe with a queue.")
+  }
+}
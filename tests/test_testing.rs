@@ -0,0 +1,62 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `bonsai test` on the project `data/test/testing`, where the process of `Failing.bonsai.java` does not print the expected output, and `Greeting.bonsai.java` uses the module of `Greeter.bonsai.java`.
+
+extern crate libbonsai;
+
+use libbonsai::driver::{TestConfig, TestBackend};
+use libbonsai::driver::javac::Javac;
+use libbonsai::testing::{test_project, TestResult};
+use std::path::PathBuf;
+
+fn test_fixture(backend: TestBackend) -> Vec<TestResult> {
+  let test = TestConfig {
+    project: PathBuf::from("data/test/testing"),
+    libs: vec![],
+    backend,
+    classpath: Javac::classpath_from_env()
+  };
+  let mut results = test_project(&test).expect("The fixture project could not be tested.");
+  results.sort_by(|a, b| a.name.cmp(&b.name));
+  results
+}
+
+fn check_results(results: Vec<TestResult>) {
+  let status: Vec<(String, bool)> = results.iter()
+    .map(|r| (r.name.replace("\\", "/"), r.result.is_ok()))
+    .collect();
+  assert_eq!(status, vec![
+    (String::from("data/test/testing/Failing.bonsai.java"), true),
+    (String::from("data/test/testing/Failing.bonsai.java: Failing.printOnce"), false),
+    (String::from("data/test/testing/Greeting.bonsai.java"), true),
+    (String::from("data/test/testing/Greeting.bonsai.java: Greeting.greet"), true),
+    (String::from("data/test/testing/Passing.bonsai.java"), true),
+    (String::from("data/test/testing/Passing.bonsai.java: Passing.printTwice"), true)]);
+  match results[1].result {
+    Err(ref msg) => assert!(msg.contains("does not match the regex"), "unexpected failure: {}", msg),
+    Ok(()) => unreachable!()
+  }
+}
+
+/// The default backend, the runtime must be compiled (see `driver/javac.rs`).
+#[test]
+fn fixture_project_javac() {
+  check_results(test_fixture(TestBackend::Javac));
+}
+
+#[test]
+fn fixture_project_interpreter() {
+  check_results(test_fixture(TestBackend::Interpreter));
+}